@@ -0,0 +1,845 @@
+//! TOTP accounts and code generation.
+
+use serde::{Serialize, Deserialize};
+use totp_rs::{TOTP, Algorithm as TotpAlgorithm};
+use std::sync::OnceLock;
+use std::time::{SystemTime, UNIX_EPOCH};
+use rand::RngCore;
+use rand::rngs::OsRng;
+use crate::error::AppError;
+use crate::recovery::RecoveryCodes;
+use crate::secret;
+
+/// Widest verification window, in periods before and after now, that
+/// [`Account::check_code`] searches
+pub const MAX_VERIFY_WINDOW: u64 = 10;
+
+/// TOTP algorithm variants that can be serialized/deserialized
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[non_exhaustive]
+pub enum Algorithm {
+    #[serde(rename = "SHA1")]
+    Sha1,
+    #[serde(rename = "SHA256")]
+    Sha256,
+    #[serde(rename = "SHA512")]
+    Sha512,
+    #[serde(rename = "STEAM")]
+    Steam,
+}
+
+// Conversion between our Algorithm and totp_rs::Algorithm
+impl From<Algorithm> for TotpAlgorithm {
+    fn from(algo: Algorithm) -> Self {
+        match algo {
+            Algorithm::Sha1 => TotpAlgorithm::SHA1,
+            Algorithm::Sha256 => TotpAlgorithm::SHA256,
+            Algorithm::Sha512 => TotpAlgorithm::SHA512,
+            Algorithm::Steam => TotpAlgorithm::Steam,
+        }
+    }
+}
+
+impl From<TotpAlgorithm> for Algorithm {
+    fn from(algo: TotpAlgorithm) -> Self {
+        match algo {
+            TotpAlgorithm::SHA1 => Algorithm::Sha1,
+            TotpAlgorithm::SHA256 => Algorithm::Sha256,
+            TotpAlgorithm::SHA512 => Algorithm::Sha512,
+            TotpAlgorithm::Steam => Algorithm::Steam,
+        }
+    }
+}
+
+impl Algorithm {
+    /// Human-readable algorithm name
+    pub fn name(&self) -> &'static str {
+        match self {
+            Algorithm::Sha1 => "SHA1",
+            Algorithm::Sha256 => "SHA256",
+            Algorithm::Sha512 => "SHA512",
+            Algorithm::Steam => "Steam",
+        }
+    }
+}
+
+/// How an account's codes may leave Quackey, for screens others can see
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum CodePolicy {
+    /// Shown on screen and offered for copying
+    #[default]
+    ShowAndCopy,
+    /// Shown on screen, never copied to the clipboard
+    DisplayOnly,
+    /// Masked on screen and copied to the clipboard straight away
+    CopyOnly,
+}
+
+impl CodePolicy {
+    pub const ALL: [CodePolicy; 3] = [CodePolicy::ShowAndCopy, CodePolicy::DisplayOnly, CodePolicy::CopyOnly];
+
+    /// Short human-readable name
+    pub fn label(&self) -> &'static str {
+        match self {
+            CodePolicy::ShowAndCopy => "show and copy",
+            CodePolicy::DisplayOnly => "display only",
+            CodePolicy::CopyOnly => "copy only",
+        }
+    }
+
+    /// What the policy does, for pickers
+    pub fn description(&self) -> &'static str {
+        match self {
+            CodePolicy::ShowAndCopy => "Show the code and offer to copy it",
+            CodePolicy::DisplayOnly => "Display only: never copy the code to the clipboard",
+            CodePolicy::CopyOnly => "Copy only: mask the code on screen and copy it straight away",
+        }
+    }
+
+    fn is_default(&self) -> bool {
+        *self == CodePolicy::default()
+    }
+}
+
+/// Color an account is drawn in, from the basic terminal palette so it reads
+/// on light and dark backgrounds alike
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum AccountColor {
+    Red,
+    Green,
+    Yellow,
+    Blue,
+    Magenta,
+    Cyan,
+}
+
+impl AccountColor {
+    pub const ALL: [AccountColor; 6] = [
+        AccountColor::Red,
+        AccountColor::Green,
+        AccountColor::Yellow,
+        AccountColor::Blue,
+        AccountColor::Magenta,
+        AccountColor::Cyan,
+    ];
+
+    /// Short human-readable name
+    pub fn label(&self) -> &'static str {
+        match self {
+            AccountColor::Red => "red",
+            AccountColor::Green => "green",
+            AccountColor::Yellow => "yellow",
+            AccountColor::Blue => "blue",
+            AccountColor::Magenta => "magenta",
+            AccountColor::Cyan => "cyan",
+        }
+    }
+}
+
+/// Generates a random base32 secret from `length` bytes of OS randomness
+pub fn generate_secret(length: usize) -> String {
+    let mut bytes = vec![0u8; length];
+    OsRng.fill_bytes(&mut bytes);
+    secret::encode_base32(&bytes)
+}
+
+/// Checks that `icon` is a single short symbol such as an emoji: no spaces and
+/// at most 8 characters, enough for emoji joined from several code points
+pub fn check_icon(icon: &str) -> Result<(), AppError> {
+    if icon.is_empty() || icon.chars().any(char::is_whitespace) || icon.chars().count() > 8 {
+        return Err(AppError::InvalidInput(format!("Icon '{}' is not a single emoji or symbol", icon)));
+    }
+    Ok(())
+}
+
+/// Checks that `alias` can be typed as a single command-line word: not empty,
+/// without spaces, commas or colons (which separate issuer and name)
+pub fn check_alias(alias: &str) -> Result<(), AppError> {
+    if alias.is_empty() {
+        return Err(AppError::InvalidInput("An alias cannot be empty".to_string()));
+    }
+    if alias.chars().any(|c| c.is_whitespace() || c == ',' || c == ':') {
+        return Err(AppError::InvalidInput(format!("Alias '{}' contains a space, comma or colon", alias))
+            .with_hint("Use a short single word, such as 'gh'"));
+    }
+    Ok(())
+}
+
+/// Checks that codes of `digits` digits can be made with `algorithm`: 6 to 8,
+/// or the 5 characters of a Steam code
+fn check_digits(digits: usize, algorithm: Algorithm) -> Result<(), AppError> {
+    if !matches!(algorithm, Algorithm::Steam) && !(6..=8).contains(&digits) {
+        return Err(AppError::InvalidInput(format!("{} digits is not supported (use 6 to 8)", digits)));
+    }
+    Ok(())
+}
+
+/// Percent-encodes a value for use in an otpauth URI
+pub(crate) fn uri_encode(value: &str) -> String {
+    let mut encoded = String::new();
+    for byte in value.bytes() {
+        match byte {
+            b'A'..=b'Z' | b'a'..=b'z' | b'0'..=b'9' | b'-' | b'.' | b'_' | b'~' => {
+                encoded.push(byte as char)
+            }
+            _ => encoded.push_str(&format!("%{:02X}", byte)),
+        }
+    }
+    encoded
+}
+
+/// Decodes %XX escapes (and '+' as space) in an otpauth URI component
+fn uri_decode(value: &str) -> Result<String, AppError> {
+    let bytes = value.as_bytes();
+    let mut decoded = Vec::with_capacity(bytes.len());
+    let mut i = 0;
+
+    while i < bytes.len() {
+        match bytes[i] {
+            b'%' => {
+                let hex = value
+                    .get(i + 1..i + 3)
+                    .and_then(|h| u8::from_str_radix(h, 16).ok())
+                    .ok_or_else(|| AppError::InvalidInput(format!("Invalid percent-encoding in '{}'", value)))?;
+                decoded.push(hex);
+                i += 3;
+            }
+            b'+' => {
+                decoded.push(b' ');
+                i += 1;
+            }
+            byte => {
+                decoded.push(byte);
+                i += 1;
+            }
+        }
+    }
+
+    String::from_utf8(decoded)
+        .map_err(|_| AppError::InvalidInput(format!("Invalid UTF-8 in '{}'", value)))
+}
+
+/// Random RFC 4122 version 4 UUID identifying an account
+fn new_id() -> String {
+    let mut bytes = [0u8; 16];
+    OsRng.fill_bytes(&mut bytes);
+    bytes[6] = (bytes[6] & 0x0f) | 0x40;
+    bytes[8] = (bytes[8] & 0x3f) | 0x80;
+
+    let hex: String = bytes.iter().map(|b| format!("{:02x}", b)).collect();
+    format!("{}-{}-{}-{}-{}", &hex[0..8], &hex[8..12], &hex[12..16], &hex[16..20], &hex[20..32])
+}
+
+/// Skew offsets kept per account
+const SKEW_HISTORY_LEN: usize = 20;
+
+// Default functions for serde
+fn is_false(value: &bool) -> bool { !value }
+fn default_period() -> u64 { 30 }
+fn default_digits() -> usize { 6 }
+fn default_algorithm() -> Algorithm { Algorithm::Sha1 }
+
+/// TOTP account information
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct Account {
+    /// Stable identifier; accounts saved before ids existed get one when loaded
+    #[serde(default = "new_id")]
+    id: String,
+    name: String,
+    secret: String,
+    #[serde(default = "default_digits")]
+    digits: usize,
+    #[serde(default = "default_period")]
+    period: u64,
+    #[serde(default = "default_algorithm")]
+    algorithm: Algorithm,
+    issuer: Option<String>,
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    tags: Vec<String>,
+    /// Short names the account can also be looked up by, such as "gh"
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    aliases: Vec<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    recovery_codes: Option<RecoveryCodes>,
+    /// Set while the provider hasn't been confirmed to accept the account's codes
+    #[serde(default, skip_serializing_if = "is_false")]
+    unverified: bool,
+    /// Hidden from tables and pickers without being deleted
+    #[serde(default, skip_serializing_if = "is_false")]
+    archived: bool,
+    #[serde(default, skip_serializing_if = "CodePolicy::is_default")]
+    code_policy: CodePolicy,
+    /// Emoji shown before the account's name, such as "🏦"
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    icon: Option<String>,
+    /// Color the account's name is drawn in
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    color: Option<AccountColor>,
+    /// Periods of clock skew accepted by [`check_code`](Self::check_code),
+    /// overriding the global setting
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    verify_window: Option<u64>,
+    /// Offsets, in periods, of the most recent codes matched by `check_code`
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    skew_history: Vec<i64>,
+    /// Unix time, in seconds, when the account was created; unknown for
+    /// accounts saved before it was recorded
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    created_at: Option<u64>,
+    /// Unix time, in seconds, when the account's details last changed
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    updated_at: Option<u64>,
+    /// Generator built from the secret and TOTP settings on first use, so refresh
+    /// loops don't decode the secret every second. Anything that changes those
+    /// settings must reset it.
+    #[serde(skip)]
+    totp: OnceLock<TOTP>,
+}
+
+impl Account {
+    pub fn new(
+        name: String,
+        secret: String,
+        digits: usize,
+        period: u64,
+        algorithm: TotpAlgorithm,
+        issuer: Option<String>
+    ) -> Self {
+        let now = SystemTime::now().duration_since(UNIX_EPOCH).unwrap_or_default().as_secs();
+        Self {
+            id: new_id(),
+            name,
+            secret,
+            digits,
+            period,
+            algorithm: algorithm.into(),
+            issuer,
+            tags: Vec::new(),
+            aliases: Vec::new(),
+            recovery_codes: None,
+            unverified: false,
+            archived: false,
+            code_policy: CodePolicy::default(),
+            icon: None,
+            color: None,
+            verify_window: None,
+            skew_history: Vec::new(),
+            created_at: Some(now),
+            updated_at: Some(now),
+            totp: OnceLock::new(),
+        }
+    }
+
+    /// Sets the account's tags
+    pub fn with_tags(mut self, tags: Vec<String>) -> Self {
+        self.tags = tags;
+        self
+    }
+
+    /// Sets the account's aliases
+    pub fn with_aliases(mut self, aliases: Vec<String>) -> Self {
+        self.aliases = aliases;
+        self
+    }
+
+    pub fn id(&self) -> &str {
+        &self.id
+    }
+
+    /// Takes over another account's id, for a version that replaces it
+    pub(crate) fn with_id(mut self, id: String) -> Self {
+        self.id = id;
+        self
+    }
+
+    /// When the account was created, in Unix seconds, if known
+    pub fn created_at(&self) -> Option<u64> {
+        self.created_at
+    }
+
+    /// Keeps the creation time of an account this one replaces
+    pub(crate) fn set_created_at(&mut self, at: Option<u64>) {
+        self.created_at = at;
+    }
+
+    /// When the account's details last changed, in Unix seconds, if known
+    pub fn updated_at(&self) -> Option<u64> {
+        self.updated_at
+    }
+
+    /// Records that the account's details changed at `at`
+    pub fn mark_updated(&mut self, at: u64) {
+        self.updated_at = Some(at);
+    }
+
+    /// Gives the account a fresh id, for copies added next to the original
+    pub(crate) fn regenerate_id(&mut self) {
+        self.id = new_id();
+    }
+
+    pub fn name(&self) -> &str {
+        &self.name
+    }
+
+    pub fn digits(&self) -> usize {
+        self.digits
+    }
+
+    pub fn period(&self) -> u64 {
+        self.period
+    }
+
+    pub fn algorithm(&self) -> TotpAlgorithm {
+        self.algorithm.into()
+    }
+
+    /// Human-readable name of the account's algorithm
+    pub fn algorithm_name(&self) -> &'static str {
+        self.algorithm.name()
+    }
+
+    pub fn issuer(&self) -> Option<&String> {
+        self.issuer.as_ref()
+    }
+
+    pub fn tags(&self) -> &[String] {
+        &self.tags
+    }
+
+    /// Short names the account can also be looked up by
+    pub fn aliases(&self) -> &[String] {
+        &self.aliases
+    }
+
+    pub fn set_aliases(&mut self, aliases: Vec<String>) {
+        self.aliases = aliases;
+    }
+
+    /// Whether `query` is one of the account's aliases, ignoring case
+    pub fn has_alias(&self, query: &str) -> bool {
+        self.aliases.iter().any(|alias| alias.eq_ignore_ascii_case(query))
+    }
+
+    /// Whether `tag` is one of the account's tags, ignoring case
+    pub fn has_tag(&self, tag: &str) -> bool {
+        self.tags.iter().any(|own| own.eq_ignore_ascii_case(tag.trim()))
+    }
+
+    /// Updates the account's descriptive details, keeping its secret and TOTP settings
+    pub fn set_details(&mut self, name: String, issuer: Option<String>, tags: Vec<String>) {
+        self.name = name;
+        self.issuer = issuer;
+        self.tags = tags;
+    }
+
+    pub fn recovery_codes(&self) -> Option<&RecoveryCodes> {
+        self.recovery_codes.as_ref()
+    }
+
+    pub fn set_recovery_codes(&mut self, codes: Option<RecoveryCodes>) {
+        self.recovery_codes = codes;
+    }
+
+    /// Whether the provider is known to accept this account's codes. Accounts
+    /// count as verified unless an add flow left them unconfirmed.
+    pub fn is_verified(&self) -> bool {
+        !self.unverified
+    }
+
+    pub fn set_verified(&mut self, verified: bool) {
+        self.unverified = !verified;
+    }
+
+    /// Whether the account is archived: kept, but hidden from tables and
+    /// pickers unless archived accounts are shown
+    pub fn is_archived(&self) -> bool {
+        self.archived
+    }
+
+    pub fn set_archived(&mut self, archived: bool) {
+        self.archived = archived;
+    }
+
+    /// Whether the account's codes may be shown and copied
+    pub fn code_policy(&self) -> CodePolicy {
+        self.code_policy
+    }
+
+    pub fn set_code_policy(&mut self, policy: CodePolicy) {
+        self.code_policy = policy;
+    }
+
+    /// Emoji shown before the account's name, if one was chosen
+    pub fn icon(&self) -> Option<&str> {
+        self.icon.as_deref()
+    }
+
+    /// Color the account's name is drawn in, if one was chosen
+    pub fn color(&self) -> Option<AccountColor> {
+        self.color
+    }
+
+    pub fn set_appearance(&mut self, icon: Option<String>, color: Option<AccountColor>) {
+        self.icon = icon;
+        self.color = color;
+    }
+
+    /// The account's own verification window, if it overrides the global one
+    pub fn verify_window(&self) -> Option<u64> {
+        self.verify_window
+    }
+
+    /// Sets the account's own verification window, capped at
+    /// [`MAX_VERIFY_WINDOW`]
+    pub fn set_verify_window(&mut self, window: Option<u64>) {
+        self.verify_window = window.map(|window| window.min(MAX_VERIFY_WINDOW));
+    }
+
+    /// Offsets of recently checked codes, oldest first
+    pub fn skew_history(&self) -> &[i64] {
+        &self.skew_history
+    }
+
+    /// Remembers the offset of a matched code, keeping the most recent ones
+    pub fn record_skew(&mut self, offset: i64) {
+        self.skew_history.push(offset);
+        if self.skew_history.len() > SKEW_HISTORY_LEN {
+            self.skew_history.remove(0);
+        }
+    }
+
+    /// Gets the account's secret key, or the reference it is fetched with
+    /// (see [`secret_reference`](Self::secret_reference))
+    pub fn secret(&self) -> &str {
+        &self.secret
+    }
+
+    /// Where the secret is fetched from when the vault only holds a
+    /// reference to it
+    pub fn secret_reference(&self) -> Option<secret::SecretReference> {
+        secret::SecretReference::parse(&self.secret)
+    }
+
+    /// Checks that the account can generate codes without generating one: a
+    /// period of at least a second, 6 to 8 digits (Steam has its own length)
+    /// and a valid Base32 secret. A reference is not fetched to check it.
+    pub fn check_settings(&self) -> Result<(), AppError> {
+        if self.period == 0 {
+            return Err(AppError::InvalidInput("The period must be at least 1 second".to_string()));
+        }
+        check_digits(self.digits, self.algorithm)?;
+        if self.secret_reference().is_none() {
+            secret::decode_base32(&self.secret)?;
+        }
+        Ok(())
+    }
+
+    /// Fails for an account that keeps its secret behind a reference. Accounts
+    /// from files, bundles and other devices go through this: generating a code
+    /// for a `cmd:` reference runs its command, so references are only ever
+    /// made when adding an account on this machine.
+    pub fn reject_secret_reference(&self) -> Result<(), AppError> {
+        match self.secret_reference() {
+            Some(reference) => Err(AppError::InvalidInput(format!(
+                "'{}' fetches its secret with '{}'; secret references can only be set when adding an account",
+                self.name, reference
+            ))),
+            None => Ok(()),
+        }
+    }
+
+    /// Secret in a canonical form for comparisons: uppercase, without spaces or padding
+    pub fn normalized_secret(&self) -> String {
+        self.secret
+            .chars()
+            .filter(|c| !c.is_whitespace() && *c != '=' && *c != '-')
+            .collect::<String>()
+            .to_uppercase()
+    }
+
+    /// Parses an otpauth://totp/ URI as produced by providers and authenticator apps
+    pub fn from_otpauth_uri(uri: &str) -> Result<Self, AppError> {
+        let uri = uri.trim();
+        let rest = uri
+            .strip_prefix("otpauth://")
+            .ok_or_else(|| AppError::InvalidInput("URI must start with otpauth://".to_string()))?;
+
+        let (kind, rest) = rest
+            .split_once('/')
+            .ok_or_else(|| AppError::InvalidInput("URI is missing the account label".to_string()))?;
+
+        if !kind.eq_ignore_ascii_case("totp") {
+            return Err(AppError::InvalidInput(format!(
+                "Only TOTP URIs are supported (got '{}')",
+                kind
+            )));
+        }
+
+        let (label, query) = rest.split_once('?').unwrap_or((rest, ""));
+        let label = uri_decode(label)?;
+
+        let (label_issuer, name) = match label.split_once(':') {
+            Some((issuer, name)) => (Some(issuer.trim().to_string()), name.trim().to_string()),
+            None => (None, label.trim().to_string()),
+        };
+
+        if name.is_empty() {
+            return Err(AppError::InvalidInput("URI has an empty account name".to_string()));
+        }
+
+        let mut secret = None;
+        let mut issuer = None;
+        let mut algorithm = Algorithm::Sha1;
+        let mut digits = default_digits();
+        let mut period = default_period();
+        let mut steam = false;
+
+        for pair in query.split('&').filter(|p| !p.is_empty()) {
+            let (key, value) = pair.split_once('=').unwrap_or((pair, ""));
+            let value = uri_decode(value)?;
+
+            match key.to_ascii_lowercase().as_str() {
+                "secret" => secret = Some(secret::normalize_base32(&value)?),
+                "issuer" => issuer = Some(value.trim().to_string()).filter(|i| !i.is_empty()),
+                "algorithm" => {
+                    algorithm = match value.to_ascii_uppercase().as_str() {
+                        "SHA1" => Algorithm::Sha1,
+                        "SHA256" => Algorithm::Sha256,
+                        "SHA512" => Algorithm::Sha512,
+                        other => {
+                            return Err(AppError::InvalidInput(format!("Unsupported algorithm '{}'", other)));
+                        }
+                    }
+                }
+                "digits" => {
+                    digits = value
+                        .parse()
+                        .ok()
+                        .ok_or_else(|| AppError::InvalidInput(format!("Invalid digits '{}'", value)))?
+                }
+                "period" => {
+                    period = value
+                        .parse()
+                        .ok()
+                        .filter(|p| *p > 0)
+                        .ok_or_else(|| AppError::InvalidInput(format!("Invalid period '{}'", value)))?
+                }
+                "encoder" => steam = value.eq_ignore_ascii_case("steam"),
+                _ => {} // Ignore parameters we don't use, like image
+            }
+        }
+
+        let secret = secret.ok_or_else(|| AppError::InvalidInput("URI has no secret parameter".to_string()))?;
+        let issuer = issuer.or(label_issuer).filter(|i| !i.is_empty());
+
+        let is_steam = steam || issuer.as_deref().is_some_and(|i| i.eq_ignore_ascii_case("steam"));
+        if is_steam {
+            algorithm = Algorithm::Steam;
+            digits = 5;
+        }
+        check_digits(digits, algorithm)?;
+
+        Ok(Self::new(name, secret, digits, period, algorithm.into(), issuer))
+    }
+
+    /// Builds the otpauth:// URI that authenticator apps use to import this account
+    pub fn otpauth_uri(&self) -> String {
+        let label = match &self.issuer {
+            Some(issuer) => format!("{}:{}", uri_encode(issuer), uri_encode(&self.name)),
+            None => uri_encode(&self.name),
+        };
+
+        let mut uri = format!("otpauth://totp/{}?secret={}", label, self.secret.trim_end_matches('='));
+        if let Some(issuer) = &self.issuer {
+            uri.push_str(&format!("&issuer={}", uri_encode(issuer)));
+        }
+
+        // Steam Guard uses SHA1 with its own code alphabet
+        match self.algorithm {
+            Algorithm::Steam => uri.push_str("&algorithm=SHA1&encoder=steam"),
+            algorithm => uri.push_str(&format!("&algorithm={}", algorithm.name())),
+        }
+        uri.push_str(&format!("&digits={}&period={}", self.digits, self.period));
+
+        uri
+    }
+
+    /// The account's code generator, built and cached on first use
+    fn totp(&self) -> Result<&TOTP, AppError> {
+        if let Some(totp) = self.totp.get() {
+            return Ok(totp);
+        }
+        if self.period == 0 {
+            return Err(AppError::InvalidInput("The period must be at least 1 second".to_string()));
+        }
+
+        let base32 = match self.secret_reference() {
+            Some(reference) => reference.resolve().map_err(|e| {
+                AppError::TotpError(format!("Failed to fetch the secret with '{}': {}", reference, e.message()))
+            })?,
+            None => self.secret.clone(),
+        };
+        let secret = secret::decode_base32(&base32)
+            .map_err(|e| AppError::TotpError(format!("Invalid secret key: {}", e)))?;
+
+        // Steam codes are 5 characters long; everything else follows the RFC digit range.
+        // Secrets shorter than the RFC's recommended 128 bits are common in the wild
+        // (80-bit keys especially), so the key length is not enforced here.
+        if !matches!(self.algorithm, Algorithm::Steam) && !(6..=8).contains(&self.digits) {
+            return Err(AppError::TotpError(format!(
+                "Failed to create TOTP: {} digits is not supported (use 6 to 8)",
+                self.digits
+            )));
+        }
+
+        let totp = TOTP::new_unchecked(
+            self.algorithm.into(),
+            self.digits,
+            1, // step_size
+            self.period,
+            secret,
+        );
+
+        Ok(self.totp.get_or_init(|| totp))
+    }
+
+    pub fn generate_totp(&self) -> Result<String, AppError> {
+        // Generate the current TOTP code
+        let code = self.totp()?.generate_current()
+            .map_err(AppError::SystemTimeError)?;
+
+        Ok(code)
+    }
+
+    /// Checks a code from the provider or another device against this account's
+    /// codes up to `window` periods before and after now, closest first.
+    /// Returns the matching offset in periods: positive when the code is ahead
+    /// of the local clock, negative when it is behind. Windows wider than
+    /// [`MAX_VERIFY_WINDOW`] are narrowed to it.
+    pub fn check_code(&self, code: &str, window: u64) -> Result<Option<i64>, AppError> {
+        let code: String = code.chars().filter(|c| !c.is_whitespace()).collect();
+        let totp = self.totp()?;
+        let now = SystemTime::now().duration_since(UNIX_EPOCH)?.as_secs();
+        let window = window.min(MAX_VERIFY_WINDOW) as i64;
+
+        let offsets = (0..=window).flat_map(|step| if step == 0 { vec![0] } else { vec![-step, step] });
+        for offset in offsets {
+            let shift = self.period.checked_mul(offset.unsigned_abs());
+            let time = match shift {
+                Some(shift) if offset < 0 => now.checked_sub(shift),
+                Some(shift) => now.checked_add(shift),
+                None => None,
+            };
+            if time.is_some_and(|time| totp.generate(time).eq_ignore_ascii_case(&code)) {
+                return Ok(Some(offset));
+            }
+        }
+
+        Ok(None)
+    }
+
+    pub fn time_remaining(&self) -> u64 {
+        let now = SystemTime::now()
+            .duration_since(UNIX_EPOCH)
+            .unwrap_or_default()
+            .as_secs();
+
+        // A period of 0 never generates codes, but must not divide by zero here
+        let period = self.period.max(1);
+        let current_period = now / period;
+        let next_period_start = (current_period + 1) * period;
+
+        next_period_start - now
+    }
+}
+
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn parses_an_otpauth_uri() {
+        let account = Account::from_otpauth_uri(
+            "otpauth://totp/ACME%20Co:john.doe%40email.com?secret=jbsw%20y3dp-ehpk3pxp&issuer=ACME%20Co&algorithm=SHA256&digits=8&period=60",
+        )
+        .unwrap();
+        assert_eq!(account.name(), "john.doe@email.com");
+        assert_eq!(account.issuer().map(String::as_str), Some("ACME Co"));
+        assert_eq!(account.secret(), "JBSWY3DPEHPK3PXP");
+        assert_eq!(account.algorithm(), TotpAlgorithm::SHA256);
+        assert_eq!(account.digits(), 8);
+        assert_eq!(account.period(), 60);
+    }
+
+    #[test]
+    fn fills_defaults_and_takes_the_issuer_from_the_label() {
+        let account = Account::from_otpauth_uri("otpauth://totp/GitHub:alice?secret=JBSWY3DPEHPK3PXP").unwrap();
+        assert_eq!(account.issuer().map(String::as_str), Some("GitHub"));
+        assert_eq!(account.algorithm(), TotpAlgorithm::SHA1);
+        assert_eq!((account.digits(), account.period()), (6, 30));
+    }
+
+    #[test]
+    fn reads_its_own_uri_back() {
+        let account = Account::new(
+            "alice@example.com".to_string(),
+            "JBSWY3DPEHPK3PXP".to_string(),
+            7,
+            45,
+            TotpAlgorithm::SHA512,
+            Some("Example & Co".to_string()),
+        );
+        let parsed = Account::from_otpauth_uri(&account.otpauth_uri()).unwrap();
+        assert_eq!(parsed.name(), account.name());
+        assert_eq!(parsed.issuer(), account.issuer());
+        assert_eq!(parsed.secret(), account.secret());
+        assert_eq!(parsed.algorithm(), account.algorithm());
+        assert_eq!((parsed.digits(), parsed.period()), (7, 45));
+    }
+
+    #[test]
+    fn steam_uris_get_five_characters() {
+        let account = Account::from_otpauth_uri("otpauth://totp/Steam:gabe?secret=JBSWY3DPEHPK3PXP&encoder=steam").unwrap();
+        assert_eq!(account.algorithm(), TotpAlgorithm::Steam);
+        assert_eq!(account.digits(), 5);
+        assert_eq!(Account::from_otpauth_uri(&account.otpauth_uri()).unwrap().algorithm(), TotpAlgorithm::Steam);
+    }
+
+    #[test]
+    fn refuses_invalid_uris() {
+        for uri in [
+            "https://example.com/?secret=JBSWY3DPEHPK3PXP",
+            "otpauth://hotp/GitHub:alice?secret=JBSWY3DPEHPK3PXP",
+            "otpauth://totp/GitHub:alice",
+            "otpauth://totp/GitHub:?secret=JBSWY3DPEHPK3PXP",
+            "otpauth://totp/GitHub:alice?secret=JBSWY3DPEHPK3PX1",
+            "otpauth://totp/GitHub:alice?secret=JBSWY3DPEHPK3PXP&algorithm=MD5",
+            "otpauth://totp/GitHub:alice?secret=JBSWY3DPEHPK3PXP&period=0",
+            "otpauth://totp/GitHub:alice?secret=JBSWY3DPEHPK3PXP&digits=five",
+            // 5 digits is only for Steam
+            "otpauth://totp/GitHub:alice?secret=JBSWY3DPEHPK3PXP&digits=5",
+            "otpauth://totp/GitHub:alice?secret=JBSWY3DPEHPK3PXP&digits=9",
+        ] {
+            assert!(Account::from_otpauth_uri(uri).is_err(), "accepted {}", uri);
+        }
+    }
+
+    #[test]
+    fn generates_the_rfc_6238_test_vector() {
+        let secret = secret::encode_base32(b"12345678901234567890");
+        let account = Account::new("rfc".to_string(), secret, 8, 30, TotpAlgorithm::SHA1, None);
+        assert_eq!(account.totp().unwrap().generate(59), "94287082");
+        assert_eq!(account.totp().unwrap().generate(1_111_111_109), "07081804");
+    }
+
+    #[test]
+    fn checks_codes_within_the_window() {
+        let account = Account::new("a".to_string(), "JBSWY3DPEHPK3PXP".to_string(), 6, 30, TotpAlgorithm::SHA1, None);
+        let code = account.generate_totp().unwrap();
+        assert_eq!(account.check_code(&code, 1).unwrap(), Some(0));
+        assert_eq!(account.check_code(&format!("{} ", &code), u64::MAX).unwrap(), Some(0));
+    }
+}
@@ -0,0 +1,63 @@
+//! Exports encrypted with a backup passphrase of their own.
+//!
+//! The passphrase is chosen for the backup alone, so a backup file handed to
+//! someone for safekeeping doesn't reveal the master password that unlocks
+//! the vault every day. The exported text, in whichever format was chosen, is
+//! encrypted with [`crypto::encrypt`] and armored like a shared account:
+//!
+//! ```text
+//! -----BEGIN QUACKEY BACKUP-----
+//! GEZDGNBV...
+//! -----END QUACKEY BACKUP-----
+//! ```
+
+use crate::crypto;
+use crate::error::AppError;
+use crate::share::{armor, dearmor};
+
+pub const BEGIN_MARKER: &str = "-----BEGIN QUACKEY BACKUP-----";
+pub const END_MARKER: &str = "-----END QUACKEY BACKUP-----";
+
+/// Encrypts exported `contents` into an armored backup
+pub fn seal(contents: &str, passphrase: &str) -> Result<String, AppError> {
+    armor(&crypto::encrypt(contents.as_bytes(), passphrase), BEGIN_MARKER, END_MARKER)
+}
+
+/// Decrypts an armored backup produced by [`seal`], returning the export inside
+pub fn open(armored: &str, passphrase: &str) -> Result<String, AppError> {
+    let data = dearmor(armored, BEGIN_MARKER, END_MARKER, "backup")?;
+    let contents = crypto::decrypt(&data, passphrase)
+        .map_err(|_| {
+            AppError::Locked("Wrong backup passphrase, or the backup was modified".to_string())
+                .with_hint("Check the backup passphrase; it is case-sensitive and not the master password")
+        })?;
+    String::from_utf8(contents.to_vec())
+        .map_err(|_| AppError::InvalidInput("The backup does not contain text".to_string()))
+}
+
+/// Whether `contents` is an encrypted backup rather than a plain export
+pub fn is_sealed(contents: &str) -> bool {
+    contents.trim_start().starts_with(BEGIN_MARKER)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn opens_what_it_sealed() {
+        let export = "otpauth://totp/GitHub:alice?secret=JBSWY3DPEHPK3PXP\n";
+        let sealed = seal(export, "backup passphrase").unwrap();
+        assert!(is_sealed(&sealed));
+        assert!(!is_sealed(export));
+        assert!(sealed.trim_end().ends_with(END_MARKER));
+        assert_eq!(open(&sealed, "backup passphrase").unwrap(), export);
+    }
+
+    #[test]
+    fn refuses_a_wrong_passphrase() {
+        let sealed = seal("contents", "backup passphrase").unwrap();
+        let error = open(&sealed, "master password").unwrap_err();
+        assert!(matches!(error.kind(), AppError::Locked(_)));
+    }
+}
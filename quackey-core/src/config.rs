@@ -0,0 +1,465 @@
+//! Application configuration stored in `config.json`.
+
+use std::fs::{self, File};
+use std::io::{Read, Write};
+use std::path::{Path, PathBuf};
+use serde::{Serialize, Deserialize};
+use serde_json::{Map, Value};
+use crate::error::AppError;
+use crate::sync::WebDavSettings;
+use crate::template::AccountTemplate;
+
+/// Default configuration file path
+pub const CONFIG_FILE: &str = "config.json";
+
+/// Default filenames
+const DEFAULT_LOG_FILENAME: &str = "totp_app.log";
+const DEFAULT_STORAGE_FILENAME: &str = "accounts.json";
+
+/// Steps that each upgrade the file layout by one version, starting at
+/// version 1 (files without a `"version"` key)
+const MIGRATIONS: &[fn(&mut Map<String, Value>)] = &[storage_dir_to_storage_path];
+
+/// Layout version of `config.json` written by this build
+pub const CONFIG_VERSION: u32 = MIGRATIONS.len() as u32 + 1;
+
+/// Keys `config.json` may contain; keep in sync with [`Config`]
+pub const KEYS: &[&str] = &[
+    "version",
+    "storage",
+    "log",
+    "syslog",
+    "encryption",
+    "auto_copy",
+    "code_grouping",
+    "mask_codes",
+    "copy_format",
+    "group_by_issuer",
+    "show_archived",
+    "recent_first",
+    "show_timestamps",
+    "disable_clipboard",
+    "keep_fresh_minutes",
+    "expiry_warning_seconds",
+    "expiry_bell",
+    "idle_blank_seconds",
+    "high_value_tags",
+    "spinner_delay_ms",
+    "verify_window",
+    "templates",
+    "webdav",
+    "pinentry",
+];
+
+/// Values of [`Config::encryption`]: a plain-text vault, one encrypted with a
+/// master password, or one protected by the Windows account
+pub const ENCRYPTION_MODES: &[&str] = &["none", "password", "windows"];
+
+/// Keys the `storage` object may contain; keep in sync with [`StorageSettings`]
+pub const STORAGE_KEYS: &[&str] = &["path"];
+
+/// Where the vault is kept
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct StorageSettings {
+    /// Folder holding the storage file, "." for the current directory
+    pub path: String,
+}
+
+/// Keys the `log` object may contain; keep in sync with [`LogSettings`]
+pub const LOG_KEYS: &[&str] = &["path"];
+
+/// Where the activity log is kept
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct LogSettings {
+    /// Folder holding the log file, "." for the current directory
+    pub path: String,
+}
+
+impl Default for LogSettings {
+    fn default() -> Self {
+        Self { path: ".".to_string() }
+    }
+}
+
+/// Application configuration
+#[derive(Debug, Serialize, Deserialize)]
+pub struct Config {
+    /// Layout version of the file, see [`CONFIG_VERSION`]
+    #[serde(default = "default_version")]
+    pub version: u32,
+    /// Where the vault is kept
+    pub storage: StorageSettings,
+    /// Where the activity log is kept
+    #[serde(default)]
+    pub log: LogSettings,
+    /// Also send log entries to the system log (Unix only)
+    #[serde(default)]
+    pub syslog: bool,
+    /// How the vault is protected, one of [`ENCRYPTION_MODES`]; chosen during
+    /// setup and kept up to date when it changes in the settings
+    #[serde(default = "default_encryption")]
+    pub encryption: String,
+    /// Copy generated codes to the clipboard without asking
+    #[serde(default)]
+    pub auto_copy: bool,
+    /// How codes are grouped on screen: "half", "none", or group sizes such as "3-3" or "2-2-2"
+    #[serde(default = "default_code_grouping")]
+    pub code_grouping: String,
+    /// Show codes as dots until Space is held, against shoulder-surfing and
+    /// screen recordings; copying is unaffected
+    #[serde(default)]
+    pub mask_codes: bool,
+    /// What is copied to the clipboard, with placeholders such as `{code}` and
+    /// `{issuer}`; just the digits by default
+    #[serde(default = "default_copy_format")]
+    pub copy_format: String,
+    /// Group account tables and pickers under issuer headings
+    #[serde(default)]
+    pub group_by_issuer: bool,
+    /// List archived accounts in tables and pickers too
+    #[serde(default)]
+    pub show_archived: bool,
+    /// List the most recently added accounts first in tables and pickers
+    #[serde(default)]
+    pub recent_first: bool,
+    /// Add columns for when accounts were added and last changed to the
+    /// accounts table
+    #[serde(default)]
+    pub show_timestamps: bool,
+    /// Never touch the clipboard: no copy prompts, no clipboard QR import
+    #[serde(default)]
+    pub disable_clipboard: bool,
+    /// Minutes to keep re-copying rotated codes after a copy (0 disables it)
+    #[serde(default)]
+    pub keep_fresh_minutes: u64,
+    /// Seconds before a shown code expires at which its countdown turns red
+    /// (0 disables the warning)
+    #[serde(default = "default_expiry_warning_seconds")]
+    pub expiry_warning_seconds: u64,
+    /// Ring the terminal bell when the expiry warning starts
+    #[serde(default = "default_expiry_bell")]
+    pub expiry_bell: bool,
+    /// Seconds without a key press after which a shown code is cleared from
+    /// the screen, and from the clipboard if it was copied (0 disables it)
+    #[serde(default)]
+    pub idle_blank_seconds: u64,
+    /// Tags marking high-value accounts, such as "banking": showing their codes
+    /// asks for the master password, or for a confirmation without one
+    #[serde(default)]
+    pub high_value_tags: Vec<String>,
+    /// Milliseconds to show a spinner after instant steps like saving an account
+    /// or generating a code (0 skips it)
+    #[serde(default)]
+    pub spinner_delay_ms: u64,
+    /// Periods of clock skew accepted when checking a code, before and after now
+    #[serde(default = "default_verify_window")]
+    pub verify_window: u64,
+    /// Saved account templates
+    #[serde(default)]
+    pub templates: Vec<AccountTemplate>,
+    /// WebDAV server to sync the encrypted vault with
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub webdav: Option<WebDavSettings>,
+    /// pinentry program for the master password and PIN, e.g. "pinentry" or
+    /// "pinentry-mac" (built-in prompt when unset)
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub pinentry: Option<String>,
+    /// Keys this build doesn't know, such as settings of a newer Quackey,
+    /// written back unchanged on save
+    #[serde(flatten)]
+    pub extra: Map<String, Value>,
+    /// Version the file was migrated from while loading
+    #[serde(skip)]
+    migrated_from: Option<u32>,
+}
+
+fn default_version() -> u32 {
+    CONFIG_VERSION
+}
+
+fn default_encryption() -> String {
+    "none".to_string()
+}
+
+fn default_code_grouping() -> String {
+    "half".to_string()
+}
+
+fn default_copy_format() -> String {
+    "{code}".to_string()
+}
+
+fn default_verify_window() -> u64 {
+    1
+}
+
+fn default_expiry_warning_seconds() -> u64 {
+    5
+}
+
+fn default_expiry_bell() -> bool {
+    true
+}
+
+impl Default for Config {
+    fn default() -> Self {
+        Self {
+            version: CONFIG_VERSION,
+            storage: StorageSettings { path: ".".to_string() },
+            log: LogSettings::default(),
+            syslog: false,
+            encryption: default_encryption(),
+            auto_copy: false,
+            code_grouping: default_code_grouping(),
+            mask_codes: false,
+            copy_format: default_copy_format(),
+            group_by_issuer: false,
+            show_archived: false,
+            recent_first: false,
+            show_timestamps: false,
+            disable_clipboard: false,
+            keep_fresh_minutes: 0,
+            expiry_warning_seconds: default_expiry_warning_seconds(),
+            expiry_bell: default_expiry_bell(),
+            idle_blank_seconds: 0,
+            high_value_tags: Vec::new(),
+            spinner_delay_ms: 0,
+            verify_window: default_verify_window(),
+            templates: Vec::new(),
+            webdav: None,
+            pinentry: None,
+            extra: Map::new(),
+            migrated_from: None,
+        }
+    }
+}
+
+/// Upgrades a parsed config file to [`CONFIG_VERSION`] in place and returns
+/// the version it was written in. Files from a newer Quackey are left as they
+/// are.
+pub fn migrate(object: &mut Map<String, Value>) -> u32 {
+    let from = object
+        .get("version")
+        .and_then(Value::as_u64)
+        .map_or(1, |version| version.min(u32::MAX as u64) as u32);
+
+    for step in MIGRATIONS.iter().skip(from.saturating_sub(1) as usize) {
+        step(object);
+    }
+    if from < CONFIG_VERSION {
+        object.insert("version".to_string(), Value::from(CONFIG_VERSION));
+    }
+    from
+}
+
+/// Version 1 to 2: `"storage_dir"` became `"storage": {"path": ...}`
+fn storage_dir_to_storage_path(object: &mut Map<String, Value>) {
+    if let Some(dir) = object.remove("storage_dir") {
+        object
+            .entry("storage")
+            .or_insert_with(|| serde_json::json!({ "path": dir }));
+    }
+}
+
+impl Config {
+    /// Load configuration from file or create default if not exists
+    pub fn load() -> Result<Self, AppError> {
+        if Path::new(CONFIG_FILE).exists() {
+            let mut file = File::open(CONFIG_FILE)
+                .map_err(|e| AppError::FileError(format!("Failed to open config file: {}", e)))?;
+
+            let mut contents = String::new();
+            file.read_to_string(&mut contents)
+                .map_err(|e| AppError::FileError(format!("Failed to read config file: {}", e)))?;
+
+            if contents.is_empty() {
+                return Ok(Config::default());
+            }
+
+            let mut object: Map<String, Value> = serde_json::from_str(&contents)
+                .map_err(|e| AppError::JsonError(format!("Failed to parse config JSON: {}", e)))?;
+            let from = migrate(&mut object);
+            let mut config: Config = serde_json::from_value(Value::Object(object))
+                .map_err(|e| AppError::JsonError(format!("Failed to parse config JSON: {}", e)))?;
+
+            if from < CONFIG_VERSION {
+                config.migrated_from = Some(from);
+                // Keep the old file for going back to an older Quackey. On a
+                // read-only disk the migration simply runs again next time.
+                let backup = format!("{}.v{}.bak", CONFIG_FILE, from);
+                if fs::write(&backup, &contents).is_ok() {
+                    let _ = config.save();
+                }
+            }
+            Ok(config)
+        } else {
+            Ok(Config::default())
+        }
+    }
+
+    /// Whether an account with `tags` is high-value (see
+    /// [`high_value_tags`](Self::high_value_tags)); tags match ignoring case
+    pub fn is_high_value(&self, tags: &[String]) -> bool {
+        tags.iter()
+            .any(|tag| self.high_value_tags.iter().any(|high| high.eq_ignore_ascii_case(tag)))
+    }
+
+    /// Version of the layout the file was migrated from while loading, if it
+    /// was older than [`CONFIG_VERSION`]
+    pub fn migrated_from(&self) -> Option<u32> {
+        self.migrated_from
+    }
+
+    /// Get the full log file path
+    pub fn get_log_file_path(&self) -> String {
+        if self.log.path == "." {
+            DEFAULT_LOG_FILENAME.to_string()
+        } else {
+            format!("{}/{}", self.log.path, DEFAULT_LOG_FILENAME)
+        }
+    }
+
+    /// Get the full storage file path
+    pub fn get_storage_file_path(&self) -> String {
+        if self.storage.path == "." {
+            DEFAULT_STORAGE_FILENAME.to_string()
+        } else {
+            format!("{}/{}", self.storage.path, DEFAULT_STORAGE_FILENAME)
+        }
+    }
+
+    /// Save configuration to file
+    pub fn save(&self) -> Result<(), AppError> {
+        let json = serde_json::to_string_pretty(self)
+            .map_err(|e| AppError::JsonError(format!("Failed to serialize config to JSON: {}", e)))?;
+
+        match File::create(CONFIG_FILE) {
+            Ok(mut file) => {
+                file.write_all(json.as_bytes())
+                    .map_err(|e| AppError::FileError(format!("Failed to write to config file: {}", e)))?;
+                Ok(())
+            },
+            Err(e) => {
+                if e.kind() == std::io::ErrorKind::PermissionDenied {
+                    Err(AppError::PermissionError(format!(
+                        "Permission denied when creating config file '{}'",
+                        CONFIG_FILE
+                    ))
+                    .with_path(std::env::current_dir().unwrap_or_default().join(CONFIG_FILE)))
+                } else {
+                    Err(AppError::FileError(format!("Failed to create config file: {}", e)))
+                }
+            }
+        }
+    }
+
+    /// Create directories for log and storage files if they don't exist
+    pub fn ensure_directories(&self) -> Result<(), AppError> {
+        // Ensure storage directory exists
+        if self.storage.path != "." {
+            self.create_and_verify_directory(Path::new(&self.storage.path), "storage")?;
+        }
+
+        // Ensure log directory exists
+        if self.log.path != "." {
+            self.create_and_verify_directory(Path::new(&self.log.path), "log")?;
+        }
+
+        Ok(())
+    }
+
+    /// Creates a directory and verifies that we can write to it
+    fn create_and_verify_directory(&self, dir: &Path, dir_type: &str) -> Result<(), AppError> {
+        // If directory doesn't exist, create it
+        if !dir.exists() {
+            match fs::create_dir_all(dir) {
+                Ok(_) => {},
+                Err(e) => {
+                    if e.kind() == std::io::ErrorKind::PermissionDenied {
+                        return Err(AppError::PermissionError(format!(
+                            "Permission denied when creating {} directory '{}'",
+                            dir_type, dir.display()
+                        ))
+                        .with_path(dir.parent().unwrap_or(dir)));
+                    } else {
+                        return Err(AppError::FileError(format!(
+                            "Failed to create {} directory '{}': {}. Please verify the path is valid and you have the necessary permissions.",
+                            dir_type, dir.display(), e
+                        )));
+                    }
+                }
+            }
+        }
+
+        // Verify we can write to the directory by attempting to create a test file
+        let test_file = dir.join(".write_test");
+        match File::create(&test_file) {
+            Ok(_) => {
+                // Clean up the test file
+                if let Err(e) = fs::remove_file(&test_file) {
+                    eprintln!("Warning: Failed to remove test file: {}", e);
+                }
+                Ok(())
+            },
+            Err(e) => {
+                if e.kind() == std::io::ErrorKind::PermissionDenied {
+                    Err(AppError::PermissionError(format!(
+                        "Permission denied when writing to {} directory '{}'",
+                        dir_type, dir.display()
+                    ))
+                    .with_path(dir))
+                } else {
+                    Err(AppError::FileError(format!(
+                        "Cannot write to {} directory '{}': {}. Please verify the path is valid and you have the necessary permissions.",
+                        dir_type, dir.display(), e
+                    )))
+                }
+            }
+        }
+    }
+
+    /// Validates and normalizes directory paths
+    pub fn validate_paths(&mut self) -> Result<(), AppError> {
+        // Normalize storage directory path
+        self.storage.path = self.normalize_path(&self.storage.path)?;
+        self.log.path = self.normalize_path(&self.log.path)?;
+        
+        // Validate that paths are not pointing to files
+        if Path::new(&self.storage.path).is_file() {
+            return Err(AppError::InvalidInput(format!(
+                "Storage directory path '{}' points to a file. Please provide a directory path.",
+                self.storage.path
+            )));
+        }
+        if Path::new(&self.log.path).is_file() {
+            return Err(AppError::InvalidInput(format!(
+                "Log directory path '{}' points to a file. Please provide a directory path.",
+                self.log.path
+            )));
+        }
+        
+        Ok(())
+    }
+    
+    /// Normalizes a directory path and ensures it's valid
+    fn normalize_path(&self, path: &str) -> Result<String, AppError> {
+        // Convert to PathBuf for manipulation
+        let path_buf = PathBuf::from(path);
+        
+        // Check if the path is empty
+        if path_buf.as_os_str().is_empty() {
+            return Err(AppError::InvalidInput("Directory path cannot be empty".to_string()));
+        }
+        
+        // If it's just a dot, return as is
+        if path == "." {
+            return Ok(path.to_string());
+        }
+        
+        // For paths with directories, we'll just normalize the path
+        // We'll let the OS handle any invalid paths when we try to create files
+        
+        // Return the normalized path as a string
+        Ok(path_buf.to_string_lossy().to_string())
+    }
+} 
\ No newline at end of file
@@ -0,0 +1,380 @@
+//! Passphrase-based encryption for data that must not sit in plain text on disk.
+//!
+//! Keys are derived with PBKDF2-HMAC-SHA256 and data is encrypted with
+//! ChaCha20-Poly1305. Data naming no cipher, or one this version doesn't
+//! know, is refused.
+
+use chacha20poly1305::aead::AeadInPlace;
+use chacha20poly1305::{ChaCha20Poly1305, Key, KeyInit, Nonce, Tag};
+use hmac::{Hmac, Mac};
+use rand::RngCore;
+use rand::rngs::OsRng;
+use serde::{Serialize, Deserialize};
+use sha2::Sha256;
+use crate::error::AppError;
+use crate::memory::SecretBytes;
+
+type HmacSha256 = Hmac<Sha256>;
+
+/// PBKDF2 iteration count for newly encrypted data
+const PBKDF2_ITERATIONS: u32 = 200_000;
+/// Most PBKDF2 iterations accepted from encrypted data, so a tampered file
+/// can't make unlocking run for hours. Also keeps the count within the 24 bits
+/// it has in the binary form.
+const MAX_ITERATIONS: u32 = 10_000_000;
+const SALT_LEN: usize = 16;
+const NONCE_LEN: usize = 12;
+const TAG_LEN: usize = 16;
+
+/// How an [`EncryptedData`] was encrypted
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+enum Cipher {
+    #[serde(rename = "chacha20poly1305")]
+    ChaCha20Poly1305,
+}
+
+impl Cipher {
+    /// Identifier stored in the top byte of the iteration count in the binary form
+    fn id(&self) -> u32 {
+        match self {
+            Cipher::ChaCha20Poly1305 => 1,
+        }
+    }
+
+    fn from_id(id: u32) -> Result<Self, AppError> {
+        match id {
+            1 => Ok(Cipher::ChaCha20Poly1305),
+            _ => Err(AppError::InvalidInput("Encrypted data uses an unknown cipher".to_string())),
+        }
+    }
+}
+
+/// Encrypted payload as stored on disk, with all binary fields hex-encoded
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct EncryptedData {
+    salt: String,
+    nonce: String,
+    iterations: u32,
+    cipher: Cipher,
+    ciphertext: String,
+    /// The Poly1305 tag
+    mac: String,
+}
+
+impl EncryptedData {
+    /// Compact binary form: salt, nonce, cipher and iterations (big-endian,
+    /// the cipher in the top byte), tag, ciphertext
+    pub fn to_bytes(&self) -> Result<Vec<u8>, AppError> {
+        let mut bytes = from_hex(&self.salt)?;
+        bytes.extend(from_hex(&self.nonce)?);
+        bytes.extend((self.cipher.id() << 24 | self.iterations).to_be_bytes());
+        bytes.extend(from_hex(&self.mac)?);
+        bytes.extend(from_hex(&self.ciphertext)?);
+        Ok(bytes)
+    }
+
+    /// Parses the form produced by [`to_bytes`](Self::to_bytes)
+    pub fn from_bytes(bytes: &[u8]) -> Result<Self, AppError> {
+        const HEADER_LEN: usize = SALT_LEN + NONCE_LEN + 4;
+        let truncated = || AppError::InvalidInput("Encrypted data is truncated".to_string());
+
+        if bytes.len() < HEADER_LEN {
+            return Err(truncated());
+        }
+
+        let (salt, rest) = bytes.split_at(SALT_LEN);
+        let (nonce, rest) = rest.split_at(NONCE_LEN);
+        let (header, rest) = rest.split_at(4);
+        let header = u32::from_be_bytes(header.try_into().expect("split at 4 bytes"));
+        let cipher = Cipher::from_id(header >> 24)?;
+        if rest.len() < TAG_LEN {
+            return Err(truncated());
+        }
+        let (mac, ciphertext) = rest.split_at(TAG_LEN);
+
+        Ok(Self {
+            salt: to_hex(salt),
+            nonce: to_hex(nonce),
+            iterations: header & 0x00ff_ffff,
+            cipher,
+            ciphertext: to_hex(ciphertext),
+            mac: to_hex(mac),
+        })
+    }
+}
+
+/// Encrypts `plaintext` with a key derived from `passphrase`
+pub fn encrypt(plaintext: &[u8], passphrase: &str) -> EncryptedData {
+    VaultKey::derive(passphrase).encrypt(plaintext)
+}
+
+/// Decrypts data produced by [`encrypt`], failing if the passphrase is wrong or
+/// the data was tampered with
+pub fn decrypt(data: &EncryptedData, passphrase: &str) -> Result<SecretBytes, AppError> {
+    VaultKey::unlock(data, passphrase)?.decrypt(data)
+}
+
+/// A key derived from a passphrase, kept in memory so the same data can be
+/// encrypted again on every save without running PBKDF2 each time
+#[derive(Clone)]
+pub struct VaultKey {
+    salt: [u8; SALT_LEN],
+    iterations: u32,
+    master: SecretBytes,
+}
+
+impl VaultKey {
+    /// Derives a new key from `passphrase` with a fresh salt
+    pub fn derive(passphrase: &str) -> Self {
+        let mut salt = [0u8; SALT_LEN];
+        OsRng.fill_bytes(&mut salt);
+        Self::with_salt(passphrase, salt, PBKDF2_ITERATIONS)
+    }
+
+    /// Derives the key `data` was encrypted with, failing if the passphrase
+    /// is wrong
+    pub fn unlock(data: &EncryptedData, passphrase: &str) -> Result<Self, AppError> {
+        check_iterations(data.iterations)?;
+        let salt = from_hex(&data.salt)?
+            .try_into()
+            .map_err(|_| AppError::InvalidInput("Encrypted data has an invalid salt".to_string()))?;
+        let key = Self::with_salt(passphrase, salt, data.iterations);
+        key.decrypt(data)?;
+        Ok(key)
+    }
+
+    fn with_salt(passphrase: &str, salt: [u8; SALT_LEN], iterations: u32) -> Self {
+        let mut master = SecretBytes::zeroed(32);
+        pbkdf2::pbkdf2_hmac::<Sha256>(passphrase.as_bytes(), &salt, iterations, &mut master);
+        Self { salt, iterations, master }
+    }
+
+    /// Encrypts `plaintext` with ChaCha20-Poly1305 and a fresh nonce
+    pub fn encrypt(&self, plaintext: &[u8]) -> EncryptedData {
+        let mut nonce = [0u8; NONCE_LEN];
+        OsRng.fill_bytes(&mut nonce);
+
+        let mut ciphertext = plaintext.to_vec();
+        let tag = self
+            .chacha()
+            .encrypt_in_place_detached(Nonce::from_slice(&nonce), b"", &mut ciphertext)
+            .expect("ChaCha20-Poly1305 encrypts up to 256 GiB");
+
+        EncryptedData {
+            salt: to_hex(&self.salt),
+            nonce: to_hex(&nonce),
+            iterations: self.iterations,
+            cipher: Cipher::ChaCha20Poly1305,
+            ciphertext: to_hex(&ciphertext),
+            mac: to_hex(&tag),
+        }
+    }
+
+    /// Decrypts data encrypted with this key, failing if it was encrypted with
+    /// another key or tampered with
+    pub fn decrypt(&self, data: &EncryptedData) -> Result<SecretBytes, AppError> {
+        let nonce = from_hex(&data.nonce)?;
+        // Decrypted in place, so the plaintext is only ever in locked memory
+        let mut ciphertext = SecretBytes::new(from_hex(&data.ciphertext)?);
+        let mac = from_hex(&data.mac)?;
+        let wrong = || AppError::InvalidInput("Wrong passphrase or corrupted encrypted data".to_string());
+
+        match data.cipher {
+            Cipher::ChaCha20Poly1305 => {
+                if nonce.len() != NONCE_LEN || mac.len() != TAG_LEN {
+                    return Err(wrong());
+                }
+                self.chacha()
+                    .decrypt_in_place_detached(Nonce::from_slice(&nonce), b"", &mut ciphertext, Tag::from_slice(&mac))
+                    .map_err(|_| wrong())?;
+                Ok(ciphertext)
+            }
+        }
+    }
+
+    /// Raw key material (salt, iterations, derived key), for wrapping the key
+    /// with another one
+    pub fn to_bytes(&self) -> SecretBytes {
+        let mut bytes = SecretBytes::zeroed(SALT_LEN + 4 + self.master.len());
+        let (salt, rest) = bytes.split_at_mut(SALT_LEN);
+        let (iterations, master) = rest.split_at_mut(4);
+        salt.copy_from_slice(&self.salt);
+        iterations.copy_from_slice(&self.iterations.to_be_bytes());
+        master.copy_from_slice(&self.master);
+        bytes
+    }
+
+    /// Parses the form produced by [`to_bytes`](Self::to_bytes)
+    pub fn from_bytes(bytes: &[u8]) -> Result<Self, AppError> {
+        if bytes.len() != SALT_LEN + 4 + 32 {
+            return Err(AppError::InvalidInput("Key material has the wrong length".to_string()));
+        }
+
+        let (salt, rest) = bytes.split_at(SALT_LEN);
+        let (iterations, master) = rest.split_at(4);
+        let iterations = u32::from_be_bytes(iterations.try_into().expect("split at 4 bytes"));
+        check_iterations(iterations)?;
+        let mut key = SecretBytes::zeroed(master.len());
+        key.copy_from_slice(master);
+        Ok(Self {
+            salt: salt.try_into().expect("split at SALT_LEN bytes"),
+            iterations,
+            master: key,
+        })
+    }
+
+    /// Short identifier of this key, like `3F2A-9C1B-77D0-42E8`. It changes
+    /// with the master password but reveals nothing about it, so it can be
+    /// printed to tell which password a vault or emergency kit belongs to.
+    pub fn fingerprint(&self) -> String {
+        let hex = to_hex(&hmac_sha256(&self.master, b"quackey-fingerprint")[..8]).to_uppercase();
+        hex.as_bytes()
+            .chunks(4)
+            .map(|group| String::from_utf8_lossy(group).into_owned())
+            .collect::<Vec<_>>()
+            .join("-")
+    }
+
+    /// The ChaCha20-Poly1305 cipher, keyed separately from the master key
+    fn chacha(&self) -> ChaCha20Poly1305 {
+        ChaCha20Poly1305::new(Key::from_slice(&hmac_sha256(&self.master, b"quackey-chacha20poly1305")))
+    }
+}
+
+impl std::fmt::Debug for VaultKey {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.write_str("VaultKey(..)")
+    }
+}
+
+/// Fails for an iteration count of 0 or above [`MAX_ITERATIONS`]
+fn check_iterations(iterations: u32) -> Result<(), AppError> {
+    if iterations == 0 || iterations > MAX_ITERATIONS {
+        return Err(AppError::InvalidInput(format!(
+            "Encrypted data asks for {} PBKDF2 iterations; 1 to {} are allowed",
+            iterations, MAX_ITERATIONS
+        )));
+    }
+    Ok(())
+}
+
+fn hmac_sha256(key: &[u8], data: &[u8]) -> [u8; 32] {
+    let mut mac = <HmacSha256 as Mac>::new_from_slice(key).expect("HMAC accepts any key length");
+    mac.update(data);
+    mac.finalize().into_bytes().into()
+}
+
+/// Lowercase hex encoding
+pub fn to_hex(bytes: &[u8]) -> String {
+    bytes.iter().map(|b| format!("{:02x}", b)).collect()
+}
+
+/// Decodes hex, accepting either case
+pub fn from_hex(hex: &str) -> Result<Vec<u8>, AppError> {
+    if !hex.is_ascii() || !hex.len().is_multiple_of(2) {
+        return Err(AppError::InvalidInput("Hex data must be an even number of hex digits".to_string()));
+    }
+
+    (0..hex.len())
+        .step_by(2)
+        .map(|i| {
+            u8::from_str_radix(&hex[i..i + 2], 16)
+                .map_err(|_| AppError::InvalidInput(format!("Invalid hex data at position {}", i + 1)))
+        })
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// A key with few iterations, so tests don't wait on PBKDF2
+    fn test_key(passphrase: &str) -> VaultKey {
+        VaultKey::with_salt(passphrase, [7; SALT_LEN], 1_000)
+    }
+
+    #[test]
+    fn decrypts_what_it_encrypted() {
+        let key = test_key("correct horse");
+        let data = key.encrypt(b"otpauth secrets");
+        assert_eq!(&*key.decrypt(&data).unwrap(), b"otpauth secrets");
+        assert_eq!(&*VaultKey::unlock(&data, "correct horse").unwrap().decrypt(&data).unwrap(), b"otpauth secrets");
+    }
+
+    #[test]
+    fn encrypts_with_a_fresh_nonce() {
+        let key = test_key("correct horse");
+        assert_ne!(key.encrypt(b"same").ciphertext, key.encrypt(b"same").ciphertext);
+    }
+
+    #[test]
+    fn refuses_a_wrong_passphrase() {
+        let data = test_key("correct horse").encrypt(b"otpauth secrets");
+        assert!(VaultKey::unlock(&data, "battery staple").is_err());
+        assert!(test_key("battery staple").decrypt(&data).is_err());
+    }
+
+    #[test]
+    fn refuses_tampered_data() {
+        let key = test_key("correct horse");
+        let mut data = key.encrypt(b"otpauth secrets");
+        let mut ciphertext = from_hex(&data.ciphertext).unwrap();
+        ciphertext[0] ^= 1;
+        data.ciphertext = to_hex(&ciphertext);
+        assert!(key.decrypt(&data).is_err());
+    }
+
+    #[test]
+    fn binary_form_round_trips() {
+        let key = test_key("correct horse");
+        let data = key.encrypt(b"otpauth secrets");
+        let parsed = EncryptedData::from_bytes(&data.to_bytes().unwrap()).unwrap();
+        assert_eq!(parsed.iterations, 1_000);
+        assert_eq!(parsed.cipher, Cipher::ChaCha20Poly1305);
+        assert_eq!(&*key.decrypt(&parsed).unwrap(), b"otpauth secrets");
+    }
+
+    #[test]
+    fn binary_form_refuses_an_unknown_cipher_or_truncated_data() {
+        let mut bytes = test_key("correct horse").encrypt(b"otpauth secrets").to_bytes().unwrap();
+        assert!(EncryptedData::from_bytes(&bytes[..SALT_LEN + NONCE_LEN + 4 + TAG_LEN - 1]).is_err());
+        bytes[SALT_LEN + NONCE_LEN] = 2;
+        assert!(EncryptedData::from_bytes(&bytes).is_err());
+    }
+
+    #[test]
+    fn json_form_requires_a_known_cipher() {
+        let data = test_key("correct horse").encrypt(b"otpauth secrets");
+        let mut value = serde_json::to_value(&data).unwrap();
+        assert!(serde_json::from_value::<EncryptedData>(value.clone()).is_ok());
+
+        value["cipher"] = "hmac-ctr".into();
+        assert!(serde_json::from_value::<EncryptedData>(value.clone()).is_err());
+        value.as_object_mut().unwrap().remove("cipher");
+        assert!(serde_json::from_value::<EncryptedData>(value).is_err());
+    }
+
+    #[test]
+    fn refuses_too_many_iterations() {
+        let mut data = test_key("correct horse").encrypt(b"otpauth secrets");
+        data.iterations = MAX_ITERATIONS + 1;
+        assert!(VaultKey::unlock(&data, "correct horse").is_err());
+    }
+
+    #[test]
+    fn key_material_round_trips() {
+        let key = test_key("correct horse");
+        let restored = VaultKey::from_bytes(&key.to_bytes()).unwrap();
+        assert_eq!(restored.fingerprint(), key.fingerprint());
+        assert_eq!(&*restored.decrypt(&key.encrypt(b"wrapped")).unwrap(), b"wrapped");
+        assert!(VaultKey::from_bytes(&key.to_bytes()[1..]).is_err());
+    }
+
+    #[test]
+    fn hex_round_trips() {
+        assert_eq!(from_hex(&to_hex(&[0, 0x7f, 0xff])).unwrap(), [0, 0x7f, 0xff]);
+        assert_eq!(from_hex("ABcd").unwrap(), [0xab, 0xcd]);
+        assert!(from_hex("abc").is_err());
+        assert!(from_hex("zz").is_err());
+    }
+}
@@ -0,0 +1,141 @@
+//! Protecting the vault with the Windows user account (DPAPI) instead of a
+//! master password.
+//!
+//! The vault is encrypted as usual, with a random passphrase that is kept
+//! next to it (`.quackey-dpapi`) encrypted by `CryptProtectData`. Only the same
+//! Windows user on the same machine can decrypt it, so a copied vault and key
+//! file are useless elsewhere, while the user is never asked for anything.
+//! Anyone who can log in as the user can still read the vault.
+
+use std::fs;
+use std::path::{Path, PathBuf};
+use rand::RngCore;
+use rand::rngs::OsRng;
+use crate::crypto::{to_hex, VaultKey};
+use crate::error::AppError;
+use crate::permissions;
+use crate::storage;
+
+const KEY_FILENAME: &str = ".quackey-dpapi";
+
+/// Whether DPAPI protection can be used on this platform
+pub fn is_available() -> bool {
+    cfg!(windows)
+}
+
+/// Whether the vault at `file_path` is protected with DPAPI
+pub fn is_set_up(file_path: &str) -> bool {
+    key_path(file_path).exists()
+}
+
+/// Creates a random vault passphrase, stores it protected for the current
+/// Windows user and returns the key to encrypt the vault with
+pub fn set_up(file_path: &str) -> Result<VaultKey, AppError> {
+    let mut secret = [0u8; 32];
+    OsRng.fill_bytes(&mut secret);
+    let passphrase = to_hex(&secret);
+
+    permissions::replace(&key_path(file_path), &protect(passphrase.as_bytes())?)
+        .map_err(|e| AppError::FileError(format!("Failed to write DPAPI key file: {}", e)))?;
+    Ok(VaultKey::derive(&passphrase))
+}
+
+/// Derives the key of the DPAPI-protected vault at `file_path`
+pub fn unlock(file_path: &str) -> Result<VaultKey, AppError> {
+    let protected = fs::read(key_path(file_path))
+        .map_err(|e| AppError::FileError(format!("Failed to read DPAPI key file: {}", e)))?;
+    let passphrase = String::from_utf8(unprotect(&protected)?)
+        .map_err(|_| AppError::Locked("The DPAPI key file is damaged".to_string()))?;
+    storage::unlock(file_path, &passphrase)
+}
+
+/// Removes the DPAPI key file, e.g. after switching to a master password
+pub fn remove(file_path: &str) -> Result<(), AppError> {
+    match fs::remove_file(key_path(file_path)) {
+        Ok(()) => Ok(()),
+        Err(e) if e.kind() == std::io::ErrorKind::NotFound => Ok(()),
+        Err(e) => Err(AppError::FileError(format!("Failed to remove DPAPI key file: {}", e))),
+    }
+}
+
+/// Where the wrapped key of the vault at `file_path` is kept
+pub(crate) fn key_path(file_path: &str) -> PathBuf {
+    match Path::new(file_path).parent() {
+        Some(parent) if !parent.as_os_str().is_empty() => parent.join(KEY_FILENAME),
+        _ => PathBuf::from(KEY_FILENAME),
+    }
+}
+
+#[cfg(windows)]
+fn protect(data: &[u8]) -> Result<Vec<u8>, AppError> {
+    use windows_sys::Win32::Security::Cryptography::{
+        CryptProtectData, CRYPTPROTECT_UI_FORBIDDEN, CRYPT_INTEGER_BLOB,
+    };
+
+    let input = CRYPT_INTEGER_BLOB { cbData: data.len() as u32, pbData: data.as_ptr() as *mut u8 };
+    let mut output = CRYPT_INTEGER_BLOB { cbData: 0, pbData: std::ptr::null_mut() };
+    let ok = unsafe {
+        CryptProtectData(
+            &input,
+            std::ptr::null(),
+            std::ptr::null(),
+            std::ptr::null(),
+            std::ptr::null(),
+            CRYPTPROTECT_UI_FORBIDDEN,
+            &mut output,
+        )
+    };
+    if ok == 0 {
+        return Err(AppError::PermissionError(format!(
+            "DPAPI could not protect the vault key: {}",
+            std::io::Error::last_os_error()
+        )));
+    }
+    Ok(take_blob(output))
+}
+
+#[cfg(windows)]
+fn unprotect(data: &[u8]) -> Result<Vec<u8>, AppError> {
+    use windows_sys::Win32::Security::Cryptography::{
+        CryptUnprotectData, CRYPTPROTECT_UI_FORBIDDEN, CRYPT_INTEGER_BLOB,
+    };
+
+    let input = CRYPT_INTEGER_BLOB { cbData: data.len() as u32, pbData: data.as_ptr() as *mut u8 };
+    let mut output = CRYPT_INTEGER_BLOB { cbData: 0, pbData: std::ptr::null_mut() };
+    let ok = unsafe {
+        CryptUnprotectData(
+            &input,
+            std::ptr::null_mut(),
+            std::ptr::null(),
+            std::ptr::null(),
+            std::ptr::null(),
+            CRYPTPROTECT_UI_FORBIDDEN,
+            &mut output,
+        )
+    };
+    if ok == 0 {
+        return Err(AppError::Locked(format!(
+            "The vault is protected for another Windows user or machine: {}",
+            std::io::Error::last_os_error()
+        )));
+    }
+    Ok(take_blob(output))
+}
+
+/// Copies a blob allocated by DPAPI and frees it
+#[cfg(windows)]
+fn take_blob(blob: windows_sys::Win32::Security::Cryptography::CRYPT_INTEGER_BLOB) -> Vec<u8> {
+    let bytes = unsafe { std::slice::from_raw_parts(blob.pbData, blob.cbData as usize) }.to_vec();
+    unsafe { windows_sys::Win32::Foundation::LocalFree(blob.pbData.cast()) };
+    bytes
+}
+
+#[cfg(not(windows))]
+fn protect(_data: &[u8]) -> Result<Vec<u8>, AppError> {
+    Err(AppError::InvalidInput("DPAPI protection is only available on Windows".to_string()))
+}
+
+#[cfg(not(windows))]
+fn unprotect(_data: &[u8]) -> Result<Vec<u8>, AppError> {
+    Err(AppError::Locked("This vault is protected with Windows DPAPI and can only be opened on Windows".to_string()))
+}
@@ -0,0 +1,89 @@
+//! Accounts defined in environment variables, for containers and CI jobs
+//! where a service account's TOTP seed is injected as a secret.
+//!
+//! Each `QUACKEY_ACCOUNT_<NAME>=<otpauth-uri>` variable is one account. They
+//! are read fresh on every run and never written to the vault unless imported.
+
+use crate::account::Account;
+use crate::error::AppError;
+use crate::import::ImportLine;
+use crate::vault;
+
+/// Prefix of the variables holding accounts
+pub const PREFIX: &str = "QUACKEY_ACCOUNT_";
+
+/// One `QUACKEY_ACCOUNT_*` variable
+#[derive(Debug)]
+pub struct EnvAccount {
+    /// Full variable name, e.g. `QUACKEY_ACCOUNT_GITHUB`
+    pub variable: String,
+    /// The account, or why its URI couldn't be parsed
+    pub account: Result<Account, AppError>,
+}
+
+impl EnvAccount {
+    /// The `<NAME>` part of the variable
+    pub fn key(&self) -> &str {
+        &self.variable[PREFIX.len()..]
+    }
+}
+
+/// Every `QUACKEY_ACCOUNT_*` variable, sorted by name
+pub fn accounts() -> Vec<EnvAccount> {
+    let mut accounts: Vec<EnvAccount> = std::env::vars_os()
+        .filter_map(|(variable, value)| {
+            let variable = variable.into_string().ok()?;
+            if variable.len() <= PREFIX.len() || !variable.starts_with(PREFIX) {
+                return None;
+            }
+            let account = value
+                .into_string()
+                .map_err(|_| AppError::InvalidInput("the value is not valid UTF-8".to_string()))
+                .and_then(|uri| Account::from_otpauth_uri(&uri));
+            Some(EnvAccount { variable, account })
+        })
+        .collect();
+    accounts.sort_by(|a, b| a.variable.cmp(&b.variable));
+    accounts
+}
+
+/// Environment accounts whose name, issuer or tag contains `query`, as in
+/// [`Vault::search`](crate::vault::Vault::search). Variables with a bad URI
+/// are left out.
+pub fn search(query: &str) -> Vec<Account> {
+    accounts()
+        .into_iter()
+        .filter_map(|entry| entry.account.ok())
+        .filter(|account| vault::contains_query(account, query))
+        .collect()
+}
+
+/// Finds the environment account matching `query`: the `<NAME>` part of its
+/// variable, or its name, issuer or `issuer:name` as in
+/// [`Vault::find`](crate::vault::Vault::find). Returns `None` when no variable
+/// matches, and an error when the matching variable holds a bad URI.
+pub fn find(query: &str) -> Result<Option<Account>, AppError> {
+    let query = query.trim();
+    for entry in accounts() {
+        let by_key = entry.key().eq_ignore_ascii_case(query);
+        let matches = match &entry.account {
+            Ok(account) => by_key || vault::matches_query(account, query),
+            Err(_) => by_key,
+        };
+        if matches {
+            return entry.account.map(Some).map_err(|e| {
+                AppError::InvalidInput(format!("{} is not a valid otpauth:// URI: {}", entry.variable, e.message()))
+            });
+        }
+    }
+    Ok(None)
+}
+
+/// The environment accounts as import entries, numbered in variable order
+pub fn import_lines() -> Vec<ImportLine> {
+    accounts()
+        .into_iter()
+        .enumerate()
+        .map(|(index, entry)| ImportLine { line: index + 1, result: entry.account })
+        .collect()
+}
@@ -0,0 +1,245 @@
+//! The error type shared by every Quackey operation.
+//!
+//! Each [`AppError`] has a stable [`code`](AppError::code) and
+//! [`exit_code`](AppError::exit_code) for scripts, and can carry the file or
+//! account it concerns and a remediation [`hint`](AppError::hint), added with
+//! [`with_path`](AppError::with_path), [`with_account`](AppError::with_account)
+//! and [`with_hint`](AppError::with_hint). The terminal UI and `--json` output
+//! both render errors from these parts.
+
+use std::io;
+use std::path::{Path, PathBuf};
+use std::time::SystemTimeError;
+use thiserror::Error;
+
+/// Application error types
+#[derive(Debug, Error)]
+#[non_exhaustive]
+pub enum AppError {
+    #[error("IO error: {0}")]
+    IoError(io::Error),
+    #[error("File error: {0}")]
+    FileError(String),
+    /// JSON that couldn't be read or written, such as an import file
+    #[error("JSON error: {0}")]
+    JsonError(String),
+    /// The storage file can't be read as a vault
+    #[error("Storage corrupt: {0}")]
+    StorageCorrupt(String),
+    #[error("TOTP error: {0}")]
+    TotpError(String),
+    #[error("System time error: {0}")]
+    SystemTimeError(#[from] SystemTimeError),
+    #[error("Invalid input: {0}")]
+    InvalidInput(String),
+    /// No account matches a name, query or id
+    #[error("Not found: {0}")]
+    NotFound(String),
+    #[error("Permission error: {0}")]
+    PermissionError(String),
+    /// The storage file was changed by another program since it was loaded
+    #[error("Conflict: {0}")]
+    Conflict(String),
+    /// The vault is encrypted and no key was given to open it
+    #[error("Locked: {0}")]
+    Locked(String),
+    /// The user backed out of a prompt with Esc or Ctrl-C; not a failure,
+    /// menus go back a level on it
+    #[error("Cancelled")]
+    Cancelled,
+    /// Another error, with the file or account it concerns and what to do
+    /// about it
+    #[error("{source}")]
+    Context {
+        source: Box<AppError>,
+        path: Option<PathBuf>,
+        account: Option<String>,
+        hint: Option<String>,
+    },
+}
+
+impl AppError {
+    /// The underlying error, without any added context
+    pub fn kind(&self) -> &AppError {
+        match self {
+            AppError::Context { source, .. } => source.kind(),
+            other => other,
+        }
+    }
+
+    /// Unwraps added context, returning the underlying error
+    pub fn into_kind(self) -> AppError {
+        match self {
+            AppError::Context { source, .. } => source.into_kind(),
+            other => other,
+        }
+    }
+
+    /// Notes the file this error concerns
+    pub fn with_path(self, path: impl AsRef<Path>) -> Self {
+        let mut error = self.into_context();
+        if let AppError::Context { path: field, .. } = &mut error {
+            *field = Some(path.as_ref().to_path_buf());
+        }
+        error
+    }
+
+    /// Notes the account (name, query or id) this error concerns
+    pub fn with_account(self, account: impl Into<String>) -> Self {
+        let mut error = self.into_context();
+        if let AppError::Context { account: field, .. } = &mut error {
+            *field = Some(account.into());
+        }
+        error
+    }
+
+    /// Suggests what the user can do about this error, replacing the default
+    /// hint for its kind
+    pub fn with_hint(self, hint: impl Into<String>) -> Self {
+        let mut error = self.into_context();
+        if let AppError::Context { hint: field, .. } = &mut error {
+            *field = Some(hint.into());
+        }
+        error
+    }
+
+    /// Whether a hint was given with [`with_hint`](Self::with_hint)
+    pub fn has_own_hint(&self) -> bool {
+        match self {
+            AppError::Context { hint: Some(_), .. } => true,
+            AppError::Context { source, .. } => source.has_own_hint(),
+            _ => false,
+        }
+    }
+
+    /// Wraps the error in an empty [`Context`](AppError::Context) unless it
+    /// already is one
+    fn into_context(self) -> Self {
+        match self {
+            context @ AppError::Context { .. } => context,
+            other => AppError::Context { source: Box::new(other), path: None, account: None, hint: None },
+        }
+    }
+
+    /// The file this error concerns, if known
+    pub fn path(&self) -> Option<&Path> {
+        match self {
+            AppError::Context { path: Some(path), .. } => Some(path),
+            AppError::Context { source, .. } => source.path(),
+            _ => None,
+        }
+    }
+
+    /// The account this error concerns, if known
+    pub fn account(&self) -> Option<&str> {
+        match self {
+            AppError::Context { account: Some(account), .. } => Some(account),
+            AppError::Context { source, .. } => source.account(),
+            _ => None,
+        }
+    }
+
+    /// What the user can do about this error: the hint given with
+    /// [`with_hint`](Self::with_hint), or a default for its kind
+    pub fn hint(&self) -> Option<String> {
+        if let AppError::Context { hint: Some(hint), .. } = self {
+            return Some(hint.clone());
+        }
+        if let AppError::Context { source, .. } = self {
+            return source.hint();
+        }
+
+        let hint = match self {
+            AppError::StorageCorrupt(_) => "Run quackey without a command to repair the storage file",
+            AppError::NotFound(_) => "Check the spelling, or use issuer:name to pick an account",
+            AppError::Locked(_) => "Check the master password; it is case-sensitive",
+            AppError::PermissionError(_) => {
+                "Run Quackey as a user who can write there, or choose a different location for your files"
+            }
+            AppError::Conflict(_) => "Run the command again to work with the latest accounts",
+            AppError::SystemTimeError(_) => "Check that the system clock is set correctly",
+            _ => return None,
+        };
+        Some(hint.to_string())
+    }
+
+    /// Process exit code for this error, so scripts can branch on the kind of
+    /// failure:
+    ///
+    /// | Code | Meaning |
+    /// |------|---------|
+    /// | 1 | Other error (I/O, network, code generation, JSON other than the storage file) |
+    /// | 2 | Invalid input or usage |
+    /// | 3 | Account not found |
+    /// | 4 | Vault locked (wrong or missing master password) |
+    /// | 5 | Storage file corrupt ([`StorageCorrupt`](AppError::StorageCorrupt) only) |
+    /// | 6 | Permission denied |
+    /// | 7 | Storage changed by another program |
+    /// | 130 | Cancelled at a prompt (Esc or Ctrl-C) |
+    pub fn exit_code(&self) -> u8 {
+        match self.kind() {
+            AppError::InvalidInput(_) => 2,
+            AppError::NotFound(_) => 3,
+            AppError::Locked(_) => 4,
+            AppError::StorageCorrupt(_) => 5,
+            AppError::PermissionError(_) => 6,
+            AppError::Conflict(_) => 7,
+            AppError::Cancelled => 130,
+            _ => 1,
+        }
+    }
+
+    /// Stable identifier of the kind of error, for machine-readable output
+    pub fn code(&self) -> &'static str {
+        match self.kind() {
+            AppError::IoError(_) => "IO_ERROR",
+            AppError::FileError(_) => "FILE_ERROR",
+            AppError::JsonError(_) => "JSON_ERROR",
+            AppError::StorageCorrupt(_) => "STORAGE_CORRUPT",
+            AppError::TotpError(_) => "TOTP_ERROR",
+            AppError::SystemTimeError(_) => "CLOCK_ERROR",
+            AppError::InvalidInput(_) => "INVALID_INPUT",
+            AppError::NotFound(_) => "ACCOUNT_NOT_FOUND",
+            AppError::PermissionError(_) => "PERMISSION_DENIED",
+            AppError::Conflict(_) => "CONFLICT",
+            AppError::Locked(_) => "VAULT_LOCKED",
+            AppError::Cancelled => "CANCELLED",
+            AppError::Context { .. } => unreachable!("kind() never returns context"),
+        }
+    }
+
+    /// The error message without the kind prefix used by `Display`
+    pub fn message(&self) -> String {
+        match self.kind() {
+            AppError::IoError(e) => e.to_string(),
+            AppError::SystemTimeError(e) => e.to_string(),
+            AppError::FileError(msg)
+            | AppError::JsonError(msg)
+            | AppError::StorageCorrupt(msg)
+            | AppError::TotpError(msg)
+            | AppError::InvalidInput(msg)
+            | AppError::NotFound(msg)
+            | AppError::PermissionError(msg)
+            | AppError::Conflict(msg)
+            | AppError::Locked(msg) => msg.clone(),
+            AppError::Cancelled => "Cancelled".to_string(),
+            AppError::Context { .. } => unreachable!("kind() never returns context"),
+        }
+    }
+}
+
+impl From<io::Error> for AppError {
+    fn from(error: io::Error) -> Self {
+        if error.kind() == io::ErrorKind::PermissionDenied {
+            AppError::PermissionError(format!("Permission denied: {}", error))
+        } else {
+            AppError::IoError(error)
+        }
+    }
+}
+
+impl From<Box<dyn std::error::Error>> for AppError {
+    fn from(error: Box<dyn std::error::Error>) -> Self {
+        AppError::FileError(error.to_string())
+    }
+}
@@ -0,0 +1,350 @@
+//! What happens in the app, as [`Event`]s handed to [`Sink`]s.
+//!
+//! The app and the vault describe what they did with an [`Event`] instead of
+//! a line of text, and [`Events`] passes each one to the log file and to any
+//! other sink plugged in, such as the system log. A sink decides what to do
+//! with an event: the log file writes its [`Display`](std::fmt::Display) text
+//! at its [`level`](Event::level), while a notifier could pick out the few it
+//! cares about.
+
+use std::fmt;
+use std::sync::{Arc, Mutex, MutexGuard, PoisonError};
+use crate::config::Config;
+use crate::error::AppError;
+use crate::logger::{Level, Logger};
+
+/// Something that happened, with what a sink needs to know about it
+#[derive(Debug, Clone)]
+pub enum Event {
+    /// The interactive app opened
+    Started,
+    Exiting,
+    VaultUnlocked,
+    /// The startup check of the storage file, see
+    /// [`integrity::check`](crate::integrity::check)
+    IntegrityChecked { summary: String },
+    /// How the vault is protected changed
+    LockChanged(LockChange),
+    /// A high-value account's code was refused after a wrong master password
+    HighValueDenied { name: String },
+    /// `config.json` was upgraded to a newer layout
+    ConfigMigrated { from: u32, to: u32 },
+    SettingChanged { setting: String },
+    PrivacyModeChanged { on: bool },
+
+    AccountAdded { name: String },
+    AccountsAdded { count: usize },
+    /// The account's name, issuer or tags were edited
+    AccountUpdated { name: String, previous_name: String },
+    /// One of the account's other settings changed, such as its aliases
+    AccountChanged { name: String, change: &'static str },
+    AccountVerified { name: String, verified: bool },
+    AccountArchived { name: String, archived: bool },
+    AccountDeleted { name: String },
+    /// Every account was replaced, such as after a sync
+    AccountsReplaced { count: usize },
+    SecretGenerated { name: String },
+    TemplateSaved { name: String },
+
+    CodeGenerated { name: String },
+    CodeGenerationFailed { name: String, error: String },
+    /// A code typed in was checked; `skew` says how far off a matching one
+    /// was, and is `None` when it didn't match
+    CodeChecked { name: String, skew: Option<String> },
+    /// Something was looked at, such as "vault overview"
+    Viewed { what: String },
+
+    SnapshotSaved { reason: String, path: String },
+    /// The vault now lives at `to`, moved or copied there with `companions`
+    /// files that belong to it
+    StorageMoved { from: String, to: String, copied: bool, companions: usize },
+    /// The vault now opens the storage file at `to`, leaving `from` alone
+    StoragePathChanged { from: String, to: String },
+    /// The storage file changed on disk and was read again
+    StorageReloaded,
+    /// Changes made on disk were merged with ours
+    StorageMerged,
+    Synced { count: usize },
+    SyncFailed { error: String },
+    Imported { source: String, summary: String },
+    /// Accounts left the vault, described by `what`, such as "2 account(s) as csv"
+    Exported { what: String },
+
+    /// Anything else worth recording
+    Note { level: Level, message: String },
+}
+
+/// Ways the vault's protection can change
+#[derive(Debug, Clone)]
+pub enum LockChange {
+    PasswordSet,
+    PasswordChanged { reencrypted: usize, skipped: usize },
+    DpapiSet,
+    DpapiRemoved,
+    EncryptionRemoved,
+    PinSetUp,
+    PinRemoved,
+    /// Plain-text snapshots were overwritten after encrypting the vault
+    PlaintextWiped { count: usize },
+}
+
+impl Event {
+    /// A free-form [`Event::Note`]
+    pub fn note(level: Level, message: impl Into<String>) -> Self {
+        Event::Note { level, message: message.into() }
+    }
+
+    /// How important the event is; security-relevant ones are
+    /// [`Level::Audit`]
+    pub fn level(&self) -> Level {
+        match self {
+            Event::VaultUnlocked
+            | Event::IntegrityChecked { .. }
+            | Event::LockChanged(_) => Level::Audit,
+            Event::HighValueDenied { .. }
+            | Event::CodeChecked { skew: None, .. }
+            | Event::AccountVerified { verified: false, .. } => Level::Warn,
+            Event::CodeGenerationFailed { .. } | Event::SyncFailed { .. } => Level::Error,
+            Event::Note { level, .. } => *level,
+            _ => Level::Info,
+        }
+    }
+}
+
+/// The text written to the log
+impl fmt::Display for Event {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            Event::Started => write!(f, "Application started"),
+            Event::Exiting => write!(f, "Application exiting"),
+            Event::VaultUnlocked => write!(f, "Vault unlocked"),
+            Event::IntegrityChecked { summary } => write!(f, "Startup integrity check: {}", summary),
+            Event::LockChanged(change) => write!(f, "{}", change),
+            Event::HighValueDenied { name } => {
+                write!(f, "Wrong master password for high-value account: {}", name)
+            }
+            Event::ConfigMigrated { from, to } => write!(
+                f,
+                "Migrated {} from layout version {} to {}",
+                crate::config::CONFIG_FILE,
+                from,
+                to
+            ),
+            Event::SettingChanged { setting } => write!(f, "Changed setting: {}", setting),
+            Event::PrivacyModeChanged { on } => {
+                write!(f, "Turned privacy mode {}", if *on { "on" } else { "off" })
+            }
+            Event::AccountAdded { name } => write!(f, "Added new account: {}", name),
+            Event::AccountsAdded { count } => write!(f, "Added {} accounts in one batch", count),
+            Event::AccountUpdated { name, previous_name } if name == previous_name => {
+                write!(f, "Updated account: {}", name)
+            }
+            Event::AccountUpdated { name, previous_name } => {
+                write!(f, "Updated account from '{}' to '{}'", previous_name, name)
+            }
+            Event::AccountChanged { name, change } => write!(f, "Changed {} of account: {}", change, name),
+            Event::AccountVerified { name, verified: true } => write!(f, "Verified account setup: {}", name),
+            Event::AccountVerified { name, verified: false } => {
+                write!(f, "Marked account as not verified: {}", name)
+            }
+            Event::AccountArchived { name, archived } => {
+                write!(f, "{} account: {}", if *archived { "Archived" } else { "Restored" }, name)
+            }
+            Event::AccountDeleted { name } => write!(f, "Deleted account: {}", name),
+            Event::AccountsReplaced { count } => write!(f, "Replaced vault with {} accounts", count),
+            Event::SecretGenerated { name } => write!(f, "Generated new secret for account: {}", name),
+            Event::TemplateSaved { name } => write!(f, "Saved account template: {}", name),
+            Event::CodeGenerated { name } => write!(f, "Generated TOTP for account: {}", name),
+            Event::CodeGenerationFailed { name, error } => {
+                write!(f, "Failed to generate TOTP for account {}: {}", name, error)
+            }
+            Event::CodeChecked { name, skew: Some(skew) } => {
+                write!(f, "Checked code for account {}: {}", name, skew)
+            }
+            Event::CodeChecked { name, skew: None } => {
+                write!(f, "Checked code did not match for account: {}", name)
+            }
+            Event::Viewed { what } => write!(f, "Viewed {}", what),
+            Event::SnapshotSaved { reason, path } => write!(f, "Saved vault snapshot before {}: {}", reason, path),
+            Event::StorageMoved { from, to, copied, companions } => write!(
+                f,
+                "Storage file {} from '{}' to '{}' with {} other file(s)",
+                if *copied { "copied" } else { "moved" },
+                from,
+                to,
+                companions
+            ),
+            Event::StoragePathChanged { from, to } => {
+                write!(f, "Storage file path changed from '{}' to '{}'", from, to)
+            }
+            Event::StorageReloaded => write!(f, "Reloaded storage after it changed on disk"),
+            Event::StorageMerged => write!(f, "Merged storage with changes made on disk"),
+            Event::Synced { count } => write!(f, "Synced {} account(s) with WebDAV", count),
+            Event::SyncFailed { error } => write!(f, "WebDAV sync failed: {}", error),
+            Event::Imported { source, summary } => write!(f, "Imported from {}: {}", source, summary),
+            Event::Exported { what } => write!(f, "Exported {}", what),
+            Event::Note { message, .. } => write!(f, "{}", message),
+        }
+    }
+}
+
+impl fmt::Display for LockChange {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            LockChange::PasswordSet => write!(f, "Vault encrypted with a master password"),
+            LockChange::PasswordChanged { reencrypted, skipped } => {
+                write!(f, "Master password changed; re-encrypted the vault and {} backup(s)", reencrypted)?;
+                if *skipped > 0 {
+                    write!(f, ", {} older backup(s) left as they were", skipped)?;
+                }
+                Ok(())
+            }
+            LockChange::DpapiSet => write!(f, "Vault protected with Windows DPAPI"),
+            LockChange::DpapiRemoved => write!(f, "Vault DPAPI protection removed"),
+            LockChange::EncryptionRemoved => write!(f, "Vault encryption removed"),
+            LockChange::PinSetUp => write!(f, "PIN unlock set up"),
+            LockChange::PinRemoved => write!(f, "PIN unlock turned off"),
+            LockChange::PlaintextWiped { count } => write!(
+                f,
+                "Securely deleted {} plain-text backup(s) after encrypting the vault",
+                count
+            ),
+        }
+    }
+}
+
+/// Somewhere events go. A sink is shared by every handle to the [`Events`] it
+/// was added to, so it may be called from any thread, one event at a time.
+pub trait Sink: Send {
+    /// Records one event
+    fn record(&self, event: &Event) -> Result<(), AppError>;
+}
+
+/// The log file writes every event as a line at its level
+impl Sink for Logger {
+    fn record(&self, event: &Event) -> Result<(), AppError> {
+        self.write(event.level(), &event.to_string())
+    }
+}
+
+/// Passes events to the log file and any other sinks. Every clone is a handle
+/// to the same sinks, whether it is kept by the vault, the menu or a
+/// background thread, so a log file that moves or a sink turned on through
+/// one handle applies to all of them. Events are recorded one at a time, so
+/// entries from different threads don't interleave. With no sinks at all,
+/// events go nowhere.
+#[derive(Clone, Default)]
+pub struct Events {
+    sinks: Arc<Mutex<Sinks>>,
+}
+
+#[derive(Default)]
+struct Sinks {
+    log: Option<Logger>,
+    others: Vec<Box<dyn Sink>>,
+}
+
+impl Events {
+    /// Events written to `log`, if given, and nowhere else yet
+    pub fn new(log: Option<Logger>) -> Self {
+        Self { sinks: Arc::new(Mutex::new(Sinks { log, others: Vec::new() })) }
+    }
+
+    /// Events as `config` asks for them, see [`configure`](Self::configure)
+    pub fn for_config(config: &Config) -> Result<Self, AppError> {
+        let events = Self::default();
+        events.configure(config)?;
+        Ok(events)
+    }
+
+    /// Sends events to the log file `config` names, and to the system log when
+    /// [`Config::syslog`] is on, replacing every sink for all handles
+    pub fn configure(&self, config: &Config) -> Result<(), AppError> {
+        let log = Logger::new(&config.get_log_file_path())?;
+        let others: Vec<Box<dyn Sink>> = match config.syslog {
+            #[cfg(unix)]
+            true => vec![Box::new(Syslog::open())],
+            _ => Vec::new(),
+        };
+        *self.lock() = Sinks { log: Some(log), others };
+        Ok(())
+    }
+
+    /// Also passes events to `sink`
+    pub fn with_sink(self, sink: impl Sink + 'static) -> Self {
+        self.lock().others.push(Box::new(sink));
+        self
+    }
+
+    /// Passes `event` to every sink. A sink that fails doesn't keep it from
+    /// the others; the first failure is returned once all have had it.
+    pub fn emit(&self, event: Event) -> Result<(), AppError> {
+        let sinks = self.lock();
+        let log = sinks.log.as_ref().map(|log| log as &dyn Sink);
+        let mut result = Ok(());
+        for sink in log.into_iter().chain(sinks.others.iter().map(|sink| sink.as_ref())) {
+            if let Err(e) = sink.record(&event)
+                && result.is_ok()
+            {
+                result = Err(e);
+            }
+        }
+        result
+    }
+
+    /// Emits an [`Event::Note`]
+    pub fn note(&self, level: Level, message: impl Into<String>) -> Result<(), AppError> {
+        self.emit(Event::note(level, message))
+    }
+
+    /// Path of the log file, if events are logged to one
+    pub fn log_path(&self) -> Option<String> {
+        self.lock().log.as_ref().map(|log| log.file_path().to_string())
+    }
+
+    /// Moves the log file for all handles, see [`Logger::relocate`]
+    pub fn relocate_log(&self, new_path: &str, keep_old: bool) -> Result<(), AppError> {
+        match &mut self.lock().log {
+            Some(log) => log.relocate(new_path, keep_old),
+            None => Ok(()),
+        }
+    }
+
+    /// The sinks, even when a thread panicked while recording an event: one
+    /// entry cut short shouldn't stop everyone else's
+    fn lock(&self) -> MutexGuard<'_, Sinks> {
+        self.sinks.lock().unwrap_or_else(PoisonError::into_inner)
+    }
+}
+
+/// The system log, through `syslog(3)`
+#[cfg(unix)]
+pub struct Syslog;
+
+#[cfg(unix)]
+impl Syslog {
+    /// Opens the system log for Quackey's entries
+    pub fn open() -> Self {
+        // The identity must outlive every entry, so it is a static string
+        unsafe { libc::openlog(c"quackey".as_ptr(), libc::LOG_PID, libc::LOG_USER) };
+        Syslog
+    }
+}
+
+#[cfg(unix)]
+impl Sink for Syslog {
+    fn record(&self, event: &Event) -> Result<(), AppError> {
+        let priority = match event.level() {
+            Level::Info => libc::LOG_INFO,
+            Level::Audit => libc::LOG_NOTICE,
+            Level::Warn => libc::LOG_WARNING,
+            Level::Error => libc::LOG_ERR,
+        };
+        // Messages come from names the user typed, which can't contain NUL
+        // in a C string
+        let message = std::ffi::CString::new(event.to_string().replace('\0', ""))
+            .expect("NUL bytes were removed");
+        unsafe { libc::syslog(priority, c"%s".as_ptr(), message.as_ptr()) };
+        Ok(())
+    }
+}
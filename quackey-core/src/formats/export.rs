@@ -0,0 +1,123 @@
+//! `quackey-export`, a self-describing backup of every account.
+//!
+//! The accounts are wrapped in a document that says what it is, which layout
+//! version it uses, when it was made and by which Quackey:
+//!
+//! ```json
+//! {
+//!   "format": "quackey-export",
+//!   "version": 1,
+//!   "created_at": "2025-01-31T09:30:00+01:00",
+//!   "app_version": "0.1.0",
+//!   "accounts": [ ... ]
+//! }
+//! ```
+//!
+//! When the layout changes, [`EXPORT_VERSION`] goes up and a step upgrading
+//! the previous version is added to [`MIGRATIONS`], so every backup ever
+//! written can still be imported. A bare accounts array, as written by the
+//! `json` format before this one existed, is read as a version 1 document.
+
+use chrono::Local;
+use serde::Serialize;
+use serde_json::{Map, Value};
+use crate::account::Account;
+use crate::error::AppError;
+use crate::import::ImportLine;
+use super::json::parse_accounts;
+use super::{Exporter, Importer};
+
+/// Value of the `"format"` key identifying an export document
+pub const FORMAT_ID: &str = "quackey-export";
+
+/// Steps that each upgrade a document by one version, starting at version 1
+const MIGRATIONS: &[fn(&mut Map<String, Value>)] = &[];
+
+/// Layout version of the documents this build writes
+pub const EXPORT_VERSION: u32 = MIGRATIONS.len() as u32 + 1;
+
+pub struct QuackeyExport;
+
+/// The document as written, with its keys in this order
+#[derive(Serialize)]
+struct Document<'a> {
+    format: &'static str,
+    version: u32,
+    created_at: String,
+    app_version: &'static str,
+    accounts: &'a [Account],
+}
+
+impl Importer for QuackeyExport {
+    fn name(&self) -> &'static str {
+        "quackey"
+    }
+
+    fn description(&self) -> &'static str {
+        "A quackey-export backup of any version, or a bare accounts array"
+    }
+
+    fn parse(&self, contents: &str) -> Result<Vec<ImportLine>, AppError> {
+        let value: Value = serde_json::from_str(contents)
+            .map_err(|e| AppError::JsonError(format!("Not a Quackey export: {}", e)))?;
+
+        let mut document = match value {
+            Value::Array(accounts) => {
+                let mut document = Map::new();
+                document.insert("version".to_string(), Value::from(1));
+                document.insert("accounts".to_string(), Value::Array(accounts));
+                document
+            }
+            Value::Object(document) if document.get("format").and_then(Value::as_str) == Some(FORMAT_ID) => document,
+            _ => {
+                return Err(AppError::InvalidInput(format!(
+                    "Not a Quackey export: the \"format\" key is not \"{}\"",
+                    FORMAT_ID
+                )));
+            }
+        };
+
+        let version = document
+            .get("version")
+            .and_then(Value::as_u64)
+            .ok_or_else(|| AppError::InvalidInput("The export has no layout version".to_string()))?;
+        if version == 0 || version > EXPORT_VERSION as u64 {
+            return Err(AppError::InvalidInput(format!(
+                "The export uses layout version {}, but this Quackey reads versions 1 to {}",
+                version, EXPORT_VERSION
+            ))
+            .with_hint("Update Quackey to import backups made by a newer version"));
+        }
+        for step in MIGRATIONS.iter().skip(version as usize - 1) {
+            step(&mut document);
+        }
+
+        match document.remove("accounts") {
+            Some(Value::Array(accounts)) => Ok(parse_accounts(accounts)),
+            _ => Err(AppError::InvalidInput("The export has no \"accounts\" list".to_string())),
+        }
+    }
+}
+
+impl Exporter for QuackeyExport {
+    fn name(&self) -> &'static str {
+        "quackey"
+    }
+
+    fn description(&self) -> &'static str {
+        "A quackey-export backup recording its version, date and Quackey version"
+    }
+
+    fn export(&self, accounts: &[Account]) -> Result<String, AppError> {
+        let document = Document {
+            format: FORMAT_ID,
+            version: EXPORT_VERSION,
+            created_at: Local::now().to_rfc3339(),
+            app_version: env!("CARGO_PKG_VERSION"),
+            accounts,
+        };
+        serde_json::to_string_pretty(&document)
+            .map(|json| json + "\n")
+            .map_err(|e| AppError::JsonError(format!("Failed to serialize accounts: {}", e)))
+    }
+}
@@ -0,0 +1,58 @@
+//! Quackey's own `accounts.json` layout, for moving accounts between vaults.
+
+use serde_json::Value;
+use crate::account::Account;
+use crate::error::AppError;
+use crate::import::ImportLine;
+use super::{Exporter, Importer};
+
+pub struct AccountsJson;
+
+impl Importer for AccountsJson {
+    fn name(&self) -> &'static str {
+        "json"
+    }
+
+    fn description(&self) -> &'static str {
+        "A Quackey accounts.json file"
+    }
+
+    fn parse(&self, contents: &str) -> Result<Vec<ImportLine>, AppError> {
+        let values: Vec<Value> = serde_json::from_str(contents)
+            .map_err(|e| AppError::JsonError(format!("Not a Quackey accounts file: {}", e)))?;
+
+        Ok(parse_accounts(values))
+    }
+}
+
+/// Reads each serialized account on its own, so one bad entry doesn't fail the
+/// rest. Entries whose secret is a `cmd:` or `env:` reference are refused.
+pub(super) fn parse_accounts(values: Vec<Value>) -> Vec<ImportLine> {
+    values
+        .into_iter()
+        .enumerate()
+        .map(|(index, value)| ImportLine {
+            line: index + 1,
+            result: serde_json::from_value::<Account>(value)
+                .map_err(|e| AppError::JsonError(format!("Invalid account: {}", e)))
+                .and_then(|account| account.reject_secret_reference().map(|_| account))
+                .and_then(|account| account.check_settings().map(|_| account)),
+        })
+        .collect()
+}
+
+impl Exporter for AccountsJson {
+    fn name(&self) -> &'static str {
+        "json"
+    }
+
+    fn description(&self) -> &'static str {
+        "A Quackey accounts.json file"
+    }
+
+    fn export(&self, accounts: &[Account]) -> Result<String, AppError> {
+        serde_json::to_string_pretty(accounts)
+            .map(|json| json + "\n")
+            .map_err(|e| AppError::JsonError(format!("Failed to serialize accounts: {}", e)))
+    }
+}
@@ -0,0 +1,90 @@
+//! Import and export formats.
+//!
+//! Each format lives in its own module and implements [`Importer`], [`Exporter`]
+//! or both; registering it in [`importers`]/[`exporters`] is all it takes to
+//! make it available to `quackey import --format` and `quackey export --format`.
+//! Formats with heavy dependencies can be gated behind a cargo feature with
+//! `#[cfg(feature = "...")]` on both the module and its registry entry.
+
+mod export;
+mod json;
+mod text;
+mod uris;
+
+use crate::account::Account;
+use crate::error::AppError;
+use crate::import::ImportLine;
+
+pub use text::account_from_parts;
+
+/// Reads accounts from a file format
+pub trait Importer {
+    /// Name used with `--format`
+    fn name(&self) -> &'static str;
+
+    /// One-line description for help output
+    fn description(&self) -> &'static str;
+
+    /// Parses file contents into accounts, one entry per source record. A
+    /// malformed record fails on its own; only an unreadable file fails as a whole.
+    fn parse(&self, contents: &str) -> Result<Vec<ImportLine>, AppError>;
+}
+
+/// Writes accounts to a file format
+pub trait Exporter {
+    /// Name used with `--format`
+    fn name(&self) -> &'static str;
+
+    /// One-line description for help output
+    fn description(&self) -> &'static str;
+
+    /// Serializes the accounts, secrets included
+    fn export(&self, accounts: &[Account]) -> Result<String, AppError>;
+}
+
+/// Every available importer
+pub fn importers() -> Vec<Box<dyn Importer>> {
+    vec![
+        Box::new(uris::UriList),
+        Box::new(json::AccountsJson),
+        Box::new(export::QuackeyExport),
+        Box::new(text::PlainText),
+    ]
+}
+
+/// Every available exporter
+pub fn exporters() -> Vec<Box<dyn Exporter>> {
+    vec![Box::new(uris::UriList), Box::new(json::AccountsJson), Box::new(export::QuackeyExport)]
+}
+
+/// Looks up an importer by name
+pub fn find_importer(name: &str) -> Result<Box<dyn Importer>, AppError> {
+    let importers = importers();
+    let names: Vec<&str> = importers.iter().map(|i| i.name()).collect();
+    let unknown = AppError::InvalidInput(format!(
+        "Unknown import format '{}' (available: {})",
+        name,
+        names.join(", ")
+    ));
+
+    importers
+        .into_iter()
+        .find(|i| i.name().eq_ignore_ascii_case(name))
+        .ok_or(unknown)
+}
+
+/// Looks up an exporter by name
+pub fn find_exporter(name: &str) -> Result<Box<dyn Exporter>, AppError> {
+    let exporters = exporters();
+    let names: Vec<&str> = exporters.iter().map(|e| e.name()).collect();
+    let unknown = AppError::InvalidInput(format!(
+        "Unknown export format '{}' (available: {})",
+        name,
+        names.join(", ")
+    ));
+
+    exporters
+        .into_iter()
+        .find(|e| e.name().eq_ignore_ascii_case(name))
+        .ok_or(unknown)
+}
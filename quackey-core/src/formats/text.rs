@@ -0,0 +1,212 @@
+//! Loose text lists kept by hand, one account per line, such as
+//!
+//! ```text
+//! GitHub JBSWY3DPEHPK3PXP
+//! AWS, me@example.com, JBSW Y3DP EHPK 3PXP
+//! Dropbox: jbswy3dpehpk3pxp
+//! Steam - gamer KRSXG5CTMVRXEZLU
+//! ```
+//!
+//! Lines are read as best they can be. Fields are split at commas,
+//! semicolons, tabs or pipes when a line has any, and at spaces otherwise,
+//! where the secret may be written in groups. The secret is the field that
+//! looks like base32, a word with `@` in it is the account name, and the rest
+//! is the issuer; a lone label is split at `:` or ` - ` into issuer and name.
+//! Issuers with a preset get its digits, period and algorithm. `otpauth://`
+//! URIs are read as they are. A line that can't be read fails on its own, so
+//! it can be fixed by hand.
+
+use crate::account::{self, Account};
+use crate::error::AppError;
+use crate::import::ImportLine;
+use crate::presets::find_preset;
+use crate::secret;
+use super::Importer;
+
+/// Characters separating fields, when a line has any
+const SEPARATORS: [char; 4] = [',', ';', '\t', '|'];
+
+/// Base32 characters a secret has at least, so short words aren't taken for one
+const MIN_SECRET_LEN: usize = 16;
+
+pub struct PlainText;
+
+impl Importer for PlainText {
+    fn name(&self) -> &'static str {
+        "text"
+    }
+
+    fn description(&self) -> &'static str {
+        "Loose lines like 'GitHub JBSWY3DPEHPK3PXP' or 'issuer, name, secret'; # comments are skipped"
+    }
+
+    fn parse(&self, contents: &str) -> Result<Vec<ImportLine>, AppError> {
+        Ok(contents
+            .lines()
+            .enumerate()
+            .map(|(index, raw)| (index, raw.trim()))
+            .filter(|(_, line)| !line.is_empty() && !line.starts_with('#'))
+            .map(|(index, line)| ImportLine {
+                line: index + 1,
+                result: parse_line(line),
+            })
+            .collect())
+    }
+}
+
+/// Reads one line of a loose text list
+fn parse_line(line: &str) -> Result<Account, AppError> {
+    if line.get(..10).is_some_and(|start| start.eq_ignore_ascii_case("otpauth://")) {
+        return Account::from_otpauth_uri(line);
+    }
+
+    let (labels, secret) = if line.contains(SEPARATORS) {
+        split_fields(line)?
+    } else {
+        split_words(line)?
+    };
+
+    // An email address is the account name, whatever comes before or after it
+    let (issuer, name) = match labels.iter().position(|label| label.contains('@')) {
+        Some(index) => {
+            let mut rest = labels;
+            let name = rest.remove(index);
+            (Some(rest.join(" ")).filter(|issuer| !issuer.is_empty()), name)
+        }
+        None => match labels.as_slice() {
+            [label] => match label.split_once(':').or_else(|| label.split_once(" - ")) {
+                Some((issuer, name)) if !issuer.trim().is_empty() && !name.trim().is_empty() => {
+                    (Some(issuer.trim().to_string()), name.trim().to_string())
+                }
+                _ => (Some(label.clone()), label.clone()),
+            },
+            [issuer, rest @ ..] => (Some(issuer.clone()), rest.join(" ")),
+            [] => return Err(AppError::InvalidInput("No issuer or name next to the secret".to_string())),
+        },
+    };
+
+    account_from_parts(issuer.as_deref(), &name, &secret)
+}
+
+/// Splits a line at [`SEPARATORS`]: the last field that looks like a secret
+/// is the secret, the others are labels
+fn split_fields(line: &str) -> Result<(Vec<String>, String), AppError> {
+    let fields: Vec<&str> = line.split(SEPARATORS).map(str::trim).filter(|f| !f.is_empty()).collect();
+    let index = fields
+        .iter()
+        .rposition(|field| is_secret_part(field) && base32_len(field) >= MIN_SECRET_LEN)
+        .ok_or_else(no_secret)?;
+
+    let mut labels = fields;
+    let secret = labels.remove(index).to_string();
+    let labels = labels.into_iter().map(trim_label).filter(|l| !l.is_empty()).map(str::to_string).collect();
+    Ok((labels, secret))
+}
+
+/// Splits a line at spaces: the secret is one long word at the end, or a run
+/// of short groups written in the same case, such as `JBSW Y3DP EHPK 3PXP`.
+/// At least one word is left over as a label.
+fn split_words(line: &str) -> Result<(Vec<String>, String), AppError> {
+    let words: Vec<&str> = line.split_whitespace().collect();
+
+    let mut start = words.len();
+    if words.last().is_some_and(|word| is_secret_part(word) && base32_len(word) >= MIN_SECRET_LEN) {
+        start -= 1;
+    } else {
+        // Groups of digits only fit either case
+        let mut case = Case::None;
+        while start > 0 {
+            let word = words[start - 1];
+            if !is_secret_part(word) || word.len() > 8 {
+                break;
+            }
+            match (case, letter_case(word)) {
+                (_, Case::None) => {}
+                (Case::None, found) => case = found,
+                (expected, found) if expected != found => break,
+                _ => {}
+            }
+            start -= 1;
+        }
+    }
+    if start == 0 && words.len() > 1 {
+        start = 1;
+    }
+
+    let secret = words[start..].concat();
+    if base32_len(&secret) < MIN_SECRET_LEN {
+        return Err(no_secret());
+    }
+
+    let label = words[..start].join(" ");
+    let label = trim_label(&label);
+    Ok((if label.is_empty() { Vec::new() } else { vec![label.to_string()] }, secret))
+}
+
+/// Builds an account from an issuer, a name and a base32 secret, taking the
+/// digits, period and algorithm from the issuer's preset if it has one, and
+/// checks that it can generate codes
+pub fn account_from_parts(issuer: Option<&str>, name: &str, secret: &str) -> Result<Account, AppError> {
+    let issuer = issuer.map(str::trim).filter(|issuer| !issuer.is_empty());
+    let name = match name.trim() {
+        "" => issuer.ok_or_else(|| AppError::InvalidInput("An account needs a name or an issuer".to_string()))?,
+        name => name,
+    };
+    let secret = secret::normalize_base32(secret)?;
+
+    let (digits, period, algorithm) = match issuer.and_then(find_preset) {
+        Some(preset) => (preset.digits, preset.period, preset.algorithm),
+        None => (6, 30, account::Algorithm::Sha1.into()),
+    };
+    let account = Account::new(name.to_string(), secret, digits, period, algorithm, issuer.map(str::to_string));
+    account.check_settings()?;
+    Ok(account)
+}
+
+/// How the letters of a word are written
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum Case {
+    Upper,
+    Lower,
+    Mixed,
+    /// Digits only
+    None,
+}
+
+fn letter_case(word: &str) -> Case {
+    let upper = word.chars().any(|c| c.is_ascii_uppercase());
+    let lower = word.chars().any(|c| c.is_ascii_lowercase());
+    match (upper, lower) {
+        (true, true) => Case::Mixed,
+        (true, false) => Case::Upper,
+        (false, true) => Case::Lower,
+        (false, false) => Case::None,
+    }
+}
+
+/// Whether `text` could be (part of) a base32 secret: only base32 characters,
+/// spaces, dashes and trailing padding, with letters in a single case
+fn is_secret_part(text: &str) -> bool {
+    let body = text.trim_end_matches('=');
+    !body.is_empty()
+        && letter_case(body) != Case::Mixed
+        && body.chars().all(|c| matches!(c, 'A'..='Z' | 'a'..='z' | '2'..='7' | ' ' | '-'))
+}
+
+/// Number of base32 characters in `text`, ignoring spaces, dashes and padding
+fn base32_len(text: &str) -> usize {
+    text.chars().filter(|c| c.is_ascii_alphanumeric()).count()
+}
+
+/// Drops punctuation left between a label and the secret, as in "GitHub:" or
+/// "GitHub -"
+fn trim_label(label: &str) -> &str {
+    label.trim().trim_end_matches([':', '-', '=']).trim()
+}
+
+fn no_secret() -> AppError {
+    AppError::InvalidInput(format!(
+        "No secret found; it should be at least {} base32 characters (A-Z, 2-7)",
+        MIN_SECRET_LEN
+    ))
+}
@@ -0,0 +1,115 @@
+//! Vault health checks, such as accounts that share a secret or a drifting clock.
+
+use crate::account::Account;
+use crate::history::History;
+
+/// Accounts without a generated code for this long count as unused (about six months)
+pub const UNUSED_AFTER_SECS: u64 = 183 * 24 * 60 * 60;
+
+/// Groups of accounts that share the same secret, in vault order
+pub fn find_duplicate_secrets(accounts: &[Account]) -> Vec<Vec<&Account>> {
+    let mut groups: Vec<Vec<&Account>> = Vec::new();
+
+    for account in accounts {
+        match groups
+            .iter_mut()
+            .find(|group| group[0].normalized_secret() == account.normalized_secret())
+        {
+            Some(group) => group.push(account),
+            None => groups.push(vec![account]),
+        }
+    }
+
+    groups.retain(|group| group.len() > 1);
+    groups
+}
+
+/// Accounts that already use the given secret
+pub fn accounts_with_secret<'a>(accounts: &'a [Account], candidate: &Account) -> Vec<&'a Account> {
+    accounts
+        .iter()
+        .filter(|a| a.normalized_secret() == candidate.normalized_secret())
+        .collect()
+}
+
+/// Clock skew measured by checking codes from providers and other devices
+#[derive(Debug, Clone, Copy)]
+pub struct SkewStats {
+    /// Number of matched codes the statistics are based on
+    pub samples: usize,
+    /// Average offset in seconds; positive when the local clock is behind
+    pub mean_seconds: f64,
+    /// Share of samples that matched a code other than the current one
+    pub off_share: f64,
+}
+
+/// Skew statistics across every account's recorded checks, or `None` before
+/// any code was checked
+pub fn skew_stats(accounts: &[Account]) -> Option<SkewStats> {
+    let offsets: Vec<f64> = accounts
+        .iter()
+        .flat_map(|a| a.skew_history().iter().map(move |&offset| (offset * a.period() as i64) as f64))
+        .collect();
+
+    if offsets.is_empty() {
+        return None;
+    }
+
+    let samples = offsets.len();
+    Some(SkewStats {
+        samples,
+        mean_seconds: offsets.iter().sum::<f64>() / samples as f64,
+        off_share: offsets.iter().filter(|&&o| o != 0.0).count() as f64 / samples as f64,
+    })
+}
+
+/// Counts and findings for the vault overview
+#[derive(Debug)]
+pub struct Overview<'a> {
+    pub total: usize,
+    /// Accounts per issuer, most common first; `None` for accounts without one
+    pub by_issuer: Vec<(Option<&'a str>, usize)>,
+    /// Accounts per algorithm, most common first
+    pub by_algorithm: Vec<(&'static str, usize)>,
+    pub unverified: Vec<&'a Account>,
+    pub duplicates: Vec<Vec<&'a Account>>,
+    /// Accounts whose last generated code is older than [`UNUSED_AFTER_SECS`]
+    pub unused: Vec<&'a Account>,
+    /// Accounts with no recorded code at all
+    pub never_used: usize,
+}
+
+/// Summarizes the vault at Unix time `now`, using its code `history` for when
+/// accounts were last used
+pub fn overview<'a>(accounts: &'a [Account], history: &History, now: u64) -> Overview<'a> {
+    let mut by_issuer: Vec<(Option<&str>, usize)> = Vec::new();
+    let mut by_algorithm: Vec<(&'static str, usize)> = Vec::new();
+    for account in accounts {
+        let issuer = account.issuer().map(|issuer| issuer.as_str());
+        match by_issuer.iter_mut().find(|(known, _)| known.map(str::to_lowercase) == issuer.map(str::to_lowercase)) {
+            Some((_, count)) => *count += 1,
+            None => by_issuer.push((issuer, 1)),
+        }
+        match by_algorithm.iter_mut().find(|(known, _)| *known == account.algorithm_name()) {
+            Some((_, count)) => *count += 1,
+            None => by_algorithm.push((account.algorithm_name(), 1)),
+        }
+    }
+    by_issuer.sort_by_key(|(_, count)| std::cmp::Reverse(*count));
+    by_algorithm.sort_by_key(|(_, count)| std::cmp::Reverse(*count));
+
+    let last_used = |account: &Account| history.last(account.id());
+
+    Overview {
+        total: accounts.len(),
+        by_issuer,
+        by_algorithm,
+        unverified: accounts.iter().filter(|a| !a.is_verified()).collect(),
+        duplicates: find_duplicate_secrets(accounts),
+        unused: accounts
+            .iter()
+            .filter(|a| last_used(a).is_some_and(|at| now.saturating_sub(at) > UNUSED_AFTER_SECS))
+            .collect(),
+        never_used: accounts.iter().filter(|a| last_used(a).is_none()).count(),
+    }
+}
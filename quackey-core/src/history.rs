@@ -0,0 +1,120 @@
+//! When codes were generated, kept in `code-history.jsonl` next to the vault.
+//!
+//! Recording a code appends one line to this file rather than rewriting the
+//! vault, so commands that only print codes (`gen`, `statusbar`, the Alfred
+//! list) never write `accounts.json` and can't collide with a session or a
+//! sync that has it open. Each line holds an account id and a Unix time, never
+//! the code. Once the file grows past [`COMPACT_AFTER`] bytes it is rewritten
+//! with only the most recent times of each account.
+
+use std::collections::HashMap;
+use std::fs;
+use std::io::Write;
+use std::path::{Path, PathBuf};
+use serde::{Serialize, Deserialize};
+use crate::error::AppError;
+use crate::permissions;
+
+const FILENAME: &str = "code-history.jsonl";
+/// Times kept per account
+pub const HISTORY_LEN: usize = 50;
+/// Size in bytes above which the file is compacted
+const COMPACT_AFTER: u64 = 64 * 1024;
+
+/// One line of the file
+#[derive(Debug, Serialize, Deserialize)]
+struct Entry {
+    id: String,
+    at: u64,
+}
+
+/// Times codes were generated, by account id
+#[derive(Debug, Clone, Default)]
+pub struct History {
+    times: HashMap<String, Vec<u64>>,
+}
+
+impl History {
+    /// Unix times, in seconds, when codes were generated for the account with
+    /// `id`, oldest first
+    pub fn times(&self, id: &str) -> &[u64] {
+        self.times.get(id).map(Vec::as_slice).unwrap_or_default()
+    }
+
+    /// When a code was last generated for the account with `id`
+    pub fn last(&self, id: &str) -> Option<u64> {
+        self.times(id).last().copied()
+    }
+
+    fn push(&mut self, id: String, at: u64) {
+        let times = self.times.entry(id).or_default();
+        times.push(at);
+        if times.len() > HISTORY_LEN {
+            times.remove(0);
+        }
+    }
+}
+
+/// Where the code history is kept for a storage file
+pub fn path_for(file_path: &str) -> PathBuf {
+    match Path::new(file_path).parent() {
+        Some(parent) if !parent.as_os_str().is_empty() => parent.join(FILENAME),
+        _ => PathBuf::from(FILENAME),
+    }
+}
+
+/// The code history of the vault at `file_path`. A missing file is an empty
+/// history, and lines that can't be read, such as one cut short by a crash,
+/// are skipped.
+pub fn load(file_path: &str) -> History {
+    let mut history = History::default();
+    let contents = fs::read_to_string(path_for(file_path)).unwrap_or_default();
+    for entry in contents.lines().filter_map(|line| serde_json::from_str::<Entry>(line).ok()) {
+        history.push(entry.id, entry.at);
+    }
+    history
+}
+
+/// Records that a code for the account with `id`, which changes every
+/// `period` seconds, was generated at `at`. The same code generated again
+/// (such as by a status bar polling every second) counts once.
+pub fn record(file_path: &str, id: &str, period: u64, at: u64) -> Result<(), AppError> {
+    let mut history = load(file_path);
+    let period = period.max(1);
+    if history.last(id).is_some_and(|last| last / period == at / period) {
+        return Ok(());
+    }
+
+    let path = path_for(file_path);
+    let line = serde_json::to_string(&Entry { id: id.to_string(), at })
+        .map_err(|e| AppError::JsonError(format!("Failed to serialize code history: {}", e)))?;
+    let write_error = |e: std::io::Error| AppError::FileError(format!("Failed to write code history: {}", e));
+
+    if fs::metadata(&path).is_ok_and(|metadata| metadata.len() > COMPACT_AFTER) {
+        history.push(id.to_string(), at);
+        return permissions::replace(&path, compact(&history).as_bytes()).map_err(write_error);
+    }
+
+    let created = !path.exists();
+    let mut file = permissions::open_options().append(true).create(true).open(&path).map_err(write_error)?;
+    if created && cfg!(not(unix)) {
+        permissions::restrict(&path).map_err(write_error)?;
+    }
+    // One write per line, so lines appended by several processes don't mix
+    file.write_all(format!("{}\n", line).as_bytes()).map_err(write_error)
+}
+
+/// The file's contents with only the times kept in `history`, oldest first
+fn compact(history: &History) -> String {
+    let mut entries: Vec<Entry> = history
+        .times
+        .iter()
+        .flat_map(|(id, times)| times.iter().map(|&at| Entry { id: id.clone(), at }))
+        .collect();
+    entries.sort_by_key(|entry| entry.at);
+    entries
+        .iter()
+        .filter_map(|entry| serde_json::to_string(entry).ok())
+        .map(|line| line + "\n")
+        .collect()
+}
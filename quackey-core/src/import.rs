@@ -0,0 +1,172 @@
+//! Validation and preview shared by every importer before accounts are saved.
+
+use crate::account::Account;
+use crate::error::AppError;
+use crate::health;
+
+/// Result of parsing one entry of an import file
+pub struct ImportLine {
+    /// 1-based line (or entry) number in the source file
+    pub line: usize,
+    pub result: Result<Account, AppError>,
+}
+
+/// Rejects parsed accounts whose secret is already in the vault (or earlier in
+/// the same file), so nothing is imported twice whatever the source format
+pub fn check_duplicates(lines: Vec<ImportLine>, existing: &[Account]) -> Vec<ImportLine> {
+    let mut accepted: Vec<Account> = Vec::new();
+
+    lines
+        .into_iter()
+        .map(|entry| {
+            let result = entry.result.and_then(|account| {
+                if let Some(duplicate) = health::accounts_with_secret(existing, &account).first() {
+                    return Err(AppError::InvalidInput(format!(
+                        "same secret as existing account '{}'",
+                        duplicate.name()
+                    )));
+                }
+
+                if let Some(duplicate) = health::accounts_with_secret(&accepted, &account).first() {
+                    return Err(AppError::InvalidInput(format!(
+                        "same secret as '{}' earlier in the file",
+                        duplicate.name()
+                    )));
+                }
+
+                accepted.push(account.clone());
+                Ok(account)
+            });
+
+            ImportLine { line: entry.line, result }
+        })
+        .collect()
+}
+
+/// How an entry relates to the accounts already in the vault
+#[derive(Debug)]
+pub enum EntryStatus {
+    /// Not in the vault yet
+    New,
+    /// An exact copy of an existing account, or of an earlier entry in the file
+    Duplicate(String),
+    /// Shares its secret or `issuer:name` with an existing account but differs
+    /// from it; the index is that account's position in the vault
+    Conflict(usize),
+    /// The entry could not be parsed
+    Invalid(AppError),
+}
+
+/// What to do with an entry when the import is applied
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Resolution {
+    Skip,
+    /// Add it as a new account
+    Add,
+    /// Overwrite the conflicting account, keeping its id and recovery codes
+    Replace,
+}
+
+/// One row of an import preview
+#[derive(Debug)]
+pub struct PreviewEntry {
+    /// 1-based line (or entry) number in the source file
+    pub line: usize,
+    pub account: Option<Account>,
+    pub status: EntryStatus,
+    pub resolution: Resolution,
+}
+
+impl PreviewEntry {
+    /// Resolutions that make sense for this entry, the default first
+    pub fn choices(&self) -> &'static [Resolution] {
+        match self.status {
+            EntryStatus::New => &[Resolution::Add, Resolution::Skip],
+            EntryStatus::Duplicate(_) => &[Resolution::Skip, Resolution::Add],
+            EntryStatus::Conflict(_) => &[Resolution::Skip, Resolution::Replace, Resolution::Add],
+            EntryStatus::Invalid(_) => &[Resolution::Skip],
+        }
+    }
+}
+
+/// Classifies parsed entries against the vault without changing anything.
+/// New entries default to being added; duplicates, conflicts and invalid
+/// entries default to being skipped.
+pub fn preview(lines: Vec<ImportLine>, existing: &[Account]) -> Vec<PreviewEntry> {
+    let mut accepted: Vec<Account> = Vec::new();
+
+    lines
+        .into_iter()
+        .map(|entry| {
+            let (account, status) = match entry.result {
+                Err(e) => (None, EntryStatus::Invalid(e)),
+                Ok(account) => {
+                    let status = classify(&account, existing, &accepted);
+                    if matches!(status, EntryStatus::New | EntryStatus::Conflict(_)) {
+                        accepted.push(account.clone());
+                    }
+                    (Some(account), status)
+                }
+            };
+
+            let mut entry = PreviewEntry {
+                line: entry.line,
+                account,
+                status,
+                resolution: Resolution::Skip,
+            };
+            entry.resolution = entry.choices()[0];
+            entry
+        })
+        .collect()
+}
+
+fn classify(account: &Account, existing: &[Account], earlier: &[Account]) -> EntryStatus {
+    let same_label = |other: &Account| {
+        other.name().eq_ignore_ascii_case(account.name()) && other.issuer() == account.issuer()
+    };
+
+    if let Some(index) = existing.iter().position(|a| a.normalized_secret() == account.normalized_secret()) {
+        return if same_label(&existing[index]) && same_settings(&existing[index], account) {
+            EntryStatus::Duplicate(format!("already in the vault as '{}'", existing[index].name()))
+        } else {
+            EntryStatus::Conflict(index)
+        };
+    }
+
+    if let Some(duplicate) = health::accounts_with_secret(earlier, account).first() {
+        return EntryStatus::Duplicate(format!("same secret as '{}' earlier in the file", duplicate.name()));
+    }
+
+    match existing.iter().position(same_label) {
+        Some(index) => EntryStatus::Conflict(index),
+        None => EntryStatus::New,
+    }
+}
+
+fn same_settings(a: &Account, b: &Account) -> bool {
+    a.digits() == b.digits() && a.period() == b.period() && a.algorithm_name() == b.algorithm_name()
+}
+
+/// The vault's accounts after applying each entry's resolution
+pub fn apply(entries: Vec<PreviewEntry>, existing: &[Account]) -> Vec<Account> {
+    let mut accounts = existing.to_vec();
+
+    for entry in entries {
+        let Some(account) = entry.account else { continue };
+
+        match (entry.resolution, entry.status) {
+            (Resolution::Replace, EntryStatus::Conflict(index)) => {
+                let replaced = &accounts[index];
+                let mut account = account.with_id(replaced.id().to_string());
+                account.set_recovery_codes(replaced.recovery_codes().cloned());
+                account.set_created_at(replaced.created_at());
+                accounts[index] = account;
+            }
+            (Resolution::Add, _) => accounts.push(account),
+            _ => {}
+        }
+    }
+
+    accounts
+}
@@ -0,0 +1,187 @@
+//! Startup self-check: storage checksum, file permissions and whether the
+//! previous session ended cleanly.
+//!
+//! Every save records a SHA-256 checksum of the storage file next to it
+//! (`.accounts.sha256`). While an interactive session runs, a marker file
+//! (`.quackey-session`) exists; finding it at startup means the last session
+//! crashed or was killed. The marker holds the session's process id, so a
+//! marker whose process is still alive means another session is open (see
+//! [`running_instance`]).
+
+use std::fs;
+use std::path::{Path, PathBuf};
+use chrono::Local;
+use sha2::{Digest, Sha256};
+use crate::crypto::to_hex;
+use crate::error::AppError;
+
+const CHECKSUM_FILENAME: &str = ".accounts.sha256";
+const SESSION_FILENAME: &str = ".quackey-session";
+
+/// How the storage file compares to the checksum recorded at the last save
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ChecksumStatus {
+    Match,
+    /// The file changed since Quackey last saved it
+    Mismatch,
+    /// No checksum recorded yet, or no storage file
+    Unknown,
+}
+
+/// Findings of [`check`]
+#[derive(Debug, Clone)]
+pub struct IntegrityReport {
+    pub checksum: ChecksumStatus,
+    /// Set when the storage file can be read by other users
+    pub permission_problem: Option<String>,
+    /// Whether the previous interactive session ended normally
+    pub clean_shutdown: bool,
+}
+
+impl IntegrityReport {
+    pub fn is_ok(&self) -> bool {
+        self.checksum != ChecksumStatus::Mismatch && self.permission_problem.is_none() && self.clean_shutdown
+    }
+
+    /// One-line summary for the audit log
+    pub fn summary(&self) -> String {
+        let checksum = match self.checksum {
+            ChecksumStatus::Match => "checksum ok",
+            ChecksumStatus::Mismatch => "checksum MISMATCH",
+            ChecksumStatus::Unknown => "no checksum recorded",
+        };
+        let permissions = match &self.permission_problem {
+            Some(problem) => problem.as_str(),
+            None => "permissions ok",
+        };
+        let shutdown = if self.clean_shutdown {
+            "previous session ended cleanly"
+        } else {
+            "previous session did NOT end cleanly"
+        };
+        format!("{}, {}, {}", checksum, permissions, shutdown)
+    }
+}
+
+/// Checks the storage file and starts a session by writing the session
+/// marker. Call [`end_session`] on a normal exit.
+pub fn check(file_path: &str) -> Result<IntegrityReport, AppError> {
+    let session = sidecar_path(file_path, SESSION_FILENAME);
+    // A marker left by a session that is still open is not a crash
+    let clean_shutdown = !session.exists() || running_instance(file_path).is_some();
+
+    let report = IntegrityReport {
+        checksum: checksum_status(file_path),
+        permission_problem: permission_problem(file_path),
+        clean_shutdown,
+    };
+
+    if let Some(parent) = session.parent().filter(|p| !p.as_os_str().is_empty()) {
+        fs::create_dir_all(parent)
+            .map_err(|e| AppError::FileError(format!("Failed to create storage directory: {}", e)))?;
+    }
+    match fs::write(&session, format!("{} {}\n", std::process::id(), Local::now().to_rfc3339())) {
+        Ok(()) => {}
+        // Nothing can be saved on a read-only mount, so there is nothing to guard
+        Err(e) if e.kind() == std::io::ErrorKind::ReadOnlyFilesystem => {}
+        Err(e) => return Err(AppError::FileError(format!("Failed to write session marker: {}", e))),
+    }
+
+    Ok(report)
+}
+
+/// Process id of another interactive session open on the vault at
+/// `file_path`, if one is still running
+pub fn running_instance(file_path: &str) -> Option<u32> {
+    let marker = fs::read_to_string(sidecar_path(file_path, SESSION_FILENAME)).ok()?;
+    let pid: u32 = marker.split_whitespace().next()?.parse().ok()?;
+    (pid != std::process::id() && is_running(pid)).then_some(pid)
+}
+
+/// Removes the session marker, recording a clean shutdown
+pub fn end_session(file_path: &str) -> Result<(), AppError> {
+    match fs::remove_file(sidecar_path(file_path, SESSION_FILENAME)) {
+        Ok(()) => Ok(()),
+        Err(e) if e.kind() == std::io::ErrorKind::NotFound => Ok(()),
+        // Left by a session from before the file system became read-only
+        Err(e) if e.kind() == std::io::ErrorKind::ReadOnlyFilesystem => Ok(()),
+        Err(e) => Err(AppError::FileError(format!("Failed to remove session marker: {}", e))),
+    }
+}
+
+/// Records the checksum of the storage file's current contents
+pub fn record_checksum(file_path: &str) -> Result<(), AppError> {
+    let contents = fs::read(file_path)
+        .map_err(|e| AppError::FileError(format!("Failed to read storage file: {}", e)))?;
+    fs::write(checksum_path(file_path), checksum(&contents))
+        .map_err(|e| AppError::FileError(format!("Failed to write storage checksum: {}", e)))
+}
+
+/// Where the checksum of the storage file at `file_path` is recorded
+pub(crate) fn checksum_path(file_path: &str) -> PathBuf {
+    sidecar_path(file_path, CHECKSUM_FILENAME)
+}
+
+fn checksum_status(file_path: &str) -> ChecksumStatus {
+    let (Ok(recorded), Ok(contents)) = (
+        fs::read_to_string(checksum_path(file_path)),
+        fs::read(file_path),
+    ) else {
+        return ChecksumStatus::Unknown;
+    };
+
+    if recorded.trim() == checksum(&contents) {
+        ChecksumStatus::Match
+    } else {
+        ChecksumStatus::Mismatch
+    }
+}
+
+fn checksum(contents: &[u8]) -> String {
+    to_hex(&Sha256::digest(contents))
+}
+
+#[cfg(unix)]
+fn permission_problem(file_path: &str) -> Option<String> {
+    use std::os::unix::fs::PermissionsExt;
+
+    let mode = fs::metadata(file_path).ok()?.permissions().mode();
+    (mode & 0o077 != 0).then(|| format!("storage readable by other users (mode {:o})", mode & 0o777))
+}
+
+#[cfg(not(unix))]
+fn permission_problem(_file_path: &str) -> Option<String> {
+    None
+}
+
+#[cfg(unix)]
+fn is_running(pid: u32) -> bool {
+    let Ok(pid) = libc::pid_t::try_from(pid) else {
+        return false;
+    };
+    // Signal 0 only checks that the process exists; EPERM means it does but
+    // belongs to another user
+    let alive = unsafe { libc::kill(pid, 0) } == 0;
+    alive || std::io::Error::last_os_error().raw_os_error() == Some(libc::EPERM)
+}
+
+#[cfg(windows)]
+fn is_running(pid: u32) -> bool {
+    std::process::Command::new("tasklist")
+        .args(["/FI", &format!("PID eq {}", pid), "/NH"])
+        .output()
+        .is_ok_and(|output| String::from_utf8_lossy(&output.stdout).contains(&pid.to_string()))
+}
+
+#[cfg(not(any(unix, windows)))]
+fn is_running(_pid: u32) -> bool {
+    false
+}
+
+/// A file kept next to the storage file
+fn sidecar_path(file_path: &str, name: &str) -> PathBuf {
+    match Path::new(file_path).parent() {
+        Some(parent) if !parent.as_os_str().is_empty() => parent.join(name),
+        _ => PathBuf::from(name),
+    }
+}
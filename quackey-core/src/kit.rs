@@ -0,0 +1,141 @@
+//! Emergency kit: a printable page with what is needed to get back into a
+//! vault after losing the computer it lived on.
+//!
+//! The kit is plain text for printing in a monospace font. It names the
+//! vault, its protection and the fingerprint of its key (see
+//! [`VaultKey::fingerprint`](crate::crypto::VaultKey::fingerprint)), leaves a
+//! blank line to write the master password on by hand, and explains how to
+//! restore. It can also carry every account as a QR code of an encrypted
+//! backup (see [`crate::backup`]), so the paper alone is enough to recover.
+
+use chrono::{DateTime, Local};
+use crate::error::AppError;
+use crate::qr::{EccLevel, QrCode};
+
+/// Modules of white space around a printed QR code
+const QUIET_ZONE: i32 = 4;
+
+/// What goes on the kit
+pub struct Kit<'a> {
+    /// Path of `accounts.json`
+    pub vault_path: &'a str,
+    /// How the vault is protected: `none`, `password` or `windows`
+    pub encryption: &'a str,
+    pub accounts: usize,
+    /// Fingerprint of the vault key, for encrypted vaults
+    pub fingerprint: Option<String>,
+    /// QR code of an encrypted backup of all accounts, when one was asked for
+    pub backup: Option<QrCode>,
+}
+
+/// Encodes an armored backup as a QR code, failing when it holds more than a
+/// QR code can carry
+pub fn backup_code(armored: &str) -> Result<QrCode, AppError> {
+    QrCode::encode(armored.as_bytes(), EccLevel::Medium).map_err(|_| {
+        AppError::InvalidInput("The vault has too many accounts to fit in a QR code".to_string())
+            .with_hint("Create the kit without a QR code and keep an encrypted export with it instead")
+    })
+}
+
+/// Renders the kit as plain text
+pub fn render(kit: &Kit, created: DateTime<Local>) -> String {
+    let mut page = String::new();
+    let mut line = |text: &str| {
+        page.push_str(text);
+        page.push('\n');
+    };
+
+    line("QUACKEY EMERGENCY KIT");
+    line("=====================");
+    line(&format!("Created {}", created.format("%Y-%m-%d %H:%M")));
+    line("");
+    line("Print this page and keep it somewhere safe, such as with your passport.");
+    line("Anyone holding it and your master password can read your accounts.");
+    line("");
+
+    line("YOUR VAULT");
+    line(&format!("  Location:          {}", kit.vault_path));
+    line(&format!("  Accounts:          {}", kit.accounts));
+    line(&format!("  Protection:        {}", protection_label(kit.encryption)));
+    if let Some(fingerprint) = &kit.fingerprint {
+        line(&format!("  Key fingerprint:   {}", fingerprint));
+    }
+    if kit.encryption == "password" {
+        line("");
+        line("  Master password:   ________________________________________");
+    }
+    line("");
+
+    if let Some(code) = &kit.backup {
+        line("ENCRYPTED BACKUP");
+        line("  Every account, encrypted with a backup passphrase of its own.");
+        line("");
+        for row in qr_rows(code) {
+            line(&format!("  {}", row.trim_end()));
+        }
+        line("");
+        line("  Backup passphrase: ________________________________________");
+        line("");
+    }
+
+    line("HOW TO RECOVER");
+    line("  1. Install Quackey on the new computer.");
+    line("  2. Copy accounts.json from a backup, a synced folder or the old disk");
+    line("     into the folder named above, or set storage.path to where it is.");
+    match kit.encryption {
+        "password" => {
+            line("  3. Start quackey and enter the master password.");
+            line("  4. Create a new kit: if its key fingerprint differs from the one above,");
+            line("     this kit belongs to an older master password.");
+        }
+        "windows" => {
+            line("  3. Start quackey as the same Windows user. A vault protected by Windows");
+            line("     opens for that user only, so keep the backup below or an encrypted");
+            line("     export (quackey export --encrypt) in case the account is lost.");
+        }
+        _ => {
+            line("  3. Start quackey. The vault is not encrypted, so anyone with the file");
+            line("     can read it; set a master password in the settings.");
+        }
+    }
+    if kit.backup.is_some() {
+        line("");
+        line("  Without the vault file: scan the QR code above, save the text it holds");
+        line("  as backup.txt and run");
+        line("      quackey import --format uris backup.txt");
+        line("  then enter the backup passphrase.");
+    }
+
+    page
+}
+
+fn protection_label(encryption: &str) -> &'static str {
+    match encryption {
+        "password" => "Master password",
+        "windows" => "Windows account (DPAPI)",
+        _ => "None (plain text)",
+    }
+}
+
+/// Rows of a QR code drawn with half blocks, two modules per line, for
+/// printing dark on light paper
+fn qr_rows(code: &QrCode) -> Vec<String> {
+    let size = code.size() as i32;
+    let mut rows = Vec::new();
+
+    let mut y = -QUIET_ZONE;
+    while y < size + QUIET_ZONE {
+        let row = (-QUIET_ZONE..size + QUIET_ZONE)
+            .map(|x| match (code.module(x, y), code.module(x, y + 1)) {
+                (true, true) => '█',
+                (true, false) => '▀',
+                (false, true) => '▄',
+                (false, false) => ' ',
+            })
+            .collect();
+        rows.push(row);
+        y += 2;
+    }
+
+    rows
+}
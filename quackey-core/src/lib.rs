@@ -0,0 +1,76 @@
+//! Vault, account and code generation logic behind the Quackey TOTP app.
+//!
+//! The `quackey` binary is a terminal front end over this crate; GUIs, bots and
+//! tests can use the same modules to read and change a vault. Most tools only
+//! need [`vault::Vault`] and [`vault::AccountBuilder`], the stable API covered
+//! by semver:
+//!
+//! ```no_run
+//! use quackey_core::vault::{AccountBuilder, Vault};
+//!
+//! # fn main() -> Result<(), quackey_core::error::AppError> {
+//! let mut vault = Vault::open_default()?;
+//! vault.add(AccountBuilder::new("alice@example.com", "JBSWY3DPEHPK3PXP").issuer("GitHub"))?;
+//! println!("{}", vault.code("github")?);
+//! # Ok(())
+//! # }
+//! ```
+//!
+//! The lower-level modules are public too, for tools that need more control:
+//!
+//! - [`config`] locates the vault and stores user settings
+//! - [`storage`] loads and saves `accounts.json`
+//! - [`events`] describes what happened, for the log and other sinks
+//! - [`account`] holds an account's settings and generates its codes
+//! - [`formats`] and [`import`] read and write other file formats
+//!
+//! Every failure is reported as an [`error::AppError`].
+//!
+//! ```no_run
+//! use quackey_core::config::Config;
+//! use quackey_core::storage::Storage;
+//!
+//! # fn main() -> Result<(), quackey_core::error::AppError> {
+//! let config = Config::load()?;
+//! let storage = Storage::new_with_events(&config.get_storage_file_path(), Default::default())?;
+//! for account in storage.get_accounts() {
+//!     println!("{}: {}", account.name(), account.generate_totp()?);
+//! }
+//! # Ok(())
+//! # }
+//! ```
+
+pub mod account;
+pub mod backup;
+pub mod config;
+pub mod crypto;
+pub mod dpapi;
+pub mod env;
+pub mod error;
+pub mod events;
+pub mod formats;
+pub mod health;
+pub mod history;
+pub mod import;
+pub mod integrity;
+pub mod kit;
+pub mod logger;
+pub mod memory;
+pub mod merge;
+pub mod migration;
+pub mod permissions;
+pub mod pin;
+pub mod pinentry;
+pub mod presets;
+pub mod qr;
+pub mod recovery;
+pub mod repair;
+pub mod scan;
+pub mod secret;
+pub mod share;
+pub mod storage;
+pub mod strength;
+pub mod sync;
+pub mod template;
+pub mod vault;
+pub mod wipe;
@@ -0,0 +1,265 @@
+//! Append-only activity log written to `totp_app.log`.
+
+use std::io::{Read, Write, Seek, SeekFrom};
+use chrono::{Local, NaiveDate, NaiveDateTime};
+use crate::error::AppError;
+use crate::permissions;
+
+/// Logger for application events
+#[derive(Clone)]
+pub struct Logger {
+    file_path: String,
+}
+
+impl Logger {
+    /// Creates a logger for `file_path`. Nothing touches the disk until the
+    /// first message is written, so opening one is free.
+    pub fn new(file_path: &str) -> Result<Self, AppError> {
+        Ok(Self {
+            file_path: file_path.to_string(),
+        })
+    }
+
+    /// Writes to `new_path` from now on, creating its folder. The entries so
+    /// far are moved there, ahead of any the file already has, or with
+    /// `keep_old` left where they are behind an entry pointing to the new log.
+    /// Either way the new log starts with an entry saying where it came from.
+    pub fn relocate(&mut self, new_path: &str, keep_old: bool) -> Result<(), AppError> {
+        // Moving the log onto itself would delete it
+        if crate::storage::same_file(&self.file_path, new_path) {
+            self.file_path = new_path.to_string();
+            return Ok(());
+        }
+
+        if let Some(parent) = std::path::Path::new(new_path).parent()
+            && !parent.as_os_str().is_empty()
+        {
+            std::fs::create_dir_all(parent)
+                .map_err(|e| AppError::FileError(format!("Failed to create log directory: {}", e)))?;
+        }
+
+        let old_path = self.file_path.clone();
+        if keep_old {
+            self.info(&format!("Log continues in '{}'", new_path))?;
+        } else {
+            // Both are newest first, and the old entries are the newer ones
+            let moved = read_if_exists(&old_path)?;
+            let existing = read_if_exists(new_path)?;
+            permissions::write(std::path::Path::new(new_path), format!("{}{}", moved, existing).as_bytes())
+                .map_err(|e| AppError::FileError(format!("Failed to write log file: {}", e)))?;
+            match std::fs::remove_file(&old_path) {
+                Ok(()) => {}
+                Err(e) if e.kind() == std::io::ErrorKind::NotFound => {}
+                Err(e) => return Err(AppError::FileError(format!("Failed to remove old log file: {}", e))),
+            }
+        }
+
+        self.file_path = new_path.to_string();
+        if keep_old {
+            self.info(&format!("Log started; earlier entries are in '{}'", old_path))
+        } else {
+            self.info(&format!("Log moved here from '{}'", old_path))
+        }
+    }
+
+    pub fn info(&self, message: &str) -> Result<(), AppError> {
+        self.write(Level::Info, message)
+    }
+
+    /// Records a security-relevant event, such as the startup integrity check
+    pub fn audit(&self, message: &str) -> Result<(), AppError> {
+        self.write(Level::Audit, message)
+    }
+
+    pub fn warn(&self, message: &str) -> Result<(), AppError> {
+        self.write(Level::Warn, message)
+    }
+
+    pub fn error(&self, message: &str) -> Result<(), AppError> {
+        self.write(Level::Error, message)
+    }
+
+    /// Adds `message` at the top of the log as an entry at `level`
+    pub fn write(&self, level: Level, message: &str) -> Result<(), AppError> {
+        let now = Local::now();
+        let timestamp = now.format("%Y-%m-%d %H:%M:%S").to_string();
+        let log_line = format!("[{}] [{}] {}\n", timestamp, level.name(), message);
+
+        // Ensure the directory exists
+        if let Some(parent) = std::path::Path::new(&self.file_path).parent()
+            && !parent.as_os_str().is_empty()
+            && !parent.exists()
+        {
+            std::fs::create_dir_all(parent)
+                .map_err(|e| AppError::FileError(format!("Failed to create directory: {}", e)))?;
+        }
+
+        // Open the file for reading and writing; a new log is private like the vault
+        let created = !std::path::Path::new(&self.file_path).exists();
+        let file = permissions::open_options()
+            .create(true)
+            .truncate(false)
+            .read(true)
+            .write(true)
+            .open(&self.file_path);
+        let mut file = match file {
+            Ok(file) => file,
+            // A vault on a read-only mount can still be used, just not logged
+            Err(e) if e.kind() == std::io::ErrorKind::ReadOnlyFilesystem => return Ok(()),
+            Err(e) => return Err(AppError::FileError(format!("Failed to open log file: {}", e))),
+        };
+        if created && cfg!(not(unix)) {
+            permissions::restrict(std::path::Path::new(&self.file_path))
+                .map_err(|e| AppError::FileError(format!("Failed to restrict log file: {}", e)))?;
+        }
+        
+        // Read the existing content
+        let mut content = String::new();
+        file.read_to_string(&mut content)
+            .map_err(|e| AppError::FileError(format!("Failed to read log file: {}", e)))?;
+        
+        // A new log starts with a header line at the bottom
+        if content.is_empty() {
+            content = format!("[{}] [INFO] Log file opened\n", timestamp);
+        }
+
+        // Create new content with the new log line at the top
+        let new_content = format!("{}{}", log_line, content);
+        
+        // Clear the file and write the new content
+        file.set_len(0)
+            .map_err(|e| AppError::FileError(format!("Failed to clear log file: {}", e)))?;
+        
+        file.seek(SeekFrom::Start(0))
+            .map_err(|e| AppError::FileError(format!("Failed to seek to beginning of log file: {}", e)))?;
+        
+        file.write_all(new_content.as_bytes())
+            .map_err(|e| AppError::FileError(format!("Failed to write to log file: {}", e)))?;
+
+        Ok(())
+    }
+
+    pub fn file_path(&self) -> &str {
+        &self.file_path
+    }
+}
+
+/// Contents of the log at `file_path`, empty if there is none
+fn read_if_exists(file_path: &str) -> Result<String, AppError> {
+    match std::fs::read_to_string(file_path) {
+        Ok(contents) => Ok(contents),
+        Err(e) if e.kind() == std::io::ErrorKind::NotFound => Ok(String::new()),
+        Err(e) => Err(AppError::FileError(format!("Failed to read log file: {}", e))),
+    }
+}
+
+/// Severity of a log entry, in increasing order
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord)]
+pub enum Level {
+    Info,
+    Audit,
+    Warn,
+    Error,
+}
+
+impl Level {
+    /// Parses a level name such as `warn` or `ERROR`
+    pub fn parse(name: &str) -> Result<Self, AppError> {
+        match name.to_ascii_uppercase().as_str() {
+            "INFO" => Ok(Level::Info),
+            "AUDIT" => Ok(Level::Audit),
+            "WARN" | "WARNING" => Ok(Level::Warn),
+            "ERROR" => Ok(Level::Error),
+            other => Err(AppError::InvalidInput(format!(
+                "Unknown log level '{}' (use info, audit, warn or error)",
+                other
+            ))),
+        }
+    }
+
+    pub fn name(&self) -> &'static str {
+        match self {
+            Level::Info => "INFO",
+            Level::Audit => "AUDIT",
+            Level::Warn => "WARN",
+            Level::Error => "ERROR",
+        }
+    }
+}
+
+/// One line of the log
+#[derive(Debug, Clone)]
+pub struct LogEntry {
+    pub timestamp: NaiveDateTime,
+    pub level: Level,
+    pub message: String,
+}
+
+impl LogEntry {
+    /// Parses a `[YYYY-MM-DD HH:MM:SS] [LEVEL] message` line
+    fn parse(line: &str) -> Option<Self> {
+        let rest = line.strip_prefix('[')?;
+        let (timestamp, rest) = rest.split_once("] [")?;
+        let (level, message) = rest.split_once("] ")?;
+
+        Some(Self {
+            timestamp: NaiveDateTime::parse_from_str(timestamp, "%Y-%m-%d %H:%M:%S").ok()?,
+            level: Level::parse(level).ok()?,
+            message: message.to_string(),
+        })
+    }
+}
+
+/// Which log entries to return from [`search`]
+#[derive(Debug, Clone, Default)]
+pub struct LogQuery {
+    /// Text the message must contain, ignoring case
+    pub pattern: Option<String>,
+    /// Least severe level to include
+    pub min_level: Option<Level>,
+    /// Earliest time to include
+    pub since: Option<NaiveDateTime>,
+}
+
+/// Parses a `--since` value: a date (`2024-01-01`) or a date and time
+/// (`2024-01-01 13:00` or `2024-01-01T13:00:00`)
+pub fn parse_since(value: &str) -> Result<NaiveDateTime, AppError> {
+    let value = value.trim();
+    for format in ["%Y-%m-%d %H:%M:%S", "%Y-%m-%dT%H:%M:%S", "%Y-%m-%d %H:%M", "%Y-%m-%dT%H:%M"] {
+        if let Ok(time) = NaiveDateTime::parse_from_str(value, format) {
+            return Ok(time);
+        }
+    }
+
+    NaiveDate::parse_from_str(value, "%Y-%m-%d")
+        .ok()
+        .and_then(|date| date.and_hms_opt(0, 0, 0))
+        .ok_or_else(|| AppError::InvalidInput(format!("Invalid date '{}' (use YYYY-MM-DD)", value)))
+}
+
+/// Entries of the log at `file_path` matching `query`, oldest first. Lines
+/// that aren't log entries are skipped; a missing log has no entries.
+pub fn search(file_path: &str, query: &LogQuery) -> Result<Vec<LogEntry>, AppError> {
+    let contents = match std::fs::read_to_string(file_path) {
+        Ok(contents) => contents,
+        Err(e) if e.kind() == std::io::ErrorKind::NotFound => return Ok(Vec::new()),
+        Err(e) => return Err(AppError::FileError(format!("Failed to read log file: {}", e))),
+    };
+    let pattern = query.pattern.as_ref().map(|p| p.to_lowercase());
+
+    // The file is newest first
+    let mut entries: Vec<LogEntry> = contents
+        .lines()
+        .rev()
+        .filter_map(LogEntry::parse)
+        .filter(|entry| query.min_level.is_none_or(|level| entry.level >= level))
+        .filter(|entry| query.since.is_none_or(|since| entry.timestamp >= since))
+        .filter(|entry| {
+            pattern.as_ref().is_none_or(|p| entry.message.to_lowercase().contains(p))
+        })
+        .collect();
+
+    // Lines logged within the same second keep their order
+    entries.sort_by_key(|entry| entry.timestamp);
+    Ok(entries)
+}
@@ -0,0 +1,182 @@
+//! Keeping decrypted secrets out of swap and crash dumps.
+//!
+//! [`SecretBytes`] holds decrypted data in pages of its own that are locked
+//! (`mlock`/`VirtualLock`) before anything is written to them, so the system
+//! never pages it out, and wipes it when dropped. As no other data shares
+//! those pages, unlocking them on drop never unlocks another secret. Both are
+//! best effort: locking can fail when the process hits its locked-memory
+//! limit, and copies made by parsing (such as the account secrets
+//! deserialized from a decrypted vault) are ordinary memory.
+
+use std::alloc::{self, Layout};
+use std::fmt;
+use std::ops::{Deref, DerefMut};
+use std::ptr::NonNull;
+use crate::error::AppError;
+
+/// Bytes kept in locked memory and zeroed on drop
+pub struct SecretBytes {
+    ptr: NonNull<u8>,
+    len: usize,
+    layout: Layout,
+}
+
+// The pages are owned by this value alone, as a Vec owns its buffer
+unsafe impl Send for SecretBytes {}
+unsafe impl Sync for SecretBytes {}
+
+impl SecretBytes {
+    /// `len` zero bytes in freshly locked pages, for writing a secret straight
+    /// into locked memory
+    pub fn zeroed(len: usize) -> Self {
+        let page = page_size();
+        let size = len.max(1).div_ceil(page) * page;
+        let layout = Layout::from_size_align(size, page).expect("page size is a power of two");
+        let ptr = NonNull::new(unsafe { alloc::alloc_zeroed(layout) })
+            .unwrap_or_else(|| alloc::handle_alloc_error(layout));
+        lock(ptr.as_ptr(), size);
+        Self { ptr, len, layout }
+    }
+
+    /// Moves `bytes` into locked memory and wipes the buffer they came from.
+    /// That buffer wasn't locked, so prefer [`zeroed`](Self::zeroed) where the
+    /// secret can be written in place.
+    pub fn new(mut bytes: Vec<u8>) -> Self {
+        let mut secret = Self::zeroed(bytes.len());
+        secret.copy_from_slice(&bytes);
+        wipe(&mut bytes);
+        secret
+    }
+}
+
+impl Deref for SecretBytes {
+    type Target = [u8];
+
+    fn deref(&self) -> &[u8] {
+        unsafe { std::slice::from_raw_parts(self.ptr.as_ptr(), self.len) }
+    }
+}
+
+impl DerefMut for SecretBytes {
+    fn deref_mut(&mut self) -> &mut [u8] {
+        unsafe { std::slice::from_raw_parts_mut(self.ptr.as_ptr(), self.len) }
+    }
+}
+
+impl Clone for SecretBytes {
+    fn clone(&self) -> Self {
+        let mut secret = Self::zeroed(self.len);
+        secret.copy_from_slice(self);
+        secret
+    }
+}
+
+impl Drop for SecretBytes {
+    fn drop(&mut self) {
+        wipe(self);
+        unlock(self.ptr.as_ptr(), self.layout.size());
+        unsafe { alloc::dealloc(self.ptr.as_ptr(), self.layout) };
+    }
+}
+
+impl fmt::Debug for SecretBytes {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "SecretBytes({} bytes)", self.len)
+    }
+}
+
+/// Zeroes `bytes` in a way the compiler can't skip as a dead store
+fn wipe(bytes: &mut [u8]) {
+    for byte in bytes.iter_mut() {
+        unsafe { std::ptr::write_volatile(byte, 0) };
+    }
+    std::sync::atomic::compiler_fence(std::sync::atomic::Ordering::SeqCst);
+}
+
+/// Stops the process from writing core dumps (and, on Linux, from being
+/// attached to by debuggers running as the same user). Call once at startup.
+#[cfg(unix)]
+pub fn disable_core_dumps() -> Result<(), AppError> {
+    let limit = libc::rlimit { rlim_cur: 0, rlim_max: 0 };
+    if unsafe { libc::setrlimit(libc::RLIMIT_CORE, &limit) } != 0 {
+        return Err(AppError::PermissionError(format!(
+            "Failed to disable core dumps: {}",
+            std::io::Error::last_os_error()
+        )));
+    }
+
+    #[cfg(any(target_os = "linux", target_os = "android"))]
+    if unsafe { libc::prctl(libc::PR_SET_DUMPABLE, 0, 0, 0, 0) } != 0 {
+        return Err(AppError::PermissionError(format!(
+            "Failed to mark the process as not dumpable: {}",
+            std::io::Error::last_os_error()
+        )));
+    }
+
+    Ok(())
+}
+
+/// Keeps Windows Error Reporting from showing a crash dialog (and collecting a
+/// dump) for this process. Call once at startup.
+#[cfg(windows)]
+pub fn disable_core_dumps() -> Result<(), AppError> {
+    use windows_sys::Win32::System::Diagnostics::Debug::{
+        SetErrorMode, SEM_FAILCRITICALERRORS, SEM_NOGPFAULTERRORBOX,
+    };
+
+    unsafe { SetErrorMode(SEM_FAILCRITICALERRORS | SEM_NOGPFAULTERRORBOX) };
+    Ok(())
+}
+
+#[cfg(not(any(unix, windows)))]
+pub fn disable_core_dumps() -> Result<(), AppError> {
+    Ok(())
+}
+
+#[cfg(unix)]
+fn page_size() -> usize {
+    match unsafe { libc::sysconf(libc::_SC_PAGESIZE) } {
+        size if size > 0 => size as usize,
+        _ => 4096,
+    }
+}
+
+#[cfg(windows)]
+fn page_size() -> usize {
+    use windows_sys::Win32::System::SystemInformation::{GetSystemInfo, SYSTEM_INFO};
+
+    let mut info: SYSTEM_INFO = unsafe { std::mem::zeroed() };
+    unsafe { GetSystemInfo(&mut info) };
+    (info.dwPageSize as usize).max(1).next_power_of_two()
+}
+
+#[cfg(not(any(unix, windows)))]
+fn page_size() -> usize {
+    4096
+}
+
+#[cfg(unix)]
+fn lock(ptr: *mut u8, size: usize) {
+    unsafe { libc::mlock(ptr.cast(), size) };
+}
+
+#[cfg(unix)]
+fn unlock(ptr: *mut u8, size: usize) {
+    unsafe { libc::munlock(ptr.cast(), size) };
+}
+
+#[cfg(windows)]
+fn lock(ptr: *mut u8, size: usize) {
+    unsafe { windows_sys::Win32::System::Memory::VirtualLock(ptr.cast(), size) };
+}
+
+#[cfg(windows)]
+fn unlock(ptr: *mut u8, size: usize) {
+    unsafe { windows_sys::Win32::System::Memory::VirtualUnlock(ptr.cast(), size) };
+}
+
+#[cfg(not(any(unix, windows)))]
+fn lock(_ptr: *mut u8, _size: usize) {}
+
+#[cfg(not(any(unix, windows)))]
+fn unlock(_ptr: *mut u8, _size: usize) {}
@@ -0,0 +1,212 @@
+//! Three-way merge of account lists, for saves that collide with outside edits.
+
+use std::collections::{HashMap, HashSet};
+use crate::account::Account;
+
+/// Which version of a conflicting account to keep
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Side {
+    Ours,
+    Theirs,
+}
+
+/// An account both sides changed in different ways. `None` means that side
+/// deleted it.
+#[derive(Debug, Clone)]
+pub struct Conflict {
+    pub ours: Option<Account>,
+    pub theirs: Option<Account>,
+    /// Where the chosen account goes in the merged list
+    position: usize,
+}
+
+/// The result of [`merge`]: accounts that merged cleanly, plus the conflicts
+/// left for the user
+#[derive(Debug, Clone)]
+pub struct Merge {
+    pub accounts: Vec<Account>,
+    pub conflicts: Vec<Conflict>,
+}
+
+impl Merge {
+    /// The merged account list, keeping the given side of each conflict (in
+    /// the order of [`conflicts`](Self::conflicts))
+    pub fn resolve(self, choices: &[Side]) -> Vec<Account> {
+        let mut accounts = self.accounts;
+
+        // Insert from the back so earlier positions stay valid
+        for (conflict, side) in self.conflicts.into_iter().zip(choices).rev() {
+            let chosen = match side {
+                Side::Ours => conflict.ours,
+                Side::Theirs => conflict.theirs,
+            };
+            if let Some(account) = chosen {
+                accounts.insert(conflict.position.min(accounts.len()), account);
+            }
+        }
+
+        accounts
+    }
+}
+
+/// How an account differs between two versions of the vault
+#[derive(Debug, Clone)]
+pub enum Change {
+    Added(Account),
+    Removed(Account),
+    Changed { before: Box<Account>, after: Box<Account> },
+}
+
+/// Accounts added, removed or changed going from `before` to `after`,
+/// matching accounts by id
+pub fn diff(before: &[Account], after: &[Account]) -> Vec<Change> {
+    let before_map: HashMap<&str, &Account> = before.iter().map(|a| (a.id(), a)).collect();
+    let after_map: HashMap<&str, &Account> = after.iter().map(|a| (a.id(), a)).collect();
+
+    let mut changes: Vec<Change> = before
+        .iter()
+        .filter(|a| !after_map.contains_key(a.id()))
+        .map(|a| Change::Removed(a.clone()))
+        .collect();
+
+    for account in after {
+        match before_map.get(account.id()) {
+            None => changes.push(Change::Added(account.clone())),
+            Some(old) if !same(Some(old), Some(account)) => changes.push(Change::Changed {
+                before: Box::new((*old).clone()),
+                after: Box::new(account.clone()),
+            }),
+            Some(_) => {}
+        }
+    }
+
+    changes
+}
+
+/// Merges our accounts and theirs against the common `base` they both started
+/// from, matching accounts by id. A side's change wins when the other side left
+/// the account as it was in `base`; accounts changed on both sides in different
+/// ways become [`Conflict`]s. Their order is kept, with our new accounts last.
+pub fn merge(base: &[Account], ours: &[Account], theirs: &[Account]) -> Merge {
+    let base: HashMap<&str, &Account> = base.iter().map(|a| (a.id(), a)).collect();
+    let our_map: HashMap<&str, &Account> = ours.iter().map(|a| (a.id(), a)).collect();
+    let their_map: HashMap<&str, &Account> = theirs.iter().map(|a| (a.id(), a)).collect();
+
+    let mut seen = HashSet::new();
+    let ids: Vec<&str> = theirs
+        .iter()
+        .chain(ours)
+        .map(|a| a.id())
+        .filter(|id| seen.insert(*id))
+        .collect();
+
+    let mut merged = Merge { accounts: Vec::new(), conflicts: Vec::new() };
+
+    for id in ids {
+        let base = base.get(id).copied();
+        let ours = our_map.get(id).copied();
+        let theirs = their_map.get(id).copied();
+
+        let chosen = if same(ours, theirs) || same(ours, base) {
+            theirs
+        } else if same(theirs, base) {
+            ours
+        } else {
+            merged.conflicts.push(Conflict {
+                ours: ours.cloned(),
+                theirs: theirs.cloned(),
+                position: merged.accounts.len(),
+            });
+            continue;
+        };
+
+        if let Some(account) = chosen {
+            merged.accounts.push(account.clone());
+        }
+    }
+
+    merged
+}
+
+/// Whether two versions of an account hold the same data
+fn same(a: Option<&Account>, b: Option<&Account>) -> bool {
+    match (a, b) {
+        (None, None) => true,
+        (Some(a), Some(b)) => serde_json::to_value(a).ok() == serde_json::to_value(b).ok(),
+        _ => false,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use totp_rs::Algorithm;
+
+    fn account(name: &str) -> Account {
+        Account::new(name.to_string(), "JBSWY3DPEHPK3PXP".to_string(), 6, 30, Algorithm::SHA1, None)
+    }
+
+    fn renamed(account: &Account, name: &str) -> Account {
+        let mut account = account.clone();
+        account.set_details(name.to_string(), account.issuer().cloned(), Vec::new());
+        account
+    }
+
+    fn names(accounts: &[Account]) -> Vec<&str> {
+        accounts.iter().map(Account::name).collect()
+    }
+
+    #[test]
+    fn keeps_changes_from_both_sides() {
+        let (a, b) = (account("a"), account("b"));
+        let base = vec![a.clone(), b.clone()];
+        let ours = vec![renamed(&a, "a2"), b.clone(), account("ours")];
+        let theirs = vec![a.clone(), account("theirs")];
+
+        let merged = merge(&base, &ours, &theirs);
+        assert!(merged.conflicts.is_empty());
+        assert_eq!(names(&merged.accounts), ["a2", "theirs", "ours"]);
+    }
+
+    #[test]
+    fn leaves_different_changes_to_the_same_account_as_a_conflict() {
+        let a = account("a");
+        let base = vec![a.clone()];
+        let ours = vec![renamed(&a, "ours")];
+        let theirs = vec![renamed(&a, "theirs")];
+
+        let merged = merge(&base, &ours, &theirs);
+        assert!(merged.accounts.is_empty());
+        assert_eq!(merged.conflicts.len(), 1);
+        assert_eq!(names(&merged.clone().resolve(&[Side::Ours])), ["ours"]);
+        assert_eq!(names(&merged.resolve(&[Side::Theirs])), ["theirs"]);
+    }
+
+    #[test]
+    fn a_deletion_conflicts_with_a_change() {
+        let a = account("a");
+        let theirs = renamed(&a, "theirs");
+        let merged = merge(&[a], &[], &[theirs]);
+        assert_eq!(merged.conflicts.len(), 1);
+        assert!(merged.resolve(&[Side::Ours]).is_empty());
+    }
+
+    #[test]
+    fn same_change_on_both_sides_merges_cleanly() {
+        let a = account("a");
+        let both = [renamed(&a, "both")];
+        let merged = merge(&[a], &both, &both);
+        assert!(merged.conflicts.is_empty());
+        assert_eq!(names(&merged.accounts), ["both"]);
+    }
+
+    #[test]
+    fn diff_lists_added_removed_and_changed_accounts() {
+        let (a, b) = (account("a"), account("b"));
+        let changes = diff(&[a.clone(), b.clone()], &[renamed(&b, "b2"), account("c")]);
+        assert_eq!(changes.len(), 3);
+        assert!(matches!(&changes[0], Change::Removed(removed) if removed.id() == a.id()));
+        assert!(matches!(&changes[1], Change::Changed { before, after } if before.name() == "b" && after.name() == "b2"));
+        assert!(matches!(&changes[2], Change::Added(added) if added.name() == "c"));
+    }
+}
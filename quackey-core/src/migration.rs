@@ -0,0 +1,144 @@
+//! Export to the `otpauth-migration://offline?data=...` format scanned by
+//! Google Authenticator and compatible apps.
+//!
+//! The payload is a protobuf `MigrationPayload` message, base64-encoded. Large
+//! exports are split into batches that share a `batch_id` and carry their own
+//! `batch_index`/`batch_size`, so the scanning app can collect every frame in
+//! any order.
+
+use rand::Rng;
+use totp_rs::Algorithm;
+use crate::account::{uri_encode, Account};
+use crate::error::AppError;
+use crate::secret;
+
+/// Longest URI per frame, keeping each QR code small enough for a terminal
+const MAX_FRAME_LEN: usize = 500;
+
+/// Migration QR frames plus the accounts the format can't represent
+pub struct MigrationExport {
+    pub frames: Vec<String>,
+    pub skipped: Vec<(String, &'static str)>,
+}
+
+/// Splits accounts into as many migration URIs as needed
+pub fn export(accounts: &[&Account]) -> Result<MigrationExport, AppError> {
+    let mut entries = Vec::new();
+    let mut skipped = Vec::new();
+
+    for account in accounts {
+        match encode_account(account)? {
+            Ok(entry) => entries.push(entry),
+            Err(reason) => skipped.push((account.name().to_string(), reason)),
+        }
+    }
+
+    // Greedily fill batches, measuring with the worst-case batch header
+    let mut batches: Vec<Vec<Vec<u8>>> = Vec::new();
+    let mut current: Vec<Vec<u8>> = Vec::new();
+    for entry in entries {
+        current.push(entry);
+        if current.len() > 1 && frame_uri(&current, u32::MAX, u32::MAX, u32::MAX).len() > MAX_FRAME_LEN {
+            let entry = current.pop().expect("batch has at least two entries");
+            batches.push(std::mem::take(&mut current));
+            current.push(entry);
+        }
+    }
+    if !current.is_empty() {
+        batches.push(current);
+    }
+
+    let batch_id = rand::thread_rng().gen_range(1..i32::MAX as u32);
+    let batch_size = batches.len() as u32;
+    let frames = batches
+        .iter()
+        .enumerate()
+        .map(|(index, batch)| frame_uri(batch, batch_size, index as u32, batch_id))
+        .collect();
+
+    Ok(MigrationExport { frames, skipped })
+}
+
+/// Encodes one account as an `OtpParameters` message, or explains why the
+/// migration format can't hold it
+fn encode_account(account: &Account) -> Result<Result<Vec<u8>, &'static str>, AppError> {
+    if account.secret_reference().is_some() {
+        return Ok(Err("its secret is fetched from outside the vault"));
+    }
+    let algorithm = match account.algorithm() {
+        Algorithm::SHA1 => 1,
+        Algorithm::SHA256 => 2,
+        Algorithm::SHA512 => 3,
+        Algorithm::Steam => return Ok(Err("Steam codes aren't supported by the migration format")),
+    };
+    let digits = match account.digits() {
+        6 => 1,
+        8 => 2,
+        _ => return Ok(Err("only 6- or 8-digit codes can be migrated")),
+    };
+    if account.period() != 30 {
+        return Ok(Err("only 30-second periods can be migrated"));
+    }
+
+    let mut message = Vec::new();
+    put_bytes(&mut message, 1, &secret::decode_base32(account.secret())?);
+    put_bytes(&mut message, 2, account.name().as_bytes());
+    if let Some(issuer) = account.issuer() {
+        put_bytes(&mut message, 3, issuer.as_bytes());
+    }
+    put_varint_field(&mut message, 4, algorithm);
+    put_varint_field(&mut message, 5, digits);
+    put_varint_field(&mut message, 6, 2); // TOTP
+
+    Ok(Ok(message))
+}
+
+/// Builds the URI for one batch of encoded accounts
+fn frame_uri(entries: &[Vec<u8>], batch_size: u32, batch_index: u32, batch_id: u32) -> String {
+    let mut payload = Vec::new();
+    for entry in entries {
+        put_bytes(&mut payload, 1, entry);
+    }
+    put_varint_field(&mut payload, 2, 1); // version
+    put_varint_field(&mut payload, 3, batch_size as u64);
+    put_varint_field(&mut payload, 4, batch_index as u64);
+    put_varint_field(&mut payload, 5, batch_id as u64);
+
+    format!("otpauth-migration://offline?data={}", uri_encode(&base64_encode(&payload)))
+}
+
+fn put_varint(buffer: &mut Vec<u8>, mut value: u64) {
+    while value >= 0x80 {
+        buffer.push((value as u8) | 0x80);
+        value >>= 7;
+    }
+    buffer.push(value as u8);
+}
+
+fn put_varint_field(buffer: &mut Vec<u8>, field: u64, value: u64) {
+    put_varint(buffer, field << 3);
+    put_varint(buffer, value);
+}
+
+fn put_bytes(buffer: &mut Vec<u8>, field: u64, bytes: &[u8]) {
+    put_varint(buffer, (field << 3) | 2);
+    put_varint(buffer, bytes.len() as u64);
+    buffer.extend_from_slice(bytes);
+}
+
+/// Standard base64 with padding
+fn base64_encode(bytes: &[u8]) -> String {
+    const ALPHABET: &[u8; 64] = b"ABCDEFGHIJKLMNOPQRSTUVWXYZabcdefghijklmnopqrstuvwxyz0123456789+/";
+
+    let mut output = String::with_capacity(bytes.len().div_ceil(3) * 4);
+    for chunk in bytes.chunks(3) {
+        let b = [chunk[0], *chunk.get(1).unwrap_or(&0), *chunk.get(2).unwrap_or(&0)];
+        let n = (b[0] as u32) << 16 | (b[1] as u32) << 8 | b[2] as u32;
+
+        output.push(ALPHABET[(n >> 18) as usize & 63] as char);
+        output.push(ALPHABET[(n >> 12) as usize & 63] as char);
+        output.push(if chunk.len() > 1 { ALPHABET[(n >> 6) as usize & 63] as char } else { '=' });
+        output.push(if chunk.len() > 2 { ALPHABET[n as usize & 63] as char } else { '=' });
+    }
+    output
+}
@@ -0,0 +1,147 @@
+//! Files only the current user can read.
+//!
+//! The vault, its backups, the log and key files are created with mode 0600
+//! on Unix. Windows has no creation mode: new files inherit the folder's
+//! access list, so [`restrict`] replaces it with one granting only the
+//! current user, using `icacls` with the user's SID read from the process
+//! token. The `USERNAME` variable isn't used: it can be changed by anyone
+//! starting Quackey, and a local user and a domain user can share a name. Files that must never be seen half written,
+//! such as the vault, are written with [`replace`].
+
+use std::fs::{File, OpenOptions};
+use std::io::{self, Write};
+use std::path::{Path, PathBuf};
+
+/// Options that create files readable and writable by their owner only
+/// (mode 0600 on Unix). Existing files keep their permissions.
+pub fn open_options() -> OpenOptions {
+    #[allow(unused_mut)]
+    let mut options = OpenOptions::new();
+    #[cfg(unix)]
+    {
+        use std::os::unix::fs::OpenOptionsExt;
+        options.mode(0o600);
+    }
+    options
+}
+
+/// Creates or truncates the file at `path` for writing. A new file is
+/// readable by the current user only.
+pub fn create(path: &Path) -> io::Result<File> {
+    let created = !path.exists();
+    let file = open_options().write(true).create(true).truncate(true).open(path)?;
+    if created && cfg!(not(unix)) {
+        restrict(path)?;
+    }
+    Ok(file)
+}
+
+/// Writes `contents` to the file at `path`, see [`create`]
+pub fn write(path: &Path, contents: &[u8]) -> io::Result<()> {
+    create(path)?.write_all(contents)
+}
+
+/// Replaces the file at `path` with `contents` without ever leaving it half
+/// written: the contents go to a temporary file next to it, readable by the
+/// current user only, which is flushed to disk and renamed over `path`. A
+/// crash, or a program reading the file meanwhile, sees either the old or the
+/// new contents. A symlink at `path` is followed, so the file it points to is
+/// replaced rather than the link.
+pub fn replace(path: &Path, contents: &[u8]) -> io::Result<()> {
+    let target = match std::fs::symlink_metadata(path) {
+        Ok(metadata) if metadata.file_type().is_symlink() => std::fs::canonicalize(path)?,
+        _ => path.to_path_buf(),
+    };
+    let mut temp = target.as_os_str().to_owned();
+    temp.push(format!(".{}.tmp", std::process::id()));
+    let temp = PathBuf::from(temp);
+
+    let written = open_options()
+        .write(true)
+        .create_new(true)
+        .open(&temp)
+        .and_then(|mut file| {
+            if cfg!(not(unix)) {
+                restrict(&temp)?;
+            }
+            file.write_all(contents)?;
+            file.sync_all()
+        })
+        .and_then(|_| std::fs::rename(&temp, &target));
+    if let Err(e) = written {
+        let _ = std::fs::remove_file(&temp);
+        return Err(e);
+    }
+
+    // Make the rename itself durable; not every file system supports this
+    #[cfg(unix)]
+    if let Some(parent) = target.parent().filter(|p| !p.as_os_str().is_empty()) {
+        let _ = File::open(parent).and_then(|dir| dir.sync_all());
+    }
+    Ok(())
+}
+
+/// Makes the existing file at `path` readable and writable by the current
+/// user only
+#[cfg(unix)]
+pub fn restrict(path: &Path) -> io::Result<()> {
+    use std::os::unix::fs::PermissionsExt;
+
+    std::fs::set_permissions(path, std::fs::Permissions::from_mode(0o600))
+}
+
+/// Makes the existing file at `path` readable and writable by the current
+/// user only
+#[cfg(windows)]
+pub fn restrict(path: &Path) -> io::Result<()> {
+    let sid = current_user_sid()?;
+    let output = std::process::Command::new("icacls")
+        .arg(path)
+        .args(["/inheritance:r", "/grant:r", &format!("*{}:F", sid)])
+        .output()?;
+    if output.status.success() {
+        Ok(())
+    } else {
+        Err(io::Error::other(String::from_utf8_lossy(&output.stderr).trim().to_string()))
+    }
+}
+
+/// The SID of the user this process runs as, such as `S-1-5-21-…-1001`
+#[cfg(windows)]
+pub(crate) fn current_user_sid() -> io::Result<String> {
+    use windows_sys::Win32::Foundation::{CloseHandle, LocalFree, HANDLE};
+    use windows_sys::Win32::Security::Authorization::ConvertSidToStringSidW;
+    use windows_sys::Win32::Security::{GetTokenInformation, TokenUser, TOKEN_QUERY, TOKEN_USER};
+    use windows_sys::Win32::System::Threading::{GetCurrentProcess, OpenProcessToken};
+
+    let mut token: HANDLE = std::ptr::null_mut();
+    if unsafe { OpenProcessToken(GetCurrentProcess(), TOKEN_QUERY, &mut token) } == 0 {
+        return Err(io::Error::last_os_error());
+    }
+
+    // The first call only asks for the size. A u64 buffer keeps TOKEN_USER aligned.
+    let mut len = 0u32;
+    unsafe { GetTokenInformation(token, TokenUser, std::ptr::null_mut(), 0, &mut len) };
+    let mut buffer = vec![0u64; (len as usize).div_ceil(8)];
+    let read = unsafe { GetTokenInformation(token, TokenUser, buffer.as_mut_ptr().cast(), len, &mut len) };
+    let error = io::Error::last_os_error();
+    unsafe { CloseHandle(token) };
+    if read == 0 {
+        return Err(error);
+    }
+
+    let user = unsafe { &*buffer.as_ptr().cast::<TOKEN_USER>() };
+    let mut wide = std::ptr::null_mut();
+    if unsafe { ConvertSidToStringSidW(user.User.Sid, &mut wide) } == 0 {
+        return Err(io::Error::last_os_error());
+    }
+    let len = (0..).take_while(|&i| unsafe { *wide.add(i) } != 0).count();
+    let sid = String::from_utf16_lossy(unsafe { std::slice::from_raw_parts(wide, len) });
+    unsafe { LocalFree(wide.cast()) };
+    Ok(sid)
+}
+
+#[cfg(not(any(unix, windows)))]
+pub fn restrict(_path: &Path) -> io::Result<()> {
+    Ok(())
+}
@@ -0,0 +1,288 @@
+//! PIN quick-unlock for an encrypted vault.
+//!
+//! The vault key is kept next to the vault (`.quackey-pin`), encrypted with a
+//! key derived from the PIN together with a random machine secret. The secret
+//! lives in the user's runtime directory (`$XDG_RUNTIME_DIR`, or else a
+//! folder of the user's own in the temporary directory), which the system
+//! clears on logout or reboot, so a PIN only works for the current machine
+//! session. The secret is only read from a folder and file no one else can
+//! open. A short PIN alone is easy to guess, so [`MAX_ATTEMPTS`] wrong PINs
+//! delete both the PIN file and the machine secret: a saved copy of the PIN
+//! file can't be tried again, and the master password is needed again.
+
+use std::fs;
+use std::io::Write;
+use std::path::{Path, PathBuf};
+use rand::RngCore;
+use rand::rngs::OsRng;
+use serde::{Serialize, Deserialize};
+use crate::crypto::{self, to_hex, EncryptedData, VaultKey};
+use crate::error::AppError;
+use crate::permissions;
+
+/// Wrong PINs allowed before PIN unlock is turned off
+pub const MAX_ATTEMPTS: u8 = 3;
+
+const PIN_FILENAME: &str = ".quackey-pin";
+const MIN_LEN: usize = 4;
+const MAX_LEN: usize = 8;
+
+/// Contents of the PIN file
+#[derive(Debug, Clone, Serialize, Deserialize)]
+struct PinFile {
+    /// The vault key, encrypted with the PIN and the machine secret
+    wrapped: EncryptedData,
+    attempts_left: u8,
+}
+
+/// Outcome of [`unlock`]
+#[derive(Debug)]
+pub enum PinAttempt {
+    Unlocked(VaultKey),
+    /// Wrong PIN; at 0 attempts left PIN unlock has been turned off
+    Wrong { attempts_left: u8 },
+}
+
+/// Checks that a PIN is 4 to 8 digits
+pub fn validate(pin: &str) -> Result<(), AppError> {
+    if pin.len() < MIN_LEN || pin.len() > MAX_LEN || !pin.chars().all(|c| c.is_ascii_digit()) {
+        return Err(AppError::InvalidInput(format!(
+            "A PIN must be {} to {} digits",
+            MIN_LEN, MAX_LEN
+        )));
+    }
+    Ok(())
+}
+
+/// Whether PIN unlock is set up for the vault at `file_path` in this machine
+/// session
+pub fn is_set_up(file_path: &str) -> bool {
+    pin_path(file_path).exists() && secret_path().exists()
+}
+
+/// Lets `pin` unlock the vault at `file_path` with `key` until the machine
+/// session ends
+pub fn set_up(file_path: &str, pin: &str, key: &VaultKey) -> Result<(), AppError> {
+    validate(pin)?;
+
+    let file = PinFile {
+        wrapped: crypto::encrypt(&key.to_bytes(), &pin_passphrase(pin, &machine_secret(true)?)),
+        attempts_left: MAX_ATTEMPTS,
+    };
+    write_pin_file(file_path, &file)
+}
+
+/// Tries to unlock the vault at `file_path` with `pin`. Too many wrong PINs
+/// delete the PIN file.
+pub fn unlock(file_path: &str, pin: &str) -> Result<PinAttempt, AppError> {
+    let path = pin_path(file_path);
+    let contents = fs::read_to_string(&path)
+        .map_err(|e| AppError::FileError(format!("Failed to read PIN file: {}", e)))?;
+    let mut file: PinFile = serde_json::from_str(&contents)
+        .map_err(|e| AppError::JsonError(format!("Failed to parse PIN file: {}", e)))?;
+
+    if let Ok(bytes) = crypto::decrypt(&file.wrapped, &pin_passphrase(pin, &machine_secret(false)?)) {
+        if file.attempts_left < MAX_ATTEMPTS {
+            file.attempts_left = MAX_ATTEMPTS;
+            write_pin_file(file_path, &file)?;
+        }
+        return Ok(PinAttempt::Unlocked(VaultKey::from_bytes(&bytes)?));
+    }
+
+    file.attempts_left = file.attempts_left.saturating_sub(1);
+    if file.attempts_left == 0 {
+        remove(file_path)?;
+        fs::remove_file(secret_path())
+            .map_err(|e| AppError::FileError(format!("Failed to remove machine secret: {}", e)))?;
+    } else {
+        write_pin_file(file_path, &file)?;
+    }
+    Ok(PinAttempt::Wrong { attempts_left: file.attempts_left })
+}
+
+/// Turns PIN unlock off for the vault at `file_path`
+pub fn remove(file_path: &str) -> Result<(), AppError> {
+    match fs::remove_file(pin_path(file_path)) {
+        Ok(()) => Ok(()),
+        Err(e) if e.kind() == std::io::ErrorKind::NotFound => Ok(()),
+        Err(e) => Err(AppError::FileError(format!("Failed to remove PIN file: {}", e))),
+    }
+}
+
+fn write_pin_file(file_path: &str, file: &PinFile) -> Result<(), AppError> {
+    let json = serde_json::to_string_pretty(file)
+        .map_err(|e| AppError::JsonError(format!("Failed to serialize PIN file: {}", e)))?;
+    permissions::replace(&pin_path(file_path), json.as_bytes())
+        .map_err(|e| AppError::FileError(format!("Failed to write PIN file: {}", e)))
+}
+
+fn pin_passphrase(pin: &str, secret: &[u8]) -> String {
+    format!("{}:{}", pin, to_hex(secret))
+}
+
+/// The random secret for this machine session, created if `create` is set.
+/// A secret that others could have read or planted is refused.
+fn machine_secret(create: bool) -> Result<Vec<u8>, AppError> {
+    let dir = secret_dir();
+    let path = secret_path();
+
+    if fs::symlink_metadata(&path).is_ok() {
+        check_private(&dir, true)?;
+        check_private(&path, false)?;
+        return fs::read(&path).map_err(|e| AppError::FileError(format!("Failed to read machine secret: {}", e)));
+    }
+    if !create {
+        return Err(AppError::Locked(
+            "PIN unlock expired with the last machine session; use the master password".to_string(),
+        ));
+    }
+
+    if !dir.exists() {
+        create_private_dir(&dir)
+            .map_err(|e| AppError::FileError(format!("Failed to create {}: {}", dir.display(), e)))?;
+    }
+    check_private(&dir, true)?;
+
+    let mut secret = vec![0u8; 32];
+    OsRng.fill_bytes(&mut secret);
+    // create_new fails rather than follow a file or link put there in the meantime
+    let mut file = permissions::open_options()
+        .write(true)
+        .create_new(true)
+        .open(&path)
+        .map_err(|e| AppError::FileError(format!("Failed to write machine secret: {}", e)))?;
+    if cfg!(not(unix)) {
+        permissions::restrict(&path)
+            .map_err(|e| AppError::FileError(format!("Failed to write machine secret: {}", e)))?;
+    }
+    file.write_all(&secret)
+        .map_err(|e| AppError::FileError(format!("Failed to write machine secret: {}", e)))?;
+    Ok(secret)
+}
+
+/// `$XDG_RUNTIME_DIR`, or a folder named after the user in the temporary
+/// directory, since anyone can create files in the temporary directory itself
+fn secret_dir() -> PathBuf {
+    std::env::var_os("XDG_RUNTIME_DIR")
+        .filter(|dir| !dir.is_empty())
+        .map(PathBuf::from)
+        .unwrap_or_else(|| std::env::temp_dir().join(format!("quackey-{}", user_id())))
+}
+
+fn secret_path() -> PathBuf {
+    secret_dir().join("quackey.session-key")
+}
+
+#[cfg(unix)]
+fn user_id() -> String {
+    unsafe { libc::geteuid() }.to_string()
+}
+
+#[cfg(windows)]
+fn user_id() -> String {
+    permissions::current_user_sid().unwrap_or_default()
+}
+
+#[cfg(not(any(unix, windows)))]
+fn user_id() -> String {
+    std::env::var("USERNAME").unwrap_or_default()
+}
+
+#[cfg(unix)]
+fn create_private_dir(dir: &Path) -> std::io::Result<()> {
+    use std::os::unix::fs::DirBuilderExt;
+
+    fs::DirBuilder::new().mode(0o700).create(dir)
+}
+
+#[cfg(not(unix))]
+fn create_private_dir(dir: &Path) -> std::io::Result<()> {
+    fs::create_dir(dir)
+}
+
+/// Fails unless `path` is a folder (`dir`) or regular file owned by the
+/// current user that no one else can open: mode 0700 or stricter for a
+/// folder, exactly 0600 for a file. Links are not followed.
+#[cfg(unix)]
+fn check_private(path: &Path, dir: bool) -> Result<(), AppError> {
+    use std::os::unix::fs::MetadataExt;
+
+    let metadata = fs::symlink_metadata(path)
+        .map_err(|e| AppError::FileError(format!("Failed to read {}: {}", path.display(), e)))?;
+    let mode = metadata.mode() & 0o777;
+    let private = if dir {
+        metadata.is_dir() && mode & 0o077 == 0
+    } else {
+        metadata.is_file() && mode == 0o600
+    };
+
+    if !private || metadata.uid() != unsafe { libc::geteuid() } {
+        return Err(AppError::PermissionError(format!(
+            "{} is not private to the current user, so PIN unlock won't use it",
+            path.display()
+        ))
+        .with_hint(if dir {
+            "It must be a folder you own with mode 0700"
+        } else {
+            "It must be a file you own with mode 0600; delete it and set the PIN up again"
+        }));
+    }
+    Ok(())
+}
+
+#[cfg(not(unix))]
+fn check_private(_path: &Path, _dir: bool) -> Result<(), AppError> {
+    Ok(())
+}
+
+/// Where the PIN-wrapped key of the vault at `file_path` is kept
+pub(crate) fn pin_path(file_path: &str) -> PathBuf {
+    match Path::new(file_path).parent() {
+        Some(parent) if !parent.as_os_str().is_empty() => parent.join(PIN_FILENAME),
+        _ => PathBuf::from(PIN_FILENAME),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn validates_length_and_digits() {
+        assert!(validate("1234").is_ok());
+        assert!(validate("12345678").is_ok());
+        assert!(validate("123").is_err());
+        assert!(validate("123456789").is_err());
+        assert!(validate("12a4").is_err());
+    }
+
+    // Uses the real machine secret of this session, as Quackey would, but never
+    // runs out of attempts, which would delete it
+    #[test]
+    fn unlocks_with_the_pin_it_was_set_up_with() {
+        let dir = std::env::temp_dir().join(format!("quackey-pin-test-{}", std::process::id()));
+        fs::create_dir_all(&dir).unwrap();
+        let file_path = dir.join("accounts.json").display().to_string();
+
+        let mut material = vec![9u8; 16];
+        material.extend(1_000u32.to_be_bytes());
+        material.extend([3u8; 32]);
+        let key = VaultKey::from_bytes(&material).unwrap();
+
+        set_up(&file_path, "2468", &key).unwrap();
+        assert!(is_set_up(&file_path));
+
+        match unlock(&file_path, "1357").unwrap() {
+            PinAttempt::Wrong { attempts_left } => assert_eq!(attempts_left, MAX_ATTEMPTS - 1),
+            PinAttempt::Unlocked(_) => panic!("a wrong PIN unlocked the vault"),
+        }
+        match unlock(&file_path, "2468").unwrap() {
+            PinAttempt::Unlocked(unlocked) => assert_eq!(unlocked.fingerprint(), key.fingerprint()),
+            PinAttempt::Wrong { .. } => panic!("the right PIN was refused"),
+        }
+
+        remove(&file_path).unwrap();
+        assert!(!is_set_up(&file_path));
+        fs::remove_dir_all(&dir).unwrap();
+    }
+}
@@ -0,0 +1,169 @@
+//! Asking for secrets through the system's `pinentry` program, as GnuPG does.
+//!
+//! pinentry speaks the Assuan protocol over its standard input and output.
+//! Quackey sends a description and prompt, asks for the PIN with `GETPIN`
+//! and reads the `D` line that comes back. With a key-info id set, pinentry
+//! programs that support it (such as pinentry-gnome3 and pinentry-mac) can
+//! offer to remember the passphrase in the desktop keyring, following the
+//! user's own caching settings.
+
+use std::io::{BufRead, BufReader, Write};
+use std::process::{Child, ChildStdin, ChildStdout, Command, Stdio};
+use sha2::{Digest, Sha256};
+use crate::crypto::to_hex;
+use crate::error::AppError;
+
+/// Assuan error code pinentry returns when the user cancels
+const CANCELLED: &str = "83886179";
+
+/// A secret prompt shown by pinentry
+#[derive(Debug, Clone)]
+pub struct Prompt<'a> {
+    /// Window title
+    pub title: &'a str,
+    /// Longer text above the entry field
+    pub description: &'a str,
+    /// Label of the entry field
+    pub prompt: &'a str,
+    /// Shown when asking again after a wrong entry
+    pub error: Option<&'a str>,
+    /// Identifies the secret to external password caches
+    pub key_info: Option<&'a str>,
+}
+
+/// Key-info id for the master password of the vault at `file_path`, so
+/// external caches keep one entry per vault
+pub fn key_info_for(file_path: &str) -> String {
+    let path = std::fs::canonicalize(file_path)
+        .map(|p| p.to_string_lossy().into_owned())
+        .unwrap_or_else(|_| file_path.to_string());
+    format!("quackey/{}", &to_hex(&Sha256::digest(path.as_bytes()))[..20])
+}
+
+/// Asks for a secret with the pinentry `program`. Returns `None` if the user
+/// cancelled.
+pub fn get_pin(program: &str, prompt: &Prompt) -> Result<Option<String>, AppError> {
+    let mut child = Command::new(program)
+        .stdin(Stdio::piped())
+        .stdout(Stdio::piped())
+        .stderr(Stdio::null())
+        .spawn()
+        .map_err(|e| AppError::FileError(format!("Failed to start '{}': {}", program, e)))?;
+
+    let result = converse(&mut child, prompt);
+    let _ = child.kill();
+    let _ = child.wait();
+    result
+}
+
+fn converse(child: &mut Child, prompt: &Prompt) -> Result<Option<String>, AppError> {
+    let mut input = child.stdin.take().expect("stdin is piped");
+    let mut output = BufReader::new(child.stdout.take().expect("stdout is piped"));
+
+    // Greeting
+    read_response(&mut output)?;
+
+    if let Some(tty) = tty_name() {
+        command(&mut input, &mut output, &format!("OPTION ttyname={}", tty))?;
+        if let Ok(term) = std::env::var("TERM") {
+            command(&mut input, &mut output, &format!("OPTION ttytype={}", term))?;
+        }
+    }
+    command(&mut input, &mut output, &format!("SETTITLE {}", escape(prompt.title)))?;
+    command(&mut input, &mut output, &format!("SETDESC {}", escape(prompt.description)))?;
+    command(&mut input, &mut output, &format!("SETPROMPT {}", escape(prompt.prompt)))?;
+    if let Some(error) = prompt.error {
+        command(&mut input, &mut output, &format!("SETERROR {}", escape(error)))?;
+    }
+    if let Some(key_info) = prompt.key_info {
+        // Older pinentry versions don't know these; caching is optional
+        let _ = command(&mut input, &mut output, "OPTION allow-external-password-cache");
+        let _ = command(&mut input, &mut output, &format!("SETKEYINFO {}", escape(key_info)));
+    }
+
+    match command(&mut input, &mut output, "GETPIN") {
+        Ok(data) => {
+            let _ = writeln!(input, "BYE");
+            Ok(Some(data))
+        }
+        Err(AppError::InvalidInput(msg)) if msg.starts_with(CANCELLED) => Ok(None),
+        Err(e) => Err(e),
+    }
+}
+
+/// Sends one command and returns the data lines of its response
+fn command(
+    input: &mut ChildStdin,
+    output: &mut BufReader<ChildStdout>,
+    line: &str,
+) -> Result<String, AppError> {
+    writeln!(input, "{}", line)
+        .and_then(|_| input.flush())
+        .map_err(|e| AppError::FileError(format!("Failed to talk to pinentry: {}", e)))?;
+    read_response(output)
+}
+
+/// Reads until `OK` or `ERR`, collecting `D` lines
+fn read_response(output: &mut BufReader<ChildStdout>) -> Result<String, AppError> {
+    let mut data = String::new();
+    loop {
+        let mut line = String::new();
+        let read = output
+            .read_line(&mut line)
+            .map_err(|e| AppError::FileError(format!("Failed to read from pinentry: {}", e)))?;
+        if read == 0 {
+            return Err(AppError::FileError("pinentry closed unexpectedly".to_string()));
+        }
+
+        let line = line.trim_end_matches(['\r', '\n']);
+        if line == "OK" || line.starts_with("OK ") {
+            return Ok(data);
+        } else if let Some(error) = line.strip_prefix("ERR ") {
+            return Err(AppError::InvalidInput(error.to_string()));
+        } else if let Some(chunk) = line.strip_prefix("D ") {
+            data.push_str(&unescape(chunk));
+        }
+        // Status (S) and comment (#) lines are ignored
+    }
+}
+
+/// Percent-escapes the characters Assuan reserves
+fn escape(text: &str) -> String {
+    text.replace('%', "%25").replace('\n', "%0A").replace('\r', "%0D")
+}
+
+fn unescape(text: &str) -> String {
+    let bytes = text.as_bytes();
+    let mut decoded = Vec::with_capacity(bytes.len());
+    let mut i = 0;
+    while i < bytes.len() {
+        if bytes[i] == b'%'
+            && let Some(byte) = text.get(i + 1..i + 3).and_then(|hex| u8::from_str_radix(hex, 16).ok())
+        {
+            decoded.push(byte);
+            i += 3;
+            continue;
+        }
+        decoded.push(bytes[i]);
+        i += 1;
+    }
+    String::from_utf8_lossy(&decoded).into_owned()
+}
+
+/// Terminal for curses-based pinentry programs: `GPG_TTY`, else stderr's terminal
+fn tty_name() -> Option<String> {
+    if let Ok(tty) = std::env::var("GPG_TTY") {
+        return Some(tty);
+    }
+
+    #[cfg(unix)]
+    {
+        let name = unsafe { libc::ttyname(2) };
+        if !name.is_null() {
+            let name = unsafe { std::ffi::CStr::from_ptr(name) };
+            return Some(name.to_string_lossy().into_owned());
+        }
+    }
+
+    None
+}
@@ -0,0 +1,77 @@
+//! Known TOTP parameters for popular issuers.
+
+use totp_rs::Algorithm;
+use crate::account::AccountColor;
+
+/// Known TOTP parameters for a popular issuer
+pub struct IssuerPreset {
+    /// Canonical issuer name
+    pub name: &'static str,
+    /// Other names the issuer is commonly entered as
+    pub aliases: &'static [&'static str],
+    /// Icon shown next to the issuer
+    pub icon: &'static str,
+    /// Color suggested for the issuer's accounts, close to its brand color
+    pub color: AccountColor,
+    pub digits: usize,
+    pub period: u64,
+    pub algorithm: Algorithm,
+}
+
+/// Built-in issuer presets
+pub const PRESETS: &[IssuerPreset] = &[
+    IssuerPreset {
+        name: "Google",
+        aliases: &["Gmail", "Google Workspace"],
+        icon: "🔍",
+        color: AccountColor::Blue,
+        digits: 6,
+        period: 30,
+        algorithm: Algorithm::SHA1,
+    },
+    IssuerPreset {
+        name: "GitHub",
+        aliases: &[],
+        icon: "🐙",
+        color: AccountColor::Magenta,
+        digits: 6,
+        period: 30,
+        algorithm: Algorithm::SHA1,
+    },
+    IssuerPreset {
+        name: "AWS",
+        aliases: &["Amazon Web Services", "Amazon"],
+        icon: "📦",
+        color: AccountColor::Yellow,
+        digits: 6,
+        period: 30,
+        algorithm: Algorithm::SHA1,
+    },
+    IssuerPreset {
+        name: "Microsoft",
+        aliases: &["Azure", "Outlook"],
+        icon: "🪟",
+        color: AccountColor::Cyan,
+        digits: 6,
+        period: 30,
+        algorithm: Algorithm::SHA1,
+    },
+    IssuerPreset {
+        name: "Steam",
+        aliases: &["Steam Guard"],
+        icon: "🎮",
+        color: AccountColor::Blue,
+        digits: 5,
+        period: 30,
+        algorithm: Algorithm::Steam,
+    },
+];
+
+/// Finds the preset for an issuer, matching the name or an alias case-insensitively
+pub fn find_preset(issuer: &str) -> Option<&'static IssuerPreset> {
+    let issuer = issuer.trim();
+    PRESETS.iter().find(|preset| {
+        preset.name.eq_ignore_ascii_case(issuer)
+            || preset.aliases.iter().any(|alias| alias.eq_ignore_ascii_case(issuer))
+    })
+}
@@ -0,0 +1,771 @@
+//! Minimal QR code encoder (byte mode only) for sharing otpauth URIs, plus the
+//! matching decoder for module grids sampled from screenshots.
+//! Follows the structure of Project Nayuki's reference QR Code generator.
+
+use crate::error::AppError;
+
+/// Error correction level
+#[derive(Debug, Clone, Copy)]
+pub enum EccLevel {
+    Low,
+    Medium,
+    Quartile,
+    High,
+}
+
+impl EccLevel {
+    fn ordinal(self) -> usize {
+        match self {
+            EccLevel::Low => 0,
+            EccLevel::Medium => 1,
+            EccLevel::Quartile => 2,
+            EccLevel::High => 3,
+        }
+    }
+
+    fn format_bits(self) -> u32 {
+        match self {
+            EccLevel::Low => 1,
+            EccLevel::Medium => 0,
+            EccLevel::Quartile => 3,
+            EccLevel::High => 2,
+        }
+    }
+
+    fn from_format_bits(bits: u32) -> Self {
+        match bits & 3 {
+            1 => EccLevel::Low,
+            0 => EccLevel::Medium,
+            3 => EccLevel::Quartile,
+            _ => EccLevel::High,
+        }
+    }
+}
+
+/// Error correction codewords per block, indexed by [ecc level][version]
+const ECC_CODEWORDS_PER_BLOCK: [[i8; 41]; 4] = [
+    [-1, 7, 10, 15, 20, 26, 18, 20, 24, 30, 18, 20, 24, 26, 30, 22, 24, 28, 30, 28, 28, 28, 28, 30, 30, 26, 28, 30, 30, 30, 30, 30, 30, 30, 30, 30, 30, 30, 30, 30, 30],
+    [-1, 10, 16, 26, 18, 24, 16, 18, 22, 22, 26, 30, 22, 22, 24, 24, 28, 28, 26, 26, 26, 26, 28, 28, 28, 28, 28, 28, 28, 28, 28, 28, 28, 28, 28, 28, 28, 28, 28, 28, 28],
+    [-1, 13, 22, 18, 26, 18, 24, 18, 22, 20, 24, 28, 26, 24, 20, 30, 24, 28, 28, 26, 30, 28, 30, 30, 30, 30, 28, 30, 30, 30, 30, 30, 30, 30, 30, 30, 30, 30, 30, 30, 30],
+    [-1, 17, 28, 22, 16, 22, 28, 26, 26, 24, 28, 24, 28, 22, 24, 24, 30, 28, 28, 26, 28, 30, 24, 30, 30, 30, 30, 30, 30, 30, 30, 30, 30, 30, 30, 30, 30, 30, 30, 30, 30],
+];
+
+/// Error correction blocks, indexed by [ecc level][version]
+const NUM_ERROR_CORRECTION_BLOCKS: [[i8; 41]; 4] = [
+    [-1, 1, 1, 1, 1, 1, 2, 2, 2, 2, 4, 4, 4, 4, 4, 6, 6, 6, 6, 7, 8, 8, 9, 9, 10, 12, 12, 12, 13, 14, 15, 16, 17, 18, 19, 19, 20, 21, 22, 24, 25],
+    [-1, 1, 1, 1, 2, 2, 4, 4, 4, 5, 5, 5, 8, 9, 9, 10, 10, 11, 13, 14, 16, 17, 17, 18, 20, 21, 23, 25, 26, 28, 29, 31, 33, 35, 37, 38, 40, 43, 45, 47, 49],
+    [-1, 1, 1, 2, 2, 4, 4, 6, 6, 8, 8, 8, 10, 12, 16, 12, 17, 16, 18, 21, 20, 23, 23, 25, 27, 29, 34, 34, 35, 38, 40, 43, 45, 48, 51, 53, 56, 59, 62, 65, 68],
+    [-1, 1, 1, 2, 4, 4, 4, 5, 6, 8, 8, 11, 11, 16, 16, 18, 16, 19, 21, 25, 25, 25, 34, 30, 32, 35, 37, 40, 42, 45, 48, 51, 54, 57, 60, 63, 66, 70, 74, 77, 81],
+];
+
+/// A fully encoded QR code
+pub struct QrCode {
+    size: usize,
+    modules: Vec<bool>,
+    is_function: Vec<bool>,
+}
+
+impl QrCode {
+    /// Encodes binary data at the smallest version that fits
+    pub fn encode(data: &[u8], ecl: EccLevel) -> Result<Self, AppError> {
+        let mut version = 1;
+        loop {
+            let capacity_bits = num_data_codewords(version, ecl) * 8;
+            let count_bits = if version <= 9 { 8 } else { 16 };
+            let used_bits = 4 + count_bits + data.len() * 8;
+            if data.len() < (1 << count_bits) && used_bits <= capacity_bits {
+                break;
+            }
+            if version >= 40 {
+                return Err(AppError::InvalidInput(format!(
+                    "Data is too long for a single QR code ({} bytes)",
+                    data.len()
+                )));
+            }
+            version += 1;
+        }
+
+        // Byte mode segment, terminator and padding
+        let count_bits = if version <= 9 { 8 } else { 16 };
+        let capacity_bits = num_data_codewords(version, ecl) * 8;
+        let mut bits: Vec<bool> = Vec::with_capacity(capacity_bits);
+        append_bits(&mut bits, 0b0100, 4);
+        append_bits(&mut bits, data.len() as u32, count_bits);
+        for &byte in data {
+            append_bits(&mut bits, byte as u32, 8);
+        }
+        let terminator = (capacity_bits - bits.len()).min(4);
+        append_bits(&mut bits, 0, terminator);
+        let padding = (8 - bits.len() % 8) % 8;
+        append_bits(&mut bits, 0, padding);
+        for pad in [0xEC, 0x11].iter().cycle() {
+            if bits.len() >= capacity_bits {
+                break;
+            }
+            append_bits(&mut bits, *pad, 8);
+        }
+
+        let data_codewords: Vec<u8> = bits
+            .chunks(8)
+            .map(|chunk| chunk.iter().fold(0u8, |acc, &bit| (acc << 1) | bit as u8))
+            .collect();
+
+        let size = version * 4 + 17;
+        let mut qr = Self {
+            size,
+            modules: vec![false; size * size],
+            is_function: vec![false; size * size],
+        };
+
+        qr.draw_function_patterns(version, ecl);
+        let all_codewords = add_ecc_and_interleave(&data_codewords, version, ecl);
+        qr.draw_codewords(&all_codewords);
+
+        // Pick the mask with the lowest penalty score
+        let mut best_mask = 0;
+        let mut min_penalty = u32::MAX;
+        for mask in 0..8 {
+            qr.apply_mask(mask);
+            qr.draw_format_bits(ecl, mask);
+            let penalty = qr.penalty_score();
+            if penalty < min_penalty {
+                best_mask = mask;
+                min_penalty = penalty;
+            }
+            qr.apply_mask(mask); // XOR again to undo
+        }
+        qr.apply_mask(best_mask);
+        qr.draw_format_bits(ecl, best_mask);
+
+        Ok(qr)
+    }
+
+    /// Whether the module at (x, y) is dark; out-of-bounds coordinates are light
+    pub fn module(&self, x: i32, y: i32) -> bool {
+        let size = self.size as i32;
+        (0..size).contains(&x) && (0..size).contains(&y) && self.modules[(y * size + x) as usize]
+    }
+
+    /// Width and height in modules
+    pub fn size(&self) -> usize {
+        self.size
+    }
+
+    /// Decodes a module grid (as sampled from an image) back into the data it
+    /// carries, correcting damaged codewords where possible
+    pub fn decode_modules(size: usize, dark: impl Fn(usize, usize) -> bool) -> Result<Vec<u8>, AppError> {
+        if !(21..=177).contains(&size) || size % 4 != 1 {
+            return Err(AppError::InvalidInput(format!("{} is not a valid QR code size", size)));
+        }
+        let version = (size - 17) / 4;
+
+        let mut qr = Self {
+            size,
+            modules: (0..size * size).map(|i| dark(i % size, i / size)).collect(),
+            is_function: vec![false; size * size],
+        };
+        let (ecl, mask) = qr.read_format_bits()?;
+
+        // Mark function modules without disturbing the sampled data modules
+        let sampled = qr.modules.clone();
+        qr.draw_function_patterns(version, ecl);
+        for (i, module) in qr.modules.iter_mut().enumerate() {
+            if !qr.is_function[i] {
+                *module = sampled[i];
+            }
+        }
+
+        qr.apply_mask(mask);
+        let codewords = qr.read_codewords(num_raw_data_modules(version) / 8);
+        let data = correct_and_deinterleave(&codewords, version, ecl)?;
+        parse_segments(&data, version)
+    }
+
+    /// Reads the error correction level and mask, picking whichever valid
+    /// format code is closest to either copy
+    fn read_format_bits(&self) -> Result<(EccLevel, u8), AppError> {
+        let size = self.size as i32;
+        let dark = |x: i32, y: i32| u32::from(self.module(x, y));
+
+        let mut first = 0;
+        for i in 0..=5 {
+            first |= dark(8, i) << i;
+        }
+        first |= dark(8, 7) << 6 | dark(8, 8) << 7 | dark(7, 8) << 8;
+        for i in 9..15 {
+            first |= dark(14 - i, 8) << i;
+        }
+
+        let mut second = 0;
+        for i in 0..8 {
+            second |= dark(size - 1 - i, 8) << i;
+        }
+        for i in 8..15 {
+            second |= dark(8, size - 15 + i) << i;
+        }
+
+        (0..32u32)
+            .map(|data| {
+                let mut remainder = data;
+                for _ in 0..10 {
+                    remainder = (remainder << 1) ^ ((remainder >> 9) * 0x537);
+                }
+                let code = ((data << 10) | remainder) ^ 0x5412;
+                let distance = (code ^ first).count_ones().min((code ^ second).count_ones());
+                (distance, data)
+            })
+            .min()
+            .filter(|(distance, _)| *distance <= 3)
+            .map(|(_, data)| (EccLevel::from_format_bits(data >> 3), (data & 7) as u8))
+            .ok_or_else(|| AppError::InvalidInput("QR code format information is unreadable".to_string()))
+    }
+
+    /// Reads codewords in the same zigzag order [`draw_codewords`](Self::draw_codewords) writes them
+    fn read_codewords(&self, count: usize) -> Vec<u8> {
+        let size = self.size;
+        let mut result = vec![0u8; count];
+        let mut index = 0;
+
+        let mut right = size as i32 - 1;
+        while right >= 1 {
+            if right == 6 {
+                right = 5;
+            }
+            for vert in 0..size {
+                for j in 0..2 {
+                    let x = (right - j) as usize;
+                    let upward = (right + 1) & 2 == 0;
+                    let y = if upward { size - 1 - vert } else { vert };
+                    if !self.is_function[y * size + x] && index < count * 8 {
+                        if self.modules[y * size + x] {
+                            result[index >> 3] |= 0x80 >> (index & 7);
+                        }
+                        index += 1;
+                    }
+                }
+            }
+            right -= 2;
+        }
+        result
+    }
+
+    fn set_function(&mut self, x: usize, y: usize, dark: bool) {
+        self.modules[y * self.size + x] = dark;
+        self.is_function[y * self.size + x] = true;
+    }
+
+    fn draw_function_patterns(&mut self, version: usize, ecl: EccLevel) {
+        // Timing patterns
+        for i in 0..self.size {
+            self.set_function(6, i, i % 2 == 0);
+            self.set_function(i, 6, i % 2 == 0);
+        }
+
+        // Finder patterns and separators
+        let far = self.size as i32 - 4;
+        self.draw_finder_pattern(3, 3);
+        self.draw_finder_pattern(far, 3);
+        self.draw_finder_pattern(3, far);
+
+        // Alignment patterns, skipping the three finder corners
+        let positions = alignment_pattern_positions(version);
+        let count = positions.len();
+        for i in 0..count {
+            for j in 0..count {
+                let corner = (i == 0 && (j == 0 || j == count - 1)) || (i == count - 1 && j == 0);
+                if !corner {
+                    self.draw_alignment_pattern(positions[i], positions[j]);
+                }
+            }
+        }
+
+        // Placeholder format bits, overwritten once the mask is chosen
+        self.draw_format_bits(ecl, 0);
+        self.draw_version(version);
+    }
+
+    fn draw_finder_pattern(&mut self, x: i32, y: i32) {
+        for dy in -4..=4 {
+            for dx in -4..=4 {
+                let (xx, yy) = (x + dx, y + dy);
+                let size = self.size as i32;
+                if (0..size).contains(&xx) && (0..size).contains(&yy) {
+                    let distance = dx.abs().max(dy.abs());
+                    self.set_function(xx as usize, yy as usize, distance != 2 && distance != 4);
+                }
+            }
+        }
+    }
+
+    fn draw_alignment_pattern(&mut self, x: usize, y: usize) {
+        for dy in -2i32..=2 {
+            for dx in -2i32..=2 {
+                let dark = dx.abs().max(dy.abs()) != 1;
+                self.set_function((x as i32 + dx) as usize, (y as i32 + dy) as usize, dark);
+            }
+        }
+    }
+
+    fn draw_format_bits(&mut self, ecl: EccLevel, mask: u8) {
+        let data = (ecl.format_bits() << 3) | mask as u32;
+        let mut remainder = data;
+        for _ in 0..10 {
+            remainder = (remainder << 1) ^ ((remainder >> 9) * 0x537);
+        }
+        let bits = ((data << 10) | remainder) ^ 0x5412;
+        let size = self.size;
+
+        // First copy, around the top-left finder
+        for i in 0..=5 {
+            self.set_function(8, i, bit(bits, i));
+        }
+        self.set_function(8, 7, bit(bits, 6));
+        self.set_function(8, 8, bit(bits, 7));
+        self.set_function(7, 8, bit(bits, 8));
+        for i in 9..15 {
+            self.set_function(14 - i, 8, bit(bits, i));
+        }
+
+        // Second copy, split between the other two finders
+        for i in 0..8 {
+            self.set_function(size - 1 - i, 8, bit(bits, i));
+        }
+        for i in 8..15 {
+            self.set_function(8, size - 15 + i, bit(bits, i));
+        }
+        self.set_function(8, size - 8, true); // Always dark
+    }
+
+    fn draw_version(&mut self, version: usize) {
+        if version < 7 {
+            return;
+        }
+
+        let mut remainder = version as u32;
+        for _ in 0..12 {
+            remainder = (remainder << 1) ^ ((remainder >> 11) * 0x1F25);
+        }
+        let bits = ((version as u32) << 12) | remainder;
+
+        for i in 0..18 {
+            let dark = bit(bits, i);
+            let a = self.size - 11 + i % 3;
+            let b = i / 3;
+            self.set_function(a, b, dark);
+            self.set_function(b, a, dark);
+        }
+    }
+
+    fn draw_codewords(&mut self, data: &[u8]) {
+        let size = self.size;
+        let total_bits = data.len() * 8;
+        let mut index = 0;
+
+        // Zigzag over pairs of columns from the right, skipping the vertical timing column
+        let mut right = size as i32 - 1;
+        while right >= 1 {
+            if right == 6 {
+                right = 5;
+            }
+            for vert in 0..size {
+                for j in 0..2 {
+                    let x = (right - j) as usize;
+                    let upward = (right + 1) & 2 == 0;
+                    let y = if upward { size - 1 - vert } else { vert };
+                    if !self.is_function[y * size + x] && index < total_bits {
+                        self.modules[y * size + x] = bit(data[index >> 3] as u32, 7 - (index & 7));
+                        index += 1;
+                    }
+                }
+            }
+            right -= 2;
+        }
+    }
+
+    fn apply_mask(&mut self, mask: u8) {
+        let size = self.size;
+        for y in 0..size {
+            for x in 0..size {
+                let invert = match mask {
+                    0 => (x + y) % 2 == 0,
+                    1 => y % 2 == 0,
+                    2 => x % 3 == 0,
+                    3 => (x + y) % 3 == 0,
+                    4 => (x / 3 + y / 2) % 2 == 0,
+                    5 => x * y % 2 + x * y % 3 == 0,
+                    6 => (x * y % 2 + x * y % 3) % 2 == 0,
+                    _ => ((x + y) % 2 + x * y % 3) % 2 == 0,
+                };
+                let index = y * size + x;
+                if invert && !self.is_function[index] {
+                    self.modules[index] = !self.modules[index];
+                }
+            }
+        }
+    }
+
+    /// Simplified penalty score (runs, 2x2 blocks and dark balance); any mask
+    /// decodes correctly, the score only helps scanners
+    fn penalty_score(&self) -> u32 {
+        let size = self.size as i32;
+        let mut penalty = 0;
+
+        for horizontal in [true, false] {
+            for a in 0..size {
+                let mut run_color = false;
+                let mut run_length = 0;
+                for b in 0..size {
+                    let dark = if horizontal { self.module(b, a) } else { self.module(a, b) };
+                    if b > 0 && dark == run_color {
+                        run_length += 1;
+                        if run_length == 5 {
+                            penalty += 3;
+                        } else if run_length > 5 {
+                            penalty += 1;
+                        }
+                    } else {
+                        run_color = dark;
+                        run_length = 1;
+                    }
+                }
+            }
+        }
+
+        for y in 0..size - 1 {
+            for x in 0..size - 1 {
+                let color = self.module(x, y);
+                if color == self.module(x + 1, y)
+                    && color == self.module(x, y + 1)
+                    && color == self.module(x + 1, y + 1)
+                {
+                    penalty += 3;
+                }
+            }
+        }
+
+        let dark = self.modules.iter().filter(|&&m| m).count() as i64;
+        let total = (self.size * self.size) as i64;
+        let k = ((dark * 20 - total * 10).abs() + total - 1) / total - 1;
+        penalty + k.max(0) as u32 * 10
+    }
+}
+
+fn append_bits(bits: &mut Vec<bool>, value: u32, length: usize) {
+    for i in (0..length).rev() {
+        bits.push((value >> i) & 1 != 0);
+    }
+}
+
+fn bit(value: u32, index: usize) -> bool {
+    (value >> index) & 1 != 0
+}
+
+fn alignment_pattern_positions(version: usize) -> Vec<usize> {
+    if version == 1 {
+        return Vec::new();
+    }
+
+    let count = version / 7 + 2;
+    let step = (version * 8 + count * 3 + 5) / (count * 4 - 4) * 2;
+    let size = version * 4 + 17;
+    let mut positions: Vec<usize> = (0..count - 1).map(|i| size - 7 - i * step).collect();
+    positions.push(6);
+    positions.reverse();
+    positions
+}
+
+fn num_raw_data_modules(version: usize) -> usize {
+    let mut result = (16 * version + 128) * version + 64;
+    if version >= 2 {
+        let count = version / 7 + 2;
+        result -= (25 * count - 10) * count - 55;
+        if version >= 7 {
+            result -= 36;
+        }
+    }
+    result
+}
+
+fn num_data_codewords(version: usize, ecl: EccLevel) -> usize {
+    num_raw_data_modules(version) / 8
+        - ECC_CODEWORDS_PER_BLOCK[ecl.ordinal()][version] as usize
+            * NUM_ERROR_CORRECTION_BLOCKS[ecl.ordinal()][version] as usize
+}
+
+fn add_ecc_and_interleave(data: &[u8], version: usize, ecl: EccLevel) -> Vec<u8> {
+    let num_blocks = NUM_ERROR_CORRECTION_BLOCKS[ecl.ordinal()][version] as usize;
+    let block_ecc_len = ECC_CODEWORDS_PER_BLOCK[ecl.ordinal()][version] as usize;
+    let raw_codewords = num_raw_data_modules(version) / 8;
+    let num_short_blocks = num_blocks - raw_codewords % num_blocks;
+    let short_block_len = raw_codewords / num_blocks;
+
+    let divisor = reed_solomon_divisor(block_ecc_len);
+    let mut blocks: Vec<Vec<u8>> = Vec::with_capacity(num_blocks);
+    let mut offset = 0;
+    for i in 0..num_blocks {
+        let data_len = short_block_len - block_ecc_len + usize::from(i >= num_short_blocks);
+        let mut block = data[offset..offset + data_len].to_vec();
+        offset += data_len;
+        let ecc = reed_solomon_remainder(&block, &divisor);
+        if i < num_short_blocks {
+            block.push(0);
+        }
+        block.extend(ecc);
+        blocks.push(block);
+    }
+
+    let mut result = Vec::with_capacity(raw_codewords);
+    for i in 0..blocks[0].len() {
+        for (j, block) in blocks.iter().enumerate() {
+            // Skip the padding byte in short blocks
+            if i != short_block_len - block_ecc_len || j >= num_short_blocks {
+                result.push(block[i]);
+            }
+        }
+    }
+    result
+}
+
+/// Splits interleaved codewords back into blocks, fixes errors in each and
+/// returns the data codewords in order
+fn correct_and_deinterleave(codewords: &[u8], version: usize, ecl: EccLevel) -> Result<Vec<u8>, AppError> {
+    let num_blocks = NUM_ERROR_CORRECTION_BLOCKS[ecl.ordinal()][version] as usize;
+    let block_ecc_len = ECC_CODEWORDS_PER_BLOCK[ecl.ordinal()][version] as usize;
+    let raw_codewords = num_raw_data_modules(version) / 8;
+    let num_short_blocks = num_blocks - raw_codewords % num_blocks;
+    let short_block_len = raw_codewords / num_blocks;
+    let short_data_len = short_block_len - block_ecc_len;
+
+    let mut blocks: Vec<Vec<u8>> = (0..num_blocks)
+        .map(|j| Vec::with_capacity(short_block_len + usize::from(j >= num_short_blocks)))
+        .collect();
+    let mut next = codewords.iter();
+    // Same order as add_ecc_and_interleave, where short blocks carry a padding byte
+    for i in 0..=short_block_len {
+        for (j, block) in blocks.iter_mut().enumerate() {
+            // Short blocks have no codeword at the last data position
+            if i == short_data_len && j < num_short_blocks {
+                continue;
+            }
+            if let Some(&codeword) = next.next() {
+                block.push(codeword);
+            }
+        }
+    }
+
+    let mut data = Vec::new();
+    for mut block in blocks {
+        reed_solomon_correct(&mut block, block_ecc_len)?;
+        let data_len = block.len() - block_ecc_len;
+        data.extend_from_slice(&block[..data_len]);
+    }
+    Ok(data)
+}
+
+/// Corrects up to `ecc_len / 2` wrong codewords in place (Berlekamp-Massey,
+/// Chien search and Forney's formula)
+fn reed_solomon_correct(block: &mut [u8], ecc_len: usize) -> Result<(), AppError> {
+    let mut exp = [0u8; 512];
+    let mut log = [0usize; 256];
+    let mut x = 1u8;
+    for (i, e) in exp.iter_mut().enumerate().take(255) {
+        *e = x;
+        log[x as usize] = i;
+        x = gf_multiply(x, 2);
+    }
+    for i in 255..512 {
+        exp[i] = exp[i - 255];
+    }
+    let inverse = |a: u8| exp[255 - log[a as usize]];
+    let evaluate = |poly: &[u8], x: u8| poly.iter().rev().fold(0u8, |acc, &c| gf_multiply(acc, x) ^ c);
+
+    // Syndromes S_i = block(α^i), with block[0] as the highest-degree coefficient
+    let syndromes: Vec<u8> = (0..ecc_len)
+        .map(|i| block.iter().fold(0u8, |acc, &c| gf_multiply(acc, exp[i]) ^ c))
+        .collect();
+    if syndromes.iter().all(|&s| s == 0) {
+        return Ok(());
+    }
+
+    // Error locator polynomial, lowest degree first
+    let mut locator = vec![1u8];
+    let mut previous = vec![1u8];
+    let mut errors = 0;
+    let mut shift = 1;
+    let mut previous_discrepancy = 1u8;
+    for n in 0..ecc_len {
+        let discrepancy = (1..=errors).fold(syndromes[n], |acc, i| {
+            acc ^ gf_multiply(*locator.get(i).unwrap_or(&0), syndromes[n - i])
+        });
+        if discrepancy == 0 {
+            shift += 1;
+            continue;
+        }
+
+        let scale = gf_multiply(discrepancy, inverse(previous_discrepancy));
+        let mut updated = locator.clone();
+        updated.resize(updated.len().max(previous.len() + shift), 0);
+        for (i, &p) in previous.iter().enumerate() {
+            updated[i + shift] ^= gf_multiply(scale, p);
+        }
+
+        if 2 * errors <= n {
+            previous = std::mem::replace(&mut locator, updated);
+            errors = n + 1 - errors;
+            previous_discrepancy = discrepancy;
+            shift = 1;
+        } else {
+            locator = updated;
+            shift += 1;
+        }
+    }
+    locator.truncate(errors + 1);
+
+    // Error evaluator Ω(x) = S(x)Λ(x) mod x^ecc_len
+    let mut evaluator = vec![0u8; ecc_len];
+    for (i, &s) in syndromes.iter().enumerate() {
+        for (j, &l) in locator.iter().enumerate() {
+            if i + j < ecc_len {
+                evaluator[i + j] ^= gf_multiply(s, l);
+            }
+        }
+    }
+    // Formal derivative Λ'(x): only odd-power terms survive in GF(2^8)
+    let derivative: Vec<u8> = locator
+        .iter()
+        .enumerate()
+        .skip(1)
+        .map(|(i, &l)| if i % 2 == 1 { l } else { 0 })
+        .collect();
+
+    let n = block.len();
+    let mut found = 0;
+    for (position, codeword) in block.iter_mut().enumerate() {
+        let power = (n - 1 - position) % 255;
+        let x_inverse = exp[(255 - power) % 255];
+        if evaluate(&locator, x_inverse) != 0 {
+            continue;
+        }
+        let denominator = evaluate(&derivative, x_inverse);
+        if denominator == 0 {
+            break;
+        }
+        let magnitude = gf_multiply(exp[power], gf_multiply(evaluate(&evaluator, x_inverse), inverse(denominator)));
+        *codeword ^= magnitude;
+        found += 1;
+    }
+
+    if found != errors || 2 * errors > ecc_len {
+        return Err(AppError::InvalidInput("QR code is too damaged to read".to_string()));
+    }
+    Ok(())
+}
+
+/// Extracts the bytes from numeric, alphanumeric and byte mode segments
+fn parse_segments(data: &[u8], version: usize) -> Result<Vec<u8>, AppError> {
+    const ALPHANUMERIC: &[u8; 45] = b"0123456789ABCDEFGHIJKLMNOPQRSTUVWXYZ $%*+-./:";
+
+    let total_bits = data.len() * 8;
+    let mut position = 0;
+    let mut read = |count: usize| -> Option<u32> {
+        if position + count > total_bits {
+            return None;
+        }
+        let value = (position..position + count)
+            .fold(0u32, |acc, i| (acc << 1) | u32::from(data[i >> 3] >> (7 - (i & 7)) & 1));
+        position += count;
+        Some(value)
+    };
+    let size_class = match version {
+        1..=9 => 0,
+        10..=26 => 1,
+        _ => 2,
+    };
+    let truncated = || AppError::InvalidInput("QR code data is truncated".to_string());
+
+    let mut output = Vec::new();
+    while let Some(mode) = read(4) {
+        match mode {
+            0b0000 => break,
+            0b0001 => {
+                let mut count = read([10, 12, 14][size_class]).ok_or_else(truncated)? as usize;
+                while count > 0 {
+                    let digits = count.min(3);
+                    let value = read([4, 7, 10][digits - 1]).ok_or_else(truncated)?;
+                    output.extend(format!("{:0width$}", value, width = digits).bytes());
+                    count -= digits;
+                }
+            }
+            0b0010 => {
+                let mut count = read([9, 11, 13][size_class]).ok_or_else(truncated)? as usize;
+                while count >= 2 {
+                    let value = read(11).ok_or_else(truncated)? as usize;
+                    output.push(*ALPHANUMERIC.get(value / 45).ok_or_else(truncated)?);
+                    output.push(ALPHANUMERIC[value % 45]);
+                    count -= 2;
+                }
+                if count == 1 {
+                    let value = read(6).ok_or_else(truncated)? as usize;
+                    output.push(*ALPHANUMERIC.get(value).ok_or_else(truncated)?);
+                }
+            }
+            0b0100 => {
+                let count = read([8, 16, 16][size_class]).ok_or_else(truncated)?;
+                for _ in 0..count {
+                    output.push(read(8).ok_or_else(truncated)? as u8);
+                }
+            }
+            0b0111 => {
+                // ECI designator: UTF-8 is assumed, so just skip it
+                let first = read(8).ok_or_else(truncated)?;
+                let extra = if first & 0x80 == 0 { 0 } else if first & 0x40 == 0 { 8 } else { 16 };
+                read(extra).ok_or_else(truncated)?;
+            }
+            _ => {
+                return Err(AppError::InvalidInput(
+                    "QR code uses an unsupported data mode".to_string(),
+                ));
+            }
+        }
+    }
+
+    Ok(output)
+}
+
+fn reed_solomon_divisor(degree: usize) -> Vec<u8> {
+    let mut result = vec![0u8; degree];
+    result[degree - 1] = 1;
+    let mut root = 1u8;
+    for _ in 0..degree {
+        for j in 0..degree {
+            result[j] = gf_multiply(result[j], root);
+            if j + 1 < degree {
+                result[j] ^= result[j + 1];
+            }
+        }
+        root = gf_multiply(root, 0x02);
+    }
+    result
+}
+
+fn reed_solomon_remainder(data: &[u8], divisor: &[u8]) -> Vec<u8> {
+    let mut result = vec![0u8; divisor.len()];
+    for &byte in data {
+        let factor = byte ^ result.remove(0);
+        result.push(0);
+        for (r, &d) in result.iter_mut().zip(divisor) {
+            *r ^= gf_multiply(d, factor);
+        }
+    }
+    result
+}
+
+/// Multiplication in GF(2^8) modulo x^8 + x^4 + x^3 + x^2 + 1
+pub(crate) fn gf_multiply(x: u8, y: u8) -> u8 {
+    let mut z: u8 = 0;
+    for i in (0..8).rev() {
+        z = (z << 1) ^ ((z >> 7) * 0x1D);
+        z ^= ((y >> i) & 1) * x;
+    }
+    z
+}
@@ -0,0 +1,59 @@
+//! Encrypted recovery codes attached to an account.
+
+use serde::{Serialize, Deserialize};
+use crate::crypto::{self, EncryptedData};
+use crate::error::AppError;
+
+/// A single one-time recovery code
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct RecoveryCode {
+    pub code: String,
+    #[serde(default)]
+    pub used: bool,
+}
+
+/// An account's recovery codes, encrypted at rest with a passphrase.
+/// Only the number of unused codes is kept in plain text.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct RecoveryCodes {
+    data: EncryptedData,
+    remaining: usize,
+}
+
+impl RecoveryCodes {
+    /// Encrypts a list of recovery codes with the given passphrase
+    pub fn seal(codes: &[RecoveryCode], passphrase: &str) -> Result<Self, AppError> {
+        let json = serde_json::to_vec(codes)
+            .map_err(|e| AppError::JsonError(format!("Failed to serialize recovery codes: {}", e)))?;
+
+        Ok(Self {
+            data: crypto::encrypt(&json, passphrase),
+            remaining: codes.iter().filter(|c| !c.used).count(),
+        })
+    }
+
+    /// Decrypts the recovery codes with the given passphrase
+    pub fn open(&self, passphrase: &str) -> Result<Vec<RecoveryCode>, AppError> {
+        let json = crypto::decrypt(&self.data, passphrase)?;
+        serde_json::from_slice(&json)
+            .map_err(|e| AppError::JsonError(format!("Failed to parse recovery codes: {}", e)))
+    }
+
+    /// Number of codes not yet marked as used
+    pub fn remaining(&self) -> usize {
+        self.remaining
+    }
+}
+
+/// Parses recovery codes pasted as whitespace-, comma- or newline-separated text
+pub fn parse_codes(input: &str) -> Vec<RecoveryCode> {
+    input
+        .split(|c: char| c.is_whitespace() || c == ',' || c == ';')
+        .map(|code| code.trim())
+        .filter(|code| !code.is_empty())
+        .map(|code| RecoveryCode {
+            code: code.to_string(),
+            used: false,
+        })
+        .collect()
+}
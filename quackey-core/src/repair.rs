@@ -0,0 +1,154 @@
+//! Recovery of accounts from a storage file that no longer parses.
+
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::time::SystemTime;
+use chrono::Local;
+use serde_json::Value;
+use crate::account::Account;
+use crate::error::AppError;
+use crate::storage::backups_dir_for;
+
+/// Accounts recovered from a damaged storage file
+pub struct Salvage {
+    pub accounts: Vec<Account>,
+    /// Entries that looked like accounts but could not be read
+    pub lost: usize,
+}
+
+/// An earlier copy of the storage file that parses cleanly
+pub struct Backup {
+    pub path: PathBuf,
+    pub modified: SystemTime,
+    pub accounts: Vec<Account>,
+}
+
+/// Recovers every account that can still be read from damaged storage contents.
+///
+/// Valid JSON with a few malformed accounts keeps the rest. Broken JSON (e.g. a
+/// truncated write) is scanned for complete top-level `{...}` objects instead.
+pub fn salvage_accounts(contents: &str) -> Salvage {
+    if let Ok(values) = serde_json::from_str::<Vec<Value>>(contents) {
+        let total = values.len();
+        let accounts: Vec<Account> = values
+            .into_iter()
+            .filter_map(|v| serde_json::from_value(v).ok())
+            .collect();
+        return Salvage { lost: total - accounts.len(), accounts };
+    }
+
+    let mut accounts = Vec::new();
+    let mut lost = 0;
+    let mut depth = 0usize;
+    let mut start = None;
+    let mut in_string = false;
+    let mut escaped = false;
+
+    for (i, c) in contents.char_indices() {
+        if in_string {
+            match c {
+                _ if escaped => escaped = false,
+                '\\' => escaped = true,
+                '"' => in_string = false,
+                _ => {}
+            }
+            continue;
+        }
+
+        match c {
+            '"' => in_string = true,
+            '{' => {
+                // Objects sit one level inside the top-level array
+                if depth == 1 {
+                    start = Some(i);
+                }
+                depth += 1;
+            }
+            '}' => {
+                depth = depth.saturating_sub(1);
+                if depth == 1
+                    && let Some(s) = start.take()
+                {
+                    match serde_json::from_str::<Account>(&contents[s..=i]) {
+                        Ok(account) => accounts.push(account),
+                        Err(_) => lost += 1,
+                    }
+                }
+            }
+            '[' => depth += 1,
+            ']' => depth = depth.saturating_sub(1),
+            _ => {}
+        }
+    }
+
+    // An object that was still open when the file ended
+    if start.is_some() {
+        lost += 1;
+    }
+
+    Salvage { accounts, lost }
+}
+
+/// Earlier copies of the storage file that still parse, newest first: vault
+/// snapshots in the backups directory and siblings such as `accounts.json.bak`
+pub fn find_backups(storage_path: &str) -> Vec<Backup> {
+    let mut backups: Vec<Backup> = backup_paths(storage_path)
+        .into_iter()
+        .filter_map(|path| {
+            let modified = fs::metadata(&path).and_then(|m| m.modified()).ok()?;
+            let contents = fs::read_to_string(&path).ok()?;
+            let accounts = serde_json::from_str(&contents).ok()?;
+            Some(Backup { path, modified, accounts })
+        })
+        .collect();
+
+    backups.sort_by_key(|b| std::cmp::Reverse(b.modified));
+    backups
+}
+
+/// Files that may be earlier copies of the storage file, in any format:
+/// vault snapshots in the backups directory and siblings such as
+/// `accounts.json.bak`
+pub fn backup_paths(storage_path: &str) -> Vec<PathBuf> {
+    let path = Path::new(storage_path);
+    let Some(file_name) = path.file_name().and_then(|n| n.to_str()) else {
+        return Vec::new();
+    };
+    let dir = match path.parent() {
+        Some(parent) if !parent.as_os_str().is_empty() => parent,
+        _ => Path::new("."),
+    };
+    let prefix = format!("{}.", file_name);
+
+    let siblings = list_files(dir).filter(|p| {
+        p.file_name().and_then(|n| n.to_str()).is_some_and(|n| n.starts_with(&prefix))
+    });
+    let snapshots = list_files(&backups_dir_for(storage_path))
+        .filter(|p| p.extension().is_some_and(|e| e == "json"));
+    siblings.chain(snapshots).collect()
+}
+
+fn list_files(dir: &Path) -> impl Iterator<Item = PathBuf> + use<> {
+    fs::read_dir(dir)
+        .into_iter()
+        .flatten()
+        .filter_map(|entry| entry.ok())
+        .map(|entry| entry.path())
+        .filter(|path| path.is_file())
+}
+
+/// Moves a damaged storage file out of the way without deleting it, returning
+/// where it went
+pub fn preserve_damaged_file(storage_path: &str) -> Result<PathBuf, AppError> {
+    let target = PathBuf::from(format!(
+        "{}.damaged-{}",
+        storage_path,
+        Local::now().format("%Y%m%d-%H%M%S")
+    ));
+
+    fs::rename(storage_path, &target).map_err(|e| {
+        AppError::FileError(format!("Failed to move damaged file '{}': {}", storage_path, e))
+    })?;
+
+    Ok(target)
+}
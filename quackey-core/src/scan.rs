@@ -0,0 +1,288 @@
+//! Finds and reads a QR code in a screenshot.
+//!
+//! Screenshots are sharp and not skewed by perspective, so this locates the
+//! three finder patterns with the classic 1:1:3:1:1 run scan, maps the module
+//! grid onto the image with an affine transform and hands the sampled grid to
+//! [`QrCode::decode_modules`].
+
+use crate::error::AppError;
+use crate::qr::QrCode;
+
+/// A finder pattern candidate: center in pixels and estimated module size
+#[derive(Debug, Clone, Copy)]
+struct Finder {
+    x: f64,
+    y: f64,
+    module: f64,
+    hits: usize,
+}
+
+/// Decodes the first QR code found in an RGBA image
+pub fn decode_rgba(width: usize, height: usize, rgba: &[u8]) -> Result<Vec<u8>, AppError> {
+    if width == 0 || height == 0 || rgba.len() < width * height * 4 {
+        return Err(AppError::InvalidInput("The image is empty".to_string()));
+    }
+
+    let luma: Vec<u8> = rgba
+        .chunks_exact(4)
+        .take(width * height)
+        .map(|p| {
+            // Treat transparent pixels as white background
+            let alpha = p[3] as u32;
+            let gray = (p[0] as u32 * 299 + p[1] as u32 * 587 + p[2] as u32 * 114) / 1000;
+            (gray * alpha / 255 + (255 - alpha)) as u8
+        })
+        .collect();
+    let threshold = otsu_threshold(&luma);
+
+    // Dark-on-light first, then inverted for dark-mode screenshots
+    let mut last_error = None;
+    for inverted in [false, true] {
+        let image = Binary {
+            width,
+            height,
+            dark: luma.iter().map(|&l| (l < threshold) != inverted).collect(),
+        };
+        match image.decode() {
+            Ok(data) => return Ok(data),
+            Err(e) => last_error = Some(e),
+        }
+    }
+
+    Err(last_error.unwrap_or_else(|| AppError::InvalidInput("No QR code found in the image".to_string())))
+}
+
+/// Threshold that best separates dark and light pixels (Otsu's method)
+fn otsu_threshold(luma: &[u8]) -> u8 {
+    let mut histogram = [0u64; 256];
+    for &l in luma {
+        histogram[l as usize] += 1;
+    }
+
+    let total = luma.len() as f64;
+    let sum: f64 = histogram.iter().enumerate().map(|(i, &c)| i as f64 * c as f64).sum();
+    let (mut background, mut background_sum) = (0.0, 0.0);
+    let (mut best, mut best_variance) = (128u8, 0.0);
+
+    for (t, &count) in histogram.iter().enumerate() {
+        background += count as f64;
+        if background == 0.0 {
+            continue;
+        }
+        let foreground = total - background;
+        if foreground == 0.0 {
+            break;
+        }
+        background_sum += t as f64 * count as f64;
+        let mean_background = background_sum / background;
+        let mean_foreground = (sum - background_sum) / foreground;
+        let variance = background * foreground * (mean_background - mean_foreground).powi(2);
+        if variance > best_variance {
+            best_variance = variance;
+            best = t as u8 + 1;
+        }
+    }
+    best
+}
+
+struct Binary {
+    width: usize,
+    height: usize,
+    dark: Vec<bool>,
+}
+
+impl Binary {
+    fn is_dark(&self, x: f64, y: f64) -> bool {
+        let (x, y) = (x.round(), y.round());
+        x >= 0.0
+            && y >= 0.0
+            && (x as usize) < self.width
+            && (y as usize) < self.height
+            && self.dark[y as usize * self.width + x as usize]
+    }
+
+    fn decode(&self) -> Result<Vec<u8>, AppError> {
+        let finders = self.find_finders();
+        let (top_left, top_right, bottom_left) = pick_corners(&finders)
+            .ok_or_else(|| AppError::InvalidInput("No QR code found in the image".to_string()))?;
+
+        // Finder centers sit 3.5 modules in from the edges
+        let module = (top_left.module + top_right.module + bottom_left.module) / 3.0;
+        let across = distance(top_left, top_right).max(distance(top_left, bottom_left)) / module + 7.0;
+        let version = ((across - 17.0) / 4.0).round().clamp(1.0, 40.0) as usize;
+
+        // Neighbouring versions cover small errors in the module size estimate
+        let mut last_error = None;
+        for version in [version, version + 1, version.saturating_sub(1)] {
+            if !(1..=40).contains(&version) {
+                continue;
+            }
+            let size = version * 4 + 17;
+            let span = size as f64 - 7.0;
+            let dx = ((top_right.x - top_left.x) / span, (top_right.y - top_left.y) / span);
+            let dy = ((bottom_left.x - top_left.x) / span, (bottom_left.y - top_left.y) / span);
+
+            let result = QrCode::decode_modules(size, |x, y| {
+                let u = x as f64 + 0.5 - 3.5;
+                let v = y as f64 + 0.5 - 3.5;
+                self.is_dark(top_left.x + u * dx.0 + v * dy.0, top_left.y + u * dx.1 + v * dy.1)
+            });
+            match result {
+                Ok(data) => return Ok(data),
+                Err(e) => last_error = Some(e),
+            }
+        }
+        Err(last_error.expect("at least one version was tried"))
+    }
+
+    /// Scans every row for 1:1:3:1:1 runs, confirms them vertically and
+    /// merges repeated hits on the same pattern
+    fn find_finders(&self) -> Vec<Finder> {
+        let mut finders: Vec<Finder> = Vec::new();
+
+        for y in 0..self.height {
+            let row = &self.dark[y * self.width..(y + 1) * self.width];
+            let mut runs: Vec<(usize, usize)> = Vec::new(); // (start, length) of alternating runs
+            let mut start = 0;
+            for x in 1..=self.width {
+                if x == self.width || row[x] != row[start] {
+                    runs.push((start, x - start));
+                    start = x;
+                }
+            }
+
+            for window in runs.windows(5) {
+                if !row[window[0].0] {
+                    continue;
+                }
+                let lengths = [window[0].1, window[1].1, window[2].1, window[3].1, window[4].1];
+                let Some(module) = finder_ratio(lengths) else { continue };
+
+                let center_x = window[2].0 as f64 + window[2].1 as f64 / 2.0 - 0.5;
+                let Some((center_y, vertical_module)) = self.check_vertical(center_x, y as f64, module) else {
+                    continue;
+                };
+                let candidate = Finder {
+                    x: center_x,
+                    y: center_y,
+                    module: (module + vertical_module) / 2.0,
+                    hits: 1,
+                };
+
+                match finders.iter_mut().find(|f| {
+                    (f.x - candidate.x).abs() < f.module * 2.0 && (f.y - candidate.y).abs() < f.module * 2.0
+                }) {
+                    Some(existing) => {
+                        let n = existing.hits as f64;
+                        existing.x = (existing.x * n + candidate.x) / (n + 1.0);
+                        existing.y = (existing.y * n + candidate.y) / (n + 1.0);
+                        existing.module = (existing.module * n + candidate.module) / (n + 1.0);
+                        existing.hits += 1;
+                    }
+                    None => finders.push(candidate),
+                }
+            }
+        }
+
+        finders
+    }
+
+    /// Walks up and down from a horizontal hit and checks the column also
+    /// reads 1:1:3:1:1, returning the vertical center and module size
+    fn check_vertical(&self, x: f64, y: f64, module: f64) -> Option<(f64, f64)> {
+        let max = (module * 5.0).ceil() as i64;
+        let column = |y: i64| self.is_dark(x, y as f64);
+        let y = y as i64;
+
+        // Count runs outward from the center: dark, light, dark
+        let mut up = [0i64; 3];
+        let mut cursor = y;
+        for (i, expected) in [true, false, true].iter().enumerate() {
+            while cursor >= 0 && column(cursor) == *expected && up[i] <= max {
+                up[i] += 1;
+                cursor -= 1;
+            }
+        }
+        let mut down = [0i64; 3];
+        let mut cursor = y + 1;
+        for (i, expected) in [true, false, true].iter().enumerate() {
+            while (cursor as usize) < self.height && column(cursor) == *expected && down[i] <= max {
+                down[i] += 1;
+                cursor += 1;
+            }
+        }
+
+        let lengths = [
+            up[2] as usize,
+            up[1] as usize,
+            (up[0] + down[0]) as usize,
+            down[1] as usize,
+            down[2] as usize,
+        ];
+        let vertical = finder_ratio(lengths)?;
+        if (vertical - module).abs() > module * 0.5 {
+            return None;
+        }
+
+        let center = (y - up[0] + 1) as f64 + (up[0] + down[0]) as f64 / 2.0 - 0.5;
+        Some((center, vertical))
+    }
+}
+
+/// Module size if the run lengths match the 1:1:3:1:1 finder ratio
+fn finder_ratio(lengths: [usize; 5]) -> Option<f64> {
+    let total: usize = lengths.iter().sum();
+    if total < 7 || lengths.contains(&0) {
+        return None;
+    }
+    let module = total as f64 / 7.0;
+    let tolerance = module / 2.0;
+    let ok = lengths
+        .iter()
+        .zip([1.0, 1.0, 3.0, 1.0, 1.0])
+        .all(|(&length, expected)| (length as f64 - expected * module).abs() < tolerance * expected.max(1.5));
+    ok.then_some(module)
+}
+
+/// Chooses the three finders forming the best right isosceles triangle,
+/// ordered top-left (the right angle), top-right and bottom-left
+fn pick_corners(finders: &[Finder]) -> Option<(Finder, Finder, Finder)> {
+    let mut candidates: Vec<Finder> = finders.to_vec();
+    candidates.sort_by_key(|f| std::cmp::Reverse(f.hits));
+    candidates.truncate(10);
+
+    let mut best: Option<(f64, (Finder, Finder, Finder))> = None;
+    for i in 0..candidates.len() {
+        for j in 0..candidates.len() {
+            for k in j + 1..candidates.len() {
+                if i == j || i == k {
+                    continue;
+                }
+                let (corner, a, b) = (candidates[i], candidates[j], candidates[k]);
+                let (ax, ay) = (a.x - corner.x, a.y - corner.y);
+                let (bx, by) = (b.x - corner.x, b.y - corner.y);
+                let (la, lb) = ((ax * ax + ay * ay).sqrt(), (bx * bx + by * by).sqrt());
+                if la < corner.module * 7.0 || lb < corner.module * 7.0 {
+                    continue;
+                }
+
+                // Right angle and equal legs both score zero
+                let cosine = (ax * bx + ay * by) / (la * lb);
+                let score = cosine.abs() + ((la - lb) / la.max(lb)).abs();
+                if score > 0.2 || best.as_ref().is_some_and(|(s, _)| *s <= score) {
+                    continue;
+                }
+
+                // With y pointing down, top-right is clockwise from bottom-left
+                let (top_right, bottom_left) = if ax * by - ay * bx > 0.0 { (a, b) } else { (b, a) };
+                best = Some((score, (corner, top_right, bottom_left)));
+            }
+        }
+    }
+
+    best.map(|(_, corners)| corners)
+}
+
+fn distance(a: Finder, b: Finder) -> f64 {
+    ((a.x - b.x).powi(2) + (a.y - b.y).powi(2)).sqrt()
+}
@@ -0,0 +1,324 @@
+//! Reading and normalizing secrets from users and scripts.
+
+use std::fmt;
+use std::io::Read;
+use std::process::Command;
+use crate::error::AppError;
+
+/// A reference kept in the vault instead of a secret, such as
+/// `cmd:pass show otp/github` or `env:GH_TOTP`, for users who don't want
+/// seeds in Quackey's file. It is resolved every time codes are generated.
+#[derive(Debug, Clone, PartialEq)]
+pub enum SecretReference {
+    /// The first line printed by a shell command
+    Command(String),
+    /// The value of an environment variable
+    Env(String),
+}
+
+impl SecretReference {
+    /// Reads a stored secret; `None` for an ordinary Base32 secret, which
+    /// can't contain a colon
+    pub fn parse(stored: &str) -> Option<Self> {
+        let stored = stored.trim();
+        if let Some(command) = stored.strip_prefix("cmd:") {
+            Some(SecretReference::Command(command.trim().to_string()))
+        } else {
+            stored
+                .strip_prefix("env:")
+                .map(|variable| SecretReference::Env(variable.trim().to_string()))
+        }
+    }
+
+    /// Fetches the secret and returns it as canonical Base32
+    pub fn resolve(&self) -> Result<String, AppError> {
+        let raw = match self {
+            SecretReference::Command(command) if command.is_empty() => {
+                return Err(AppError::InvalidInput("A cmd: reference needs a command".to_string()));
+            }
+            SecretReference::Env(variable) if variable.is_empty() => {
+                return Err(AppError::InvalidInput("An env: reference needs a variable name".to_string()));
+            }
+            SecretReference::Command(command) => read_from_command(command)?,
+            SecretReference::Env(variable) => std::env::var(variable).map_err(|_| {
+                AppError::InvalidInput(format!("Environment variable {} is not set", variable))
+            })?,
+        };
+        normalize_base32(&raw)
+    }
+}
+
+impl fmt::Display for SecretReference {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            SecretReference::Command(command) => write!(f, "cmd:{}", command),
+            SecretReference::Env(variable) => write!(f, "env:{}", variable),
+        }
+    }
+}
+
+/// How a secret key was handed out by the provider
+#[derive(Debug, Clone, Copy, PartialEq, Default)]
+#[non_exhaustive]
+pub enum SecretEncoding {
+    #[default]
+    Base32,
+    Hex,
+    Raw,
+}
+
+impl SecretEncoding {
+    pub const ALL: [SecretEncoding; 3] = [SecretEncoding::Base32, SecretEncoding::Hex, SecretEncoding::Raw];
+
+    pub fn label(&self) -> &'static str {
+        match self {
+            SecretEncoding::Base32 => "Base32 (most providers, e.g. 'JBSWY3DPEHPK3PXP')",
+            SecretEncoding::Hex => "Hex (e.g. '48656c6c6f21deadbeef')",
+            SecretEncoding::Raw => "Raw text (the key bytes as typed)",
+        }
+    }
+}
+
+/// Converts a secret in the given encoding to the canonical base32 form
+/// stored in the vault, so every exported URI carries the same key bytes
+pub fn to_canonical_base32(input: &str, encoding: SecretEncoding) -> Result<String, AppError> {
+    match encoding {
+        SecretEncoding::Base32 => normalize_base32(input),
+        SecretEncoding::Hex => Ok(encode_base32(&decode_hex(input)?)),
+        SecretEncoding::Raw => {
+            let raw = input.trim();
+            if raw.is_empty() {
+                return Err(AppError::InvalidInput("Secret key cannot be empty".to_string()));
+            }
+            Ok(encode_base32(raw.as_bytes()))
+        }
+    }
+}
+
+/// Warnings about secrets that decode fine but are unlikely to be real TOTP
+/// seeds, such as very short keys or something that looks like a password
+pub fn sanity_warnings(input: &str, encoding: SecretEncoding, bytes: &[u8]) -> Vec<String> {
+    let mut warnings = Vec::new();
+    let input = input.trim();
+
+    let bits = bytes.len() * 8;
+    if bits < 80 {
+        warnings.push(format!(
+            "The key decodes to only {} bits. Real TOTP seeds are at least 80 bits (usually 160).",
+            bits
+        ));
+    }
+
+    let significant: Vec<char> = input
+        .chars()
+        .filter(|c| !c.is_whitespace() && !matches!(c, '-' | '=' | ':'))
+        .collect();
+    if significant.len() > 1 && significant.iter().all(|c| c.eq_ignore_ascii_case(&significant[0])) {
+        warnings.push("The key is a single repeated character, which is almost certainly a placeholder.".to_string());
+    }
+
+    let has_upper = input.chars().any(|c| c.is_ascii_uppercase());
+    let has_lower = input.chars().any(|c| c.is_ascii_lowercase());
+    match encoding {
+        SecretEncoding::Base32 if has_upper && has_lower => warnings.push(
+            "The key mixes upper and lower case letters, which looks like a password rather than a generated base32 seed."
+                .to_string(),
+        ),
+        SecretEncoding::Raw => warnings.push(
+            "Raw text keys are rare. Make sure this is the provider's setup key and not your account password."
+                .to_string(),
+        ),
+        _ => {}
+    }
+
+    warnings
+}
+
+/// Encodes key bytes as unpadded uppercase base32
+pub fn encode_base32(bytes: &[u8]) -> String {
+    base32::encode(base32::Alphabet::Rfc4648 { padding: false }, bytes)
+}
+
+/// Decodes a hex secret, ignoring spaces, colons and a leading "0x"
+pub fn decode_hex(input: &str) -> Result<Vec<u8>, AppError> {
+    let input = input.trim();
+    let body = input
+        .strip_prefix("0x")
+        .or_else(|| input.strip_prefix("0X"))
+        .unwrap_or(input);
+    let offset = input.len() - body.len();
+
+    let mut digits = Vec::with_capacity(body.len());
+    for (index, c) in body.chars().enumerate() {
+        match c {
+            ' ' | '\t' | ':' | '-' => continue,
+            _ => match c.to_digit(16) {
+                Some(digit) => digits.push(digit as u8),
+                None => {
+                    return Err(invalid_character(
+                        input,
+                        index + offset,
+                        "hex only uses the digits 0-9 and letters A-F",
+                    ));
+                }
+            },
+        }
+    }
+
+    if digits.is_empty() {
+        return Err(AppError::InvalidInput("Secret key cannot be empty".to_string()));
+    }
+
+    if digits.len() % 2 != 0 {
+        return Err(AppError::InvalidInput(format!(
+            "Hex secret has an odd number of digits ({}). Check for a missing or extra character.",
+            digits.len()
+        )));
+    }
+
+    Ok(digits.chunks(2).map(|pair| (pair[0] << 4) | pair[1]).collect())
+}
+
+/// Normalizes a base32 secret as typed or pasted: lowercase is accepted, spaces
+/// and dashes are ignored and trailing `=` padding is dropped. Invalid
+/// characters are reported with a pointer to their position in the input.
+pub fn normalize_base32(input: &str) -> Result<String, AppError> {
+    let input = input.trim();
+    let mut normalized = String::with_capacity(input.len());
+    let mut padding_started = false;
+
+    for (index, c) in input.chars().enumerate() {
+        match c {
+            ' ' | '\t' | '-' => continue,
+            '=' => padding_started = true,
+            'A'..='Z' | 'a'..='z' | '2'..='7' if !padding_started => {
+                normalized.push(c.to_ascii_uppercase())
+            }
+            'A'..='Z' | 'a'..='z' | '2'..='7' => {
+                return Err(invalid_character(input, index, "padding '=' may only appear at the end"));
+            }
+            '0' | '1' | '8' | '9' => {
+                return Err(invalid_character(input, index, "base32 only uses the digits 2-7"));
+            }
+            _ => {
+                return Err(invalid_character(input, index, "only letters A-Z and digits 2-7 are allowed"));
+            }
+        }
+    }
+
+    if normalized.is_empty() {
+        return Err(AppError::InvalidInput("Secret key cannot be empty".to_string()));
+    }
+
+    if matches!(normalized.len() % 8, 1 | 3 | 6) {
+        return Err(AppError::InvalidInput(format!(
+            "Secret key has an impossible length for base32 ({} characters). Check for a missing or extra character.",
+            normalized.len()
+        )));
+    }
+
+    Ok(normalized)
+}
+
+/// Decodes a base32 secret (normalizing it first) into raw key bytes
+pub fn decode_base32(input: &str) -> Result<Vec<u8>, AppError> {
+    let normalized = normalize_base32(input)?;
+    base32::decode(base32::Alphabet::Rfc4648 { padding: false }, &normalized)
+        .ok_or_else(|| AppError::InvalidInput("Secret key is not valid base32".to_string()))
+}
+
+/// Builds an error message pointing at the offending character
+fn invalid_character(input: &str, index: usize, hint: &str) -> AppError {
+    let c = input.chars().nth(index).unwrap_or(' ');
+    AppError::InvalidInput(format!(
+        "invalid character '{}' at position {} ({})\n    {}\n    {}^",
+        c,
+        index + 1,
+        hint,
+        input,
+        " ".repeat(index)
+    ))
+}
+
+/// Reads a secret from standard input, ignoring surrounding whitespace
+pub fn read_from_stdin() -> Result<String, AppError> {
+    let mut input = String::new();
+    std::io::stdin().read_to_string(&mut input)?;
+
+    let secret = input.trim().to_string();
+    if secret.is_empty() {
+        return Err(AppError::InvalidInput("No secret received on standard input".to_string()));
+    }
+    Ok(secret)
+}
+
+/// Runs a shell command (e.g. `pass show totp/github`) and returns the first
+/// line of its output, the convention password managers use for the secret
+pub fn read_from_command(command: &str) -> Result<String, AppError> {
+    let output = if cfg!(windows) {
+        Command::new("cmd").args(["/C", command]).output()
+    } else {
+        Command::new("sh").args(["-c", command]).output()
+    }
+    .map_err(|e| AppError::InvalidInput(format!("Failed to run secret command: {}", e)))?;
+
+    if !output.status.success() {
+        return Err(AppError::InvalidInput(format!(
+            "Secret command exited with {}",
+            output.status
+        )));
+    }
+
+    let stdout = String::from_utf8(output.stdout)
+        .map_err(|_| AppError::InvalidInput("Secret command printed invalid UTF-8".to_string()))?;
+
+    stdout
+        .lines()
+        .map(str::trim)
+        .find(|line| !line.is_empty())
+        .map(str::to_string)
+        .ok_or_else(|| AppError::InvalidInput("Secret command printed nothing".to_string()))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn normalizes_base32_as_typed() {
+        assert_eq!(normalize_base32(" jbsw y3dp-ehpk 3pxp ").unwrap(), "JBSWY3DPEHPK3PXP");
+        assert_eq!(normalize_base32("MFRGG===").unwrap(), "MFRGG");
+    }
+
+    #[test]
+    fn refuses_invalid_base32() {
+        assert!(normalize_base32("").is_err());
+        assert!(normalize_base32("JBSW1Y3D").is_err());
+        assert!(normalize_base32("JBSW!Y3D").is_err());
+        assert!(normalize_base32("MFRG=G").is_err());
+        // 9 characters can't come from whole bytes
+        assert!(normalize_base32("JBSWY3DPE").is_err());
+    }
+
+    #[test]
+    fn base32_round_trips() {
+        let bytes = b"12345678901234567890";
+        let encoded = encode_base32(bytes);
+        assert_eq!(encoded, "GEZDGNBVGY3TQOJQGEZDGNBVGY3TQOJQ");
+        assert_eq!(decode_base32(&encoded.to_lowercase()).unwrap(), bytes);
+    }
+
+    #[test]
+    fn converts_other_encodings_to_base32() {
+        assert_eq!(to_canonical_base32("48656c6c6f", SecretEncoding::Hex).unwrap(), encode_base32(b"Hello"));
+        assert_eq!(to_canonical_base32(" Hello ", SecretEncoding::Raw).unwrap(), encode_base32(b"Hello"));
+        assert!(to_canonical_base32("4865zz", SecretEncoding::Hex).is_err());
+    }
+
+    #[test]
+    fn parses_secret_references() {
+        assert!(SecretReference::parse("cmd:pass show github").is_some());
+        assert!(SecretReference::parse("env:GITHUB_TOTP").is_some());
+        assert!(SecretReference::parse("JBSWY3DPEHPK3PXP").is_none());
+    }
+}
@@ -0,0 +1,88 @@
+//! Passphrase-encrypted bundles for handing a single account to another
+//! Quackey instance.
+//!
+//! A bundle is the account's JSON encrypted with [`crypto::encrypt`], in its
+//! compact binary form, base32-encoded inside armored text that survives being
+//! pasted into chat or email:
+//!
+//! ```text
+//! -----BEGIN QUACKEY ACCOUNT-----
+//! GEZDGNBV...
+//! -----END QUACKEY ACCOUNT-----
+//! ```
+
+use crate::account::Account;
+use crate::crypto::{self, EncryptedData};
+use crate::error::AppError;
+use crate::secret;
+
+pub const BEGIN_MARKER: &str = "-----BEGIN QUACKEY ACCOUNT-----";
+pub const END_MARKER: &str = "-----END QUACKEY ACCOUNT-----";
+const LINE_WIDTH: usize = 64;
+
+/// Encrypts an account into an armored bundle. Recovery codes are left out;
+/// they belong to the person who set up the account. An account whose secret
+/// is a reference can't be shared, as the bundle would only carry the reference.
+pub fn seal(account: &Account, passphrase: &str) -> Result<String, AppError> {
+    if let Some(reference) = account.secret_reference() {
+        return Err(AppError::InvalidInput(format!(
+            "'{}' fetches its secret with '{}', so there is no secret to share",
+            account.name(),
+            reference
+        )));
+    }
+
+    let mut shared = account.clone();
+    shared.set_recovery_codes(None);
+
+    let json = serde_json::to_vec(&shared)
+        .map_err(|e| AppError::JsonError(format!("Failed to serialize account: {}", e)))?;
+    let data = crypto::encrypt(&json, passphrase);
+
+    armor(&data, BEGIN_MARKER, END_MARKER)
+}
+
+/// Decrypts an armored bundle produced by [`seal`]. Text around the markers
+/// (such as a chat message) is ignored. Bundles holding a secret reference
+/// instead of a secret are refused.
+pub fn open(armored: &str, passphrase: &str) -> Result<Account, AppError> {
+    let data = dearmor(armored, BEGIN_MARKER, END_MARKER, "account bundle")?;
+
+    let json = crypto::decrypt(&data, passphrase)?;
+    let account: Account = serde_json::from_slice(&json)
+        .map_err(|e| AppError::JsonError(format!("Failed to parse shared account: {}", e)))?;
+    account.reject_secret_reference()?;
+    Ok(account)
+}
+
+/// Wraps encrypted data in base32 lines between `begin` and `end`
+pub(crate) fn armor(data: &EncryptedData, begin: &str, end: &str) -> Result<String, AppError> {
+    let body = secret::encode_base32(&data.to_bytes()?);
+    let mut armored = String::from(begin);
+    for line in body.as_bytes().chunks(LINE_WIDTH) {
+        armored.push('\n');
+        armored.push_str(std::str::from_utf8(line).expect("base32 is ASCII"));
+    }
+    armored.push('\n');
+    armored.push_str(end);
+    armored.push('\n');
+
+    Ok(armored)
+}
+
+/// Reads the encrypted data [`armor`] wrapped; `what` names it in errors
+pub(crate) fn dearmor(armored: &str, begin: &str, end: &str, what: &str) -> Result<EncryptedData, AppError> {
+    let start = armored
+        .find(begin)
+        .ok_or_else(|| AppError::InvalidInput(format!("No Quackey {} found", what)))?
+        + begin.len();
+    let stop = armored[start..]
+        .find(end)
+        .map(|i| start + i)
+        .ok_or_else(|| AppError::InvalidInput(format!("The {} is incomplete", what)))?;
+
+    let body: String = armored[start..stop].chars().filter(|c| !c.is_whitespace()).collect();
+    let data = base32::decode(base32::Alphabet::Rfc4648 { padding: false }, &body)
+        .ok_or_else(|| AppError::InvalidInput(format!("The {} is damaged", what)))?;
+    EncryptedData::from_bytes(&data).map_err(|_| AppError::InvalidInput(format!("The {} is damaged", what)))
+}
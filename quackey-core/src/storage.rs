@@ -0,0 +1,1214 @@
+//! The `accounts.json` vault and its snapshots.
+//!
+//! The file is either a plain JSON array of accounts or, once a master
+//! password is set, an [`EncryptedVault`] holding that array encrypted with
+//! the [`VaultKey`] derived from it.
+
+use std::collections::HashMap;
+use std::fs::{self, File};
+use std::io::Read;
+use std::path::{Path, PathBuf};
+use std::time::{SystemTime, UNIX_EPOCH};
+use chrono::Local;
+use sha2::{Digest, Sha256};
+use serde::{Serialize, Deserialize};
+use crate::account::{self, Account, AccountColor, CodePolicy};
+use crate::crypto::{EncryptedData, VaultKey};
+use crate::dpapi;
+use crate::error::AppError;
+use crate::integrity;
+use crate::events::{Event, Events};
+use crate::history::{self, History};
+use crate::logger::Level;
+use crate::merge::{self, Change, Merge};
+use crate::permissions;
+use crate::pin;
+use crate::recovery::RecoveryCodes;
+use crate::sync;
+use crate::wipe;
+use std::sync::atomic::{AtomicBool, Ordering};
+
+/// Storage manager for TOTP accounts
+///
+/// Every change is written to disk straight away unless a batch is open: after
+/// [`begin_batch`](Self::begin_batch), changes only mark the vault dirty and are
+/// written once by [`flush`](Self::flush).
+///
+/// A vault can be handed to another thread, or shared as
+/// `Arc<Mutex<Storage>>`; its [`Events`] go to the same sinks as every other
+/// handle, whichever thread it is used from.
+pub struct Storage {
+    file_path: String,
+    accounts: Vec<Account>,
+    events: Events,
+    dirty: bool,
+    batching: bool,
+    /// Position of each account by id
+    by_id: HashMap<String, usize>,
+    /// Positions of the accounts with each name, keyed by [`normalize_name`]
+    by_name: HashMap<String, Vec<usize>>,
+    /// The file as it was when last loaded or saved, to notice outside edits
+    stamp: Option<FileStamp>,
+    /// Accounts as they were when last loaded or saved, the common base for
+    /// merging with outside edits
+    base: Vec<Account>,
+    /// Key the file is encrypted with, `None` for a plain-text vault
+    key: Option<VaultKey>,
+    /// Set when the file is on a read-only file system: accounts can be read
+    /// and codes generated, but saving fails with [`read_only_error`]
+    read_only: bool,
+    /// When a code generation was last recorded for each account, so refresh
+    /// loops don't read the history file every second
+    last_generated: HashMap<String, u64>,
+}
+
+/// On-disk form of a vault protected by a master password
+#[derive(Debug, Clone, Serialize, Deserialize)]
+struct EncryptedVault {
+    /// Format version, for later changes to the layout
+    quackey_vault: u32,
+    data: EncryptedData,
+}
+
+/// Modification time, size and content hash of the storage file. The hash
+/// catches edits within the same second as the last save and ignores rewrites
+/// that left the contents as they were.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+struct FileStamp {
+    modified: SystemTime,
+    len: u64,
+    hash: [u8; 32],
+}
+
+impl FileStamp {
+    fn read(path: &str) -> Option<Self> {
+        let metadata = fs::metadata(path).ok()?;
+        let contents = fs::read(path).ok()?;
+        Some(Self {
+            modified: metadata.modified().ok()?,
+            len: metadata.len(),
+            hash: Sha256::digest(&contents).into(),
+        })
+    }
+
+    /// Whether the file at `path` differs from `stamp`, hashing it only when
+    /// its modification time or size changed
+    fn changed(stamp: Option<&Self>, path: &str) -> bool {
+        let metadata = fs::metadata(path).ok();
+        match (stamp, metadata) {
+            (None, None) => false,
+            (Some(stamp), Some(metadata))
+                if metadata.modified().ok() == Some(stamp.modified) && metadata.len() == stamp.len =>
+            {
+                false
+            }
+            (Some(stamp), Some(_)) => Self::read(path).is_none_or(|current| current.hash != stamp.hash),
+            _ => true,
+        }
+    }
+}
+
+// Static flag to track if directory creation has been logged
+static DIRECTORY_CREATED: AtomicBool = AtomicBool::new(false);
+
+impl Storage {
+    /// Opens a plain-text vault. An encrypted one fails with
+    /// [`AppError::Locked`]; open it with [`new_with_key`](Self::new_with_key).
+    pub fn new_with_events(file_path: &str, events: Events) -> Result<Self, AppError> {
+        Self::new_with_key(file_path, events, None)
+    }
+
+    /// Opens a vault, decrypting it with `key` if it is encrypted
+    pub fn new_with_key(file_path: &str, events: Events, key: Option<VaultKey>) -> Result<Self, AppError> {
+        let mut storage = Self {
+            file_path: file_path.to_string(),
+            accounts: Vec::new(),
+            events,
+            dirty: false,
+            batching: false,
+            last_generated: HashMap::new(),
+            by_id: HashMap::new(),
+            by_name: HashMap::new(),
+            stamp: None,
+            base: Vec::new(),
+            key,
+            read_only: on_read_only_fs(file_path),
+        };
+
+        // Load existing accounts if file exists. A file that can't be parsed is
+        // reported to the caller untouched so it can be repaired rather than lost.
+        // Nothing is created on disk until the first save.
+        if Path::new(file_path).exists() {
+            storage.load().map_err(|e| e.with_path(file_path))?;
+        }
+
+        Ok(storage)
+    }
+
+    /// Passes `event` to the vault's event sinks
+    fn emit(&self, event: Event) -> Result<(), AppError> {
+        self.events.emit(event)
+    }
+
+    /// Records a message that isn't an [`Event`] of its own
+    fn note(&self, level: Level, message: &str) -> Result<(), AppError> {
+        self.events.note(level, message)
+    }
+
+    /// Ensures the directory for the storage file exists
+    fn ensure_directory(&mut self) -> Result<(), AppError> {
+        if self.read_only {
+            return Err(read_only_error(&self.file_path));
+        }
+        let path = Path::new(&self.file_path);
+        
+        // If the file path has a parent directory
+        if let Some(parent) = path.parent() {
+            // Check if the directory exists
+            if !parent.exists() {
+                // Use a static flag to ensure we only log this once
+                let should_log = !DIRECTORY_CREATED.load(Ordering::SeqCst);
+                
+                if should_log {
+                    // Log that we're creating the directory
+                    let message = format!("Storage directory not found. Auto-creating: {}", parent.display());
+                    
+                    // Create the directory and all parent directories
+                    fs::create_dir_all(parent)
+                        .map_err(|e| AppError::FileError(format!("Failed to create directory: {}", e)))?;
+                    
+                    // Log successful creation
+                    let success_message = format!("Successfully created storage directory: {}", parent.display());
+                    self.note(Level::Warn, &message)?;
+                    self.note(Level::Info, &success_message)?;
+                    
+                    // Set the flag to indicate we've logged this
+                    DIRECTORY_CREATED.store(true, Ordering::SeqCst);
+                } else {
+                    // Just create the directory without logging
+                    fs::create_dir_all(parent)
+                        .map_err(|e| AppError::FileError(format!("Failed to create directory: {}", e)))?;
+                }
+            }
+        } else {
+            // No parent directory (file is in current directory)
+            // Check if the file exists
+            if !path.exists() {
+                // Log that we're creating the file
+                let message = format!("Storage file not found. Will be created: {}", path.display());
+                
+                self.note(Level::Warn, &message)?;
+            }
+        }
+        
+        Ok(())
+    }
+
+    /// Gets the current storage file path
+    pub fn file_path(&self) -> &str {
+        &self.file_path
+    }
+
+    /// Directory holding vault snapshots, next to the storage file
+    pub fn backups_dir(&self) -> PathBuf {
+        backups_dir_for(&self.file_path)
+    }
+
+    /// When the newest snapshot in the backups directory was written, if any
+    pub fn last_snapshot(&self) -> Option<SystemTime> {
+        fs::read_dir(self.backups_dir())
+            .ok()?
+            .filter_map(|entry| entry.ok()?.metadata().ok())
+            .filter(|metadata| metadata.is_file())
+            .filter_map(|metadata| metadata.modified().ok())
+            .max()
+    }
+
+    /// Copies the current storage file into the backups directory before a
+    /// destructive change. Returns the snapshot path, or `None` if there is no
+    /// file to back up yet.
+    pub fn snapshot(&mut self, reason: &str) -> Result<Option<PathBuf>, AppError> {
+        if !Path::new(&self.file_path).exists() {
+            return Ok(None);
+        }
+        if self.read_only {
+            return Err(read_only_error(&self.file_path));
+        }
+
+        let target = snapshot_file(&self.file_path, reason)?;
+        self.emit(Event::SnapshotSaved { reason: reason.to_string(), path: target.display().to_string() })?;
+        Ok(Some(target))
+    }
+
+    /// The accounts in a snapshot or other copy of the storage file,
+    /// decrypted with this vault's key if needed. Every account must have
+    /// settings that can generate codes. Secret references are only taken
+    /// from the snapshots Quackey wrote in [`backups_dir`](Self::backups_dir);
+    /// a file from anywhere else is treated like an import, as generating a
+    /// code for a `cmd:` reference runs its command.
+    pub fn read_snapshot(&mut self, path: &Path) -> Result<Vec<Account>, AppError> {
+        let contents = fs::read_to_string(path)
+            .map_err(|e| AppError::FileError(format!("Failed to read '{}': {}", path.display(), e)))?;
+        if contents.trim().is_empty() {
+            return Ok(Vec::new());
+        }
+        let accounts = self.parse(&contents)?;
+
+        let own = match (fs::canonicalize(path), fs::canonicalize(self.backups_dir())) {
+            (Ok(path), Ok(backups)) => path.parent() == Some(backups.as_path()),
+            _ => false,
+        };
+        for account in &accounts {
+            account
+                .check_settings()
+                .and_then(|_| if own { Ok(()) } else { account.reject_secret_reference() })
+                .map_err(|e| e.with_account(account.name()).with_path(path))?;
+        }
+        Ok(accounts)
+    }
+
+    /// Moves the storage file to `new_path`, or copies it there with
+    /// `keep_old`, together with the files that belong to it: copies such as
+    /// `accounts.json.bak`, the snapshots, its checksum, the sync base, the code
+    /// history and the DPAPI and PIN keys.
+    ///
+    /// Every file is copied under a temporary name and renamed into place, the
+    /// storage file last, and the old files are only removed once all copies
+    /// are in place, so a failure leaves the vault where it was. A storage file
+    /// already at `new_path` is replaced after being kept as a snapshot next
+    /// to it; the snapshot's path is returned.
+    pub fn relocate(&mut self, new_path: &str, keep_old: bool) -> Result<Option<PathBuf>, AppError> {
+        // Moving a file onto itself would delete it
+        if same_file(&self.file_path, new_path) {
+            return Err(AppError::InvalidInput(format!("'{}' is already the storage file", new_path)));
+        }
+        if !keep_old && self.read_only {
+            return Err(read_only_error(&self.file_path));
+        }
+        // Pending changes belong in the file being moved
+        self.flush()?;
+
+        let old_path = self.file_path.clone();
+        let old_backups = backups_dir_for(&old_path);
+        let new_backups = backups_dir_for(new_path);
+        let new_dir = match Path::new(new_path).parent() {
+            Some(parent) if !parent.as_os_str().is_empty() => parent.to_path_buf(),
+            _ => PathBuf::from("."),
+        };
+
+        let mut copies: Vec<(PathBuf, PathBuf)> = companion_files(&old_path)
+            .into_iter()
+            .filter_map(|from| {
+                let dir = if from.parent() == Some(old_backups.as_path()) { &new_backups } else { &new_dir };
+                let to = dir.join(from.file_name()?);
+                Some((from, to))
+            })
+            .collect();
+        if Path::new(&old_path).exists() {
+            copies.push((PathBuf::from(&old_path), PathBuf::from(new_path)));
+        }
+
+        let replaced = if Path::new(new_path).exists() {
+            Some(snapshot_file(new_path, "replaced")?)
+        } else {
+            None
+        };
+
+        // Files created so far, removed again if a copy fails
+        let mut placed: Vec<&Path> = Vec::new();
+        for (from, to) in &copies {
+            let existed = to.exists();
+            if let Err(e) = copy_into_place(from, to) {
+                for path in placed {
+                    let _ = fs::remove_file(path);
+                }
+                let error_message = format!("Failed to copy '{}' to '{}': {}", from.display(), to.display(), e);
+                self.note(Level::Error, &error_message)?;
+                return Err(AppError::FileError(error_message));
+            }
+            if !existed {
+                placed.push(to);
+            }
+        }
+
+        if !keep_old {
+            for (from, _) in &copies {
+                // The copies are safe; a file left behind only needs a warning
+                if let Err(e) = wipe::secure_delete(from) {
+                    self.note(Level::Warn, &format!("Failed to remove '{}' after moving it: {}", from.display(), e))?;
+                }
+            }
+            // Only goes if it is empty now
+            let _ = fs::remove_dir(&old_backups);
+        }
+
+        self.file_path = new_path.to_string();
+        self.read_only = on_read_only_fs(new_path);
+        self.emit(Event::StorageMoved {
+            from: old_path,
+            to: new_path.to_string(),
+            copied: keep_old,
+            companions: copies.len().saturating_sub(1),
+        })?;
+
+        self.load()?;
+        Ok(replaced)
+    }
+
+    /// Updates the storage file path
+    pub fn update_file_path(&mut self, new_path: &str) -> Result<(), AppError> {
+        // Pending changes belong to the old file
+        self.flush()?;
+
+        let old_path = self.file_path.clone();
+        
+        // Update the file path
+        self.file_path = new_path.to_string();
+        
+        self.emit(Event::StoragePathChanged { from: old_path, to: new_path.to_string() })?;
+        
+        // Ensure the directory exists, unless it can't be written at all
+        self.read_only = on_read_only_fs(new_path);
+        if !self.read_only {
+            self.ensure_directory()?;
+        }
+
+        // Load accounts from the new file
+        self.load()
+    }
+
+    /// Whether the storage file is on a read-only file system, so changes
+    /// can't be saved
+    pub fn is_read_only(&self) -> bool {
+        self.read_only
+    }
+
+    pub fn add_account(&mut self, account: Account) -> Result<(), AppError> {
+        // Ensure the directory exists before saving
+        self.ensure_directory()?;
+        
+        let mut account = account;
+        if self.by_id.contains_key(account.id()) {
+            account.regenerate_id();
+        }
+        self.accounts.push(account.clone());
+        
+        self.emit(Event::AccountAdded { name: account.name().to_string() })?;
+        
+        self.changed()
+    }
+
+    /// Adds several accounts with a single save
+    pub fn add_accounts(&mut self, accounts: Vec<Account>) -> Result<(), AppError> {
+        self.ensure_directory()?;
+
+        let count = accounts.len();
+        self.accounts.extend(accounts);
+        self.reindex();
+
+        self.emit(Event::AccountsAdded { count })?;
+
+        self.changed()
+    }
+
+    /// Replaces every account with a single save, e.g. after merging in
+    /// changes synced from another device
+    pub fn replace_accounts(&mut self, accounts: Vec<Account>) -> Result<(), AppError> {
+        self.ensure_directory()?;
+
+        self.accounts = accounts;
+        self.emit(Event::AccountsReplaced { count: self.accounts.len() })?;
+
+        self.changed()
+    }
+
+    /// All accounts, in vault order
+    pub fn get_accounts(&self) -> &[Account] {
+        &self.accounts
+    }
+
+    /// The account with the given id
+    pub fn find_by_id(&self, id: &str) -> Option<&Account> {
+        self.by_id.get(id).map(|&index| &self.accounts[index])
+    }
+
+    /// Accounts whose name matches ignoring case and surrounding spaces, in vault order
+    pub fn find_by_name(&self, name: &str) -> Vec<&Account> {
+        self.by_name
+            .get(&normalize_name(name))
+            .map(|positions| positions.iter().map(|&index| &self.accounts[index]).collect())
+            .unwrap_or_default()
+    }
+
+    /// Position of the first account named exactly `name`, or else of the
+    /// only account whose name matches ignoring case and surrounding spaces
+    fn position_of(&self, name: &str) -> Option<usize> {
+        let positions = self.by_name.get(&normalize_name(name))?;
+        positions
+            .iter()
+            .copied()
+            .find(|&index| self.accounts[index].name() == name)
+            .or(match positions.as_slice() {
+                [index] => Some(*index),
+                _ => None,
+            })
+    }
+
+    /// Rebuilds the lookup maps after the account list changed. Accounts that
+    /// share an id (such as a copy imported next to its original) get a new one.
+    fn reindex(&mut self) {
+        self.by_id.clear();
+        self.by_name.clear();
+
+        for index in 0..self.accounts.len() {
+            if self.by_id.contains_key(self.accounts[index].id()) {
+                self.accounts[index].regenerate_id();
+                self.dirty = true;
+            }
+
+            let account = &self.accounts[index];
+            self.by_id.insert(account.id().to_string(), index);
+            self.by_name.entry(normalize_name(account.name())).or_default().push(index);
+        }
+    }
+
+    /// Deletes an account by name
+    pub fn delete_account(&mut self, name: &str) -> Result<(), AppError> {
+        // Find the account by name
+        let position = self.position_of(name);
+        
+        match position {
+            Some(index) => {
+                // Remove the account at the found position
+                self.accounts.remove(index);
+                
+                self.emit(Event::AccountDeleted { name: name.to_string() })?;
+                
+                // Save the updated accounts list
+                self.changed()
+            },
+            None => {
+                let error_message = format!("Account '{}' not found", name);
+                self.note(Level::Error, &error_message)?;
+                Err(AppError::NotFound(error_message))
+            }
+        }
+    }
+
+    /// Deletes the accounts at the given positions (as returned by
+    /// [`get_accounts`](Self::get_accounts)) with a single save
+    pub fn delete_accounts(&mut self, indices: &[usize]) -> Result<(), AppError> {
+        if let Some(&index) = indices.iter().find(|&&i| i >= self.accounts.len()) {
+            return Err(AppError::InvalidInput(format!("No account at position {}", index)));
+        }
+
+        let mut index = 0;
+        let mut deleted = Vec::new();
+        self.accounts.retain(|account| {
+            let keep = !indices.contains(&index);
+            if !keep {
+                deleted.push(account.name().to_string());
+            }
+            index += 1;
+            keep
+        });
+
+        for name in deleted {
+            self.emit(Event::AccountDeleted { name })?;
+        }
+        self.changed()
+    }
+
+    /// Updates an account's details
+    pub fn update_account(
+        &mut self,
+        old_name: &str,
+        new_name: String,
+        new_issuer: Option<String>,
+        new_tags: Vec<String>,
+    ) -> Result<(), AppError> {
+        // Find the account by name
+        let position = self.position_of(old_name);
+        
+        match position {
+            Some(index) => {
+                let account = &self.accounts[index];
+                if account.name() == new_name && account.issuer() == new_issuer.as_ref() && account.tags() == new_tags {
+                    return Ok(());
+                }
+
+                // Update the details in place so secrets, settings and recovery codes are kept
+                self.accounts[index].set_details(new_name.clone(), new_issuer, new_tags);
+                self.touch(index)?;
+                
+                self.emit(Event::AccountUpdated { name: new_name, previous_name: old_name.to_string() })?;
+                
+                // Save the updated accounts list
+                self.changed()
+            },
+            None => {
+                let error_message = format!("Account '{}' not found", old_name);
+                self.note(Level::Error, &error_message)?;
+                Err(AppError::NotFound(error_message))
+            }
+        }
+    }
+
+    /// The account other than the one with id `except` that already goes by `alias`
+    pub fn alias_owner(&self, alias: &str, except: Option<&str>) -> Option<&Account> {
+        self.accounts
+            .iter()
+            .find(|account| Some(account.id()) != except && account.has_alias(alias))
+    }
+
+    /// Checks that `aliases` are well-formed and not used by an account other
+    /// than the one with id `except`
+    pub fn check_aliases(&self, aliases: &[String], except: Option<&str>) -> Result<(), AppError> {
+        for alias in aliases {
+            account::check_alias(alias)?;
+            if let Some(owner) = self.alias_owner(alias, except) {
+                return Err(AppError::InvalidInput(format!("Alias '{}' is already used by '{}'", alias, owner.name()))
+                    .with_account(alias)
+                    .with_hint("Pick another alias, or remove it from that account first"));
+            }
+        }
+        Ok(())
+    }
+
+    /// Replaces an account's aliases, refusing any that another account uses
+    pub fn set_aliases(&mut self, id: &str, aliases: Vec<String>) -> Result<(), AppError> {
+        let index = self.index_of_id(id)?;
+        if self.accounts[index].aliases() == aliases {
+            return Ok(());
+        }
+        self.check_aliases(&aliases, Some(id))?;
+
+        self.accounts[index].set_aliases(aliases);
+        self.touch(index)?;
+        self.emit(Event::AccountChanged { name: self.accounts[index].name().to_string(), change: "aliases" })?;
+
+        self.changed()
+    }
+
+    /// Replaces an account's encrypted recovery codes
+    pub fn set_recovery_codes(&mut self, id: &str, codes: Option<RecoveryCodes>) -> Result<(), AppError> {
+        let index = self.index_of_id(id)?;
+        self.accounts[index].set_recovery_codes(codes);
+        self.touch(index)?;
+
+        self.emit(Event::AccountChanged {
+            name: self.accounts[index].name().to_string(),
+            change: "recovery codes",
+        })?;
+
+        self.changed()
+    }
+
+    /// Records whether the provider accepted the account's codes
+    pub fn set_verified(&mut self, id: &str, verified: bool) -> Result<(), AppError> {
+        let index = self.index_of_id(id)?;
+        self.accounts[index].set_verified(verified);
+        self.touch(index)?;
+
+        self.emit(Event::AccountVerified { name: self.accounts[index].name().to_string(), verified })?;
+
+        self.changed()
+    }
+
+    /// Archives an account, hiding it from tables and pickers, or restores it
+    pub fn set_archived(&mut self, id: &str, archived: bool) -> Result<(), AppError> {
+        let index = self.index_of_id(id)?;
+        self.accounts[index].set_archived(archived);
+        self.touch(index)?;
+
+        self.emit(Event::AccountArchived { name: self.accounts[index].name().to_string(), archived })?;
+
+        self.changed()
+    }
+
+    /// Sets the icon and color an account is shown with
+    pub fn set_appearance(
+        &mut self,
+        id: &str,
+        icon: Option<String>,
+        color: Option<AccountColor>,
+    ) -> Result<(), AppError> {
+        let index = self.index_of_id(id)?;
+        let account = &self.accounts[index];
+        if account.icon() == icon.as_deref() && account.color() == color {
+            return Ok(());
+        }
+        self.accounts[index].set_appearance(icon, color);
+        self.touch(index)?;
+
+        self.emit(Event::AccountChanged {
+            name: self.accounts[index].name().to_string(),
+            change: "icon and color",
+        })?;
+
+        self.changed()
+    }
+
+    /// Sets whether the account's codes may be shown and copied
+    pub fn set_code_policy(&mut self, id: &str, policy: CodePolicy) -> Result<(), AppError> {
+        let index = self.index_of_id(id)?;
+        if self.accounts[index].code_policy() == policy {
+            return Ok(());
+        }
+        self.accounts[index].set_code_policy(policy);
+        self.touch(index)?;
+
+        let message = format!(
+            "Set code policy of account {} to: {}",
+            self.accounts[index].name(),
+            policy.label()
+        );
+        self.note(Level::Info, &message)?;
+
+        self.changed()
+    }
+
+    /// Records the offset of a code that matched during verification
+    pub fn record_skew(&mut self, id: &str, offset: i64) -> Result<(), AppError> {
+        let index = self.index_of_id(id)?;
+        self.accounts[index].record_skew(offset);
+        self.changed()
+    }
+
+    /// Records in the [code history](history) that a code was generated for
+    /// the account just now. The vault itself isn't written. The history is
+    /// only a convenience, so showing a code never fails on it; a history that
+    /// can't be written is noted in the log.
+    pub fn record_generation(&mut self, id: &str) {
+        let Some(&index) = self.by_id.get(id) else {
+            return;
+        };
+        if self.read_only {
+            return;
+        }
+        let period = self.accounts[index].period().max(1);
+        let now = SystemTime::now().duration_since(UNIX_EPOCH).unwrap_or_default().as_secs();
+        if self.last_generated.get(id).is_some_and(|last| last / period == now / period) {
+            return;
+        }
+
+        self.last_generated.insert(id.to_string(), now);
+        if let Err(e) = history::record(&self.file_path, id, period, now) {
+            self.note(Level::Warn, &e.to_string()).ok();
+        }
+    }
+
+    /// When codes were generated for each account
+    pub fn generation_history(&self) -> History {
+        history::load(&self.file_path)
+    }
+
+    /// Sets or clears the account's own verification window, which can't be
+    /// wider than [`MAX_VERIFY_WINDOW`](account::MAX_VERIFY_WINDOW)
+    pub fn set_verify_window(&mut self, id: &str, window: Option<u64>) -> Result<(), AppError> {
+        if let Some(window) = window.filter(|&window| window > account::MAX_VERIFY_WINDOW) {
+            return Err(AppError::InvalidInput(format!(
+                "A verification window of {} periods is too wide; the most is {}",
+                window,
+                account::MAX_VERIFY_WINDOW
+            )));
+        }
+        let index = self.index_of_id(id)?;
+        self.accounts[index].set_verify_window(window);
+        self.touch(index)?;
+
+        self.emit(Event::AccountChanged {
+            name: self.accounts[index].name().to_string(),
+            change: "verification window",
+        })?;
+
+        self.changed()
+    }
+
+    /// Records that the account at `index` was changed just now
+    fn touch(&mut self, index: usize) -> Result<(), AppError> {
+        let now = SystemTime::now().duration_since(UNIX_EPOCH)?.as_secs();
+        self.accounts[index].mark_updated(now);
+        Ok(())
+    }
+
+    /// Position of the account with the given id
+    fn index_of_id(&mut self, id: &str) -> Result<usize, AppError> {
+        match self.by_id.get(id).copied() {
+            Some(index) => Ok(index),
+            None => {
+                let error_message = format!("No account with id '{}'", id);
+                self.note(Level::Error, &error_message)?;
+                Err(AppError::NotFound(error_message))
+            }
+        }
+    }
+
+    /// Defers writes until [`flush`](Self::flush), so a series of changes
+    /// rewrites the file once
+    pub fn begin_batch(&mut self) {
+        self.batching = true;
+    }
+
+    /// Ends any open batch and writes pending changes. Does nothing when the
+    /// file already matches memory.
+    pub fn flush(&mut self) -> Result<(), AppError> {
+        self.batching = false;
+        if !self.dirty {
+            return Ok(());
+        }
+
+        self.save()?;
+        self.dirty = false;
+        Ok(())
+    }
+
+    /// Whether there are changes that haven't been written yet
+    pub fn is_dirty(&self) -> bool {
+        self.dirty
+    }
+
+    /// Whether another program (such as a sync client) changed the file since
+    /// it was last loaded or saved
+    pub fn changed_on_disk(&self) -> bool {
+        FileStamp::changed(self.stamp.as_ref(), &self.file_path)
+    }
+
+    /// Discards the accounts in memory, including unsaved changes, and reads
+    /// the file again
+    pub fn reload(&mut self) -> Result<(), AppError> {
+        self.dirty = false;
+        self.load()?;
+        // The outside version is now the accepted one
+        if Path::new(&self.file_path).exists() {
+            integrity::record_checksum(&self.file_path)?;
+        }
+        self.emit(Event::StorageReloaded)
+    }
+
+    /// Three-way merges the accounts in memory with the file's current
+    /// contents, using the accounts last loaded or saved as the base
+    pub fn merge_with_disk(&mut self) -> Result<Merge, AppError> {
+        let theirs = self.read_disk_accounts()?;
+        Ok(merge::merge(&self.base, &self.accounts, &theirs))
+    }
+
+    /// What another program changed in the file since it was last loaded or
+    /// saved
+    pub fn disk_changes(&mut self) -> Result<Vec<Change>, AppError> {
+        let theirs = self.read_disk_accounts()?;
+        Ok(merge::diff(&self.base, &theirs))
+    }
+
+    /// Changes in memory that haven't been written yet
+    pub fn pending_changes(&self) -> Vec<Change> {
+        merge::diff(&self.base, &self.accounts)
+    }
+
+    /// The accounts currently in the file, which may differ from memory
+    fn read_disk_accounts(&mut self) -> Result<Vec<Account>, AppError> {
+        let contents = fs::read_to_string(&self.file_path).unwrap_or_default();
+        if contents.trim().is_empty() {
+            Ok(Vec::new())
+        } else {
+            self.parse(&contents)
+        }
+    }
+
+    /// Saves a merged account list from [`merge_with_disk`](Self::merge_with_disk)
+    /// over the file that changed on disk
+    pub fn apply_merge(&mut self, accounts: Vec<Account>) -> Result<(), AppError> {
+        self.accounts = accounts;
+        self.stamp = FileStamp::read(&self.file_path);
+        self.emit(Event::StorageMerged)?;
+        self.changed()
+    }
+
+    /// Writes the accounts in memory even if the file changed on disk,
+    /// replacing the outside edits
+    pub fn overwrite(&mut self) -> Result<(), AppError> {
+        self.stamp = FileStamp::read(&self.file_path);
+        self.note(Level::Warn, "Overwriting storage file that changed on disk")?;
+        self.flush()
+    }
+
+    /// Records a change, writing it now unless a batch is open
+    fn changed(&mut self) -> Result<(), AppError> {
+        self.reindex();
+        self.dirty = true;
+        if self.batching {
+            return Ok(());
+        }
+        self.flush()
+    }
+
+    fn load(&mut self) -> Result<(), AppError> {
+        self.stamp = FileStamp::read(&self.file_path);
+
+        // Check if the file exists
+        if !Path::new(&self.file_path).exists() {
+            // If the file doesn't exist, start with an empty accounts list
+            self.accounts = Vec::new();
+            self.reindex();
+            self.base = Vec::new();
+            
+            // Log that we're starting with an empty accounts list
+            let message = format!("Storage file '{}' not found. Starting with empty accounts list.", self.file_path);
+            self.note(Level::Warn, &message)?;
+            
+            return Ok(());
+        }
+        
+        let mut file = File::open(&self.file_path)
+            .map_err(|e| {
+                let error_message = format!("Failed to open file: {}", e);
+                self.note(Level::Error, &error_message).ok();
+                AppError::FileError(error_message)
+            })?;
+
+        let mut contents = String::new();
+        file.read_to_string(&mut contents)
+            .map_err(|e| {
+                let error_message = format!("Failed to read file: {}", e);
+                self.note(Level::Error, &error_message).ok();
+                AppError::FileError(error_message)
+            })?;
+
+        if contents.is_empty() {
+            self.accounts = Vec::new();
+            self.reindex();
+            self.base = Vec::new();
+            self.note(Level::Warn, "Storage file is empty. Starting with empty accounts list.")?;
+            return Ok(());
+        }
+
+        self.accounts = self.parse(&contents)?;
+        self.reindex();
+        self.base = self.accounts.clone();
+        let count = self.accounts.len();
+        self.note(Level::Info, &format!("Loaded {} accounts from storage", count))?;
+        Ok(())
+    }
+
+    /// Reads the accounts from the file's contents, decrypting them if needed
+    fn parse(&mut self, contents: &str) -> Result<Vec<Account>, AppError> {
+        let accounts = match serde_json::from_str::<EncryptedVault>(contents) {
+            Ok(vault) => {
+                let Some(key) = &self.key else {
+                    return Err(AppError::Locked(format!(
+                        "'{}' is encrypted; unlock it with the master password",
+                        self.file_path
+                    )));
+                };
+                // Parsed straight from the locked buffer, which is wiped afterwards
+                let plaintext = key.decrypt(&vault.data).map_err(|_| {
+                    AppError::Locked("Wrong master password or damaged vault".to_string())
+                })?;
+                serde_json::from_slice(&plaintext)
+            }
+            // Accepting plain text here would let anyone who can write the
+            // file swap in accounts of their choosing, re-encrypted on save
+            Err(_) if self.key.is_some() => {
+                let error_message = format!(
+                    "'{}' is not encrypted, but this vault is; another program may have replaced it",
+                    self.file_path
+                );
+                self.note(Level::Warn, &error_message).ok();
+                return Err(AppError::Locked(error_message)
+                    .with_hint("Check the file, or restore the vault from a snapshot in 'backups'"));
+            }
+            Err(_) => serde_json::from_str(contents),
+        };
+
+        accounts.map_err(|e| {
+            let error_message = format!("Failed to parse JSON: {}", e);
+            self.note(Level::Error, &error_message).ok();
+            AppError::StorageCorrupt(error_message)
+        })
+    }
+
+    /// Whether the vault is protected by a master password
+    pub fn is_encrypted(&self) -> bool {
+        self.key.is_some()
+    }
+
+    /// Key the vault is encrypted with, if any
+    pub fn key(&self) -> Option<&VaultKey> {
+        self.key.as_ref()
+    }
+
+    /// Encrypts the vault with `key` from now on, or stores it as plain text
+    /// with `None`, rewriting the file straight away. When a plain-text file
+    /// is encrypted, the old contents are overwritten with
+    /// [`secure_delete`](wipe::secure_delete) rather than just truncated.
+    pub fn set_key(&mut self, key: Option<VaultKey>) -> Result<(), AppError> {
+        if self.changed_on_disk() {
+            return Err(AppError::Conflict(format!(
+                "'{}' was changed by another program since it was loaded",
+                self.file_path
+            )));
+        }
+
+        let message = match (&self.key, &key) {
+            (None, Some(_)) => "Encrypted storage with a master password",
+            (Some(_), Some(_)) => "Re-encrypted storage with a new master password",
+            (_, None) => "Removed storage encryption",
+        };
+
+        // Move the plain text aside so it can be wiped once the encrypted
+        // file is safely written
+        let plaintext = if self.key.is_none() && key.is_some() && Path::new(&self.file_path).exists() {
+            let aside = PathBuf::from(format!("{}.plaintext", self.file_path));
+            fs::rename(&self.file_path, &aside)
+                .map_err(|e| AppError::FileError(format!("Failed to move plain-text vault aside: {}", e)))?;
+            self.stamp = None;
+            Some(aside)
+        } else {
+            None
+        };
+
+        let previous = std::mem::replace(&mut self.key, key);
+        self.dirty = true;
+        if let Err(e) = self.flush() {
+            self.key = previous;
+            if let Some(aside) = &plaintext {
+                fs::rename(aside, &self.file_path).ok();
+                self.stamp = FileStamp::read(&self.file_path);
+            }
+            return Err(e);
+        }
+
+        if let Some(aside) = plaintext {
+            wipe::secure_delete(&aside)?;
+        }
+        self.note(Level::Info, message)
+    }
+
+    fn save(&mut self) -> Result<(), AppError> {
+        // The file can't change, so neither may the accounts shown from it
+        if self.read_only {
+            self.accounts = self.base.clone();
+            self.reindex();
+            self.dirty = false;
+            return Err(read_only_error(&self.file_path));
+        }
+
+        // Don't silently replace edits made by another program
+        if self.changed_on_disk() {
+            let error_message = format!(
+                "'{}' was changed by another program since it was loaded",
+                self.file_path
+            );
+            self.note(Level::Warn, &error_message)?;
+            return Err(AppError::Conflict(error_message));
+        }
+
+        // Ensure the directory exists before saving
+        self.ensure_directory()?;
+        
+        let mut json = serde_json::to_string_pretty(&self.accounts)
+            .map_err(|e| {
+                let error_message = format!("Failed to serialize to JSON: {}", e);
+                self.note(Level::Error, &error_message).ok();
+                AppError::JsonError(error_message)
+            })?;
+
+        if let Some(key) = &self.key {
+            let vault = EncryptedVault { quackey_vault: 1, data: key.encrypt(json.as_bytes()) };
+            json = serde_json::to_string_pretty(&vault)
+                .map_err(|e| AppError::JsonError(format!("Failed to serialize encrypted vault: {}", e)))?;
+        }
+
+        if let Err(e) = permissions::replace(Path::new(&self.file_path), json.as_bytes()) {
+            let error_message = format!("Failed to write to file: {}", e);
+            self.note(Level::Error, &error_message)?;
+            return Err(AppError::FileError(error_message));
+        }
+
+        self.stamp = FileStamp::read(&self.file_path);
+        self.base = self.accounts.clone();
+        if let Err(e) = integrity::record_checksum(&self.file_path) {
+            self.note(Level::Warn, &e.to_string())?;
+        }
+
+        // More specific log message
+        if self.accounts.len() == 1 {
+            self.note(Level::Info, "Saved 1 account to storage")?;
+        } else {
+            self.note(Level::Info, &format!("Saved {} accounts to storage", self.accounts.len()))?;
+        }
+        Ok(())
+    }
+}
+
+impl Drop for Storage {
+    /// Writes changes left in an unfinished batch. Errors can't be reported
+    /// here; call [`flush`](Storage::flush) to handle them.
+    fn drop(&mut self) {
+        if self.dirty {
+            let _ = self.save();
+        }
+    }
+}
+
+/// Key for name lookups: trimmed and lowercased
+fn normalize_name(name: &str) -> String {
+    name.trim().to_lowercase()
+}
+
+/// Backups directory for a storage file: `backups/` in the same directory
+pub fn backups_dir_for(file_path: &str) -> PathBuf {
+    match Path::new(file_path).parent() {
+        Some(parent) if !parent.as_os_str().is_empty() => parent.join("backups"),
+        _ => PathBuf::from("backups"),
+    }
+}
+
+/// Copies the storage file at `file_path` into its backups directory, named
+/// after the time and `reason`
+fn snapshot_file(file_path: &str, reason: &str) -> Result<PathBuf, AppError> {
+    let dir = backups_dir_for(file_path);
+    fs::create_dir_all(&dir)
+        .map_err(|e| AppError::FileError(format!("Failed to create backups directory: {}", e)))?;
+
+    let target = dir.join(format!(
+        "accounts-{}-{}.json",
+        Local::now().format("%Y%m%d-%H%M%S"),
+        reason
+    ));
+    fs::copy(file_path, &target)
+        .and_then(|_| permissions::restrict(&target))
+        .map_err(|e| AppError::FileError(format!("Failed to write snapshot: {}", e)))?;
+    Ok(target)
+}
+
+/// Files next to the storage file at `file_path` that belong to it, and go
+/// where it goes: earlier copies, its checksum, the sync base, the code
+/// history and the DPAPI and PIN keys
+fn companion_files(file_path: &str) -> Vec<PathBuf> {
+    let mut files = crate::repair::backup_paths(file_path);
+    files.extend(
+        [
+            integrity::checksum_path(file_path),
+            dpapi::key_path(file_path),
+            pin::pin_path(file_path),
+            sync::base_path_for(file_path),
+            history::path_for(file_path),
+        ]
+            .into_iter()
+            .filter(|path| path.is_file()),
+    );
+    files
+}
+
+/// Whether `a` and `b` name the same file, however the paths are written;
+/// paths that don't exist are compared as written
+pub fn same_file(a: &str, b: &str) -> bool {
+    match (fs::canonicalize(a), fs::canonicalize(b)) {
+        (Ok(a), Ok(b)) => a == b,
+        _ => a == b,
+    }
+}
+
+/// Copies `from` to a temporary name next to `to` and renames it into place,
+/// so `to` is never left half-written
+fn copy_into_place(from: &Path, to: &Path) -> std::io::Result<()> {
+    if let Some(dir) = to.parent().filter(|dir| !dir.as_os_str().is_empty()) {
+        fs::create_dir_all(dir)?;
+    }
+    let mut temp = to.as_os_str().to_owned();
+    temp.push(".moving");
+    let temp = PathBuf::from(temp);
+
+    fs::copy(from, &temp)
+        .and_then(|_| permissions::restrict(&temp))
+        .and_then(|_| fs::rename(&temp, to))
+        .inspect_err(|_| {
+            let _ = fs::remove_file(&temp);
+        })
+}
+
+/// Whether the storage file at `file_path`, or the folder it would be created
+/// in, is on a read-only file system
+pub fn on_read_only_fs(file_path: &str) -> bool {
+    let path = Path::new(file_path);
+    let result = if path.exists() {
+        fs::OpenOptions::new().append(true).open(path).map(drop)
+    } else {
+        // The folders that would be created for it are on the same mount
+        let Some(dir) = path
+            .ancestors()
+            .skip(1)
+            .map(|dir| if dir.as_os_str().is_empty() { Path::new(".") } else { dir })
+            .find(|dir| dir.exists())
+        else {
+            return false;
+        };
+        let probe = dir.join(".quackey-probe");
+        File::create(&probe).map(|_| {
+            let _ = fs::remove_file(&probe);
+        })
+    };
+    result.is_err_and(|e| e.kind() == std::io::ErrorKind::ReadOnlyFilesystem)
+}
+
+/// Re-encrypts the retained copies of the vault at `file_path` (see
+/// [`repair::backup_paths`](crate::repair::backup_paths)) that are encrypted
+/// with `old` so they open with `new`, after the master password changed.
+/// Returns how many were re-encrypted and the encrypted copies `old` can't
+/// open, such as snapshots from before an earlier password change.
+pub fn reencrypt_backups(file_path: &str, old: &VaultKey, new: &VaultKey) -> Result<(usize, Vec<PathBuf>), AppError> {
+    let mut updated = 0;
+    let mut skipped = Vec::new();
+
+    for path in crate::repair::backup_paths(file_path) {
+        let Ok(contents) = fs::read_to_string(&path) else {
+            continue;
+        };
+        let Ok(vault) = serde_json::from_str::<EncryptedVault>(&contents) else {
+            // Plain-text copies have nothing to re-encrypt
+            continue;
+        };
+        let Ok(plaintext) = old.decrypt(&vault.data) else {
+            skipped.push(path);
+            continue;
+        };
+
+        let vault = EncryptedVault { quackey_vault: vault.quackey_vault, data: new.encrypt(&plaintext) };
+        let json = serde_json::to_string_pretty(&vault)
+            .map_err(|e| AppError::JsonError(format!("Failed to serialize encrypted vault: {}", e)))?;
+        permissions::replace(&path, json.as_bytes())
+            .map_err(|e| AppError::FileError(format!("Failed to re-encrypt '{}': {}", path.display(), e)))?;
+        updated += 1;
+    }
+
+    Ok((updated, skipped))
+}
+
+/// The error for saving a vault on a read-only file system
+pub fn read_only_error(file_path: &str) -> AppError {
+    AppError::PermissionError(format!(
+        "'{}' is on a read-only file system, so changes can't be saved",
+        file_path
+    ))
+    .with_path(file_path)
+    .with_hint("Remount it read-write, or choose a writable storage location in the settings")
+}
+
+/// Whether the storage file at `file_path` is encrypted with a master password
+pub fn is_encrypted(file_path: &str) -> bool {
+    fs::read_to_string(file_path)
+        .is_ok_and(|contents| serde_json::from_str::<EncryptedVault>(&contents).is_ok())
+}
+
+/// Derives the key of the encrypted storage file at `file_path` from its
+/// master password
+pub fn unlock(file_path: &str, passphrase: &str) -> Result<VaultKey, AppError> {
+    let contents = fs::read_to_string(file_path)
+        .map_err(|e| AppError::FileError(format!("Failed to read storage file: {}", e)))?;
+    let vault: EncryptedVault = serde_json::from_str(&contents)
+        .map_err(|_| AppError::InvalidInput(format!("'{}' is not encrypted", file_path)))?;
+
+    VaultKey::unlock(&vault.data, passphrase)
+        .map_err(|_| AppError::Locked("Wrong master password".to_string()))
+}
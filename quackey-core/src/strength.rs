@@ -0,0 +1,250 @@
+//! Strength estimates for master passwords, in the spirit of zxcvbn.
+//!
+//! A passphrase is split into the patterns people actually use: common
+//! passwords (also with `@` for `a` and similar swaps), keyboard runs such as
+//! `qwerty`, sequences such as `abc` or `123`, repeated characters or words,
+//! and years. Each pattern costs about as many guesses as an attacker trying
+//! that kind of pattern would need, and everything else costs a brute-force
+//! guess per character. The total, in bits, decides the [`Strength`]. It is a
+//! guide for people choosing a password, not a guarantee.
+
+/// Passphrases shorter than this are only accepted after a warning
+pub const MIN_LENGTH: usize = 12;
+
+/// Among the most common leaked passwords, most common first
+const COMMON: &[&str] = &[
+    "password", "123456", "qwerty", "letmein", "welcome", "monkey", "dragon", "football",
+    "baseball", "iloveyou", "admin", "login", "master", "sunshine", "princess", "shadow",
+    "superman", "trustno1", "passw0rd", "starwars", "whatever", "freedom", "michael",
+    "charlie", "jennifer", "computer", "secret", "summer", "winter", "hello", "love",
+    "quackey", "duck", "changeme", "default", "google", "github", "mustang", "access",
+    "batman", "soccer", "hockey", "killer", "pepper", "ginger", "cheese", "flower",
+];
+
+/// Rows of a QWERTY keyboard, for spotting runs along them
+const KEYBOARD_ROWS: &[&str] = &["1234567890", "qwertyuiop", "asdfghjkl", "zxcvbnm"];
+
+/// Shortest keyboard run, sequence or repeat treated as a pattern
+const MIN_PATTERN: usize = 3;
+
+/// How hard a passphrase is to guess
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord)]
+pub enum Strength {
+    VeryWeak,
+    Weak,
+    Fair,
+    Strong,
+    VeryStrong,
+}
+
+impl Strength {
+    /// Short description for the meter
+    pub fn label(self) -> &'static str {
+        match self {
+            Strength::VeryWeak => "very weak",
+            Strength::Weak => "weak",
+            Strength::Fair => "fair",
+            Strength::Strong => "strong",
+            Strength::VeryStrong => "very strong",
+        }
+    }
+
+    /// 0 for very weak up to 4 for very strong
+    pub fn score(self) -> usize {
+        self as usize
+    }
+
+    /// Whether to ask for confirmation before accepting the passphrase
+    pub fn is_weak(self) -> bool {
+        self <= Strength::Weak
+    }
+}
+
+/// The estimate for a passphrase, with what makes it easy to guess
+#[derive(Debug, Clone)]
+pub struct Estimate {
+    pub strength: Strength,
+    /// Rough number of guesses needed, as a power of two
+    pub bits: f64,
+    /// Why it is easy to guess, most important first
+    pub warnings: Vec<&'static str>,
+}
+
+impl Estimate {
+    /// Whether to warn and ask before accepting the passphrase: it is weak or
+    /// shorter than [`MIN_LENGTH`]
+    pub fn needs_confirmation(&self) -> bool {
+        self.strength.is_weak() || self.warnings.contains(&SHORT)
+    }
+}
+
+const SHORT: &str = "It is shorter than 12 characters";
+const COMMON_WARNING: &str = "It contains a commonly used password";
+const KEYBOARD_WARNING: &str = "Keyboard runs like qwerty are easy to guess";
+const SEQUENCE_WARNING: &str = "Sequences like abc or 123 are easy to guess";
+const REPEAT_WARNING: &str = "Repeats like aaa or abcabc are easy to guess";
+const YEAR_WARNING: &str = "Years are easy to guess";
+
+/// Estimates the strength of `passphrase`
+pub fn estimate(passphrase: &str) -> Estimate {
+    let chars: Vec<char> = passphrase.chars().collect();
+    let pool = pool_bits(&chars);
+    let mut bits = 0.0;
+    let mut warnings = Vec::new();
+    if !chars.is_empty() && chars.len() < MIN_LENGTH {
+        warnings.push(SHORT);
+    }
+
+    let mut i = 0;
+    while i < chars.len() {
+        match find_pattern(&chars[i..]) {
+            Some((len, cost, warning)) => {
+                bits += cost;
+                if !warnings.contains(&warning) {
+                    warnings.push(warning);
+                }
+                i += len;
+            }
+            None => {
+                bits += pool;
+                i += 1;
+            }
+        }
+    }
+
+    let strength = match bits {
+        bits if bits < 28.0 => Strength::VeryWeak,
+        bits if bits < 40.0 => Strength::Weak,
+        bits if bits < 60.0 => Strength::Fair,
+        bits if bits < 80.0 => Strength::Strong,
+        _ => Strength::VeryStrong,
+    };
+    // A strong passphrase may contain a common word; that is only worth
+    // mentioning when it makes the whole easy to guess
+    if strength >= Strength::Strong {
+        warnings.retain(|&warning| warning == SHORT);
+    }
+    Estimate { strength, bits, warnings }
+}
+
+/// The longest pattern `chars` starts with: its length, cost in bits and warning
+fn find_pattern(chars: &[char]) -> Option<(usize, f64, &'static str)> {
+    let raw: Vec<char> = chars.iter().map(char::to_ascii_lowercase).collect();
+    let unleeted: Vec<char> = raw.iter().map(|&c| unleet(c)).collect();
+    let mut best: Option<(usize, f64, &'static str)> = None;
+    let mut consider = |len: usize, cost: f64, warning: &'static str| {
+        if best.is_none_or(|(best_len, _, _)| len > best_len) {
+            best = Some((len, cost, warning));
+        }
+    };
+
+    // Common passwords cost their rank, plus a bit for capitals or swaps
+    for (rank, word) in COMMON.iter().enumerate() {
+        let word: Vec<char> = word.chars().collect();
+        if raw.starts_with(&word) || unleeted.starts_with(&word) {
+            let varied = chars[..word.len()].iter().zip(&word).any(|(c, w)| c != w);
+            consider(word.len(), ((rank + 1) as f64).log2() + 1.0 + f64::from(u8::from(varied)), COMMON_WARNING);
+        }
+    }
+
+    // Keyboard runs, forwards or backwards along a row
+    let keyboard_run = KEYBOARD_ROWS
+        .iter()
+        .map(|row| {
+            let row: Vec<char> = row.chars().collect();
+            let step = |a: char, b: char| {
+                let (Some(x), Some(y)) = (row.iter().position(|&c| c == a), row.iter().position(|&c| c == b)) else {
+                    return None;
+                };
+                Some(y as i64 - x as i64)
+            };
+            run_length(&raw, |a, b| step(a, b).filter(|d| d.abs() == 1))
+        })
+        .max()
+        .unwrap_or(0);
+    if keyboard_run >= MIN_PATTERN {
+        consider(keyboard_run, (40.0_f64).log2() + (keyboard_run as f64).log2() + 1.0, KEYBOARD_WARNING);
+    }
+
+    // Alphabet and digit sequences, going up or down by one
+    let sequence = run_length(&raw, |a, b| {
+        let same_kind = (a.is_ascii_lowercase() && b.is_ascii_lowercase()) || (a.is_ascii_digit() && b.is_ascii_digit());
+        let step = b as i64 - a as i64;
+        (same_kind && step.abs() == 1).then_some(step)
+    });
+    if sequence >= MIN_PATTERN {
+        consider(sequence, (36.0_f64).log2() + (sequence as f64).log2() + 1.0, SEQUENCE_WARNING);
+    }
+
+    // The same character over and over
+    let repeat = chars.iter().take_while(|&&c| c == chars[0]).count();
+    if repeat >= MIN_PATTERN {
+        consider(repeat, pool_bits(&chars[..1]) + (repeat as f64).log2(), REPEAT_WARNING);
+    }
+
+    // A block written more than once, such as "hunter2hunter2": the block
+    // itself plus a guess at how often it repeats
+    for block in 2..=chars.len() / 2 {
+        let times = chars.chunks(block).take_while(|chunk| *chunk == &chars[..block]).count();
+        if times >= 2 {
+            let first: String = chars[..block].iter().collect();
+            consider(block * times, estimate(&first).bits + (times as f64).log2(), REPEAT_WARNING);
+        }
+    }
+
+    // Years from 1900 to 2099
+    if chars.len() >= 4 {
+        let year: String = chars[..4].iter().collect();
+        if year.parse::<u32>().is_ok_and(|y| (1900..2100).contains(&y)) {
+            consider(4, (200.0_f64).log2(), YEAR_WARNING);
+        }
+    }
+
+    best
+}
+
+/// Length of the run at the start of `chars` in which every step between
+/// neighbours is `Some` and the same
+fn run_length(chars: &[char], step: impl Fn(char, char) -> Option<i64>) -> usize {
+    let Some(first) = chars.get(..2).and_then(|pair| step(pair[0], pair[1])) else {
+        return 1;
+    };
+    2 + chars[1..]
+        .windows(2)
+        .take_while(|pair| step(pair[0], pair[1]) == Some(first))
+        .count()
+}
+
+/// Undoes the usual letter-to-symbol swaps, such as `@` for `a`
+fn unleet(c: char) -> char {
+    match c {
+        '@' | '4' => 'a',
+        '3' => 'e',
+        '1' | '!' => 'i',
+        '0' => 'o',
+        '$' | '5' => 's',
+        '7' => 't',
+        c => c,
+    }
+}
+
+/// Bits per character of a brute-force search over the character kinds used
+fn pool_bits(chars: &[char]) -> f64 {
+    let mut pool = 0;
+    if chars.iter().any(char::is_ascii_lowercase) {
+        pool += 26;
+    }
+    if chars.iter().any(char::is_ascii_uppercase) {
+        pool += 26;
+    }
+    if chars.iter().any(char::is_ascii_digit) {
+        pool += 10;
+    }
+    if chars.iter().any(|c| c.is_ascii() && !c.is_ascii_alphanumeric()) {
+        pool += 33;
+    }
+    if chars.iter().any(|c| !c.is_ascii()) {
+        pool += 100;
+    }
+    if pool == 0 { 0.0 } else { (pool as f64).log2() }
+}
@@ -0,0 +1,279 @@
+//! Syncing the vault through a WebDAV server such as Nextcloud.
+//!
+//! The server only ever sees the accounts encrypted with a sync passphrase
+//! ([`seal`]). Uploads are conditional on the file's ETag, so a copy uploaded
+//! from another device in the meantime is detected instead of overwritten.
+//! The accounts as of the last sync are kept next to the vault
+//! (`sync-base.json`) as the base for a [`merge`](crate::merge::merge) with
+//! the server's copy.
+
+use std::fs;
+use std::io::Read;
+use std::path::{Path, PathBuf};
+use std::time::Duration;
+use serde::{Serialize, Deserialize};
+use crate::account::Account;
+use crate::crypto::{self, EncryptedData, VaultKey};
+use crate::error::AppError;
+use crate::permissions;
+
+const TIMEOUT: Duration = Duration::from_secs(30);
+const BASE_FILENAME: &str = "sync-base.json";
+
+/// Keys the `webdav` object in `config.json` may contain; keep in sync with
+/// [`WebDavSettings`]
+pub const WEBDAV_KEYS: &[&str] = &["url", "username", "password", "sealed_password", "etag"];
+
+/// WebDAV settings saved in `config.json`
+///
+/// The password is never written as plain text: it is saved encrypted with
+/// the vault key ([`seal_password`](Self::seal_password)), or not at all for
+/// a vault without a master password, in which case it is asked for when
+/// syncing.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct WebDavSettings {
+    /// Full URL of the vault file, e.g.
+    /// `https://cloud.example.com/remote.php/dav/files/alice/quackey.vault`
+    pub url: String,
+    pub username: String,
+    /// The password in memory; empty until [`unseal_password`](Self::unseal_password).
+    /// Still read from older config files that stored it as plain text.
+    #[serde(default, skip_serializing)]
+    pub password: String,
+    /// The password encrypted with the vault key
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    sealed_password: Option<EncryptedData>,
+    /// ETag of the server's copy after the last sync
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub etag: Option<String>,
+}
+
+impl WebDavSettings {
+    pub fn new(url: String, username: String, password: String) -> Self {
+        Self { url, username, password, sealed_password: None, etag: None }
+    }
+
+    /// Encrypts the password with `key` for saving, or keeps it out of the
+    /// config file when the vault has no key
+    pub fn seal_password(&mut self, key: Option<&VaultKey>) {
+        self.sealed_password = key
+            .filter(|_| !self.password.is_empty())
+            .map(|key| key.encrypt(self.password.as_bytes()));
+    }
+
+    /// Whether the config file holds the password encrypted
+    pub fn is_password_sealed(&self) -> bool {
+        self.sealed_password.is_some()
+    }
+
+    /// Decrypts the saved password with the vault key
+    pub fn unseal_password(&mut self, key: &VaultKey) -> Result<(), AppError> {
+        if let Some(sealed) = &self.sealed_password {
+            let password = key.decrypt(sealed)?;
+            self.password = String::from_utf8(password.to_vec())
+                .map_err(|_| AppError::InvalidInput("The saved WebDAV password is damaged".to_string()))?;
+        }
+        Ok(())
+    }
+}
+
+/// The server's copy of the vault
+#[derive(Debug, Clone)]
+pub struct Remote {
+    pub data: Vec<u8>,
+    pub etag: Option<String>,
+}
+
+/// What happened to an upload
+#[derive(Debug, Clone)]
+pub enum Upload {
+    /// Stored; the server's new ETag if it reported one
+    Stored { etag: Option<String> },
+    /// The server's copy changed since the ETag the upload was based on
+    Conflict,
+}
+
+/// A place the encrypted vault can be synced to
+pub trait SyncProvider {
+    /// The server's copy, or `None` if nothing was uploaded yet
+    fn download(&self) -> Result<Option<Remote>, AppError>;
+
+    /// Stores `data` if the server's copy still has ETag `expected`
+    /// (`None`: only if there is no copy yet)
+    fn upload(&self, data: &[u8], expected: Option<&str>) -> Result<Upload, AppError>;
+}
+
+/// Syncs with a single file on a WebDAV server
+pub struct WebDav {
+    settings: WebDavSettings,
+    agent: ureq::Agent,
+}
+
+impl WebDav {
+    pub fn new(settings: &WebDavSettings) -> Self {
+        Self {
+            settings: settings.clone(),
+            agent: ureq::AgentBuilder::new().timeout(TIMEOUT).build(),
+        }
+    }
+
+    fn authorization(&self) -> String {
+        let credentials = format!("{}:{}", self.settings.username, self.settings.password);
+        format!("Basic {}", encode_base64(credentials.as_bytes()))
+    }
+}
+
+impl SyncProvider for WebDav {
+    fn download(&self) -> Result<Option<Remote>, AppError> {
+        let response = match self
+            .agent
+            .get(&self.settings.url)
+            .set("Authorization", &self.authorization())
+            .call()
+        {
+            Ok(response) => response,
+            Err(ureq::Error::Status(404, _)) => return Ok(None),
+            Err(e) => return Err(request_error("download", e)),
+        };
+
+        let etag = etag_of(&response);
+        let mut data = Vec::new();
+        response
+            .into_reader()
+            .read_to_end(&mut data)
+            .map_err(|e| AppError::FileError(format!("Failed to download the vault: {}", e)))?;
+
+        Ok(Some(Remote { data, etag }))
+    }
+
+    fn upload(&self, data: &[u8], expected: Option<&str>) -> Result<Upload, AppError> {
+        let request = self
+            .agent
+            .put(&self.settings.url)
+            .set("Authorization", &self.authorization())
+            .set("Content-Type", "application/octet-stream");
+        let request = match expected {
+            Some(etag) => request.set("If-Match", etag),
+            None => request.set("If-None-Match", "*"),
+        };
+
+        match request.send_bytes(data) {
+            Ok(response) => Ok(Upload::Stored { etag: etag_of(&response) }),
+            Err(ureq::Error::Status(412, _)) => Ok(Upload::Conflict),
+            Err(e) => Err(request_error("upload", e)),
+        }
+    }
+}
+
+/// ETag of a response; Nextcloud also sends it as `OC-ETag`
+fn etag_of(response: &ureq::Response) -> Option<String> {
+    response
+        .header("ETag")
+        .or_else(|| response.header("OC-ETag"))
+        .map(str::to_string)
+}
+
+fn request_error(action: &str, error: ureq::Error) -> AppError {
+    match error {
+        ureq::Error::Status(401 | 403, _) => AppError::PermissionError(format!(
+            "The WebDAV server refused the {}; check the username and password",
+            action
+        )),
+        ureq::Error::Status(code, response) => AppError::FileError(format!(
+            "WebDAV {} failed: {} {}",
+            action,
+            code,
+            response.status_text()
+        )),
+        e => AppError::FileError(format!("WebDAV {} failed: {}", action, e)),
+    }
+}
+
+/// Encrypts the accounts for upload
+pub fn seal(accounts: &[Account], passphrase: &str) -> Result<Vec<u8>, AppError> {
+    let json = serde_json::to_vec(accounts)
+        .map_err(|e| AppError::JsonError(format!("Failed to serialize accounts: {}", e)))?;
+    crypto::encrypt(&json, passphrase).to_bytes()
+}
+
+/// Decrypts a vault produced by [`seal`]
+pub fn open(data: &[u8], passphrase: &str) -> Result<Vec<Account>, AppError> {
+    let data = EncryptedData::from_bytes(data)
+        .map_err(|_| AppError::InvalidInput("The synced vault is damaged".to_string()))?;
+    let json = crypto::decrypt(&data, passphrase)?;
+    serde_json::from_slice(&json)
+        .map_err(|e| AppError::JsonError(format!("Failed to parse synced vault: {}", e)))
+}
+
+/// Undoes changes in the server's copy that would bring in a secret reference
+/// this device doesn't already hold for the same account, since generating a
+/// code for a `cmd:` reference runs its command. The account is put back as we
+/// have it (or as it was at the last sync), so the merge keeps our version.
+/// Returns the accounts to merge and the names of those whose change was undone.
+pub fn drop_new_references(theirs: Vec<Account>, ours: &[Account], base: &[Account]) -> (Vec<Account>, Vec<String>) {
+    let mut dropped = Vec::new();
+    let accounts = theirs
+        .into_iter()
+        .filter_map(|account| {
+            if account.secret_reference().is_none() {
+                return Some(account);
+            }
+            let known = |other: &&Account| other.id() == account.id();
+            let ours = ours.iter().find(known);
+            let base = base.iter().find(known);
+            if [ours, base].into_iter().flatten().any(|other| other.secret() == account.secret()) {
+                return Some(account);
+            }
+
+            dropped.push(account.name().to_string());
+            ours.or(base).cloned()
+        })
+        .collect();
+
+    (accounts, dropped)
+}
+
+/// Where the accounts as of the last sync are kept for a storage file
+pub fn base_path_for(file_path: &str) -> PathBuf {
+    match Path::new(file_path).parent() {
+        Some(parent) if !parent.as_os_str().is_empty() => parent.join(BASE_FILENAME),
+        _ => PathBuf::from(BASE_FILENAME),
+    }
+}
+
+/// The accounts as of the last sync; empty before the first one
+pub fn load_base(file_path: &str) -> Result<Vec<Account>, AppError> {
+    match fs::read_to_string(base_path_for(file_path)) {
+        Ok(contents) => serde_json::from_str(&contents)
+            .map_err(|e| AppError::JsonError(format!("Failed to parse sync base: {}", e))),
+        Err(_) => Ok(Vec::new()),
+    }
+}
+
+/// Remembers the accounts as of this sync
+pub fn save_base(file_path: &str, accounts: &[Account]) -> Result<(), AppError> {
+    let json = serde_json::to_string_pretty(accounts)
+        .map_err(|e| AppError::JsonError(format!("Failed to serialize sync base: {}", e)))?;
+    permissions::replace(&base_path_for(file_path), json.as_bytes())
+        .map_err(|e| AppError::FileError(format!("Failed to write sync base: {}", e)))
+}
+
+/// Standard base64 with padding, for HTTP basic authentication
+fn encode_base64(bytes: &[u8]) -> String {
+    const ALPHABET: &[u8; 64] = b"ABCDEFGHIJKLMNOPQRSTUVWXYZabcdefghijklmnopqrstuvwxyz0123456789+/";
+
+    let mut encoded = String::new();
+    for chunk in bytes.chunks(3) {
+        let b = [chunk[0], *chunk.get(1).unwrap_or(&0), *chunk.get(2).unwrap_or(&0)];
+        let n = ((b[0] as u32) << 16) | ((b[1] as u32) << 8) | b[2] as u32;
+
+        for i in 0..4 {
+            if i <= chunk.len() {
+                encoded.push(ALPHABET[((n >> (18 - 6 * i)) & 0x3f) as usize] as char);
+            } else {
+                encoded.push('=');
+            }
+        }
+    }
+    encoded
+}
@@ -0,0 +1,72 @@
+//! Reusable account settings for enrolling similar accounts.
+
+use serde::{Serialize, Deserialize};
+use totp_rs::Algorithm as TotpAlgorithm;
+use crate::account::{Account, Algorithm};
+
+/// Reusable account settings for enrolling many similar accounts
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct AccountTemplate {
+    name: String,
+    issuer: Option<String>,
+    digits: usize,
+    period: u64,
+    algorithm: Algorithm,
+    #[serde(default)]
+    tags: Vec<String>,
+}
+
+impl AccountTemplate {
+    /// Creates a template from an existing account's settings
+    pub fn from_account(name: String, account: &Account) -> Self {
+        Self {
+            name,
+            issuer: account.issuer().cloned(),
+            digits: account.digits(),
+            period: account.period(),
+            algorithm: account.algorithm().into(),
+            tags: account.tags().to_vec(),
+        }
+    }
+
+    pub fn name(&self) -> &str {
+        &self.name
+    }
+
+    pub fn issuer(&self) -> Option<&String> {
+        self.issuer.as_ref()
+    }
+
+    pub fn digits(&self) -> usize {
+        self.digits
+    }
+
+    pub fn period(&self) -> u64 {
+        self.period
+    }
+
+    pub fn algorithm(&self) -> TotpAlgorithm {
+        self.algorithm.into()
+    }
+
+    pub fn tags(&self) -> &[String] {
+        &self.tags
+    }
+
+    /// One-line summary of the template's settings
+    pub fn summary(&self) -> String {
+        let mut summary = format!(
+            "{} digits, {}s, {}",
+            self.digits,
+            self.period,
+            self.algorithm.name()
+        );
+        if let Some(issuer) = &self.issuer {
+            summary = format!("{}, {}", issuer, summary);
+        }
+        if !self.tags.is_empty() {
+            summary = format!("{}, tags: {}", summary, self.tags.join(", "));
+        }
+        summary
+    }
+}
@@ -0,0 +1,351 @@
+//! High-level vault API for tools that embed Quackey.
+//!
+//! [`Vault`] and [`AccountBuilder`] are the stable entry points of this crate:
+//! they follow semver, so a minor release never breaks code written against
+//! them. The lower-level modules may change more often.
+
+use crate::account::{self, Account, Algorithm};
+use crate::config::Config;
+use crate::crypto::VaultKey;
+use crate::error::AppError;
+use crate::events::Events;
+use crate::health;
+use crate::presets::find_preset;
+use crate::secret::{self, SecretEncoding, SecretReference};
+use crate::storage::Storage;
+
+/// Builds a validated [`Account`], filling settings that aren't given from the
+/// issuer's preset and then the usual defaults (6 digits, 30 seconds, SHA1)
+///
+/// ```
+/// use quackey_core::vault::AccountBuilder;
+///
+/// let account = AccountBuilder::new("alice@example.com", "JBSWY3DPEHPK3PXP")
+///     .issuer("GitHub")
+///     .tags(["work"])
+///     .build()?;
+/// assert_eq!(account.digits(), 6);
+/// # Ok::<(), quackey_core::error::AppError>(())
+/// ```
+#[derive(Debug, Clone)]
+pub struct AccountBuilder {
+    name: String,
+    secret: String,
+    encoding: SecretEncoding,
+    issuer: Option<String>,
+    digits: Option<usize>,
+    period: Option<u64>,
+    algorithm: Option<Algorithm>,
+    tags: Vec<String>,
+    aliases: Vec<String>,
+}
+
+impl AccountBuilder {
+    /// Starts an account with its name and Base32 secret
+    pub fn new(name: impl Into<String>, secret: impl Into<String>) -> Self {
+        Self {
+            name: name.into(),
+            secret: secret.into(),
+            encoding: SecretEncoding::Base32,
+            issuer: None,
+            digits: None,
+            period: None,
+            algorithm: None,
+            tags: Vec::new(),
+            aliases: Vec::new(),
+        }
+    }
+
+    /// How the secret passed to [`new`](Self::new) is encoded (Base32 by default)
+    pub fn encoding(mut self, encoding: SecretEncoding) -> Self {
+        self.encoding = encoding;
+        self
+    }
+
+    pub fn issuer(mut self, issuer: impl Into<String>) -> Self {
+        self.issuer = Some(issuer.into()).filter(|i: &String| !i.trim().is_empty());
+        self
+    }
+
+    pub fn digits(mut self, digits: usize) -> Self {
+        self.digits = Some(digits);
+        self
+    }
+
+    /// Code lifetime in seconds
+    pub fn period(mut self, period: u64) -> Self {
+        self.period = Some(period);
+        self
+    }
+
+    /// Hash algorithm, such as [`Algorithm::Sha256`]
+    pub fn algorithm(mut self, algorithm: Algorithm) -> Self {
+        self.algorithm = Some(algorithm);
+        self
+    }
+
+    pub fn tags<I, S>(mut self, tags: I) -> Self
+    where
+        I: IntoIterator<Item = S>,
+        S: Into<String>,
+    {
+        self.tags = tags.into_iter().map(Into::into).collect();
+        self
+    }
+
+    /// Short names the account can also be looked up by, such as "gh"
+    pub fn aliases<I, S>(mut self, aliases: I) -> Self
+    where
+        I: IntoIterator<Item = S>,
+        S: Into<String>,
+    {
+        self.aliases = aliases.into_iter().map(Into::into).collect();
+        self
+    }
+
+    /// Converts the secret to canonical Base32 (a `cmd:` or `env:` reference
+    /// is kept as it is), applies presets and checks that the account can
+    /// generate codes
+    pub fn build(self) -> Result<Account, AppError> {
+        let name = self.name.trim().to_string();
+        if name.is_empty() {
+            return Err(AppError::InvalidInput("Account name cannot be empty".to_string()));
+        }
+        for alias in &self.aliases {
+            account::check_alias(alias)?;
+        }
+
+        let secret = match SecretReference::parse(&self.secret) {
+            Some(reference) => reference.to_string(),
+            None => secret::to_canonical_base32(&self.secret, self.encoding)?,
+        };
+
+        let preset = self.issuer.as_deref().and_then(find_preset);
+        let digits = self.digits.or(preset.map(|p| p.digits)).unwrap_or(6);
+        let period = self.period.or(preset.map(|p| p.period)).unwrap_or(30);
+        let algorithm = self
+            .algorithm
+            .or(preset.map(|p| Algorithm::from(p.algorithm)))
+            .unwrap_or(Algorithm::Sha1);
+
+        let account = Account::new(name, secret, digits, period, algorithm.into(), self.issuer)
+            .with_tags(self.tags)
+            .with_aliases(self.aliases);
+        account.check_settings()?;
+        account.generate_totp()?;
+
+        Ok(account)
+    }
+}
+
+/// An open vault: the accounts in one storage file
+///
+/// ```no_run
+/// use quackey_core::vault::{AccountBuilder, Vault};
+///
+/// let mut vault = Vault::open("accounts.json")?;
+/// vault.add(AccountBuilder::new("alice@example.com", "JBSWY3DPEHPK3PXP").issuer("GitHub"))?;
+/// println!("{}", vault.code("github")?);
+/// # Ok::<(), quackey_core::error::AppError>(())
+/// ```
+pub struct Vault {
+    storage: Storage,
+}
+
+impl Vault {
+    /// Opens the vault stored at `path`, starting empty if the file doesn't exist yet
+    pub fn open(path: &str) -> Result<Self, AppError> {
+        Ok(Self { storage: Storage::new_with_events(path, Events::default())? })
+    }
+
+    /// Opens the vault stored at `path`, decrypting it with `key` if it is
+    /// encrypted (see [`unlock`](crate::storage::unlock) for getting the key from the master
+    /// password)
+    pub fn open_with_key(path: &str, key: Option<VaultKey>) -> Result<Self, AppError> {
+        Ok(Self { storage: Storage::new_with_key(path, Events::default(), key)? })
+    }
+
+    /// Opens the vault configured in `config.json`, logging changes to the
+    /// application log (and the system log, if turned on) like the `quackey`
+    /// binary does
+    pub fn open_default() -> Result<Self, AppError> {
+        let config = Config::load()?;
+        let events = Events::for_config(&config)?;
+        Ok(Self { storage: Storage::new_with_events(&config.get_storage_file_path(), events)? })
+    }
+
+    /// Path of the storage file
+    pub fn path(&self) -> &str {
+        self.storage.file_path()
+    }
+
+    /// All accounts, in vault order
+    pub fn accounts(&self) -> &[Account] {
+        self.storage.get_accounts()
+    }
+
+    /// Builds and saves an account. Fails if its secret is already in the
+    /// vault or another account has one of its aliases.
+    pub fn add(&mut self, builder: AccountBuilder) -> Result<Account, AppError> {
+        let account = builder.build()?;
+
+        self.storage.check_aliases(account.aliases(), None)?;
+
+        if let Some(duplicate) = health::accounts_with_secret(self.storage.get_accounts(), &account).first() {
+            return Err(AppError::InvalidInput(format!(
+                "This secret is already used by account '{}'",
+                duplicate.name()
+            )));
+        }
+
+        self.storage.add_account(account.clone())?;
+        Ok(account)
+    }
+
+    /// The account with the given [`id`](Account::id)
+    pub fn get(&self, id: &str) -> Option<&Account> {
+        self.storage.find_by_id(id)
+    }
+
+    /// Finds the one account matching `query`, see [`find_in`]
+    pub fn find(&self, query: &str) -> Result<&Account, AppError> {
+        find_in(self.storage.get_accounts(), query)
+    }
+
+    /// Current code for the account matching `query`: its alias, name, issuer
+    /// or `issuer:name`, or the start or part of one of those (case-insensitive)
+    /// as long as only one account matches, see [`find_in`]
+    pub fn code(&self, query: &str) -> Result<String, AppError> {
+        self.find(query)?.generate_totp()
+    }
+
+    /// Accounts whose name, issuer, a tag or an alias contains `query`, ignoring case,
+    /// in vault order. An empty query returns every account.
+    pub fn search(&self, query: &str) -> Vec<&Account> {
+        self.storage
+            .get_accounts()
+            .iter()
+            .filter(|a| contains_query(a, query))
+            .collect()
+    }
+
+    /// Removes the account matching `query`, see [`code`](Self::code)
+    pub fn remove(&mut self, query: &str) -> Result<Account, AppError> {
+        let account = self.find(query)?;
+        let index = self
+            .storage
+            .get_accounts()
+            .iter()
+            .position(|a| std::ptr::eq(a, account))
+            .ok_or_else(|| AppError::NotFound(format!("Account '{}' not found", account.name())))?;
+        let account = account.clone();
+
+        self.storage.delete_accounts(&[index])?;
+        Ok(account)
+    }
+
+    /// Holds further changes in memory until [`flush`](Self::flush), so bulk
+    /// edits rewrite the file once
+    pub fn begin_batch(&mut self) {
+        self.storage.begin_batch();
+    }
+
+    /// Writes changes made since [`begin_batch`](Self::begin_batch)
+    pub fn flush(&mut self) -> Result<(), AppError> {
+        self.storage.flush()
+    }
+
+    /// Records in the code history that a code was just generated for the
+    /// account with `id`. Best effort, and the vault itself isn't written.
+    pub fn record_generation(&mut self, id: &str) {
+        self.storage.record_generation(id);
+    }
+}
+
+/// Finds the one account in `accounts` matching `query`, ignoring case. Tries
+/// an alias first, then the exact name, issuer or `issuer:name`, then those
+/// starting with `query`, then those containing it; the first step that
+/// matches anything decides. When it matches several accounts the error lists
+/// them.
+pub fn find_in<'a>(accounts: &'a [Account], query: &str) -> Result<&'a Account, AppError> {
+    let query = query.trim();
+    if query.is_empty() {
+        return Err(AppError::InvalidInput("No account name given".to_string()));
+    }
+
+    // Aliases are unique, so one wins over names and issuers it may clash with
+    if let Some(account) = accounts.iter().find(|a| a.has_alias(query)) {
+        return Ok(account);
+    }
+
+    let lowercase = query.to_lowercase();
+    let steps: [&dyn Fn(&Account) -> bool; 3] = [
+        &|account| matches_query(account, query),
+        &|account| lookup_keys(account).iter().any(|key| key.starts_with(&lowercase)),
+        &|account| lookup_keys(account).iter().any(|key| key.contains(&lowercase)),
+    ];
+    for step in steps {
+        let matches: Vec<&Account> = accounts.iter().filter(|account| step(account)).collect();
+        match matches.as_slice() {
+            [] => continue,
+            [account] => return Ok(account),
+            _ => return Err(ambiguous(query, &matches)),
+        }
+    }
+
+    Err(AppError::NotFound(format!("No account matches '{}'", query)).with_account(query))
+}
+
+/// Most candidates named by an ambiguous lookup error
+const MAX_CANDIDATES: usize = 5;
+
+fn ambiguous(query: &str, matches: &[&Account]) -> AppError {
+    let mut candidates: Vec<String> = matches
+        .iter()
+        .take(MAX_CANDIDATES)
+        .map(|account| match account.issuer() {
+            Some(issuer) => format!("{}:{}", issuer, account.name()),
+            None => account.name().to_string(),
+        })
+        .collect();
+    if matches.len() > MAX_CANDIDATES {
+        candidates.push(format!("and {} more", matches.len() - MAX_CANDIDATES));
+    }
+
+    AppError::InvalidInput(format!("'{}' matches {} accounts: {}", query, matches.len(), candidates.join(", ")))
+        .with_account(query)
+        .with_hint("Type more of the name, use issuer:name, or give the account an alias")
+}
+
+/// Lowercase name, issuer and `issuer:name` of `account`, for partial lookups
+fn lookup_keys(account: &Account) -> Vec<String> {
+    let name = account.name().to_lowercase();
+    match account.issuer() {
+        Some(issuer) => {
+            let issuer = issuer.to_lowercase();
+            vec![format!("{}:{}", issuer, name), issuer, name]
+        }
+        None => vec![name],
+    }
+}
+
+/// Whether `account`'s name, issuer, one of its tags or aliases contains
+/// `query`, ignoring case
+pub(crate) fn contains_query(account: &Account, query: &str) -> bool {
+    let query = query.trim().to_lowercase();
+    account.name().to_lowercase().contains(&query)
+        || account.issuer().is_some_and(|i| i.to_lowercase().contains(&query))
+        || account.tags().iter().any(|t| t.to_lowercase().contains(&query))
+        || account.aliases().iter().any(|a| a.to_lowercase().contains(&query))
+}
+
+/// Whether `account`'s name, issuer, `issuer:name` or an alias is `query`,
+/// ignoring case
+pub(crate) fn matches_query(account: &Account, query: &str) -> bool {
+    account.has_alias(query)
+        || account.name().eq_ignore_ascii_case(query)
+        || account.issuer().is_some_and(|i| i.eq_ignore_ascii_case(query))
+        || account
+            .issuer()
+            .is_some_and(|i| query.eq_ignore_ascii_case(&format!("{}:{}", i, account.name())))
+}
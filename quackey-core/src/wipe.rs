@@ -0,0 +1,51 @@
+//! Overwriting files that held plain-text secrets before deleting them.
+//!
+//! This is best effort. On SSDs, flash drives and copy-on-write or journaling
+//! file systems (btrfs, ZFS, APFS) the overwrite may land on different blocks
+//! than the old contents, which then stay readable with forensic tools until
+//! the drive reuses them. Snapshots, sync clients and backups may also hold
+//! copies. Full-disk encryption is the reliable protection there.
+
+use std::fs::{self, OpenOptions};
+use std::io::{Seek, SeekFrom, Write};
+use std::path::Path;
+use rand::RngCore;
+use rand::rngs::OsRng;
+use crate::error::AppError;
+
+const CHUNK_LEN: usize = 64 * 1024;
+
+/// Overwrites the file at `path` with random bytes and then zeros, flushing
+/// each pass to disk, and deletes it. A missing file is not an error.
+pub fn secure_delete(path: &Path) -> Result<(), AppError> {
+    let len = match fs::metadata(path) {
+        Ok(metadata) => metadata.len(),
+        Err(e) if e.kind() == std::io::ErrorKind::NotFound => return Ok(()),
+        Err(e) => return Err(AppError::FileError(format!("Failed to read '{}': {}", path.display(), e))),
+    };
+
+    let error = |e: std::io::Error| {
+        AppError::FileError(format!("Failed to overwrite '{}': {}", path.display(), e))
+    };
+
+    let mut file = OpenOptions::new().write(true).open(path).map_err(error)?;
+    for random in [true, false] {
+        file.seek(SeekFrom::Start(0)).map_err(error)?;
+
+        let mut chunk = vec![0u8; CHUNK_LEN];
+        let mut left = len;
+        while left > 0 {
+            let n = left.min(CHUNK_LEN as u64) as usize;
+            if random {
+                OsRng.fill_bytes(&mut chunk[..n]);
+            }
+            file.write_all(&chunk[..n]).map_err(error)?;
+            left -= n as u64;
+        }
+        file.sync_all().map_err(error)?;
+    }
+    drop(file);
+
+    fs::remove_file(path)
+        .map_err(|e| AppError::FileError(format!("Failed to delete '{}': {}", path.display(), e)))
+}
@@ -0,0 +1,484 @@
+use totp_rs::Algorithm;
+use quackey_core::error::AppError;
+use quackey_core::logger::{self, Level, LogQuery};
+use quackey_core::secret::{SecretEncoding, SecretReference};
+use crate::completions;
+
+/// Usage text printed by `quackey --help`
+pub const USAGE: &str = "\
+Usage: quackey [COMMAND]
+
+Run without a command to start the interactive menu.
+
+Commands:
+  gen <account>          Print the current code for an account, matched by alias,
+                         name, issuer or issuer:name, or a unique part of one
+  gen --tag <tag>        Print the current code of every account with the tag,
+                         one 'code account' pair per line
+  list [query]           List accounts whose name, issuer or tag contains the
+                         query, one gen-ready name per line
+      --format alfred        Alfred script filter JSON with current codes, for
+                             Alfred workflows and Raycast or Wox extensions
+      --names                Only issuers and names starting with the query, for
+                             shell completion; never prompts or prints errors
+      --archived             Include archived accounts
+  statusbar <account>    Print one line such as 'GitHub 123 456 (12s)' for
+                         waybar, polybar or tmux to poll; --json for waybar
+  import --format <name> <file>
+                         Import accounts from a file (see formats below)
+  import --uris <file>   Same as --format uris: one otpauth:// URI per line
+  import --from-env      Import the accounts in QUACKEY_ACCOUNT_<NAME> variables
+  export --format <name> [--output <file>]
+                         Write all accounts, secrets included, to a file or stdout
+      --encrypt              Encrypt it with a backup passphrase, separate from
+                             the master password; import asks for it
+  emergency-kit [--output <file>]
+                         Write a printable page with the vault's location, key
+                         fingerprint and recovery steps
+      --qr                   Add a QR code of all accounts, encrypted with a
+                             backup passphrase
+  add --name <name>      Add an account, reading the secret from one of:
+      --secret-stdin         standard input
+      --secret-cmd <cmd>     the first line of a command's output
+      --secret-ref <ref>     nowhere: store a reference such as
+                             'cmd:pass show otp/github' or 'env:GH_TOTP'
+                             and fetch the secret each time instead
+    Options:
+      --issuer <issuer>      Issuer (known issuers fill in their TOTP settings)
+      --encoding <format>    Secret format: base32 (default), hex or raw
+      --digits <n>           Code length: 6, 7 or 8 (default 6)
+      --period <seconds>     Refresh period (default 30)
+      --algorithm <name>     SHA1 (default), SHA256 or SHA512
+      --tags <a,b>           Comma-separated tags
+      --aliases <a,b>        Comma-separated short names to look it up by, e.g. 'gh'
+  delete --name <name>   Delete an account, matched as for gen
+  delete --tag <tag>     Delete every account with the tag
+  restore <snapshot>     Replace the accounts with those in a snapshot, given by
+                         path or by name in the backups directory
+  sync                   Sync the encrypted vault with the configured WebDAV server
+  log                    Show log entries, oldest first
+      --grep <text>          Only entries containing the text (ignoring case)
+      --level <level>        Only entries at this level or worse: info, audit, warn, error
+      --since <date>         Only entries from this date (YYYY-MM-DD) or time on
+  doctor                 Check configuration, storage, clipboard, terminal and clock
+  config validate [file] Check config.json (or another file) for unknown keys,
+                         invalid paths and conflicting options
+  completions <shell>    Print a completion script for bash, zsh or fish that
+                         also completes account names
+  help                   Show this help
+
+Options for import, add, delete, restore and sync:
+  --dry-run              Show what would change without writing anything
+
+Options for gen, list, export and delete:
+  --tag <tag>            Only accounts with this tag (ignoring case); with an
+                         account name, only look for it among them
+
+Environment:
+  QUACKEY_ACCOUNT_<NAME>=<otpauth-uri>
+                         An account gen can use without a vault, matched by
+                         <NAME> or the account's name or issuer; checked first
+
+Global options:
+  --json                 Print errors, and the output of gen, list and statusbar,
+                         as JSON
+  --demo                 Use a throwaway vault of made-up accounts with random
+                         codes, for demos and screenshots
+  --privacy              Start the menu in privacy mode: account names, issuers
+                         and codes are replaced with placeholders
+
+Exit codes:
+  0 success, 1 other error (such as an unreadable import file),
+  2 invalid input, 3 account not found, 4 vault locked,
+  5 storage file corrupt, 6 permission denied,
+  7 storage changed by another program, 130 cancelled at a prompt";
+
+/// Removes the global flag `name` (such as `--json`) from `args`, returning
+/// whether it was given
+pub fn take_global_flag(args: &mut Vec<String>, name: &str) -> bool {
+    let before = args.len();
+    args.retain(|arg| arg != name);
+    args.len() != before
+}
+
+/// What the user asked Quackey to do
+#[derive(Debug)]
+pub enum Command {
+    /// Start the interactive menu
+    Interactive,
+    /// Print usage information
+    Help,
+    /// Print the current code for one account, looked up among the accounts
+    /// with `tag` if one is given
+    Gen { query: String, tag: Option<String> },
+    /// Print the current codes of every account with a tag
+    GenTagged { tag: String },
+    /// List accounts matching a search, archived ones only with `archived`
+    List { query: String, format: ListFormat, archived: bool, tag: Option<String> },
+    /// Print one status-bar line for an account
+    Statusbar { query: String },
+    /// Import accounts from a file in one of the registered formats
+    Import { format: String, path: String, dry_run: bool },
+    /// Import the accounts defined in `QUACKEY_ACCOUNT_*` variables
+    ImportEnv { dry_run: bool },
+    /// Export all accounts, or those with `tag`, in one of the registered formats
+    Export { format: String, output: Option<String>, encrypt: bool, tag: Option<String> },
+    /// Write a printable emergency kit for recovering the vault
+    EmergencyKit { output: Option<String>, qr: bool },
+    /// Add an account non-interactively
+    Add(AddArgs),
+    /// Delete an account non-interactively: the one matching `name` (among
+    /// those with `tag`, if given), or every account with `tag`
+    Delete { name: Option<String>, tag: Option<String>, dry_run: bool },
+    /// Run environment diagnostics
+    Doctor,
+    /// Print a shell completion script
+    Completions { shell: String },
+    /// Check a config file without using it
+    ConfigValidate { path: Option<String> },
+    /// Replace the accounts with those in a snapshot of the vault
+    Restore { snapshot: String, dry_run: bool },
+    /// Sync with the configured WebDAV server
+    Sync { dry_run: bool },
+    /// Search the log
+    Log(LogQuery),
+}
+
+/// Output of `quackey list`
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ListFormat {
+    /// One line per account, or a JSON array with `--json`
+    Text,
+    /// Alfred script filter items
+    Alfred,
+    /// Issuers and names for shell completion
+    Names,
+}
+
+/// Where `quackey add` reads the secret from, so it never appears in shell history
+#[derive(Debug)]
+pub enum SecretSource {
+    Stdin,
+    Command(String),
+    /// Keep only a `cmd:` or `env:` reference in the vault
+    Reference(SecretReference),
+}
+
+/// Arguments for `quackey add`
+#[derive(Debug)]
+pub struct AddArgs {
+    pub name: String,
+    pub issuer: Option<String>,
+    pub secret_source: SecretSource,
+    pub encoding: SecretEncoding,
+    pub digits: Option<usize>,
+    pub period: Option<u64>,
+    pub algorithm: Option<Algorithm>,
+    pub tags: Vec<String>,
+    pub aliases: Vec<String>,
+    pub dry_run: bool,
+}
+
+/// Parses command-line arguments (without the program name)
+pub fn parse_args<I: IntoIterator<Item = String>>(args: I) -> Result<Command, AppError> {
+    let mut args = ArgParser::new(args.into_iter().collect());
+
+    let Some(command) = args.next_positional() else {
+        if args.flag("--help") || args.flag("-h") {
+            return Ok(Command::Help);
+        }
+        args.finish()?;
+        return Ok(Command::Interactive);
+    };
+
+    let parsed = match command.as_str() {
+        "help" => Command::Help,
+        "doctor" => Command::Doctor,
+        "completions" => {
+            let shell = args.next_positional().ok_or_else(|| {
+                AppError::InvalidInput(format!("completions requires a shell: {}", completions::SHELLS.join(", ")))
+            })?;
+            if completions::script(&shell).is_none() {
+                return Err(AppError::InvalidInput(format!(
+                    "No completion script for '{}' (available: {})",
+                    shell,
+                    completions::SHELLS.join(", ")
+                )));
+            }
+            Command::Completions { shell }
+        }
+        "config" => match args.next_positional().as_deref() {
+            Some("validate") => Command::ConfigValidate { path: args.next_positional() },
+            Some(other) => {
+                return Err(AppError::InvalidInput(format!("Unknown config command '{}'", other)));
+            }
+            None => return Err(AppError::InvalidInput("config requires a command: validate".to_string())),
+        },
+        "restore" => {
+            let snapshot = args
+                .next_positional()
+                .ok_or_else(|| AppError::InvalidInput("restore requires a snapshot file".to_string()))?;
+            Command::Restore { snapshot, dry_run: args.flag("--dry-run") }
+        }
+        "sync" => Command::Sync { dry_run: args.flag("--dry-run") },
+        "log" => Command::Log(LogQuery {
+            pattern: args.value("--grep")?,
+            min_level: args.value("--level")?.as_deref().map(Level::parse).transpose()?,
+            since: args.value("--since")?.as_deref().map(logger::parse_since).transpose()?,
+        }),
+        "gen" => {
+            // Read before the account name, so its value isn't taken for one
+            let tag = args.value("--tag")?;
+            match (args.next_positional(), tag) {
+                (Some(query), tag) => Command::Gen { query, tag },
+                (None, Some(tag)) => Command::GenTagged { tag },
+                (None, None) => {
+                    return Err(AppError::InvalidInput("gen requires an account name or --tag <tag>".to_string()));
+                }
+            }
+        }
+        "list" => {
+            let format = match args.value("--format")?.as_deref() {
+                None if args.flag("--names") => ListFormat::Names,
+                None | Some("text") => ListFormat::Text,
+                Some("alfred") => ListFormat::Alfred,
+                Some(other) => {
+                    return Err(AppError::InvalidInput(format!(
+                        "Unknown list format '{}' (available: text, alfred)",
+                        other
+                    )));
+                }
+            };
+            let tag = args.value("--tag")?;
+            Command::List {
+                query: args.next_positional().unwrap_or_default(),
+                format,
+                archived: args.flag("--archived"),
+                tag,
+            }
+        }
+        "statusbar" => {
+            let query = args
+                .next_positional()
+                .ok_or_else(|| AppError::InvalidInput("statusbar requires an account name".to_string()))?;
+            Command::Statusbar { query }
+        }
+        "import" if args.flag("--from-env") => Command::ImportEnv { dry_run: args.flag("--dry-run") },
+        "import" => {
+            let (format, path) = match (args.value("--uris")?, args.value("--format")?) {
+                (Some(path), None) => ("uris".to_string(), path),
+                (None, Some(format)) => {
+                    let path = args
+                        .next_positional()
+                        .ok_or_else(|| AppError::InvalidInput("import requires a file to read".to_string()))?;
+                    (format, path)
+                }
+                (Some(_), Some(_)) => {
+                    return Err(AppError::InvalidInput(
+                        "Use either --uris <file> or --format <name> <file>, not both".to_string(),
+                    ));
+                }
+                (None, None) => {
+                    return Err(AppError::InvalidInput(
+                        "import requires --format <name> <file> or --uris <file>".to_string(),
+                    ));
+                }
+            };
+            Command::Import { format, path, dry_run: args.flag("--dry-run") }
+        }
+        "export" => {
+            let format = args
+                .value("--format")?
+                .ok_or_else(|| AppError::InvalidInput("export requires --format <name>".to_string()))?;
+            Command::Export {
+                format,
+                output: args.value("--output")?,
+                encrypt: args.flag("--encrypt"),
+                tag: args.value("--tag")?,
+            }
+        }
+        "emergency-kit" => Command::EmergencyKit { output: args.value("--output")?, qr: args.flag("--qr") },
+        "add" => Command::Add(parse_add_args(&mut args)?),
+        "delete" => {
+            let name = args.value("--name")?;
+            let tag = args.value("--tag")?;
+            if name.is_none() && tag.is_none() {
+                return Err(AppError::InvalidInput("delete requires --name <name> or --tag <tag>".to_string()));
+            }
+            Command::Delete {
+                name,
+                tag,
+                dry_run: args.flag("--dry-run"),
+            }
+        }
+        other => {
+            return Err(AppError::InvalidInput(format!("Unknown command '{}'", other)));
+        }
+    };
+
+    if args.flag("--help") || args.flag("-h") {
+        return Ok(Command::Help);
+    }
+
+    args.finish()?;
+    Ok(parsed)
+}
+
+fn parse_add_args(args: &mut ArgParser) -> Result<AddArgs, AppError> {
+    let name = args
+        .value("--name")?
+        .map(|n| n.trim().to_string())
+        .filter(|n| !n.is_empty())
+        .ok_or_else(|| AppError::InvalidInput("add requires --name <name>".to_string()))?;
+
+    let stdin = args.flag("--secret-stdin");
+    let command = args.value("--secret-cmd")?;
+    let reference = args.value("--secret-ref")?;
+    let secret_source = match (stdin, command, reference) {
+        (true, None, None) => SecretSource::Stdin,
+        (false, Some(command), None) => SecretSource::Command(command),
+        (false, None, Some(reference)) => SecretSource::Reference(SecretReference::parse(&reference).ok_or_else(
+            || AppError::InvalidInput("--secret-ref must start with cmd: or env:".to_string()),
+        )?),
+        (false, None, None) => {
+            return Err(AppError::InvalidInput(
+                "add requires --secret-stdin, --secret-cmd <command> or --secret-ref <reference>".to_string(),
+            ));
+        }
+        _ => {
+            return Err(AppError::InvalidInput(
+                "Use only one of --secret-stdin, --secret-cmd and --secret-ref".to_string(),
+            ));
+        }
+    };
+
+    let encoding = match args.value("--encoding")?.as_deref().map(str::to_ascii_lowercase).as_deref() {
+        None | Some("base32") => SecretEncoding::Base32,
+        Some("hex") => SecretEncoding::Hex,
+        Some("raw") => SecretEncoding::Raw,
+        Some(other) => {
+            return Err(AppError::InvalidInput(format!("Unknown secret encoding '{}'", other)));
+        }
+    };
+
+    let digits = match args.value("--digits")? {
+        Some(value) => Some(
+            value
+                .parse()
+                .ok()
+                .filter(|d| (6..=8).contains(d))
+                .ok_or_else(|| AppError::InvalidInput(format!("Invalid --digits '{}' (use 6, 7 or 8)", value)))?,
+        ),
+        None => None,
+    };
+
+    let period = match args.value("--period")? {
+        Some(value) => Some(
+            value
+                .parse()
+                .ok()
+                .filter(|p| *p > 0)
+                .ok_or_else(|| AppError::InvalidInput(format!("Invalid --period '{}'", value)))?,
+        ),
+        None => None,
+    };
+
+    let algorithm = match args.value("--algorithm")?.map(|a| a.to_ascii_uppercase()).as_deref() {
+        None => None,
+        Some("SHA1") => Some(Algorithm::SHA1),
+        Some("SHA256") => Some(Algorithm::SHA256),
+        Some("SHA512") => Some(Algorithm::SHA512),
+        Some(other) => {
+            return Err(AppError::InvalidInput(format!("Unknown algorithm '{}'", other)));
+        }
+    };
+
+    let tags = args
+        .value("--tags")?
+        .map(|tags| {
+            tags.split(',')
+                .map(|t| t.trim().to_string())
+                .filter(|t| !t.is_empty())
+                .collect()
+        })
+        .unwrap_or_default();
+
+    let aliases = args
+        .value("--aliases")?
+        .map(|aliases| {
+            aliases
+                .split(',')
+                .map(|a| a.trim().to_string())
+                .filter(|a| !a.is_empty())
+                .collect()
+        })
+        .unwrap_or_default();
+
+    Ok(AddArgs {
+        name,
+        issuer: args.value("--issuer")?.map(|i| i.trim().to_string()).filter(|i| !i.is_empty()),
+        secret_source,
+        encoding,
+        digits,
+        period,
+        algorithm,
+        tags,
+        aliases,
+        dry_run: args.flag("--dry-run"),
+    })
+}
+
+/// Minimal flag parser supporting `--flag`, `--name value` and `--name=value`
+struct ArgParser {
+    args: Vec<String>,
+}
+
+impl ArgParser {
+    fn new(args: Vec<String>) -> Self {
+        Self { args }
+    }
+
+    /// Removes and returns the first argument that isn't a flag
+    fn next_positional(&mut self) -> Option<String> {
+        let index = self.args.iter().position(|a| !a.starts_with('-'))?;
+        Some(self.args.remove(index))
+    }
+
+    /// Removes a boolean flag, returning whether it was present
+    fn flag(&mut self, name: &str) -> bool {
+        match self.args.iter().position(|a| a == name) {
+            Some(index) => {
+                self.args.remove(index);
+                true
+            }
+            None => false,
+        }
+    }
+
+    /// Removes a flag with a value, returning the value if the flag was present
+    fn value(&mut self, name: &str) -> Result<Option<String>, AppError> {
+        let prefix = format!("{}=", name);
+
+        if let Some(index) = self.args.iter().position(|a| a.starts_with(&prefix)) {
+            let arg = self.args.remove(index);
+            return Ok(Some(arg[prefix.len()..].to_string()));
+        }
+
+        match self.args.iter().position(|a| a == name) {
+            Some(index) if index + 1 < self.args.len() => {
+                self.args.remove(index);
+                Ok(Some(self.args.remove(index)))
+            }
+            Some(_) => Err(AppError::InvalidInput(format!("{} requires a value", name))),
+            None => Ok(None),
+        }
+    }
+
+    /// Fails if any arguments were not consumed
+    fn finish(self) -> Result<(), AppError> {
+        match self.args.first() {
+            Some(arg) => Err(AppError::InvalidInput(format!("Unexpected argument '{}'", arg))),
+            None => Ok(()),
+        }
+    }
+}
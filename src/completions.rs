@@ -0,0 +1,144 @@
+//! Shell completion scripts for `quackey completions <shell>`.
+//!
+//! Commands and flags are completed from the scripts themselves. Account
+//! names are not: the scripts run `quackey list --names <word>` each time, so
+//! `quackey gen gi<TAB>` offers the issuers and names in the vault as it is
+//! now. That listing never prompts and prints nothing on errors.
+
+/// Shells a completion script exists for
+pub const SHELLS: &[&str] = &["bash", "zsh", "fish"];
+
+const BASH: &str = r#"# quackey completion for bash; load with: eval "$(quackey completions bash)"
+_quackey() {
+    local cur="${COMP_WORDS[COMP_CWORD]}"
+    local prev="${COMP_WORDS[COMP_CWORD-1]}"
+    local command="" i
+    for ((i = 1; i < COMP_CWORD; i++)); do
+        case "${COMP_WORDS[i]}" in
+            -*) ;;
+            *) command="${COMP_WORDS[i]}"; break ;;
+        esac
+    done
+
+    if [ -z "$command" ]; then
+        COMPREPLY=($(compgen -W "gen list statusbar import export emergency-kit add delete restore sync log doctor config completions help --json --demo --privacy --help" -- "$cur"))
+        return
+    fi
+
+    local names=0
+    case "$command" in
+        gen|statusbar) names=1 ;;
+        delete) [ "$prev" = "--name" ] && names=1 ;;
+    esac
+    if [ "$names" = 1 ]; then
+        local IFS=$'\n'
+        COMPREPLY=($(quackey list --names "$cur" 2>/dev/null))
+        return
+    fi
+
+    local flags=""
+    case "$command" in
+        list) flags="--format --names --archived --tag" ;;
+        import) flags="--format --uris --from-env --dry-run" ;;
+        export) flags="--format --output --encrypt --tag" ;;
+        emergency-kit) flags="--output --qr" ;;
+        add) flags="--name --issuer --secret-stdin --secret-cmd --secret-ref --encoding --digits --period --algorithm --tags --aliases --dry-run" ;;
+        delete) flags="--name --tag --dry-run" ;;
+        restore|sync) flags="--dry-run" ;;
+        log) flags="--grep --level --since" ;;
+        config) flags="validate" ;;
+        completions) flags="bash zsh fish" ;;
+    esac
+    COMPREPLY=($(compgen -W "$flags" -- "$cur"))
+}
+complete -o default -F _quackey quackey
+"#;
+
+const ZSH: &str = r#"#compdef quackey
+# quackey completion for zsh; load with: source <(quackey completions zsh)
+_quackey() {
+    local -a commands names
+    commands=(gen list statusbar import export emergency-kit add delete restore sync log doctor config completions help)
+    local command=${${words[2,CURRENT-1]:#-*}[1]}
+
+    if [[ -z $command ]]; then
+        compadd -a commands
+        return
+    fi
+
+    if [[ $command == (gen|statusbar) || ( $command == delete && ${words[CURRENT-1]} == --name ) ]]; then
+        names=("${(@f)$(quackey list --names "${words[CURRENT]}" 2>/dev/null)}")
+        # Matched ignoring case by quackey, so don't filter by prefix again
+        compadd -U -a names
+        return
+    fi
+
+    case $command in
+        list) compadd -- --format --names --archived --tag ;;
+        import) compadd -- --format --uris --from-env --dry-run ;;
+        export) compadd -- --format --output --encrypt --tag ;;
+        emergency-kit) compadd -- --output --qr ;;
+        add) compadd -- --name --issuer --secret-stdin --secret-cmd --secret-ref --encoding --digits --period --algorithm --tags --aliases --dry-run ;;
+        delete) compadd -- --name --tag --dry-run ;;
+        restore) compadd -- --dry-run; _files ;;
+        sync) compadd -- --dry-run ;;
+        log) compadd -- --grep --level --since ;;
+        config) compadd validate ;;
+        completions) compadd bash zsh fish ;;
+        *) _files ;;
+    esac
+}
+compdef _quackey quackey
+"#;
+
+const FISH: &str = r#"# quackey completion for fish; load with: quackey completions fish | source
+function __quackey_names
+    quackey list --names (commandline -ct) 2>/dev/null
+end
+
+set -l commands gen list statusbar import export emergency-kit add delete restore sync log doctor config completions help
+complete -c quackey -n "not __fish_seen_subcommand_from $commands" -f -a "$commands"
+complete -c quackey -n "not __fish_seen_subcommand_from $commands" -l json -d "Print JSON"
+complete -c quackey -n "not __fish_seen_subcommand_from $commands" -l demo -d "Use a throwaway demo vault"
+complete -c quackey -n "not __fish_seen_subcommand_from $commands" -l privacy -d "Hide names, issuers and codes on screen"
+
+complete -c quackey -n "__fish_seen_subcommand_from gen statusbar" -f -a "(__quackey_names)"
+complete -c quackey -n "__fish_seen_subcommand_from delete" -l name -x -a "(__quackey_names)"
+complete -c quackey -n "__fish_seen_subcommand_from delete import add restore sync" -l dry-run
+complete -c quackey -n "__fish_seen_subcommand_from gen list export delete" -l tag -x -d "Only accounts with this tag"
+complete -c quackey -n "__fish_seen_subcommand_from list" -l names
+complete -c quackey -n "__fish_seen_subcommand_from list" -l archived
+complete -c quackey -n "__fish_seen_subcommand_from list" -l format -x -a "text alfred"
+complete -c quackey -n "__fish_seen_subcommand_from import export" -l format -x
+complete -c quackey -n "__fish_seen_subcommand_from import" -l uris -r
+complete -c quackey -n "__fish_seen_subcommand_from import" -l from-env
+complete -c quackey -n "__fish_seen_subcommand_from export emergency-kit" -l output -r
+complete -c quackey -n "__fish_seen_subcommand_from export" -l encrypt
+complete -c quackey -n "__fish_seen_subcommand_from emergency-kit" -l qr
+complete -c quackey -n "__fish_seen_subcommand_from add" -l name -x
+complete -c quackey -n "__fish_seen_subcommand_from add" -l issuer -x
+complete -c quackey -n "__fish_seen_subcommand_from add" -l secret-stdin
+complete -c quackey -n "__fish_seen_subcommand_from add" -l secret-cmd -x
+complete -c quackey -n "__fish_seen_subcommand_from add" -l secret-ref -x
+complete -c quackey -n "__fish_seen_subcommand_from add" -l encoding -x -a "base32 hex raw"
+complete -c quackey -n "__fish_seen_subcommand_from add" -l digits -x -a "6 7 8"
+complete -c quackey -n "__fish_seen_subcommand_from add" -l period -x
+complete -c quackey -n "__fish_seen_subcommand_from add" -l algorithm -x -a "SHA1 SHA256 SHA512"
+complete -c quackey -n "__fish_seen_subcommand_from add" -l tags -x
+complete -c quackey -n "__fish_seen_subcommand_from add" -l aliases -x
+complete -c quackey -n "__fish_seen_subcommand_from log" -l grep -x
+complete -c quackey -n "__fish_seen_subcommand_from log" -l level -x -a "info audit warn error"
+complete -c quackey -n "__fish_seen_subcommand_from log" -l since -x
+complete -c quackey -n "__fish_seen_subcommand_from config" -f -a validate
+complete -c quackey -n "__fish_seen_subcommand_from completions" -f -a "bash zsh fish"
+"#;
+
+/// The completion script for `shell`, if there is one
+pub fn script(shell: &str) -> Option<&'static str> {
+    match shell {
+        "bash" => Some(BASH),
+        "zsh" => Some(ZSH),
+        "fish" => Some(FISH),
+        _ => None,
+    }
+}
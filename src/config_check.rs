@@ -0,0 +1,334 @@
+//! Checks for `quackey config validate`.
+//!
+//! The file is read as plain JSON first, so unknown keys and wrong types are
+//! reported with the line they are on instead of stopping at the first error.
+//! Apart from probe files that test whether the storage and log folders are
+//! writable, nothing is written.
+
+use std::fs;
+use std::path::Path;
+use serde_json::Value;
+use quackey_core::account::MAX_VERIFY_WINDOW;
+use quackey_core::config::{self, Config, CONFIG_VERSION};
+use quackey_core::sync::WEBDAV_KEYS;
+use crate::doctor::{is_writable, on_path, Status};
+use crate::ui::COPY_PLACEHOLDERS;
+
+/// Verify windows wider than this accept codes from minutes ago
+const MAX_SENSIBLE_VERIFY_WINDOW: u64 = 3;
+
+/// One problem found in the config file
+#[derive(Debug)]
+pub struct Issue {
+    /// [`Status::Warn`] or [`Status::Fail`]
+    pub status: Status,
+    /// 1-based line in the file, when the problem can be pinned to one
+    pub line: Option<usize>,
+    pub message: String,
+}
+
+/// Checks the config file at `path`
+pub fn validate(path: &Path) -> Vec<Issue> {
+    let contents = match fs::read_to_string(path) {
+        Ok(contents) => contents,
+        Err(e) => return vec![fail(None, format!("Cannot read '{}': {}", path.display(), e))],
+    };
+
+    let value: Value = match serde_json::from_str(&contents) {
+        Ok(value) => value,
+        Err(e) => return vec![fail(Some(e.line()), format!("Not valid JSON: {}", e))],
+    };
+    let Some(mut object) = value.as_object().cloned() else {
+        return vec![fail(Some(1), "The file must contain a JSON object".to_string())];
+    };
+
+    // Check what Quackey will actually read: older layouts are migrated first
+    let mut issues = Vec::new();
+    let version = config::migrate(&mut object);
+    if version < CONFIG_VERSION {
+        issues.push(warn(
+            line_of(&contents, "version", None),
+            format!(
+                "Layout version {} is upgraded to {} the next time Quackey starts",
+                version, CONFIG_VERSION
+            ),
+        ));
+    } else if version > CONFIG_VERSION {
+        issues.push(warn(
+            line_of(&contents, "version", None),
+            format!(
+                "Layout version {} is newer than this Quackey ({}); settings it doesn't know are kept but unused",
+                version, CONFIG_VERSION
+            ),
+        ));
+    }
+
+    check_keys(&contents, object.keys(), config::KEYS, None, &mut issues);
+    if let Some(storage) = object.get("storage").and_then(Value::as_object) {
+        check_keys(&contents, storage.keys(), config::STORAGE_KEYS, Some("storage"), &mut issues);
+    }
+    if let Some(log) = object.get("log").and_then(Value::as_object) {
+        check_keys(&contents, log.keys(), config::LOG_KEYS, Some("log"), &mut issues);
+    }
+    if let Some(webdav) = object.get("webdav").and_then(Value::as_object) {
+        check_keys(&contents, webdav.keys(), WEBDAV_KEYS, Some("webdav"), &mut issues);
+    }
+
+    // Types and values; unknown keys were reported above and are kept as they
+    // are. Line numbers only match the file when it needed no migration.
+    let parsed = if version < CONFIG_VERSION {
+        serde_json::from_value::<Config>(Value::Object(object.clone())).map_err(|e| (None, e))
+    } else {
+        serde_json::from_str::<Config>(&contents).map_err(|e| (Some(e.line()), e))
+    };
+    let config = match parsed {
+        Ok(config) => config,
+        Err((line, e)) => {
+            issues.push(fail(line, format!("Invalid value: {}", e)));
+            return issues;
+        }
+    };
+
+    let storage_line = line_of(&contents, "path", Some("storage")).or_else(|| line_of(&contents, "storage_dir", None));
+    check_folder(storage_line, "storage.path", &config.storage.path, &mut issues);
+    check_folder(line_of(&contents, "path", Some("log")), "log.path", &config.log.path, &mut issues);
+    check_options(&contents, &config, &mut issues);
+    check_webdav(&contents, object.get("webdav"), &mut issues);
+
+    issues.sort_by_key(|issue| issue.line);
+    issues
+}
+
+fn check_keys<'a>(
+    contents: &str,
+    keys: impl Iterator<Item = &'a String>,
+    known: &[&str],
+    parent: Option<&str>,
+    issues: &mut Vec<Issue>,
+) {
+    for key in keys {
+        if known.contains(&key.as_str()) {
+            continue;
+        }
+
+        let name = match parent {
+            Some(parent) => format!("{}.{}", parent, key),
+            None => key.clone(),
+        };
+        let mut message = format!("Unknown key '{}' is kept but not used", name);
+        if let Some(suggestion) = closest(key, known) {
+            message.push_str(&format!("; did you mean '{}'?", suggestion));
+        }
+        issues.push(warn(line_of(contents, key, parent), message));
+    }
+}
+
+/// Checks a folder setting such as `storage.path`, named `key` in messages
+fn check_folder(line: Option<usize>, key: &str, path: &str, issues: &mut Vec<Issue>) {
+    let dir = Path::new(path);
+
+    if path.trim().is_empty() {
+        issues.push(fail(line, format!("{} is empty", key)));
+    } else if dir.is_file() {
+        issues.push(fail(line, format!("{} '{}' is a file, not a folder", key, dir.display())));
+    } else if !dir.exists() {
+        issues.push(warn(line, format!("{} '{}' does not exist yet; it is created on first use", key, dir.display())));
+    } else if !is_writable(dir) {
+        issues.push(fail(line, format!("{} '{}' is not writable", key, dir.display())));
+    }
+
+    if dir.is_relative() && path != "." {
+        issues.push(warn(
+            line,
+            format!(
+                "{} '{}' is relative, so it depends on the folder Quackey is started from",
+                key,
+                dir.display()
+            ),
+        ));
+    }
+}
+
+fn check_options(contents: &str, config: &Config, issues: &mut Vec<Issue>) {
+    if !is_valid_grouping(&config.code_grouping) {
+        issues.push(warn(
+            line_of(contents, "code_grouping", None),
+            format!(
+                "code_grouping '{}' is not \"half\", \"none\" or group sizes like \"3-3\"; \"half\" is used",
+                config.code_grouping
+            ),
+        ));
+    }
+
+    check_copy_format(contents, &config.copy_format, issues);
+
+    if !config::ENCRYPTION_MODES.contains(&config.encryption.as_str()) {
+        issues.push(warn(
+            line_of(contents, "encryption", None),
+            format!(
+                "encryption '{}' is not one of {}; Quackey records it again when the vault's protection changes",
+                config.encryption,
+                config::ENCRYPTION_MODES.join(", ")
+            ),
+        ));
+    }
+
+    if config.verify_window > MAX_VERIFY_WINDOW {
+        issues.push(fail(
+            line_of(contents, "verify_window", None),
+            format!(
+                "verify_window {} is wider than the most Quackey checks, {}",
+                config.verify_window, MAX_VERIFY_WINDOW
+            ),
+        ));
+    } else if config.verify_window > MAX_SENSIBLE_VERIFY_WINDOW {
+        issues.push(warn(
+            line_of(contents, "verify_window", None),
+            format!(
+                "verify_window {} accepts codes up to {} periods old, which weakens verification",
+                config.verify_window, config.verify_window
+            ),
+        ));
+    }
+
+    let clipboard_built = cfg!(feature = "clipboard");
+    for (key, set) in [("auto_copy", config.auto_copy), ("keep_fresh_minutes", config.keep_fresh_minutes > 0)] {
+        if !set {
+            continue;
+        }
+        if config.disable_clipboard {
+            issues.push(warn(
+                line_of(contents, key, None),
+                format!("{} has no effect because disable_clipboard is set", key),
+            ));
+        } else if !clipboard_built {
+            issues.push(warn(
+                line_of(contents, key, None),
+                format!("{} has no effect because this build has no clipboard support", key),
+            ));
+        }
+    }
+
+    if config.expiry_bell && config.expiry_warning_seconds == 0 && line_of(contents, "expiry_bell", None).is_some() {
+        issues.push(warn(
+            line_of(contents, "expiry_bell", None),
+            "expiry_bell has no effect because expiry_warning_seconds is 0".to_string(),
+        ));
+    }
+
+    if config.syslog && cfg!(not(unix)) {
+        issues.push(warn(
+            line_of(contents, "syslog", None),
+            "syslog has no effect because this system has no syslog".to_string(),
+        ));
+    }
+
+    if let Some(program) = &config.pinentry
+        && !on_path(program)
+    {
+        issues.push(fail(
+            line_of(contents, "pinentry", None),
+            format!("pinentry program '{}' was not found", program),
+        ));
+    }
+}
+
+fn check_copy_format(contents: &str, format: &str, issues: &mut Vec<Issue>) {
+    let line = line_of(contents, "copy_format", None);
+    if !format.contains("{code}") {
+        issues.push(warn(line, format!("copy_format '{}' has no {{code}}, so the code itself is never copied", format)));
+    }
+
+    for placeholder in format.split('{').skip(1).filter_map(|rest| rest.split_once('}')).map(|(name, _)| name) {
+        if !COPY_PLACEHOLDERS.contains(&placeholder) {
+            issues.push(warn(
+                line,
+                format!("copy_format has unknown placeholder {{{}}}, which is copied as it is", placeholder),
+            ));
+        }
+    }
+}
+
+fn check_webdav(contents: &str, webdav: Option<&Value>, issues: &mut Vec<Issue>) {
+    let Some(webdav) = webdav.and_then(Value::as_object) else {
+        return;
+    };
+
+    let url = webdav.get("url").and_then(Value::as_str).unwrap_or_default();
+    let line = line_of(contents, "url", Some("webdav"));
+    if url.trim().is_empty() {
+        issues.push(fail(line, "webdav.url is empty".to_string()));
+    } else if url.starts_with("http://") {
+        issues.push(warn(line, "webdav.url uses http://, which sends the password unencrypted".to_string()));
+    } else if !url.starts_with("https://") {
+        issues.push(fail(line, format!("webdav.url '{}' is not an http(s) URL", url)));
+    }
+
+    if webdav.get("password").and_then(Value::as_str).is_some_and(|p| !p.is_empty()) {
+        issues.push(warn(
+            line_of(contents, "password", Some("webdav")),
+            "webdav.password is stored as plain text; start Quackey once to remove it".to_string(),
+        ));
+    }
+}
+
+fn warn(line: Option<usize>, message: String) -> Issue {
+    Issue { status: Status::Warn, line, message }
+}
+
+fn fail(line: Option<usize>, message: String) -> Issue {
+    Issue { status: Status::Fail, line, message }
+}
+
+/// Line of the first `"key":`, searched after the line of `"parent":` when given
+fn line_of(contents: &str, key: &str, parent: Option<&str>) -> Option<usize> {
+    let is_key = |line: &str, key: &str| {
+        line.trim_start()
+            .strip_prefix(&format!("\"{}\"", key))
+            .is_some_and(|rest| rest.trim_start().starts_with(':'))
+    };
+
+    let start = match parent {
+        Some(parent) => contents.lines().position(|line| is_key(line, parent))?,
+        None => 0,
+    };
+    contents
+        .lines()
+        .enumerate()
+        .skip(start)
+        .find(|(_, line)| is_key(line, key))
+        .map(|(index, _)| index + 1)
+}
+
+/// The known key `key` is most likely a typo of
+fn closest<'a>(key: &str, known: &[&'a str]) -> Option<&'a str> {
+    known
+        .iter()
+        .map(|candidate| (distance(key, candidate), *candidate))
+        .filter(|(d, _)| *d <= 2)
+        .min_by_key(|(d, _)| *d)
+        .map(|(_, candidate)| candidate)
+}
+
+/// Levenshtein distance between two short strings
+fn distance(a: &str, b: &str) -> usize {
+    let b: Vec<char> = b.chars().collect();
+    let mut previous: Vec<usize> = (0..=b.len()).collect();
+    for (i, ca) in a.chars().enumerate() {
+        let mut current = vec![i + 1];
+        for (j, cb) in b.iter().enumerate() {
+            let cost = usize::from(ca != *cb);
+            current.push((previous[j] + cost).min(previous[j + 1] + 1).min(current[j] + 1));
+        }
+        previous = current;
+    }
+    previous[b.len()]
+}
+
+/// Same patterns [`crate::ui::format_totp`] understands
+fn is_valid_grouping(grouping: &str) -> bool {
+    match grouping.trim() {
+        "half" | "none" | "" => true,
+        pattern => pattern.split('-').all(|size| size.trim().parse::<usize>().is_ok_and(|n| n > 0)),
+    }
+}
@@ -0,0 +1,72 @@
+//! `quackey --demo`: a throwaway vault of made-up accounts for recording demos
+//! and taking documentation screenshots.
+//!
+//! Quackey keeps its configuration, log and vault relative to the working
+//! directory, so demo mode moves into a fresh temporary folder holding only
+//! fabricated accounts and deletes it on exit. The real vault is never opened.
+//! Codes on screen are random digits (see [`crate::ui::set_demo`]).
+
+use std::fs;
+use std::path::PathBuf;
+use quackey_core::account::{generate_secret, Account};
+use quackey_core::config::Config;
+use quackey_core::error::AppError;
+use quackey_core::storage::Storage;
+use totp_rs::Algorithm;
+
+/// Issuer, account name and tags of the fabricated accounts
+const ACCOUNTS: &[(&str, &str, &[&str])] = &[
+    ("GitHub", "mallard@example.com", &["work", "dev"]),
+    ("Google", "mallard.duck@example.com", &["personal"]),
+    ("AWS", "root", &["work", "cloud"]),
+    ("Discord", "quackers", &["social"]),
+    ("Dropbox", "mallard@example.com", &["personal"]),
+    ("Microsoft", "m.duck@example.org", &["work"]),
+    ("Proton", "mallard@proton.example", &["personal"]),
+    ("Cloudflare", "ops@pond.example", &["work", "cloud"]),
+];
+
+/// The temporary folder demo mode runs in, removed when dropped
+pub struct DemoDir {
+    path: PathBuf,
+    previous: Option<PathBuf>,
+}
+
+impl DemoDir {
+    /// Creates the folder with a default configuration and a vault of
+    /// fabricated accounts, and makes it the working directory
+    pub fn enter() -> Result<Self, AppError> {
+        let path = std::env::temp_dir().join(format!("quackey-demo-{}", std::process::id()));
+        fs::create_dir_all(&path).map_err(|e| AppError::from(e).with_path(&path))?;
+
+        let dir = Self { path, previous: std::env::current_dir().ok() };
+        std::env::set_current_dir(&dir.path).map_err(|e| AppError::from(e).with_path(&dir.path))?;
+
+        let config = Config::default();
+        config.save()?;
+        let mut storage = Storage::new_with_events(&config.get_storage_file_path(), Default::default())?;
+        storage.add_accounts(accounts())?;
+
+        Ok(dir)
+    }
+}
+
+impl Drop for DemoDir {
+    fn drop(&mut self) {
+        if let Some(previous) = &self.previous {
+            let _ = std::env::set_current_dir(previous);
+        }
+        let _ = fs::remove_dir_all(&self.path);
+    }
+}
+
+/// Fabricated accounts with fresh random secrets
+fn accounts() -> Vec<Account> {
+    ACCOUNTS
+        .iter()
+        .map(|(issuer, name, tags)| {
+            Account::new(name.to_string(), generate_secret(20), 6, 30, Algorithm::SHA1, Some(issuer.to_string()))
+                .with_tags(tags.iter().map(|tag| tag.to_string()).collect())
+        })
+        .collect()
+}
@@ -0,0 +1,436 @@
+//! Environment diagnostics for `quackey doctor`.
+//!
+//! Every check reads files directly instead of going through `Storage`, so a
+//! broken vault is reported as-is rather than being moved aside.
+
+use std::fs::{self, File, OpenOptions};
+use std::io::IsTerminal;
+use std::net::UdpSocket;
+use std::path::Path;
+use std::time::{Duration, SystemTime, UNIX_EPOCH};
+use quackey_core::account::Account;
+use quackey_core::config::{Config, CONFIG_FILE};
+use quackey_core::storage;
+
+/// Outcome of a single check
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Status {
+    Pass,
+    Warn,
+    Fail,
+}
+
+/// Result of one diagnostic, with a suggested fix when something is wrong
+#[derive(Debug)]
+pub struct Check {
+    pub name: &'static str,
+    pub status: Status,
+    pub detail: String,
+    pub fix: Option<String>,
+}
+
+impl Check {
+    fn pass(name: &'static str, detail: impl Into<String>) -> Self {
+        Self { name, status: Status::Pass, detail: detail.into(), fix: None }
+    }
+
+    fn warn(name: &'static str, detail: impl Into<String>, fix: impl Into<String>) -> Self {
+        Self { name, status: Status::Warn, detail: detail.into(), fix: Some(fix.into()) }
+    }
+
+    fn fail(name: &'static str, detail: impl Into<String>, fix: impl Into<String>) -> Self {
+        Self { name, status: Status::Fail, detail: detail.into(), fix: Some(fix.into()) }
+    }
+}
+
+/// Seconds of clock drift before codes start failing for typical 30-second periods
+const DRIFT_FAIL_SECS: f64 = 15.0;
+const DRIFT_WARN_SECS: f64 = 2.0;
+const NTP_SERVER: &str = "pool.ntp.org:123";
+
+/// Runs every diagnostic in order
+pub fn run_checks() -> Vec<Check> {
+    let mut checks = Vec::new();
+
+    let config = check_config(&mut checks);
+    check_storage(&config, &mut checks);
+    checks.push(check_log(&config));
+    checks.push(check_clipboard(&config));
+    checks.push(check_terminal());
+    checks.push(check_clock());
+
+    checks
+}
+
+fn check_config(checks: &mut Vec<Check>) -> Config {
+    if !Path::new(CONFIG_FILE).exists() {
+        checks.push(Check::warn(
+            "Configuration",
+            format!("'{}' not found in the current directory; defaults are in use", CONFIG_FILE),
+            "Run quackey once from this directory to create it, or cd to where your config.json lives",
+        ));
+        return Config::default();
+    }
+
+    match Config::load() {
+        Ok(config) => {
+            checks.push(Check::pass("Configuration", format!("'{}' is valid", CONFIG_FILE)));
+            config
+        }
+        Err(e) => {
+            checks.push(Check::fail(
+                "Configuration",
+                e.to_string(),
+                format!("Fix the JSON syntax in '{}' or delete it to run the setup again", CONFIG_FILE),
+            ));
+            Config::default()
+        }
+    }
+}
+
+fn check_storage(config: &Config, checks: &mut Vec<Check>) {
+    let path = config.get_storage_file_path();
+
+    if !Path::new(&path).exists() {
+        checks.push(Check::warn(
+            "Storage file",
+            format!("'{}' does not exist yet", path),
+            "Add an account to create it, or point storage.path in config.json at your existing vault",
+        ));
+        return;
+    }
+
+    if storage::on_read_only_fs(&path) {
+        checks.push(Check::warn(
+            "Storage access",
+            format!("'{}' is on a read-only file system, so Quackey opens it read-only", path),
+            "Codes work, but changes can't be saved until it is remounted read-write",
+        ));
+    }
+
+    if config.encryption != "none" && !storage::is_encrypted(&path) {
+        checks.push(Check::warn(
+            "Storage encryption",
+            format!("config.json says the vault is protected ({}), but '{}' is plain text", config.encryption, path),
+            "If you didn't remove the encryption yourself, check where the file came from, then encrypt it again in the settings",
+        ));
+    }
+
+    if storage::is_encrypted(&path) {
+        checks.push(Check::pass(
+            "Storage file",
+            format!("'{}' is encrypted with a master password", path),
+        ));
+        checks.push(check_permissions(&path));
+        return;
+    }
+
+    let contents = match fs::read_to_string(&path) {
+        Ok(contents) => contents,
+        Err(e) => {
+            checks.push(Check::fail(
+                "Storage file",
+                format!("Cannot read '{}': {}", path, e),
+                "Make sure your user owns the file and can read it",
+            ));
+            return;
+        }
+    };
+
+    let accounts: Vec<Account> = if contents.trim().is_empty() {
+        Vec::new()
+    } else {
+        match serde_json::from_str(&contents) {
+            Ok(accounts) => accounts,
+            Err(e) => {
+                checks.push(Check::fail(
+                    "Storage file",
+                    format!("'{}' is not valid account data: {}", path, e),
+                    "Restore the file from a backup, or fix the JSON at the reported line",
+                ));
+                return;
+            }
+        }
+    };
+
+    let broken: Vec<&str> = accounts
+        .iter()
+        .filter(|a| a.generate_totp().is_err())
+        .map(|a| a.name())
+        .collect();
+
+    if broken.is_empty() {
+        checks.push(Check::pass(
+            "Storage file",
+            format!("'{}' holds {} account(s)", path, accounts.len()),
+        ));
+    } else {
+        checks.push(Check::fail(
+            "Storage file",
+            format!("Cannot generate codes for: {}", broken.join(", ")),
+            "Edit or re-add these accounts with their correct secret and settings",
+        ));
+    }
+
+    checks.push(check_permissions(&path));
+}
+
+#[cfg(unix)]
+fn check_permissions(path: &str) -> Check {
+    use std::os::unix::fs::PermissionsExt;
+
+    match fs::metadata(path) {
+        Ok(metadata) if metadata.permissions().mode() & 0o077 != 0 => Check::warn(
+            "Storage permissions",
+            format!("'{}' is readable by other users (mode {:o})", path, metadata.permissions().mode() & 0o777),
+            format!("Run: chmod 600 {}", path),
+        ),
+        Ok(_) => Check::pass("Storage permissions", "Only your user can read the vault"),
+        Err(e) => Check::fail(
+            "Storage permissions",
+            format!("Cannot inspect '{}': {}", path, e),
+            "Make sure your user owns the file",
+        ),
+    }
+}
+
+#[cfg(not(unix))]
+fn check_permissions(path: &str) -> Check {
+    match fs::metadata(path) {
+        Ok(metadata) if metadata.permissions().readonly() => Check::warn(
+            "Storage permissions",
+            format!("'{}' is read-only", path),
+            "Clear the read-only attribute so new accounts can be saved",
+        ),
+        Ok(_) => Check::pass("Storage permissions", "The vault is writable"),
+        Err(e) => Check::fail(
+            "Storage permissions",
+            format!("Cannot inspect '{}': {}", path, e),
+            "Make sure your user owns the file",
+        ),
+    }
+}
+
+fn check_log(config: &Config) -> Check {
+    let path = config.get_log_file_path();
+
+    match OpenOptions::new().create(true).append(true).open(&path) {
+        Ok(_) => Check::pass("Log file", format!("'{}' is writable", path)),
+        Err(e) if e.kind() == std::io::ErrorKind::ReadOnlyFilesystem => Check::warn(
+            "Log file",
+            format!("'{}' is on a read-only file system, so nothing is logged", path),
+            "Remount it read-write to keep a log",
+        ),
+        Err(e) => Check::fail(
+            "Log file",
+            format!("Cannot write '{}': {}", path, e),
+            "Choose another log folder in the settings, or fix the file's permissions",
+        ),
+    }
+}
+
+/// Read and write access to the storage file, its backups folder and the log
+/// file, for the check before the menu opens. Only problems are returned, so
+/// they can be shown together instead of one failed save at a time. A
+/// read-only file system is not a problem here: Quackey opens the vault
+/// read-only instead.
+pub fn check_access(config: &Config) -> Vec<Check> {
+    let storage = config.get_storage_file_path();
+    let log = config.get_log_file_path();
+    let locations = [
+        (
+            "Storage file",
+            Path::new(&storage).to_path_buf(),
+            false,
+            "Fix the permissions, or choose another storage location in the settings",
+        ),
+        (
+            "Backups folder",
+            storage::backups_dir_for(&storage),
+            true,
+            "Fix the folder's permissions so snapshots can be saved before deleting accounts",
+        ),
+        (
+            "Log file",
+            Path::new(&log).to_path_buf(),
+            false,
+            "Fix the file's permissions, or run quackey from a directory you can write to",
+        ),
+    ];
+
+    locations
+        .into_iter()
+        .filter_map(|(name, path, is_dir, fix)| {
+            access_problem(&path, is_dir).map(|problem| Check::fail(name, problem, fix))
+        })
+        .collect()
+}
+
+/// Why the file or folder at `path` can't be read and written, if it can't.
+/// A missing one is fine when it can be created in the nearest existing folder.
+fn access_problem(path: &Path, is_dir: bool) -> Option<String> {
+    let read_only = |e: &std::io::Error| e.kind() == std::io::ErrorKind::ReadOnlyFilesystem;
+
+    if path.exists() {
+        if is_dir {
+            return probe(path)
+                .err()
+                .filter(|e| !read_only(e))
+                .map(|e| format!("Cannot write to '{}': {}", path.display(), e));
+        }
+        return OpenOptions::new()
+            .read(true)
+            .append(true)
+            .open(path)
+            .err()
+            .filter(|e| !read_only(e))
+            .map(|e| format!("Cannot read and write '{}': {}", path.display(), e));
+    }
+
+    let parent = path
+        .ancestors()
+        .skip(1)
+        .map(|dir| if dir.as_os_str().is_empty() { Path::new(".") } else { dir })
+        .find(|dir| dir.exists())?;
+    probe(parent).err().filter(|e| !read_only(e)).map(|_| {
+        format!("Cannot create '{}': '{}' is not a writable folder", path.display(), parent.display())
+    })
+}
+
+/// Whether a file can be created in `dir`, by creating and removing one
+pub fn is_writable(dir: &Path) -> bool {
+    probe(dir).is_ok()
+}
+
+fn probe(dir: &Path) -> std::io::Result<()> {
+    let probe = dir.join(".quackey-probe");
+    File::create(&probe)?;
+    let _ = fs::remove_file(&probe);
+    Ok(())
+}
+
+fn check_clipboard(config: &Config) -> Check {
+    if config.disable_clipboard {
+        return Check::pass("Clipboard", "Disabled in config.json; codes are only shown on screen");
+    }
+
+    #[cfg(not(feature = "clipboard"))]
+    return Check::pass("Clipboard", "Not built in; codes are only shown on screen");
+
+    #[cfg(feature = "clipboard")]
+    match arboard::Clipboard::new() {
+        Ok(_) => Check::pass("Clipboard", "Available"),
+        Err(e) => match crate::ui::CLIPBOARD_COMMANDS.iter().find(|(program, _)| on_path(program)) {
+            Some((program, _)) => Check::pass(
+                "Clipboard",
+                format!("Not directly available ({}); copying with {} instead", e, program),
+            ),
+            None => Check::warn(
+                "Clipboard",
+                format!("Unavailable: {}", e),
+                "Codes can still be read on screen. On Linux, run inside a graphical session (X11 or Wayland), or install wl-clipboard or xclip",
+            ),
+        },
+    }
+}
+
+fn check_terminal() -> Check {
+    if !std::io::stdout().is_terminal() || !std::io::stdin().is_terminal() {
+        return Check::warn(
+            "Terminal",
+            "Input or output is not a terminal",
+            "The interactive menu needs a terminal; use the command-line commands in scripts",
+        );
+    }
+
+    if std::env::var("TERM").is_ok_and(|t| t == "dumb") {
+        return Check::warn(
+            "Terminal",
+            "TERM=dumb: colors and menus may not render",
+            "Use a terminal emulator that sets TERM, e.g. xterm-256color",
+        );
+    }
+
+    match term_size::dimensions() {
+        Some((width, _)) if width < 60 => Check::warn(
+            "Terminal",
+            format!("Only {} columns wide", width),
+            "Widen the window to at least 60 columns so tables and QR codes fit",
+        ),
+        Some((width, height)) => Check::pass("Terminal", format!("{}x{}", width, height)),
+        None => Check::pass("Terminal", "Interactive (size unknown)"),
+    }
+}
+
+fn check_clock() -> Check {
+    match clock_offset() {
+        Ok(offset) if offset.abs() >= DRIFT_FAIL_SECS => Check::fail(
+            "System clock",
+            format!("Off by {:.1}s compared to {}", offset, NTP_SERVER),
+            "Codes will be rejected. Enable automatic time synchronization in your OS settings",
+        ),
+        Ok(offset) if offset.abs() >= DRIFT_WARN_SECS => Check::warn(
+            "System clock",
+            format!("Off by {:.1}s compared to {}", offset, NTP_SERVER),
+            "Codes may fail near the end of their period. Enable automatic time synchronization",
+        ),
+        Ok(offset) => Check::pass("System clock", format!("Within {:.2}s of network time", offset.abs())),
+        Err(e) => Check::warn(
+            "System clock",
+            format!("Could not reach {}: {}", NTP_SERVER, e),
+            "Check your network connection, or verify the time manually against a trusted clock",
+        ),
+    }
+}
+
+/// Seconds the local clock is behind (positive) or ahead (negative) of an SNTP server
+fn clock_offset() -> std::io::Result<f64> {
+    const NTP_UNIX_OFFSET: f64 = 2_208_988_800.0;
+
+    let socket = UdpSocket::bind("0.0.0.0:0")?;
+    socket.set_read_timeout(Some(Duration::from_secs(3)))?;
+    socket.connect(NTP_SERVER)?;
+
+    // SNTP v4 client request
+    let mut request = [0u8; 48];
+    request[0] = 0x23;
+
+    let sent = unix_now();
+    socket.send(&request)?;
+
+    let mut response = [0u8; 48];
+    let received = socket.recv(&mut response)?;
+    let arrived = unix_now();
+
+    if received < 48 {
+        return Err(std::io::Error::new(std::io::ErrorKind::InvalidData, "short response"));
+    }
+
+    let seconds = u32::from_be_bytes([response[40], response[41], response[42], response[43]]) as f64;
+    let fraction = u32::from_be_bytes([response[44], response[45], response[46], response[47]]) as f64;
+    let server_time = seconds - NTP_UNIX_OFFSET + fraction / 4_294_967_296.0;
+
+    Ok(server_time - (sent + arrived) / 2.0)
+}
+
+fn unix_now() -> f64 {
+    SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .map(|d| d.as_secs_f64())
+        .unwrap_or(0.0)
+}
+
+/// Whether `program` is a path to a file or found on `PATH`
+pub fn on_path(program: &str) -> bool {
+    if program.contains(std::path::MAIN_SEPARATOR) || program.contains('/') {
+        return Path::new(program).is_file();
+    }
+
+    let Some(paths) = std::env::var_os("PATH") else {
+        return false;
+    };
+    std::env::split_paths(&paths).any(|dir| {
+        let candidate = dir.join(program);
+        candidate.is_file() || (cfg!(windows) && candidate.with_extension("exe").is_file())
+    })
+}
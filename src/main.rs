@@ -1,810 +1,5722 @@
 //! A TOTP (Time-based One-Time Password) generator application.
 //! This application allows users to store and generate TOTP codes for various accounts directly from their terminal.
 
-mod account;
-mod config;
-mod error;
-mod logger;
-mod storage;
+mod cli;
+mod completions;
+mod config_check;
+mod demo;
+mod doctor;
 mod ui;
+mod wizard;
 
-use account::Account;
+use quackey_core::{account, backup, dpapi, env, formats, health, import, integrity, kit, memory, merge, migration, permissions, pin, pinentry, recovery, repair, scan, secret, share, storage, strength, sync, wipe};
+use quackey_core::account::{generate_secret, Account, AccountColor, CodePolicy};
+use cli::{AddArgs, Command, ListFormat, SecretSource};
 use colored::*;
-use config::Config;
-use dialoguer::{Confirm, Input, Select};
-use error::AppError;
-use logger::Logger;
-use std::io::{self};
-use std::thread;
-use std::time::Duration;
-use storage::Storage;
-use totp_rs::{Algorithm, TOTP};
-use ui::{display_screen, display_welcome_screen, display_exit_screen, 
+use quackey_core::config::Config;
+use quackey_core::crypto::VaultKey;
+use dialoguer::{Confirm, Input, MultiSelect, Password, Select};
+use quackey_core::error::AppError;
+use quackey_core::events::{Event, Events, LockChange};
+use quackey_core::logger::{self, Level, LogQuery};
+use quackey_core::presets::{find_preset, IssuerPreset};
+use quackey_core::qr::{EccLevel, QrCode};
+use quackey_core::recovery::{RecoveryCode, RecoveryCodes};
+use quackey_core::secret::SecretEncoding;
+use std::io::{self, IsTerminal};
+use std::process::ExitCode;
+use std::time::{Duration, SystemTime, UNIX_EPOCH};
+use quackey_core::storage::Storage;
+use quackey_core::sync::SyncProvider;
+use quackey_core::template::AccountTemplate;
+use quackey_core::vault::{self, AccountBuilder, Vault};
+use totp_rs::Algorithm;
+use wizard::{answer, Step, Wizard};
+use ui::{Answered, Chosen, display_screen, display_welcome_screen, display_exit_screen, 
          get_terminal_width, center_text, clear_screen, 
-         create_spinner, wait_for_input,
-         display_accounts_table, display_totp_results};
-
-/// Application entry point that initializes the TOTP generator
-fn main() -> Result<(), AppError> {
-    let config = match run_onboarding() {
-        Ok(config) => config,
-        Err(AppError::PermissionError(msg)) => {
-            eprintln!("{}", "Error:".red().bold());
-            eprintln!("{}", msg);
-            eprintln!();
-            eprintln!("{}", "Please run the application with appropriate permissions or choose a different location for your files.".bright_black());
-            eprintln!("{}", "You can try running the application in a directory where you have write permissions.".bright_black());
-            return Err(AppError::PermissionError(msg));
+         create_spinner, show_spinner_delay, wait_for_input,
+         display_accounts_table, display_import_preview, display_totp_results, clipboard_text, keep_clipboard_fresh, watch_code, clipboard_enabled, clipboard_image, play_qr_frames, render_qr,
+         issuer_label, issuer_groups, display_order, group_heading, is_grouped_by_issuer,
+         IssuerCompletion, COPY_PLACEHOLDERS};
+
+/// Application entry point that dispatches command-line subcommands or starts
+/// the interactive menu. Exits with [`AppError::exit_code`] on failure.
+fn main() -> ExitCode {
+    // Decrypted secrets must not end up in a core dump
+    if let Err(e) = memory::disable_core_dumps() {
+        eprintln!("{} {}", "Warning:".yellow().bold(), e);
+    }
+
+    // Piped or redirected output (scripts, cron) gets plain text
+    if !io::stdout().is_terminal() {
+        colored::control::set_override(false);
+    }
+
+    let mut args: Vec<String> = std::env::args().skip(1).collect();
+    let json = cli::take_global_flag(&mut args, "--json");
+    let demo = cli::take_global_flag(&mut args, "--demo");
+    ui::set_privacy(cli::take_global_flag(&mut args, "--privacy"));
+
+    let command = match cli::parse_args(args) {
+        Ok(command) => command,
+        Err(e) => {
+            print_error(&e, json);
+            if !json {
+                eprintln!();
+                eprintln!("{}", cli::USAGE);
+            }
+            return ExitCode::from(e.exit_code());
         }
-        Err(e) => return Err(e),
     };
 
-    let mut logger = match Logger::new(&config.get_log_file_path()) {
-        Ok(logger) => logger,
-        Err(AppError::PermissionError(msg)) => {
-            eprintln!("{}", "Error:".red().bold());
-            eprintln!("{}", msg);
+    if matches!(command, Command::Interactive)
+        && (!io::stdout().is_terminal() || !io::stdin().is_terminal())
+    {
+        let e = AppError::InvalidInput(
+            "The interactive menu needs a terminal; use a command such as 'quackey gen <account>'".to_string(),
+        );
+        print_error(&e, json);
+        if !json {
             eprintln!();
-            eprintln!("{}", "Please run the application with appropriate permissions or choose a different location for your log file.".bright_black());
-            return Err(AppError::PermissionError(msg));
+            eprintln!("{}", cli::USAGE);
         }
-        Err(e) => return Err(e),
-    };
+        return ExitCode::from(e.exit_code());
+    }
 
-    let mut storage = match Storage::new_with_logger(&config.get_storage_file_path(), Some(logger.clone())) {
-        Ok(storage) => storage,
-        Err(AppError::PermissionError(msg)) => {
-            eprintln!("{}", "Error:".red().bold());
-            eprintln!("{}", msg);
-            eprintln!();
-            eprintln!("{}", "Please run the application with appropriate permissions or choose a different location for your storage file.".bright_black());
-            return Err(AppError::PermissionError(msg));
+    // Kept until the end of main so the demo folder is removed on exit
+    let _demo_dir = if demo {
+        ui::set_demo(true);
+        match demo::DemoDir::enter() {
+            Ok(dir) => Some(dir),
+            Err(e) => {
+                print_error(&e, json);
+                return ExitCode::from(e.exit_code());
+            }
         }
-        Err(e) => return Err(e),
+    } else {
+        None
     };
 
-    logger.info("Application started")?;
-
-    run_main_loop(&mut storage, &mut logger)?;
-
-    Ok(())
+    // The menu adds its own, more specific hints
+    let interactive = matches!(command, Command::Interactive);
+    match run(command, json) {
+        Ok(()) => ExitCode::SUCCESS,
+        Err(e) => {
+            let e = if interactive { e } else { permission_hint(e, "your files") };
+            print_error(&e, json);
+            ExitCode::from(e.exit_code())
+        }
+    }
 }
 
-/// Runs the onboarding process if configuration doesn't exist
-fn run_onboarding() -> Result<Config, AppError> {
-    let config = Config::load()?;
-
-    if !std::path::Path::new("config.json").exists() {
-        display_screen("Welcome to Quackey - Initial Setup");
+/// Prints an error to stderr with the file or account it concerns and a hint,
+/// as `{"error": {"code": ..., "message": ...}}` when `json` is set
+fn print_error(error: &AppError, json: bool) {
+    if json {
+        let value = serde_json::json!({
+            "error": {
+                "code": error.code(),
+                "message": error.message(),
+                "exit_code": error.exit_code(),
+                "path": error.path(),
+                "account": error.account(),
+                "hint": error.hint(),
+            }
+        });
+        eprintln!("{}", value);
+    } else {
+        eprintln!("{} {}", "Error:".red().bold(), error);
+        if let Some(path) = error.path() {
+            eprintln!("  {} {}", "File:".bright_black(), path.display());
+        }
+        if let Some(hint) = error.hint() {
+            for (i, line) in hint.lines().enumerate() {
+                let label = if i == 0 { "Hint:" } else { "     " };
+                eprintln!("  {} {}", label.bright_black(), line.bright_black());
+            }
+        }
+    }
+}
 
-        println!("{}", "Default Configuration:".bright_black());
-        println!("{}", "  - Accounts will be saved in the same directory as the application".bright_black());
-        println!("{}", "  - You can change these settings later from the menu".bright_black());
-        println!();
-        
-        let use_defaults = Confirm::with_theme(&dialoguer::theme::ColorfulTheme::default())
-            .with_prompt("Would you like to use the default configuration?")
-            .default(true)
-            .interact()
-            .unwrap_or(true);
+/// Runs a parsed command; `json` selects machine-readable output
+fn run(command: Command, json: bool) -> Result<(), AppError> {
+    match command {
+        Command::Interactive => run_interactive(),
+        Command::Help => {
+            println!("{}", cli::USAGE);
+            print_formats();
+            Ok(())
+        }
+        Command::Gen { query, tag } => run_gen(&query, tag.as_deref(), json),
+        Command::GenTagged { tag } => run_gen_tagged(&tag, json),
+        Command::List { query, format, archived, tag } => run_list(&query, format, archived, tag.as_deref(), json),
+        Command::Statusbar { query } => run_statusbar(&query, json),
+        Command::Import { format, path, dry_run } => run_import(&format, &path, dry_run),
+        Command::ImportEnv { dry_run } => run_import_env(dry_run),
+        Command::Export { format, output, encrypt, tag } => {
+            run_export(&format, output.as_deref(), encrypt, tag.as_deref())
+        }
+        Command::EmergencyKit { output, qr } => run_emergency_kit(output.as_deref(), qr),
+        Command::Add(args) => run_add(args),
+        Command::Delete { name, tag, dry_run } => match name {
+            Some(name) => run_delete(&name, tag.as_deref(), dry_run),
+            None => run_delete_tagged(tag.as_deref().unwrap_or_default(), dry_run),
+        },
+        Command::Doctor => run_doctor(),
+        Command::Completions { shell } => {
+            print!("{}", completions::script(&shell).unwrap_or_default());
+            Ok(())
+        }
+        Command::ConfigValidate { path } => run_config_validate(path.as_deref()),
+        Command::Log(query) => run_log(&query),
+        Command::Restore { snapshot, dry_run } => run_restore(&snapshot, dry_run),
+        Command::Sync { dry_run } => {
+            let mut config = Config::load()?;
+            let (events, mut storage) = open_vault()?;
+            unseal_config(&mut config, &storage, &events)?;
+            sync_vault(&mut storage, &events, &mut config, dry_run)
+        }
+    }
+}
 
-        if use_defaults {
-            println!();
-            println!("{}", "Using default configuration.".bright_black());
-            println!(
-                "{}",
-                "You can change these settings later from the menu.".bright_black()
-            );
-            println!();
+/// Adds an account from command-line arguments, reading the secret from stdin
+/// or a command so it never shows up in shell history or scrollback
+fn run_add(args: AddArgs) -> Result<(), AppError> {
+    let secret = match &args.secret_source {
+        // Checked when the account is built, by fetching the secret once
+        SecretSource::Reference(reference) => reference.to_string(),
+        source => {
+            let raw_secret = match source {
+                SecretSource::Command(command) => secret::read_from_command(command)?,
+                _ => secret::read_from_stdin()?,
+            };
+            let secret = secret::to_canonical_base32(&raw_secret, args.encoding)?;
+            let secret_bytes = secret::decode_base32(&secret)?;
 
-            config.save()?;
+            for warning in secret::sanity_warnings(&raw_secret, args.encoding, &secret_bytes) {
+                eprintln!("{} {}", "Warning:".yellow().bold(), warning);
+            }
+            secret
+        }
+    };
 
-            println!("{}", "✅ Configuration saved successfully!".green().bold());
-            println!("{}", "Your Quackey TOTP generator is ready to use, quack quack!".bright_black());
+    // Unspecified settings come from the issuer preset, then the usual defaults
+    let mut builder = AccountBuilder::new(args.name, secret).tags(args.tags).aliases(args.aliases);
+    if let Some(issuer) = args.issuer {
+        builder = builder.issuer(issuer);
+    }
+    if let Some(digits) = args.digits {
+        builder = builder.digits(digits);
+    }
+    if let Some(period) = args.period {
+        builder = builder.period(period);
+    }
+    if let Some(algorithm) = args.algorithm {
+        builder = builder.algorithm(algorithm.into());
+    }
+    let account = builder.build()?;
 
-            wait_for_input()?;
+    let (_, mut storage) = open_vault()?;
+    let existing = storage.get_accounts();
+    if let Some(duplicate) = health::accounts_with_secret(existing, &account).first() {
+        return Err(AppError::InvalidInput(format!(
+            "This secret is already used by account '{}'",
+            duplicate.name()
+        )));
+    }
+    storage.check_aliases(account.aliases(), None)?;
 
-            return Ok(config);
-        }
+    let label = account_label(&account);
+    if args.dry_run {
+        println!("{} {}", "Would add:".cyan().bold(), label);
+        println!("{}", "Dry run: nothing was written.".bright_black());
+        return Ok(());
+    }
 
-        let storage_dir = get_file_path("accounts storage file", ".")?;
+    storage.add_account(account)?;
 
-        let mut new_config = Config { storage_dir };
+    println!("{} {}", "👌 Account added:".green().bold(), label);
+    Ok(())
+}
 
-        new_config.validate_paths()?;
-        new_config.ensure_directories()?;
-        new_config.save()?;
+/// Prints the current code for one account, looked up among the accounts
+/// with `tag` if given. Only reads `config.json` and the vault, apart from
+/// noting the generation in the vault's history, so it stays fast enough to
+/// call from scripts and key bindings.
+fn run_gen(query: &str, tag: Option<&str>, json: bool) -> Result<(), AppError> {
+    let (account, vault) = find_account(query, tag)?;
+    refuse_high_value(&Config::load()?, &account)?;
+    // Random digits in demo mode
+    let code = ui::format_totp(&account.generate_totp()?, "none");
 
-        println!();
-        println!("{}", "✅ Configuration saved successfully!".green().bold());
-        println!("{}", "Your Quackey TOTP generator is ready to use, quack quack!".bright_black());
+    if json {
+        let value = serde_json::json!({
+            "name": account.name(),
+            "issuer": account.issuer(),
+            "code": code,
+            "remaining": account.time_remaining(),
+        });
+        println!("{}", value);
+    } else {
+        println!("{}", code);
+    }
+    record_generations(vault, std::slice::from_ref(&account));
+    Ok(())
+}
 
-        wait_for_input()?;
+/// Prints the current code of every unarchived account with `tag`, one
+/// `code account` pair per line, like a dashboard of [`run_gen`]. High-value
+/// accounts are left out, with a note on stderr.
+fn run_gen_tagged(tag: &str, json: bool) -> Result<(), AppError> {
+    let config = Config::load()?;
+    let (accounts, vault) = all_accounts()?;
+    let (accounts, high_value): (Vec<Account>, Vec<Account>) = accounts
+        .into_iter()
+        .filter(|account| account.has_tag(tag) && !account.is_archived())
+        .partition(|account| !config.is_high_value(account.tags()));
+    if accounts.is_empty() && high_value.is_empty() {
+        return Err(no_accounts_tagged(tag));
+    }
+    for account in &high_value {
+        eprintln!("Skipped {}: high-value codes are only shown in the menu", account_label(account));
+    }
 
-        Ok(new_config)
+    if json {
+        let items = accounts
+            .iter()
+            .map(|account| {
+                Ok(serde_json::json!({
+                    "name": account.name(),
+                    "issuer": account.issuer(),
+                    "code": ui::format_totp(&account.generate_totp()?, "none"),
+                    "remaining": account.time_remaining(),
+                }))
+            })
+            .collect::<Result<Vec<_>, AppError>>()?;
+        println!("{}", serde_json::Value::Array(items));
     } else {
-        Ok(config)
+        for account in &accounts {
+            println!("{} {}", ui::format_totp(&account.generate_totp()?, "none"), account_label(account));
+        }
     }
+    record_generations(Some(vault), &accounts);
+    Ok(())
 }
 
-/// Gets a file path from user input with validation
-fn get_file_path(prompt: &str, default: &str) -> Result<String, AppError> {
-    println!();
-    println!("{}", "Path format options:".bright_black());
-    println!("{}", "  - Relative path (e.g., 'totp')".bright_black());
-    println!(
-        "{}",
-        "  - Absolute path (e.g., '/home/user/quackey/totp' or 'D:/Quackey/totp')".bright_black()
-    );
-    println!();
-    println!("{}", "Notes:".bright_black());
-    println!(
-        "{}",
-        "  - Use forward slashes (/) even on Windows for consistency".bright_black()
-    );
-    println!(
-        "{}",
-        "  - Non-existent directories will be created automatically".bright_black()
-    );
-    println!(
-        "{}",
-        "  - You must have write permissions for the specified location".bright_black()
+/// Lists the accounts matching `query`, and having `tag` if given,
+/// environment accounts first. Each line is a query [`run_gen`] accepts. The
+/// Alfred format includes current codes, and asks Alfred to rerun it every
+/// second so they stay fresh.
+fn run_list(query: &str, format: ListFormat, archived: bool, tag: Option<&str>, json: bool) -> Result<(), AppError> {
+    if format == ListFormat::Names {
+        print_names(query);
+        return Ok(());
+    }
+
+    let config = Config::load()?;
+    let mut accounts = env::search(query);
+    let path = config.get_storage_file_path();
+    let vault = Vault::open_with_key(&path, unlock_key(&path, config.pinentry.as_deref())?)?;
+    accounts.extend(
+        vault
+            .search(query)
+            .into_iter()
+            .filter(|account| archived || !account.is_archived())
+            .cloned(),
     );
-    println!();
+    if let Some(tag) = tag {
+        accounts.retain(|account| account.has_tag(tag));
+    }
 
-    let path: String = Input::with_theme(&dialoguer::theme::ColorfulTheme::default())
-        .with_prompt(format!(
-            "Directory path for {} (press Enter for default)",
-            prompt
-        ))
-        .default(default.to_string())
-        .interact_text()
-        .unwrap_or_else(|_| default.to_string());
+    let gen_query = |account: &Account| match (account.aliases().first(), account.issuer()) {
+        (Some(alias), _) => alias.clone(),
+        (None, Some(issuer)) => format!("{}:{}", issuer, account.name()),
+        (None, None) => account.name().to_string(),
+    };
 
-    if path.trim().is_empty() {
-        return Err(AppError::InvalidInput(format!(
-            "{} path cannot be empty",
-            prompt
-        )));
+    match format {
+        ListFormat::Alfred => {
+            let items: Vec<serde_json::Value> = accounts
+                .iter()
+                .map(|account| {
+                    if config.is_high_value(account.tags()) {
+                        return serde_json::json!({
+                            "uid": account.id(),
+                            "title": account_label(account),
+                            "subtitle": "High-value account: open Quackey to see its code",
+                            "valid": false,
+                        });
+                    }
+                    match account.generate_totp() {
+                        Ok(code) => {
+                            // Random digits in demo mode
+                            let shown = ui::format_totp(&code, &config.code_grouping);
+                            let code = shown.replace(' ', "");
+                            let remaining = account.time_remaining();
+                            match account.code_policy() {
+                                CodePolicy::DisplayOnly => serde_json::json!({
+                                    "uid": account.id(),
+                                    "title": account_label(account),
+                                    "subtitle": format!("{} · expires in {}s · display only", shown, remaining),
+                                    "valid": false,
+                                    "autocomplete": gen_query(account),
+                                    "text": { "largetype": code },
+                                }),
+                                CodePolicy::CopyOnly => serde_json::json!({
+                                    "uid": account.id(),
+                                    "title": account_label(account),
+                                    "subtitle": format!("{} · expires in {}s · ⏎ to copy", ui::mask_code(&shown), remaining),
+                                    "arg": code,
+                                    "autocomplete": gen_query(account),
+                                    "text": { "copy": code },
+                                }),
+                                CodePolicy::ShowAndCopy => serde_json::json!({
+                                    "uid": account.id(),
+                                    "title": account_label(account),
+                                    "subtitle": format!("{} · expires in {}s · ⏎ to copy", shown, remaining),
+                                    "arg": code,
+                                    "autocomplete": gen_query(account),
+                                    "text": { "copy": code, "largetype": code },
+                                }),
+                            }
+                        }
+                        Err(e) => serde_json::json!({
+                            "uid": account.id(),
+                            "title": account_label(account),
+                            "subtitle": format!("Can't generate a code: {}", e),
+                            "valid": false,
+                        }),
+                    }
+                })
+                .collect();
+            println!("{}", serde_json::json!({ "rerun": 1, "items": items }));
+
+            accounts.retain(|account| !config.is_high_value(account.tags()));
+            record_generations(Some(vault), &accounts);
+        }
+        ListFormat::Text if json => {
+            let items: Vec<serde_json::Value> = accounts
+                .iter()
+                .map(|account| {
+                    serde_json::json!({
+                        "name": account.name(),
+                        "issuer": account.issuer(),
+                        "tags": account.tags(),
+                        "aliases": account.aliases(),
+                        "archived": account.is_archived(),
+                        "query": gen_query(account),
+                    })
+                })
+                .collect();
+            println!("{}", serde_json::Value::Array(items));
+        }
+        ListFormat::Text => {
+            for account in &accounts {
+                println!("{}", gen_query(account));
+            }
+        }
+        ListFormat::Names => unreachable!(),
     }
+    Ok(())
+}
 
-    Ok(path.trim().to_string())
+/// Prints the issuers and names starting with `prefix` (ignoring case) that
+/// [`run_gen`] accepts, for shell completion. Runs on every Tab press, so it
+/// never prompts: an encrypted vault is only read when Windows account
+/// protection unlocks it, and errors just leave names out.
+fn print_names(prefix: &str) {
+    let prefix = prefix.to_lowercase();
+    let mut names: Vec<String> = env::accounts().iter().map(|entry| entry.key().to_lowercase()).collect();
+
+    let vault = Config::load().ok().and_then(|config| {
+        let path = config.get_storage_file_path();
+        let key = match (storage::is_encrypted(&path), dpapi::is_set_up(&path)) {
+            (false, _) => None,
+            (true, true) => Some(dpapi::unlock(&path).ok()?),
+            (true, false) => return None,
+        };
+        std::path::Path::new(&path).exists().then(|| Vault::open_with_key(&path, key).ok())?
+    });
+    if let Some(vault) = &vault {
+        for account in vault.accounts() {
+            names.extend(account.issuer().cloned());
+            names.push(account.name().to_string());
+            names.extend(account.aliases().iter().cloned());
+        }
+    }
+
+    names.retain(|name| name.to_lowercase().starts_with(&prefix));
+    names.sort_by_key(|name| name.to_lowercase());
+    names.dedup_by(|a, b| a.eq_ignore_ascii_case(b));
+    for name in names {
+        println!("{}", name);
+    }
 }
 
-/// Runs the main application loop
-fn run_main_loop(storage: &mut Storage, logger: &mut Logger) -> Result<(), AppError> {
-    loop {
-        clear_screen();
-        display_welcome_screen();
+/// Prints one line for a status bar, such as `GitHub 123 456 (12s)`, or with
+/// `json` an object in the format waybar's custom modules read. Meant to be
+/// polled every second, so like [`run_gen`] it only reads, apart from noting
+/// each new code in the vault's history.
+fn run_statusbar(query: &str, json: bool) -> Result<(), AppError> {
+    let config = Config::load()?;
+    let (account, vault) = find_account(query, None)?;
+    refuse_high_value(&config, &account)?;
+    let code = ui::display_code(&account, &account.generate_totp()?, &config.code_grouping);
+    let remaining = account.time_remaining();
+    let label = account.issuer().map_or(account.name(), String::as_str);
+    let text = format!("{} {} ({}s)", label, code, remaining);
 
-        let selection = display_menu_and_get_selection()?;
+    if json {
+        let expiring = config.expiry_warning_seconds > 0 && remaining <= config.expiry_warning_seconds;
+        let value = serde_json::json!({
+            "text": text,
+            "tooltip": account_label(&account),
+            "class": if expiring { "expiring" } else { "valid" },
+            "percentage": remaining * 100 / account.period().max(1),
+        });
+        println!("{}", value);
+    } else {
+        println!("{}", text);
+    }
+    record_generations(vault, std::slice::from_ref(&account));
+    Ok(())
+}
 
-        clear_screen();
+/// Fails for a high-value account (see `high_value_tags`): commands that print
+/// codes can't ask for the master password first, so those codes are only
+/// shown in the menu
+fn refuse_high_value(config: &Config, account: &Account) -> Result<(), AppError> {
+    if config.is_high_value(account.tags()) {
+        return Err(AppError::PermissionError(format!(
+            "{} is a high-value account",
+            account_label(account)
+        ))
+        .with_hint("Show its code from the menu, which checks with you first"));
+    }
+    Ok(())
+}
 
-        if handle_menu_selection(selection, storage, logger)? {
-            break;
+/// Finds the account a non-interactive command names, checking
+/// `QUACKEY_ACCOUNT_*` variables before the vault so they work without one,
+/// e.g. in CI. With `tag`, only accounts having it are considered. Also
+/// returns the vault if it was opened, for [`record_generations`].
+fn find_account(query: &str, tag: Option<&str>) -> Result<(Account, Option<Vault>), AppError> {
+    let Some(tag) = tag else {
+        if let Some(account) = env::find(query)? {
+            return Ok((account, None));
         }
+        let config = Config::load()?;
+        let path = config.get_storage_file_path();
+        let vault = Vault::open_with_key(&path, unlock_key(&path, config.pinentry.as_deref())?)?;
+        let account = vault.find(query)?.clone();
+        return Ok((account, Some(vault)));
+    };
+
+    let (accounts, vault) = all_accounts()?;
+    let tagged: Vec<Account> = accounts.into_iter().filter(|account| account.has_tag(tag)).collect();
+    if tagged.is_empty() {
+        return Err(no_accounts_tagged(tag));
     }
-    Ok(())
+    let account = vault::find_in(&tagged, query)?.clone();
+    Ok((account, Some(vault)))
 }
 
-/// Displays menu and gets user selection
-fn display_menu_and_get_selection() -> Result<usize, AppError> {
-    let selections = &[
-        "🔢 Generate TOTP",
-        "📂 Manage Accounts",
-        "⚙️ Configure Settings",
-        "🦆 Exit",
-    ];
+/// Every account non-interactive commands can use: those in
+/// `QUACKEY_ACCOUNT_*` variables, then the vault's. Also returns the vault,
+/// for [`record_generations`].
+fn all_accounts() -> Result<(Vec<Account>, Vault), AppError> {
+    let mut accounts = env::search("");
+    let config = Config::load()?;
+    let path = config.get_storage_file_path();
+    let vault = Vault::open_with_key(&path, unlock_key(&path, config.pinentry.as_deref())?)?;
+    accounts.extend(vault.accounts().iter().cloned());
+    Ok((accounts, vault))
+}
 
-    Ok(
-        Select::with_theme(&dialoguer::theme::ColorfulTheme::default())
-            .with_prompt("Select an option")
-            .default(0)
-            .items(selections)
-            .interact()
-            .unwrap_or(3),
-    )
+/// Notes in the vault's code history that codes were just printed for
+/// `accounts`; those from `QUACKEY_ACCOUNT_*` variables have none. The vault
+/// itself isn't written.
+fn record_generations(vault: Option<Vault>, accounts: &[Account]) {
+    let Some(mut vault) = vault else {
+        return;
+    };
+    for account in accounts {
+        vault.record_generation(account.id());
+    }
 }
 
-/// Displays the account management submenu and gets user selection
-fn display_account_management_menu() -> Result<usize, AppError> {
-    let selections = &[
-        "👀 View saved accounts",
-        "📄 Add new account",
-        "📝 Edit account",
-        "🗑️ Delete account",
-        "👈 Back to main menu",
-    ];
+fn no_accounts_tagged(tag: &str) -> AppError {
+    AppError::NotFound(format!("No accounts are tagged '{}'", tag.trim()))
+        .with_hint("Run 'quackey list --json' to see the tags of every account")
+}
 
-    Ok(
-        Select::with_theme(&dialoguer::theme::ColorfulTheme::default())
-            .with_prompt("Select an account management option")
-            .default(0)
-            .items(selections)
-            .interact()
-            .unwrap_or(4),
-    )
+/// Opens the event sinks and storage for non-interactive commands, using the
+/// saved configuration (or the defaults) without running onboarding
+fn open_vault() -> Result<(Events, Storage), AppError> {
+    let config = Config::load()?;
+    let events = Events::for_config(&config)?;
+    let path = config.get_storage_file_path();
+    let storage = Storage::new_with_key(&path, events.clone(), unlock_key(&path, config.pinentry.as_deref())?)?;
+    Ok((events, storage))
 }
 
-/// Handles the menu selection and returns whether the application should exit
-fn handle_menu_selection(
-    selection: usize,
-    storage: &mut Storage,
-    logger: &mut Logger,
-) -> Result<bool, AppError> {
-    match selection {
-        0 => generate_totp(storage, logger)?,
-        1 => {
-            loop {
-                clear_screen();
-                display_screen("Account Management");
+/// Asks for the PIN or master password when the vault at `path` is encrypted,
+/// through the configured pinentry program if there is one. Returns `None`
+/// for a plain-text vault.
+fn unlock_key(path: &str, pinentry_program: Option<&str>) -> Result<Option<VaultKey>, AppError> {
+    if !storage::is_encrypted(path) {
+        return Ok(None);
+    }
+    if dpapi::is_set_up(path) {
+        return dpapi::unlock(path).map(Some);
+    }
 
-                let submenu_selection = display_account_management_menu()?;
+    let key_info = pinentry::key_info_for(path);
+    let description = format!("Enter the master password of the Quackey vault at {}", path);
+    let ask = |label: &str, error: Option<&str>, cacheable: bool| -> Result<String, AppError> {
+        match pinentry_program {
+            Some(program) => {
+                let prompt = pinentry::Prompt {
+                    title: "Quackey",
+                    description: &description,
+                    prompt: label,
+                    error,
+                    key_info: cacheable.then_some(key_info.as_str()),
+                };
+                pinentry::get_pin(program, &prompt)?
+                    .ok_or_else(|| AppError::Locked("Unlocking the vault was cancelled".to_string()))
+            }
+            None => {
+                if let Some(error) = error {
+                    eprintln!("{}", format!("⛔ {}", error).red());
+                }
+                Password::with_theme(&dialoguer::theme::ColorfulTheme::default())
+                    .with_prompt(label)
+                    .allow_empty_password(!cacheable)
+                    .interact()
+                    .answered()
+                    .map_err(|e| match e {
+                        AppError::Cancelled => e,
+                        _ => AppError::Locked(
+                            "The vault is encrypted; run Quackey in a terminal to unlock it".to_string(),
+                        ),
+                    })
+            }
+        }
+    };
 
-                clear_screen();
+    if pin::is_set_up(path) {
+        let mut error = None;
+        loop {
+            let entered = ask("PIN (leave empty to use the master password)", error.as_deref(), false)?;
+            if entered.is_empty() {
+                break;
+            }
 
-                if submenu_selection == 4 {
+            match pin::unlock(path, &entered)? {
+                pin::PinAttempt::Unlocked(key) => return Ok(Some(key)),
+                pin::PinAttempt::Wrong { attempts_left: 0 } => {
+                    eprintln!(
+                        "{}",
+                        "⛔ Too many wrong PINs. PIN unlock is off until you set it up again.".red().bold()
+                    );
                     break;
                 }
-
-                handle_account_management_selection(submenu_selection, storage, logger)?;
+                pin::PinAttempt::Wrong { attempts_left } => {
+                    error = Some(format!("Wrong PIN, {} attempt(s) left.", attempts_left));
+                }
             }
         }
-        2 => configure_settings(storage, logger)?,
-        3 => {
-            logger.info("Application exiting")?;
-            display_exit_screen();
+    }
 
-            println!("\n{}", "Press Enter to exit...".bright_black());
-            let mut buffer = String::new();
-            io::stdin().read_line(&mut buffer)?;
+    let mut error = None;
+    let mut attempts = 0;
+    loop {
+        let passphrase = ask("Master password", error, true)?;
 
-            return Ok(true);
+        match storage::unlock(path, &passphrase) {
+            Ok(key) => return Ok(Some(key)),
+            Err(e) => {
+                attempts += 1;
+                if attempts == 3 {
+                    return Err(e);
+                }
+                error = Some("Wrong master password, try again.");
+            }
         }
-        _ => unreachable!(),
     }
-    Ok(false)
 }
 
-/// Handles the account management menu selection
-fn handle_account_management_selection(
-    selection: usize,
-    storage: &mut Storage,
-    logger: &mut Logger,
-) -> Result<(), AppError> {
-    match selection {
-        0 => view_accounts(storage, logger)?,
-        1 => add_account(storage, logger)?,
-        2 => edit_account(storage, logger)?,
-        3 => delete_account(storage, logger)?,
-        4 => (), // Back to main menu
-        _ => unreachable!(),
+/// Lists the registered import and export formats after the usage text
+fn print_formats() {
+    println!();
+    println!("Import formats:");
+    for importer in formats::importers() {
+        println!("  {:<22} {}", importer.name(), importer.description());
+    }
+    println!();
+    println!("Export formats:");
+    for exporter in formats::exporters() {
+        println!("  {:<22} {}", exporter.name(), exporter.description());
     }
-    Ok(())
 }
 
-/// Adds a new TOTP account
-fn add_account(storage: &mut Storage, logger: &mut Logger) -> Result<(), AppError> {
-    display_screen("Add New Account");
+/// Imports accounts from a file in the given format. Every entry is shown in a
+/// preview first; on a terminal the user picks what to import before the
+/// accounts are saved all at once.
+fn run_import(format: &str, path: &str, dry_run: bool) -> Result<(), AppError> {
+    let importer = formats::find_importer(format)?;
+    let mut contents = std::fs::read_to_string(path)
+        .map_err(|e| AppError::FileError(format!("Failed to read '{}': {}", path, e)))?;
+    if backup::is_sealed(&contents) {
+        let passphrase = Password::with_theme(&dialoguer::theme::ColorfulTheme::default())
+            .with_prompt("Backup passphrase")
+            .interact()
+            .answered()
+            .map_err(|e| match e {
+                AppError::Cancelled => e,
+                _ => AppError::Locked("The file is an encrypted backup; run the import in a terminal".to_string()),
+            })?;
+        contents = backup::open(&contents, &passphrase)?;
+    }
 
-    let (name, issuer) = match get_new_account_details() {
-        Ok(details) => details,
-        Err(e) => {
-            println!("{}", format!("⛔ Error: {}", e).red().bold());
-            println!();
-            println!(
-                "{}",
-                "Please try again with a valid account name.".bright_black()
-            );
-            wait_for_input()?;
-            return Ok(());
-        }
-    };
+    let source = format!("{} file '{}'", importer.name(), path);
+    let mut lines = importer.parse(&contents)?;
+    if importer.name() == "text" && !dry_run && io::stdin().is_terminal() {
+        fix_text_lines(&contents, &mut lines)?;
+    }
+    import_lines(lines, &source, "the file", dry_run)
+}
 
-    let secret = match get_validated_secret() {
-        Ok(secret) => secret,
-        Err(e) => {
+/// Offers to enter the details of the lines of a loose text list that
+/// couldn't be read, one by one, or to skip them
+fn fix_text_lines(contents: &str, lines: &mut [import::ImportLine]) -> Result<(), AppError> {
+    let failed = lines.iter().filter(|entry| entry.result.is_err()).count();
+    if failed == 0 {
+        return Ok(());
+    }
+
+    println!();
+    let fix = Confirm::with_theme(&dialoguer::theme::ColorfulTheme::default())
+        .with_prompt(format!("{} line(s) couldn't be read. Fix them by hand now?", failed))
+        .default(true)
+        .interact_opt()
+        .chosen()?;
+    if !fix {
+        return Ok(());
+    }
+
+    let raw_lines: Vec<&str> = contents.lines().collect();
+    for entry in lines.iter_mut() {
+        let Err(error) = &entry.result else {
+            continue;
+        };
+
+        println!();
+        let raw = raw_lines.get(entry.line - 1).map(|line| line.trim()).unwrap_or_default();
+        println!("{} {}", format!("Line {}:", entry.line).bold(), raw);
+        println!("{}", error.message().bright_red());
+
+        let choice = Select::with_theme(&dialoguer::theme::ColorfulTheme::default())
+            .with_prompt("What would you like to do?")
+            .default(0)
+            .items(&["✏️ Enter its details", "⏭️ Skip this line"])
+            .interact_opt()
+            .chosen()?;
+        if choice != 0 {
+            continue;
+        }
+
+        loop {
+            let issuer: String = Input::with_theme(&dialoguer::theme::ColorfulTheme::default())
+                .with_prompt("Issuer (optional, e.g., 'GitHub')")
+                .allow_empty(true)
+                .interact_text()
+                .answered()?;
+            let name: String = Input::with_theme(&dialoguer::theme::ColorfulTheme::default())
+                .with_prompt("Account name (optional if there is an issuer)")
+                .allow_empty(true)
+                .interact_text()
+                .answered()?;
+            let secret: String = Input::with_theme(&dialoguer::theme::ColorfulTheme::default())
+                .with_prompt("Secret key (base32)")
+                .interact_text()
+                .answered()?;
+
+            match formats::account_from_parts(Some(&issuer), &name, &secret) {
+                Ok(account) => {
+                    println!("{}", format!("✅ Line {} is now {}.", entry.line, ui::shown_label(&account)).green());
+                    entry.result = Ok(account);
+                    break;
+                }
+                Err(e) => {
+                    println!("{}", format!("🦉 {}", e.message()).bright_red());
+                    let again = Confirm::with_theme(&dialoguer::theme::ColorfulTheme::default())
+                        .with_prompt("Try this line again?")
+                        .default(true)
+                        .interact_opt()
+                        .chosen()?;
+                    if !again {
+                        break;
+                    }
+                }
+            }
+        }
+    }
+    Ok(())
+}
+
+/// Imports the accounts defined in `QUACKEY_ACCOUNT_*` variables
+fn run_import_env(dry_run: bool) -> Result<(), AppError> {
+    let source = format!("{}* environment variables", env::PREFIX);
+    import_lines(env::import_lines(), &source, "the environment", dry_run)
+}
+
+/// Previews parsed accounts, lets the user review conflicts and saves them.
+/// `source` names where they came from in the log, `place` in messages.
+fn import_lines(lines: Vec<import::ImportLine>, source: &str, place: &str, dry_run: bool) -> Result<(), AppError> {
+    let (events, mut storage) = open_vault()?;
+    let mut entries = import::preview(lines, storage.get_accounts());
+
+    if entries.is_empty() {
+        println!("{}", format!("🦉 No accounts found in {}.", place).bright_red());
+        return Ok(());
+    }
+
+    display_import_preview(&entries, storage.get_accounts());
+
+    if dry_run {
+        println!();
+        println!("{}", format!("Would {}.", import_summary(&entries)).bold());
+        println!("{}", "Dry run: nothing was written.".bright_black());
+        return Ok(());
+    }
+
+    if io::stdin().is_terminal() {
+        review_import(&mut entries, storage.get_accounts())?;
+
+        println!();
+        let proceed = Confirm::with_theme(&dialoguer::theme::ColorfulTheme::default())
+            .with_prompt(format!("Import: {}?", import_summary(&entries)))
+            .default(true)
+            .interact_opt()
+            .chosen()?;
+        if !proceed {
+            println!("{}", "Import cancelled; nothing was written.".bright_black());
+            return Ok(());
+        }
+    }
+
+    let summary = import_summary(&entries);
+    let replaces = entries.iter().any(|e| e.resolution == import::Resolution::Replace);
+    let changes = entries.iter().any(|e| e.resolution != import::Resolution::Skip);
+
+    if changes {
+        let snapshot = if replaces { storage.snapshot("import")? } else { None };
+        let accounts = import::apply(entries, storage.get_accounts());
+        storage.replace_accounts(accounts)?;
+        print_snapshot_note(snapshot.as_deref());
+    }
+
+    println!();
+    println!("{}", format!("Import done: {}.", summary).bold());
+    events.emit(Event::Imported { source: source.to_string(), summary: summary.to_string() })?;
+
+    Ok(())
+}
+
+/// Lets the user choose which previewed entries to import and, for entries
+/// that conflict with an existing account, whether to replace it or keep both
+fn review_import(entries: &mut [import::PreviewEntry], existing: &[Account]) -> Result<(), AppError> {
+    let selectable: Vec<usize> = entries
+        .iter()
+        .enumerate()
+        .filter(|(_, e)| e.account.is_some())
+        .map(|(i, _)| i)
+        .collect();
+    if selectable.is_empty() {
+        return Ok(());
+    }
+
+    let items: Vec<String> = selectable
+        .iter()
+        .map(|&i| {
+            let entry = &entries[i];
+            let account = entry.account.as_ref().map(ui::shown_label).unwrap_or_default();
+            match &entry.status {
+                import::EntryStatus::Duplicate(reason) => format!("{} [duplicate: {}]", account, reason),
+                import::EntryStatus::Conflict(index) => {
+                    format!("{} [conflicts with '{}']", account, ui::shown_name(&existing[*index]))
+                }
+                _ => account,
+            }
+        })
+        .collect();
+    let defaults: Vec<bool> = selectable
+        .iter()
+        .map(|&i| entries[i].resolution != import::Resolution::Skip)
+        .collect();
+
+    println!();
+    let chosen = MultiSelect::with_theme(&dialoguer::theme::ColorfulTheme::default())
+        .with_prompt("Select the entries to import (space to toggle, enter to confirm)")
+        .items(&items)
+        .defaults(&defaults)
+        .interact_opt()
+        .chosen()?;
+
+    for (position, &index) in selectable.iter().enumerate() {
+        let entry = &mut entries[index];
+        if !chosen.contains(&position) {
+            entry.resolution = import::Resolution::Skip;
+            continue;
+        }
+
+        entry.resolution = match entry.status {
+            import::EntryStatus::Conflict(existing_index) => {
+                let choice = Select::with_theme(&dialoguer::theme::ColorfulTheme::default())
+                    .with_prompt(format!(
+                        "Line {} conflicts with '{}'",
+                        entry.line,
+                        ui::shown_name(&existing[existing_index])
+                    ))
+                    .default(0)
+                    .items(&["🔁 Replace the existing account", "➕ Keep both"])
+                    .interact_opt()
+                    .chosen()?;
+                if choice == 0 { import::Resolution::Replace } else { import::Resolution::Add }
+            }
+            _ => import::Resolution::Add,
+        };
+    }
+    Ok(())
+}
+
+/// Counts of what an import will do, e.g. "add 3, replace 1, skip 2"
+fn import_summary(entries: &[import::PreviewEntry]) -> String {
+    let count = |resolution| entries.iter().filter(|e| e.resolution == resolution).count();
+    format!(
+        "add {}, replace {}, skip {}",
+        count(import::Resolution::Add),
+        count(import::Resolution::Replace),
+        count(import::Resolution::Skip)
+    )
+}
+
+/// Writes every account, or those with `tag`, in the given format to a file,
+/// or stdout when no output path is given
+fn run_export(format: &str, output: Option<&str>, encrypt: bool, tag: Option<&str>) -> Result<(), AppError> {
+    let exporter = formats::find_exporter(format)?;
+    let (events, storage) = open_vault()?;
+    let accounts: Vec<Account> = storage
+        .get_accounts()
+        .iter()
+        .filter(|account| tag.is_none_or(|tag| account.has_tag(tag)))
+        .cloned()
+        .collect();
+    if let Some(tag) = tag.filter(|_| accounts.is_empty()) {
+        return Err(no_accounts_tagged(tag));
+    }
+    let mut contents = exporter.export(&accounts)?;
+    if encrypt {
+        let passphrase = ask_backup_passphrase(&storage)?;
+        contents = backup::seal(&contents, &passphrase)?;
+    }
+
+    match output {
+        Some(path) => {
+            permissions::write(std::path::Path::new(path), contents.as_bytes())
+                .map_err(|e| AppError::FileError(format!("Failed to write '{}': {}", path, e)))?;
+            eprintln!(
+                "{}",
+                format!("✅ Exported {} account(s) to {}", accounts.len(), path).green().bold()
+            );
+        }
+        None => print!("{}", contents),
+    }
+
+    if encrypt {
+        eprintln!(
+            "{}",
+            "🔐 The export is encrypted. Keep the backup passphrase apart from the file.".bright_black()
+        );
+    } else {
+        eprintln!(
+            "{}",
+            "⚠️  The export contains your secrets in plain text. Store it somewhere safe.".yellow()
+        );
+    }
+    events.emit(Event::Exported {
+        what: format!(
+            "{} account(s){} as {}{}{}",
+            accounts.len(),
+            tag.map(|tag| format!(" tagged '{}'", tag.trim())).unwrap_or_default(),
+            exporter.name(),
+            if encrypt { ", encrypted with a backup passphrase" } else { "" },
+            output.map(|p| format!(" to '{}'", p)).unwrap_or_default()
+        ),
+    })?;
+
+    Ok(())
+}
+
+/// Asks for a new backup passphrase, refusing the vault's master password so
+/// the backup can be handed to someone without giving them the vault
+fn ask_backup_passphrase(storage: &Storage) -> Result<String, AppError> {
+    eprintln!(
+        "{}",
+        "Choose a passphrase for this backup. Use a different one than your master password.".bright_black()
+    );
+    let passphrase = Password::with_theme(&dialoguer::theme::ColorfulTheme::default())
+        .with_prompt("Backup passphrase")
+        .with_confirmation("Confirm passphrase", "⛔ Passphrases don't match.")
+        .interact()
+        .answered()
+        .map_err(|e| match e {
+            AppError::Cancelled => e,
+            _ => AppError::InvalidInput("A backup passphrase is needed; run this in a terminal".to_string()),
+        })?;
+
+    if passphrase.is_empty() {
+        return Err(AppError::InvalidInput("The backup passphrase cannot be empty".to_string()));
+    }
+    if storage.key().is_some() && storage::unlock(storage.file_path(), &passphrase).is_ok() {
+        return Err(AppError::InvalidInput(
+            "The backup passphrase is the vault's master password".to_string(),
+        )
+        .with_hint("Choose a passphrase of its own, so the backup doesn't reveal how to unlock the vault"));
+    }
+    Ok(passphrase)
+}
+
+/// Writes the emergency kit to `output` or stdout, with a QR code of an
+/// encrypted backup when `qr` is set
+fn run_emergency_kit(output: Option<&str>, qr: bool) -> Result<(), AppError> {
+    let (events, storage) = open_vault()?;
+    let accounts = storage.get_accounts();
+    let backup = if qr {
+        let contents = formats::find_exporter("uris")?.export(accounts)?;
+        let passphrase = ask_backup_passphrase(&storage)?;
+        Some(kit::backup_code(&backup::seal(&contents, &passphrase)?)?)
+    } else {
+        None
+    };
+
+    let encryption = encryption_mode(storage.file_path());
+    let vault_path = std::path::absolute(storage.file_path())
+        .map(|path| path.display().to_string())
+        .unwrap_or_else(|_| storage.file_path().to_string());
+    let page = kit::render(
+        &kit::Kit {
+            vault_path: &vault_path,
+            encryption,
+            accounts: accounts.len(),
+            fingerprint: storage.key().map(VaultKey::fingerprint),
+            backup,
+        },
+        chrono::Local::now(),
+    );
+
+    match output {
+        Some(path) => {
+            permissions::write(std::path::Path::new(path), page.as_bytes())
+                .map_err(|e| AppError::FileError(format!("Failed to write '{}': {}", path, e)))?;
+            eprintln!("{}", format!("✅ Wrote the emergency kit to {}", path).green().bold());
+            eprintln!("{}", "🖨️  Print it and keep it somewhere safe; fill in the blanks by hand.".bright_black());
+        }
+        None => print!("{}", page),
+    }
+    if encryption == "none" {
+        eprintln!("{}", "⚠️  The vault is not encrypted; set a master password in the settings.".yellow());
+    }
+
+    events.note(Level::Audit, format!(
+        "Created an emergency kit{}{}",
+        if qr { " with an encrypted backup" } else { "" },
+        output.map(|p| format!(" at '{}'", p)).unwrap_or_default()
+    ))?;
+    Ok(())
+}
+
+/// Deletes an account by name, looked up among the accounts with `tag` if
+/// given, refusing when the name is ambiguous
+fn run_delete(name: &str, tag: Option<&str>, dry_run: bool) -> Result<(), AppError> {
+    let (_, mut storage) = open_vault()?;
+    let accounts = storage.get_accounts();
+
+    let account = match tag {
+        Some(tag) => {
+            let tagged: Vec<Account> = accounts.iter().filter(|account| account.has_tag(tag)).cloned().collect();
+            if tagged.is_empty() {
+                return Err(no_accounts_tagged(tag));
+            }
+            let id = vault::find_in(&tagged, name)?.id().to_string();
+            accounts.iter().find(|account| account.id() == id).expect("found among the vault's accounts")
+        }
+        None => vault::find_in(accounts, name)?,
+    };
+    let index = accounts
+        .iter()
+        .position(|a| std::ptr::eq(a, account))
+        .ok_or_else(|| AppError::NotFound(format!("Account '{}' not found", name)))?;
+
+    let label = account_label(account);
+    if dry_run {
+        println!("{} {}", "Would delete:".cyan().bold(), label);
+        println!("{}", "Dry run: nothing was written.".bright_black());
+        return Ok(());
+    }
+
+    let snapshot = storage.snapshot("delete")?;
+    storage.delete_accounts(&[index])?;
+
+    println!("{} {}", "✅ Account deleted:".green().bold(), label);
+    print_snapshot_note(snapshot.as_deref());
+    Ok(())
+}
+
+/// Deletes every account with `tag` at once, after saving a snapshot
+fn run_delete_tagged(tag: &str, dry_run: bool) -> Result<(), AppError> {
+    let (_, mut storage) = open_vault()?;
+    let (indices, labels): (Vec<usize>, Vec<String>) = storage
+        .get_accounts()
+        .iter()
+        .enumerate()
+        .filter(|(_, account)| account.has_tag(tag))
+        .map(|(index, account)| (index, account_label(account)))
+        .unzip();
+    if indices.is_empty() {
+        return Err(no_accounts_tagged(tag));
+    }
+
+    if dry_run {
+        for label in &labels {
+            println!("{} {}", "Would delete:".cyan().bold(), label);
+        }
+        println!("{}", "Dry run: nothing was written.".bright_black());
+        return Ok(());
+    }
+
+    let snapshot = storage.snapshot("delete")?;
+    storage.delete_accounts(&indices)?;
+
+    for label in &labels {
+        println!("{} {}", "✅ Account deleted:".green().bold(), label);
+    }
+    print_snapshot_note(snapshot.as_deref());
+    Ok(())
+}
+
+/// Replaces the accounts with those in a snapshot, given by path or by its
+/// name in the backups directory, after saving the current vault as a snapshot
+fn run_restore(snapshot: &str, dry_run: bool) -> Result<(), AppError> {
+    let (events, mut storage) = open_vault()?;
+    let path = match std::path::PathBuf::from(snapshot) {
+        path if path.exists() => path,
+        _ => storage.backups_dir().join(snapshot),
+    };
+    if !path.is_file() {
+        return Err(AppError::NotFound(format!("Snapshot '{}' not found", snapshot))
+            .with_hint(format!("Snapshots are kept in {}", storage.backups_dir().display())));
+    }
+
+    let accounts = storage.read_snapshot(&path)?;
+    let changes = merge::diff(storage.get_accounts(), &accounts);
+    if dry_run {
+        print_dry_run(&changes);
+        return Ok(());
+    }
+
+    let previous = storage.snapshot("restore")?;
+    storage.replace_accounts(accounts)?;
+    events.note(Level::Audit, format!("Restored accounts from snapshot {}", path.display()))?;
+
+    print_changes(&format!("✅ Restored the vault from {}", path.display()), &changes);
+    print_snapshot_note(previous.as_deref());
+    Ok(())
+}
+
+/// Prints what a dry run of `restore` or `sync` would change
+fn print_dry_run(changes: &[merge::Change]) {
+    if changes.is_empty() {
+        println!("{}", "No accounts would change.".bold());
+    }
+    for change in changes {
+        match change {
+            merge::Change::Added(account) => println!("{} {}", "Would add:".cyan().bold(), account_label(account)),
+            merge::Change::Removed(account) => println!("{} {}", "Would delete:".cyan().bold(), account_label(account)),
+            merge::Change::Changed { before, after } => {
+                let (before, after) = (account_label(before), account_label(after));
+                if before == after {
+                    println!("{} {}", "Would change:".cyan().bold(), after);
+                } else {
+                    println!("{} {} → {}", "Would change:".cyan().bold(), before, after);
+                }
+            }
+        }
+    }
+    println!("{}", "Dry run: nothing was written.".bright_black());
+}
+
+/// Syncs the vault with the configured WebDAV server: merges in changes
+/// uploaded from other devices, then uploads the result. A dry run only
+/// downloads, printing what the merge would change.
+fn sync_vault(storage: &mut Storage, events: &Events, config: &mut Config, dry_run: bool) -> Result<(), AppError> {
+    let Some(mut settings) = config.webdav.clone() else {
+        return Err(AppError::InvalidInput(
+            "WebDAV sync is not set up; configure it under Configure Settings".to_string(),
+        ));
+    };
+
+    // Only saved for vaults with a master password
+    if settings.password.is_empty() {
+        settings.password = Password::with_theme(&dialoguer::theme::ColorfulTheme::default())
+            .with_prompt(format!("WebDAV password for {}", settings.username))
+            .interact()
+            .answered()?;
+    }
+
+    let passphrase = Password::with_theme(&dialoguer::theme::ColorfulTheme::default())
+        .with_prompt("Sync passphrase")
+        .interact()
+        .answered()?;
+    if passphrase.is_empty() {
+        return Err(AppError::InvalidInput("Passphrase cannot be empty".to_string()));
+    }
+
+    let provider = sync::WebDav::new(&settings);
+    let remote = provider.download()?;
+
+    // Merge only when another device uploaded since our last sync
+    if let Some(remote) = remote.as_ref().filter(|r| r.etag.is_none() || r.etag != settings.etag) {
+        let theirs = sync::open(&remote.data, &passphrase)?;
+        let base = sync::load_base(storage.file_path())?;
+        let (theirs, dropped) = sync::drop_new_references(theirs, storage.get_accounts(), &base);
+        for name in &dropped {
+            events.note(Level::Warn, format!("Ignored a synced secret reference for '{}'", name))?;
+            println!(
+                "{}",
+                format!("⚠️ Kept this device's '{}': the synced copy fetches its secret with a command or variable.", name)
+                    .yellow()
+            );
+        }
+        let merged = merge::merge(&base, storage.get_accounts(), &theirs);
+        if dry_run {
+            // Conflicts would be asked about, so show them instead of the accounts
+            for conflict in &merged.conflicts {
+                if let Some(account) = conflict.ours.as_ref().or(conflict.theirs.as_ref()) {
+                    println!("{} {}", "Would ask which version to keep:".cyan().bold(), account_label(account));
+                }
+            }
+            let kept = vec![merge::Side::Ours; merged.conflicts.len()];
+            print_sync_dry_run(storage.get_accounts(), &merged.resolve(&kept));
+            return Ok(());
+        }
+        let choices = choose_conflict_sides(&merged.conflicts)?;
+        storage.replace_accounts(merged.resolve(&choices))?;
+    } else if dry_run {
+        print_sync_dry_run(storage.get_accounts(), storage.get_accounts());
+        return Ok(());
+    }
+
+    let data = sync::seal(storage.get_accounts(), &passphrase)?;
+    let expected = remote.as_ref().and_then(|r| r.etag.as_deref());
+    match provider.upload(&data, expected)? {
+        sync::Upload::Stored { etag } => {
+            sync::save_base(storage.file_path(), storage.get_accounts())?;
+            if let Some(webdav) = config.webdav.as_mut() {
+                webdav.etag = etag;
+            }
+            config.save()?;
+        }
+        sync::Upload::Conflict => {
+            return Err(AppError::Conflict(
+                "Another device uploaded while syncing; run the sync again".to_string(),
+            ));
+        }
+    }
+
+    let count = storage.get_accounts().len();
+    events.emit(Event::Synced { count })?;
+    println!("{}", format!("✅ Synced {} account(s).", count).green().bold());
+    Ok(())
+}
+
+/// Prints what a sync would change here, given the merged accounts it would
+/// keep and upload
+fn print_sync_dry_run(ours: &[Account], merged: &[Account]) {
+    println!("{} {} account(s)", "Would upload:".cyan().bold(), merged.len());
+    print_dry_run(&merge::diff(ours, merged));
+}
+
+/// Decrypts the credentials saved in the config with the vault key. A
+/// password left in plain text by an older version is encrypted, or dropped
+/// from the file if the vault has no master password.
+fn unseal_config(config: &mut Config, storage: &Storage, events: &Events) -> Result<(), AppError> {
+    let Some(webdav) = config.webdav.as_mut() else {
+        return Ok(());
+    };
+
+    if !webdav.is_password_sealed() && !webdav.password.is_empty() {
+        webdav.seal_password(storage.key());
+        config.save()?;
+        events.note(Level::Audit, "Removed the plain-text WebDAV password from config.json")?;
+        return Ok(());
+    }
+
+    if let Some(key) = storage.key()
+        && let Err(e) = webdav.unseal_password(key)
+    {
+        events.note(Level::Warn, format!("Could not decrypt the saved WebDAV password: {}", e))?;
+    }
+    Ok(())
+}
+
+/// Prints the log entries matching the query, oldest first
+fn run_log(query: &LogQuery) -> Result<(), AppError> {
+    let config = Config::load()?;
+    let entries = logger::search(&config.get_log_file_path(), query)?;
+
+    if entries.is_empty() {
+        eprintln!("{}", "🦉 No matching log entries.".bright_red());
+        return Ok(());
+    }
+
+    for entry in entries {
+        let level = match entry.level {
+            Level::Info => entry.level.name().normal(),
+            Level::Audit => entry.level.name().cyan(),
+            Level::Warn => entry.level.name().yellow(),
+            Level::Error => entry.level.name().red().bold(),
+        };
+        println!(
+            "{} {:<5} {}",
+            entry.timestamp.format("%Y-%m-%d %H:%M:%S").to_string().bright_black(),
+            level,
+            entry.message
+        );
+    }
+
+    Ok(())
+}
+
+/// Tells the user where the pre-change vault snapshot was saved
+fn print_snapshot_note(snapshot: Option<&std::path::Path>) {
+    if let Some(path) = snapshot {
+        println!(
+            "{}",
+            format!("🗄️  Previous vault saved to {}", path.display()).bright_black()
+        );
+    }
+}
+
+/// Prints environment diagnostics with a suggested fix for each problem
+fn run_doctor() -> Result<(), AppError> {
+    println!("{}", "🩺 Quackey doctor".bold());
+    println!();
+
+    let checks = doctor::run_checks();
+    for check in &checks {
+        print_check(check);
+    }
+
+    let failed = checks.iter().filter(|c| c.status == doctor::Status::Fail).count();
+    let warned = checks.iter().filter(|c| c.status == doctor::Status::Warn).count();
+
+    println!();
+    if failed == 0 && warned == 0 {
+        println!("{}", "Everything looks good!".green().bold());
+    } else {
+        println!(
+            "{}",
+            format!("{} problem(s), {} warning(s).", failed, warned).bold()
+        );
+    }
+
+    Ok(())
+}
+
+/// Prints one diagnostic with its suggested fix
+fn print_check(check: &doctor::Check) {
+    let marker = match check.status {
+        doctor::Status::Pass => "✅",
+        doctor::Status::Warn => "⚠️ ",
+        doctor::Status::Fail => "⛔",
+    };
+    println!("{} {}: {}", marker, check.name.bold(), check.detail);
+    if let Some(fix) = &check.fix {
+        println!("   {} {}", "↳".bright_black(), fix.bright_black());
+    }
+}
+
+/// Checks a config file and prints each problem with its line
+fn run_config_validate(path: Option<&str>) -> Result<(), AppError> {
+    let path = std::path::Path::new(path.unwrap_or(quackey_core::config::CONFIG_FILE));
+    let issues = config_check::validate(path);
+
+    for issue in &issues {
+        let marker = match issue.status {
+            doctor::Status::Fail => "⛔",
+            _ => "⚠️ ",
+        };
+        let location = match issue.line {
+            Some(line) => format!("{}:{}", path.display(), line),
+            None => path.display().to_string(),
+        };
+        println!("{} {} {}", marker, location.bold(), issue.message);
+    }
+
+    let failed = issues.iter().filter(|i| i.status == doctor::Status::Fail).count();
+    let warned = issues.len() - failed;
+    if issues.is_empty() {
+        println!("{}", format!("✅ '{}' is valid", path.display()).green().bold());
+    } else {
+        println!();
+        println!("{}", format!("{} problem(s), {} warning(s).", failed, warned).bold());
+    }
+
+    if failed > 0 {
+        return Err(AppError::InvalidInput(format!("'{}' has {} problem(s)", path.display(), failed))
+            .with_path(path));
+    }
+    Ok(())
+}
+
+/// Adds platform-specific steps for fixing access to the file it concerns when
+/// `error` is a permission problem without a hint of its own, and suggests
+/// another location for `what`
+fn permission_hint(error: AppError, what: &str) -> AppError {
+    if !matches!(error.kind(), AppError::PermissionError(_)) || error.has_own_hint() {
+        return error;
+    }
+
+    let mut steps = permission_steps(error.path());
+    steps.push(format!("Or choose a different location for {}.", what));
+    error.with_hint(steps.join("\n"))
+}
+
+/// Commands that give the current user access to `path` on this platform
+fn permission_steps(path: Option<&std::path::Path>) -> Vec<String> {
+    let target = match path {
+        Some(path) => format!("\"{}\"", path.display()),
+        None => "the file or folder".to_string(),
+    };
+
+    if cfg!(windows) {
+        vec![
+            format!("Check the owner and access list with: icacls {}", target),
+            format!("Give yourself access with: icacls {} /grant \"%USERNAME%\":(M)", target),
+            "Folders such as Program Files need an administrator; Controlled folder access in Windows Security can also block writes.".to_string(),
+        ]
+    } else {
+        let mut steps = vec![
+            format!("Check the owner and mode with: ls -ld {}", target),
+            format!("Take ownership with: sudo chown \"$USER\" {}", target),
+            format!("Allow reading and writing with: chmod u+rw {}", target),
+        ];
+        if cfg!(target_os = "macos") {
+            steps.push(
+                "Desktop, Documents and Downloads may also need Full Disk Access for your terminal in System Settings > Privacy & Security.".to_string(),
+            );
+        }
+        steps
+    }
+}
+
+/// Folder in the user's home directory to fall back to when the current one
+/// can't be written
+fn home_fallback_dir() -> Option<std::path::PathBuf> {
+    let home = std::env::var_os("HOME").or_else(|| std::env::var_os("USERPROFILE"))?;
+    Some(std::path::PathBuf::from(home).join(".quackey"))
+}
+
+/// Offers to start over in [`home_fallback_dir`] after a permission error
+/// during startup. Returns whether the working directory was changed.
+fn offer_home_fallback(error: &AppError) -> Result<bool, AppError> {
+    let Some(dir) = home_fallback_dir() else {
+        return Ok(false);
+    };
+    if std::env::current_dir().is_ok_and(|cwd| cwd == dir) {
+        return Ok(false);
+    }
+
+    println!("{}", format!("⛔ {}", error.message()).red().bold());
+    println!();
+    println!(
+        "{}",
+        format!(
+            "Quackey can keep its configuration, log and accounts in {} instead. \
+             Accounts saved in the current location stay there.",
+            dir.display()
+        )
+        .bright_black()
+    );
+    let retry = Confirm::with_theme(&dialoguer::theme::ColorfulTheme::default())
+        .with_prompt(format!("Retry in {}?", dir.display()))
+        .default(true)
+        .interact_opt()
+        .chosen()?;
+    if !retry {
+        return Ok(false);
+    }
+
+    std::fs::create_dir_all(&dir)
+        .and_then(|_| std::env::set_current_dir(&dir))
+        .map_err(|e| AppError::from(e).with_path(&dir))?;
+    Ok(true)
+}
+
+/// Sets up the configuration, log and storage for the interactive menu.
+/// Returns `None` when the user gave up on repairing a damaged storage file.
+fn open_interactive() -> Result<Option<(Config, Events, Storage)>, AppError> {
+    let (config, lock) = run_onboarding().map_err(|e| permission_hint(e, "your files"))?;
+    if !check_access(&config)? {
+        return Ok(None);
+    }
+    let events = Events::for_config(&config).map_err(|e| permission_hint(e, "your log file"))?;
+
+    let key = unlock_key(&config.get_storage_file_path(), config.pinentry.as_deref())?;
+    if key.is_some() {
+        events.emit(Event::VaultUnlocked)?;
+    }
+
+    let mut storage = match Storage::new_with_key(&config.get_storage_file_path(), events.clone(), key) {
+        Ok(storage) => storage,
+        Err(e) => match e.into_kind() {
+            AppError::StorageCorrupt(msg) => {
+                match repair_storage(&config.get_storage_file_path(), &msg, &events)? {
+                    Some(storage) => storage,
+                    None => return Ok(None),
+                }
+            }
+            e => {
+                let path = config.get_storage_file_path();
+                return Err(permission_hint(e.with_path(&path), "your storage file"));
+            }
+        },
+    };
+    if let Some(lock) = lock {
+        apply_vault_lock(&mut storage, &events, lock)?;
+    }
+
+    Ok(Some((config, events, storage)))
+}
+
+/// Checks up front that the storage file, backups folder and log can be read
+/// and written, and shows every problem on one screen. Returns whether to
+/// continue.
+fn check_access(config: &Config) -> Result<bool, AppError> {
+    let problems = doctor::check_access(config);
+    if problems.is_empty() {
+        return Ok(true);
+    }
+
+    display_screen("Startup Check");
+    println!("{}", "⚠️  Quackey can't use some of its files:".yellow().bold());
+    println!();
+    for problem in &problems {
+        print_check(problem);
+    }
+    println!();
+    println!(
+        "{}",
+        "Saving accounts, backups or log entries will fail until this is fixed.".bright_black()
+    );
+
+    Confirm::with_theme(&dialoguer::theme::ColorfulTheme::default())
+        .with_prompt("Continue anyway?")
+        .default(false)
+        .interact_opt()
+        .chosen()
+}
+
+/// Runs the interactive terminal application
+fn run_interactive() -> Result<(), AppError> {
+    ui::catch_interrupts();
+    let is_first_run = || !std::path::Path::new(quackey_core::config::CONFIG_FILE).exists();
+    let mut first_run = is_first_run();
+    let opened = match open_interactive() {
+        Err(e) if matches!(e.kind(), AppError::PermissionError(_)) && offer_home_fallback(&e)? => {
+            first_run = is_first_run();
+            open_interactive()?
+        }
+        result => result?,
+    };
+    let Some((mut config, events, mut storage)) = opened else {
+        return Ok(());
+    };
+
+    events.emit(Event::Started)?;
+    if let Some(from) = config.migrated_from() {
+        events.emit(Event::ConfigMigrated { from, to: quackey_core::config::CONFIG_VERSION })?;
+    }
+    if storage.is_read_only() {
+        events.note(Level::Warn, format!(
+            "'{}' is on a read-only file system; opened read-only",
+            storage.file_path()
+        ))?;
+    }
+    unseal_config(&mut config, &storage, &events)?;
+    ui::set_group_by_issuer(config.group_by_issuer);
+    ui::set_show_archived(config.show_archived);
+    ui::set_recent_first(config.recent_first);
+    ui::set_show_timestamps(config.show_timestamps);
+    ui::set_mask_codes(config.mask_codes);
+    ui::set_idle_blank(config.idle_blank_seconds);
+
+    if !guard_single_instance(&mut storage, &events)? {
+        return Ok(());
+    }
+
+    let report = integrity::check(storage.file_path())?;
+    events.emit(Event::IntegrityChecked { summary: report.summary() })?;
+    if !report.is_ok() {
+        display_integrity_warnings(&report, storage.file_path(), &events)?;
+    }
+
+    if first_run && storage.get_accounts().is_empty() && !storage.is_read_only() {
+        run_tutorial(&mut storage, &events, &config)?;
+    }
+
+    run_main_loop(&mut storage, &events, &mut config)?;
+
+    Ok(())
+}
+
+/// Checks for another interactive session on the same vault, so two menus
+/// don't race each other's saves. Returns whether to continue.
+fn guard_single_instance(storage: &mut Storage, events: &Events) -> Result<bool, AppError> {
+    let Some(pid) = integrity::running_instance(storage.file_path()) else {
+        return Ok(true);
+    };
+    events.note(Level::Warn, format!("Another Quackey session (PID {}) has the vault open", pid))?;
+
+    display_screen("Quackey Is Already Running");
+    println!(
+        "{}",
+        format!("⚠️  Another Quackey session (PID {}) has this vault open.", pid).yellow().bold()
+    );
+    println!(
+        "{}",
+        "Two sessions saving the same vault can undo each other's changes.".bright_black()
+    );
+    println!();
+
+    let choice = Select::with_theme(&dialoguer::theme::ColorfulTheme::default())
+        .with_prompt("What would you like to do?")
+        .default(0)
+        .items(&[
+            "⏳ Wait for the other session to close",
+            "🦆 Exit and switch to the other session",
+            "⚡ Open anyway (take over)",
+        ])
+        .interact_opt()
+        .chosen()?;
+
+    match choice {
+        0 => {
+            let spinner = create_spinner(format!(
+                "Waiting for Quackey (PID {}) to close... press Ctrl+C to give up",
+                pid
+            ));
+            ui::take_interrupt();
+            while integrity::running_instance(storage.file_path()).is_some() {
+                if ui::take_interrupt() {
+                    spinner.finish_and_clear();
+                    return Ok(false);
+                }
+                std::thread::sleep(Duration::from_secs(1));
+            }
+            spinner.finish_and_clear();
+
+            // The other session may have saved changes on its way out
+            reload_if_changed(storage)?;
+            events.note(Level::Info, format!("Other Quackey session (PID {}) closed", pid))?;
+            Ok(true)
+        }
+        2 => {
+            events.note(Level::Warn, format!("Opened the vault while Quackey (PID {}) is still running", pid))?;
+            Ok(true)
+        }
+        _ => Ok(false),
+    }
+}
+
+/// Explains problems found by the startup integrity check
+fn display_integrity_warnings(
+    report: &integrity::IntegrityReport,
+    file_path: &str,
+    events: &Events,
+) -> Result<(), AppError> {
+    display_screen("Integrity Check");
+
+    if !report.clean_shutdown {
+        println!("{}", "⚠️  Quackey didn't shut down cleanly last time.".yellow().bold());
+        println!(
+            "{}",
+            "It may have crashed or been closed from outside. Your accounts were saved after every change.".bright_black()
+        );
+        println!();
+    }
+
+    if report.checksum == integrity::ChecksumStatus::Mismatch {
+        println!("{}", "⚠️  The storage file changed outside Quackey.".yellow().bold());
+        println!(
+            "{}",
+            "This is expected if it is synced between machines or you edited it by hand. Otherwise, check the accounts and restore a snapshot from 'backups' if anything looks wrong.".bright_black()
+        );
+        println!();
+    }
+
+    if let Some(problem) = &report.permission_problem {
+        println!("{}", format!("⚠️  Permissions: {}", problem).yellow().bold());
+        println!(
+            "{}",
+            "Anyone with an account on this machine can copy your secrets from it.".bright_black()
+        );
+
+        let fix = Confirm::with_theme(&dialoguer::theme::ColorfulTheme::default())
+            .with_prompt("Make it readable by you only?")
+            .default(true)
+            .interact_opt()
+            .chosen()?;
+        if fix {
+            match permissions::restrict(std::path::Path::new(file_path)) {
+                Ok(()) => {
+                    events.note(Level::Audit, format!("Restricted '{}' to its owner", file_path))?;
+                    println!("{}", "✅ Only your user can read the storage file now.".green());
+                }
+                Err(e) => {
+                    println!("{}", format!("⛔ Could not change the permissions: {}", e).red());
+                    println!("{}", "Run 'quackey doctor' for how to fix it.".bright_black());
+                }
+            }
+        }
+        println!();
+    }
+
+    wait_for_input()
+}
+
+/// Walks the user through recovering a storage file that no longer parses.
+/// Returns `None` if they chose to leave it untouched and exit.
+fn repair_storage(path: &str, error: &str, events: &Events) -> Result<Option<Storage>, AppError> {
+    display_screen("Repair Storage");
+
+    println!("{}", "⚠️  Your accounts file could not be read:".yellow().bold());
+    println!("{}", error.bright_black());
+    println!();
+
+    let salvage = std::fs::read_to_string(path)
+        .map(|contents| repair::salvage_accounts(&contents))
+        .unwrap_or(repair::Salvage { accounts: Vec::new(), lost: 0 });
+    let backups = repair::find_backups(path);
+
+    if salvage.accounts.is_empty() {
+        println!("{}", "🦉 No accounts could be recovered from the damaged file.".bright_red());
+    } else {
+        println!(
+            "{}",
+            format!("Recovered {} account(s) from the damaged file:", salvage.accounts.len()).green()
+        );
+        display_accounts_table(&salvage.accounts);
+    }
+    if salvage.lost > 0 {
+        println!(
+            "{}",
+            format!("{} entry(ies) were too damaged to recover.", salvage.lost).bright_black()
+        );
+    }
+    println!();
+
+    let mut options = Vec::new();
+    if !salvage.accounts.is_empty() {
+        options.push(format!("🩹 Restore the {} recovered account(s)", salvage.accounts.len()));
+    }
+    for backup in &backups {
+        let modified: chrono::DateTime<chrono::Local> = backup.modified.into();
+        options.push(format!(
+            "🗄️  Restore {} ({}, {} account(s))",
+            backup.path.file_name().unwrap_or_default().to_string_lossy(),
+            modified.format("%Y-%m-%d %H:%M"),
+            backup.accounts.len()
+        ));
+    }
+    options.push("🆕 Start with an empty vault".to_string());
+    options.push("🦆 Exit and leave the file as it is".to_string());
+
+    let selection = Select::with_theme(&dialoguer::theme::ColorfulTheme::default())
+        .with_prompt("How would you like to continue?")
+        .default(0)
+        .items(&options)
+        .interact_opt()
+        .chosen()?;
+
+    let salvage_offset = usize::from(!salvage.accounts.is_empty());
+    let discards_recovered = selection >= salvage_offset && selection < options.len() - 1;
+    let cancelled = selection == options.len() - 1
+        || (discards_recovered
+            && salvage.accounts.len() > 1
+            && !confirm_by_typing(
+                &salvage.accounts.len().to_string(),
+                &format!(
+                    "This replaces the {} recovered accounts. Type {} to continue",
+                    salvage.accounts.len(),
+                    salvage.accounts.len()
+                ),
+            )?);
+
+    let (accounts, source) = if cancelled {
+        events.note(Level::Warn, "Storage repair cancelled; damaged file left untouched")?;
+        return Ok(None);
+    } else if selection == options.len() - 2 {
+        (Vec::new(), "an empty vault".to_string())
+    } else if selection < salvage_offset {
+        (salvage.accounts, "the recovered accounts".to_string())
+    } else {
+        let backup = &backups[selection - salvage_offset];
+        (backup.accounts.clone(), backup.path.display().to_string())
+    };
+
+    let preserved = repair::preserve_damaged_file(path)?;
+    let mut storage = Storage::new_with_events(path, events.clone())?;
+    let count = accounts.len();
+    if count > 0 {
+        storage.add_accounts(accounts)?;
+    }
+
+    events.note(Level::Info, format!(
+        "Repaired storage from {} ({} account(s)); damaged file kept at {}",
+        source,
+        count,
+        preserved.display()
+    ))?;
+
+    println!();
+    println!("{}", format!("✅ Restored {} account(s) from {}.", count, source).green().bold());
+    println!(
+        "{}",
+        format!("The damaged file was kept at {}", preserved.display()).bright_black()
+    );
+    wait_for_input()?;
+
+    Ok(Some(storage))
+}
+
+/// Runs the onboarding process if configuration doesn't exist
+fn run_onboarding() -> Result<(Config, Option<NewVaultLock>), AppError> {
+    let mut config = Config::load()?;
+
+    if !std::path::Path::new("config.json").exists() {
+        display_screen("Welcome to Quackey - Initial Setup");
+
+        println!("{}", "Default Configuration:".bright_black());
+        println!("{}", "  - Accounts will be saved in the same directory as the application".bright_black());
+        println!("{}", "  - You can change these settings later from the menu".bright_black());
+        println!();
+        
+        let use_defaults = Confirm::with_theme(&dialoguer::theme::ColorfulTheme::default())
+            .with_prompt("Would you like to use the default configuration?")
+            .default(true)
+            .interact_opt()
+            .chosen()?;
+
+        if use_defaults {
+            println!();
+            println!("{}", "Using default configuration.".bright_black());
+            println!(
+                "{}",
+                "You can change these settings later from the menu.".bright_black()
+            );
+
+            let lock = choose_vault_lock(&mut config)?;
+            println!();
+
+            config.save()?;
+
+            println!("{}", "✅ Configuration saved successfully!".green().bold());
+            println!("{}", "Your Quackey TOTP generator is ready to use, quack quack!".bright_black());
+
+            wait_for_input()?;
+
+            return Ok((config, lock));
+        }
+
+        let path = get_file_path("accounts storage file", ".")?;
+
+        let mut new_config = Config::default();
+        new_config.storage.path = path;
+
+        new_config.validate_paths()?;
+        new_config.ensure_directories()?;
+        let lock = choose_vault_lock(&mut new_config)?;
+        new_config.save()?;
+
+        println!();
+        println!("{}", "✅ Configuration saved successfully!".green().bold());
+        println!("{}", "Your Quackey TOTP generator is ready to use, quack quack!".bright_black());
+
+        wait_for_input()?;
+
+        Ok((new_config, lock))
+    } else {
+        Ok((config, None))
+    }
+}
+
+/// Protection chosen during setup for a vault that doesn't exist yet, applied
+/// once it is opened
+enum NewVaultLock {
+    Password(VaultKey),
+    Windows,
+}
+
+/// Setup step choosing how a new vault is protected, encrypted with a master
+/// password by default. An existing vault keeps its protection, which is
+/// recorded in `config`.
+fn choose_vault_lock(config: &mut Config) -> Result<Option<NewVaultLock>, AppError> {
+    let path = config.get_storage_file_path();
+    if std::path::Path::new(&path).exists() {
+        config.encryption = encryption_mode(&path).to_string();
+        return Ok(None);
+    }
+
+    println!();
+    println!("{}", "How should your accounts be protected?".bold());
+    println!(
+        "{}",
+        "Anyone who can read an unencrypted vault can generate your codes.".bright_black()
+    );
+    println!();
+
+    let mut modes = vec![("password", "🔐 Encrypt with a master password (recommended)")];
+    if dpapi::is_available() {
+        modes.push(("windows", "🪟 Protect with my Windows account (no password)"));
+    }
+    modes.push(("none", "Keep the vault unencrypted"));
+    let labels: Vec<&str> = modes.iter().map(|(_, label)| *label).collect();
+    let choice = Select::with_theme(&dialoguer::theme::ColorfulTheme::default())
+        .with_prompt("Vault encryption")
+        .default(0)
+        .items(&labels)
+        .interact_opt()
+        .chosen()?;
+
+    let lock = match modes[choice].0 {
+        "password" => {
+            println!(
+                "{}",
+                "Without the master password your accounts cannot be recovered. Keep it somewhere safe.".yellow()
+            );
+            match ask_new_master_password()? {
+                Some(passphrase) => Some(NewVaultLock::Password(VaultKey::derive(&passphrase))),
+                None => {
+                    println!("{}", "No password entered; the vault stays unencrypted.".bright_black());
+                    None
+                }
+            }
+        }
+        "windows" => Some(NewVaultLock::Windows),
+        _ => None,
+    };
+    if lock.is_none() {
+        println!("{}", "You can encrypt the vault later in the settings.".bright_black());
+    }
+
+    config.encryption = match lock {
+        Some(NewVaultLock::Password(_)) => "password",
+        Some(NewVaultLock::Windows) => "windows",
+        None => "none",
+    }
+    .to_string();
+    Ok(lock)
+}
+
+/// Encrypts the vault created during setup as chosen in [`choose_vault_lock`]
+fn apply_vault_lock(storage: &mut Storage, events: &Events, lock: NewVaultLock) -> Result<(), AppError> {
+    match lock {
+        NewVaultLock::Password(key) => {
+            storage.set_key(Some(key))?;
+            events.emit(Event::LockChanged(LockChange::PasswordSet))?;
+        }
+        NewVaultLock::Windows => {
+            storage.set_key(Some(dpapi::set_up(storage.file_path())?))?;
+            events.emit(Event::LockChanged(LockChange::DpapiSet))?;
+        }
+    }
+    Ok(())
+}
+
+/// How the vault at `path` is protected, as one of
+/// [`ENCRYPTION_MODES`](quackey_core::config::ENCRYPTION_MODES)
+fn encryption_mode(path: &str) -> &'static str {
+    if !storage::is_encrypted(path) {
+        "none"
+    } else if dpapi::is_set_up(path) {
+        "windows"
+    } else {
+        "password"
+    }
+}
+
+/// Asks for a new master password twice and shows how strong it is. A weak or
+/// short one is only accepted after confirming; `None` when nothing was entered.
+fn ask_new_master_password() -> Result<Option<String>, AppError> {
+    loop {
+        let passphrase = Password::with_theme(&dialoguer::theme::ColorfulTheme::default())
+            .with_prompt("New master password")
+            .with_confirmation("Confirm master password", "⛔ Passwords don't match.")
+            .allow_empty_password(true)
+            .interact()
+            .answered()?;
+        if passphrase.is_empty() {
+            return Ok(None);
+        }
+
+        let estimate = strength::estimate(&passphrase);
+        ui::display_strength(&estimate);
+        if !estimate.needs_confirmation() {
+            return Ok(Some(passphrase));
+        }
+
+        let use_anyway = Confirm::with_theme(&dialoguer::theme::ColorfulTheme::default())
+            .with_prompt(if estimate.strength.is_weak() {
+                "This password is easy to guess. Use it anyway?"
+            } else {
+                "This password is short. Use it anyway?"
+            })
+            .default(false)
+            .interact_opt()
+            .chosen()?;
+        if use_anyway {
+            return Ok(Some(passphrase));
+        }
+    }
+}
+
+/// Name of the account created by the first-run tour
+const TUTORIAL_ACCOUNT: &str = "Demo account (safe to delete)";
+
+/// Optional guided tour after the first setup: adds a demo account with a
+/// throwaway secret, shows a code from it and offers to delete it again
+fn run_tutorial(storage: &mut Storage, events: &Events, config: &Config) -> Result<(), AppError> {
+    display_screen("Quick Tour");
+
+    println!("{}", "New to Quackey? A one-minute tour shows how codes work, using a demo account.".bright_black());
+    println!("{}", "The demo account isn't connected to any service, so nothing can go wrong.".bright_black());
+    println!();
+
+    let take_tour = Confirm::with_theme(&dialoguer::theme::ColorfulTheme::default())
+        .with_prompt("Take the tour?")
+        .default(true)
+        .interact_opt()
+        .chosen()?;
+    if !take_tour {
+        return Ok(());
+    }
+
+    // Step 1: the account
+    display_screen("Quick Tour (1/3): Accounts");
+    println!("When you turn on two-factor authentication, a website shows a QR code or a secret key.");
+    println!("Quackey stores that secret as an account. Here is a demo account with a made-up secret:");
+    println!();
+
+    let account = Account::new(
+        TUTORIAL_ACCOUNT.to_string(),
+        generate_secret(20),
+        6,
+        30,
+        Algorithm::SHA1,
+        Some("Quackey Tour".to_string()),
+    )
+    .with_tags(vec!["demo".to_string()]);
+    let id = account.id().to_string();
+    storage.add_account(account)?;
+    events.note(Level::Info, "Added the tour's demo account")?;
+
+    println!("  {} {}", "Name:".blue(), TUTORIAL_ACCOUNT);
+    println!("  {} Quackey Tour", "Issuer:".blue());
+    println!();
+    println!(
+        "{}",
+        "For real accounts, choose \"Manage Accounts\" and \"Add Account\", or import a QR code.".bright_black()
+    );
+    wait_for_input()?;
+
+    // Step 2: generating (and copying) a code
+    display_screen("Quick Tour (2/3): Codes");
+    println!("The secret turns into a new 6-digit code every 30 seconds. This is what you type into a login page:");
+    println!();
+    let shown = match storage.find_by_id(&id) {
+        Some(account) if confirm_high_value(storage, events, config, account)? => {
+            let code = account.generate_totp()?;
+            let copy_text = clipboard_text(&config.copy_format, account, &code);
+            display_totp_results(
+                account,
+                &code,
+                &copy_text,
+                account.time_remaining(),
+                clipboard_enabled(config),
+                false,
+                &config.code_grouping,
+            )?;
+            true
+        }
+        _ => false,
+    };
+    if shown {
+        storage.record_generation(&id);
+    }
+    println!();
+    println!(
+        "{}",
+        "Later, choose \"Generate TOTP\" from the main menu, or run 'quackey gen <account>' in a script.".bright_black()
+    );
+    wait_for_input()?;
+
+    // Step 3: cleaning up
+    display_screen("Quick Tour (3/3): Done");
+    println!("That's all there is to it, quack!");
+    println!();
+    let delete = Confirm::with_theme(&dialoguer::theme::ColorfulTheme::default())
+        .with_prompt("Delete the demo account now?")
+        .default(true)
+        .interact_opt()
+        .chosen()?;
+    if delete {
+        if let Some(index) = storage.get_accounts().iter().position(|a| a.id() == id) {
+            storage.delete_accounts(&[index])?;
+            events.note(Level::Info, "Deleted the tour's demo account")?;
+        }
+        println!("{}", "✅ Demo account deleted.".green());
+    } else {
+        println!(
+            "{}",
+            format!("The demo account stays as \"{}\"; delete it any time from Manage Accounts.", TUTORIAL_ACCOUNT)
+                .bright_black()
+        );
+    }
+
+    wait_for_input()
+}
+
+/// Gets a file path from user input with validation
+fn get_file_path(prompt: &str, default: &str) -> Result<String, AppError> {
+    println!();
+    println!("{}", "Path format options:".bright_black());
+    println!("{}", "  - Relative path (e.g., 'totp')".bright_black());
+    println!(
+        "{}",
+        "  - Absolute path (e.g., '/home/user/quackey/totp' or 'D:/Quackey/totp')".bright_black()
+    );
+    println!();
+    println!("{}", "Notes:".bright_black());
+    println!(
+        "{}",
+        "  - Use forward slashes (/) even on Windows for consistency".bright_black()
+    );
+    println!(
+        "{}",
+        "  - Non-existent directories will be created automatically".bright_black()
+    );
+    println!(
+        "{}",
+        "  - You must have write permissions for the specified location".bright_black()
+    );
+    println!();
+
+    let path: String = Input::with_theme(&dialoguer::theme::ColorfulTheme::default())
+        .with_prompt(format!(
+            "Directory path for {} (press Enter for default)",
+            prompt
+        ))
+        .default(default.to_string())
+        .interact_text()
+        .answered()?;
+
+    if path.trim().is_empty() {
+        return Err(AppError::InvalidInput(format!(
+            "{} path cannot be empty",
+            prompt
+        )));
+    }
+
+    Ok(path.trim().to_string())
+}
+
+/// Runs the main application loop
+fn run_main_loop(storage: &mut Storage, events: &Events, config: &mut Config) -> Result<(), AppError> {
+    loop {
+        reload_if_changed(storage)?;
+
+        clear_screen();
+        display_welcome_screen();
+        if storage.is_read_only() {
+            println!(
+                "{}",
+                "🔒 Read-only: the vault is on a read-only file system. Codes work, changes can't be saved."
+                    .yellow()
+                    .bold()
+            );
+            println!();
+        }
+        if ui::is_private() {
+            println!(
+                "{}",
+                "🕶️  Privacy mode: names, issuers and codes are hidden. Turn it off below when you're done."
+                    .yellow()
+                    .bold()
+            );
+            println!();
+        }
+
+        let selection = display_menu_and_get_selection()?;
+
+        clear_screen();
+
+        match handle_menu_selection(selection, storage, events, config) {
+            Ok(true) => break,
+            Ok(false) => {}
+            // Expected while read-only, so report it and keep going
+            Err(e) if storage.is_read_only() && matches!(e.kind(), AppError::PermissionError(_)) => {
+                show_read_only_error(&e)?
+            }
+            Err(e) => match e.into_kind() {
+                // Backed out of a prompt; back to this menu
+                AppError::Cancelled => {}
+                AppError::Conflict(msg) => resolve_save_conflict(storage, &msg)?,
+                e => return Err(e),
+            },
+        }
+    }
+    Ok(())
+}
+
+/// Shows why a change to a read-only vault was not saved
+fn show_read_only_error(error: &AppError) -> Result<(), AppError> {
+    println!("{}", format!("⛔ {}", error.message()).red().bold());
+    if let Some(hint) = error.hint() {
+        println!("{}", hint.bright_black());
+    }
+    wait_for_input()
+}
+
+/// Picks up edits another program (such as a sync client) made to the storage
+/// file. Every change is saved right away, so there is nothing of ours to lose.
+fn reload_if_changed(storage: &mut Storage) -> Result<(), AppError> {
+    if storage.changed_on_disk() && !storage.is_dirty() {
+        storage.reload()?;
+    }
+    Ok(())
+}
+
+/// Asks which version to keep for each account changed on both sides of a merge
+fn choose_conflict_sides(conflicts: &[merge::Conflict]) -> Result<Vec<merge::Side>, AppError> {
+    if !conflicts.is_empty() {
+        println!();
+        println!(
+            "{}",
+            format!("{} account(s) were changed on both sides.", conflicts.len()).bold()
+        );
+    }
+
+    let label = |account: Option<&Account>| match account {
+        Some(account) => ui::shown_label(account),
+        None => "(deleted)".to_string(),
+    };
+
+    conflicts
+        .iter()
+        .map(|conflict| {
+            let choice = Select::with_theme(&dialoguer::theme::ColorfulTheme::default())
+                .with_prompt("Which version should be kept?")
+                .default(0)
+                .items(&[
+                    format!("Mine: {}", label(conflict.ours.as_ref())),
+                    format!("Theirs: {}", label(conflict.theirs.as_ref())),
+                ])
+                .interact_opt()
+                .chosen()?;
+            Ok(if choice == 0 { merge::Side::Ours } else { merge::Side::Theirs })
+        })
+        .collect()
+}
+
+/// Lists account changes diff-style: + added, - removed, ~ changed
+fn print_changes(title: &str, changes: &[merge::Change]) {
+    println!("{}", title.bold());
+    if changes.is_empty() {
+        println!("  {}", "(no account changes)".bright_black());
+    }
+    for change in changes {
+        match change {
+            merge::Change::Added(account) => println!("{}", format!("  + {}", ui::shown_label(account)).green()),
+            merge::Change::Removed(account) => println!("{}", format!("  - {}", ui::shown_label(account)).red()),
+            merge::Change::Changed { before, after } => {
+                let (before, after) = (ui::shown_label(before), ui::shown_label(after));
+                if before == after {
+                    println!("{}", format!("  ~ {}", after).yellow());
+                } else {
+                    println!("{}", format!("  ~ {} → {}", before, after).yellow());
+                }
+            }
+        }
+    }
+    println!();
+}
+
+/// Asks what to do when a save found the storage file changed by another program
+fn resolve_save_conflict(storage: &mut Storage, error: &str) -> Result<(), AppError> {
+    display_screen("Storage Changed on Disk");
+
+    println!("{}", format!("⚠️  {}", error).yellow().bold());
+    println!(
+        "{}",
+        "Another program, such as a sync client, edited your accounts while Quackey was open.".bright_black()
+    );
+    println!();
+
+    match storage.disk_changes() {
+        Ok(changes) => print_changes("Changed by the other program:", &changes),
+        Err(e) => println!("{}", format!("The file on disk can't be read: {}", e).bright_black()),
+    }
+    print_changes("Your unsaved changes:", &storage.pending_changes());
+
+    let choice = Select::with_theme(&dialoguer::theme::ColorfulTheme::default())
+        .with_prompt("What would you like to do?")
+        .default(0)
+        .items(&[
+            "🔀 Merge both versions",
+            "🔄 Reload the file (discard my last change)",
+            "💾 Keep my version (discard the other program's changes)",
+        ])
+        .interact_opt()
+        .chosen()?;
+
+    match choice {
+        0 => {
+            let merged = storage.merge_with_disk()?;
+            let choices = choose_conflict_sides(&merged.conflicts)?;
+            storage.apply_merge(merged.resolve(&choices))?;
+            println!("{}", "✅ Merged both versions.".green().bold());
+        }
+        2 => {
+            storage.snapshot("overwrite")?;
+            storage.overwrite()?;
+            println!("{}", "✅ Saved your version.".green().bold());
+        }
+        _ => {
+            storage.reload()?;
+            println!("{}", "✅ Reloaded the file from disk.".green().bold());
+        }
+    }
+
+    wait_for_input()
+}
+
+/// Displays menu and gets user selection
+fn display_menu_and_get_selection() -> Result<usize, AppError> {
+    let privacy = format!("🕶️ Privacy mode: {}", if ui::is_private() { "on" } else { "off" });
+    let selections = &[
+        "🔢 Generate TOTP",
+        "📂 Manage Accounts",
+        "⚙️ Configure Settings",
+        "☁️ Sync with WebDAV",
+        privacy.as_str(),
+        "🦆 Exit",
+    ];
+
+    // Ctrl-C here leaves the app the way Exit does
+    match Select::with_theme(&dialoguer::theme::ColorfulTheme::default())
+        .with_prompt("Select an option")
+        .default(0)
+        .items(selections)
+        .interact()
+        .answered()
+    {
+        Err(AppError::Cancelled) => Ok(5),
+        selection => selection,
+    }
+}
+
+/// Displays the account management submenu and gets user selection
+fn display_account_management_menu() -> Result<usize, AppError> {
+    let selections = &[
+        "👀 View saved accounts",
+        "📄 Add new account",
+        "📷 Add account from clipboard QR",
+        "🧩 Add account from template",
+        "🎲 Create new secret",
+        "📝 Edit account",
+        "🗑️ Delete account",
+        "📦 Archive or restore accounts",
+        "🔐 Recovery codes",
+        "📤 Share account",
+        "📥 Import shared account",
+        "📲 Export to authenticator app",
+        "💾 Save account as template",
+        "🩺 Vault health",
+        "📊 Vault overview",
+        "✅ Verify account setup",
+        "🕒 Code history",
+        "🔎 View account details",
+        "👈 Back to main menu",
+    ];
+
+    // Esc or Ctrl-C goes back to the main menu
+    match Select::with_theme(&dialoguer::theme::ColorfulTheme::default())
+        .with_prompt("Select an account management option")
+        .default(0)
+        .items(selections)
+        .interact_opt()
+        .chosen()
+    {
+        Err(AppError::Cancelled) => Ok(18),
+        selection => selection,
+    }
+}
+
+/// Handles the menu selection and returns whether the application should exit
+fn handle_menu_selection(
+    selection: usize,
+    storage: &mut Storage,
+    events: &Events,
+    config: &mut Config,
+) -> Result<bool, AppError> {
+    match selection {
+        0 => generate_totp(storage, events, config)?,
+        1 => {
+            loop {
+                reload_if_changed(storage)?;
+
+                clear_screen();
+                display_screen("Account Management");
+
+                let submenu_selection = display_account_management_menu()?;
+
+                clear_screen();
+
+                if submenu_selection == 18 {
+                    break;
+                }
+
+                // Backing out of a prompt returns to this menu
+                match handle_account_management_selection(submenu_selection, storage, events, config) {
+                    Err(e) if matches!(e.kind(), AppError::Cancelled) => {}
+                    result => result?,
+                }
+            }
+        }
+        2 => configure_settings(storage, events, config)?,
+        3 => {
+            display_screen("Sync with WebDAV");
+            if let Err(e) = sync_vault(storage, events, config, false) {
+                println!("{}", format!("⛔ Sync failed: {}", e).red().bold());
+                events.emit(Event::SyncFailed { error: e.to_string() })?;
+            }
+            wait_for_input()?;
+        }
+        4 => {
+            ui::set_privacy(!ui::is_private());
+            events.emit(Event::PrivacyModeChanged { on: ui::is_private() })?;
+        }
+        5 => {
+            integrity::end_session(storage.file_path())?;
+            events.emit(Event::Exiting)?;
+            display_exit_screen();
+
+            println!("\n{}", "Press Enter to exit...".bright_black());
+            let mut buffer = String::new();
+            io::stdin().read_line(&mut buffer)?;
+
+            return Ok(true);
+        }
+        _ => unreachable!(),
+    }
+    Ok(false)
+}
+
+/// Handles the account management menu selection
+/// Account management options that only change the vault, unavailable while
+/// it is read-only
+const READ_ONLY_REFUSED: &[usize] = &[1, 2, 3, 4, 5, 6, 7, 10];
+
+fn handle_account_management_selection(
+    selection: usize,
+    storage: &mut Storage,
+    events: &Events,
+    config: &mut Config,
+) -> Result<(), AppError> {
+    // Refuse changes up front rather than after all their prompts
+    if storage.is_read_only() && READ_ONLY_REFUSED.contains(&selection) {
+        display_screen("Read-Only Vault");
+        return show_read_only_error(&storage::read_only_error(storage.file_path()));
+    }
+
+    match selection {
+        0 => view_accounts(storage, events)?,
+        1 => add_account(storage, events, config)?,
+        2 => add_account_from_clipboard_qr(storage, events, config)?,
+        3 => add_account_from_template(storage, events, config)?,
+        4 => create_new_secret(storage, events, config)?,
+        5 => edit_account(storage, events)?,
+        6 => delete_account(storage, events)?,
+        7 => archive_accounts(storage, events)?,
+        8 => manage_recovery_codes(storage, events)?,
+        9 => share_account(storage, events)?,
+        10 => import_shared_account(storage, events, config)?,
+        11 => export_to_authenticator(storage, events)?,
+        12 => save_account_as_template(storage, events, config)?,
+        13 => vault_health(storage, events)?,
+        14 => vault_overview(storage, events, config)?,
+        15 => verify_account(storage, events, config)?,
+        16 => code_history(storage, events)?,
+        17 => account_details(storage, events, config)?,
+        18 => (), // Back to main menu
+        _ => unreachable!(),
+    }
+    Ok(())
+}
+
+/// Answers given so far while adding an account
+#[derive(Default)]
+struct NewAccount {
+    name: String,
+    issuer: String,
+    encoding: SecretEncoding,
+    /// The secret key as typed, shown again when going back to it
+    secret_input: String,
+    secret: String,
+    parameters: Option<(usize, u64, Algorithm)>,
+    tags: Vec<String>,
+    aliases: Vec<String>,
+    /// The account as reviewed, once saving it is confirmed
+    reviewed: Option<Account>,
+}
+
+impl NewAccount {
+    fn account(&self) -> Account {
+        let (digits, period, algorithm) = self.parameters.unwrap_or((6, 30, Algorithm::SHA1));
+        Account::new(
+            self.name.clone(),
+            self.secret.clone(),
+            digits,
+            period,
+            algorithm,
+            Some(self.issuer.clone()).filter(|issuer| !issuer.is_empty()),
+        )
+        .with_tags(self.tags.clone())
+        .with_aliases(self.aliases.clone())
+    }
+}
+
+/// Choices on the review screen of a new account, with the step each one
+/// goes back to
+const REVIEW_CHOICES: [(&str, Option<&str>); 8] = [
+    ("💾 Save the account", None),
+    ("✏️ Change the name", Some("Account name")),
+    ("✏️ Change the issuer", Some("Issuer")),
+    ("✏️ Change the secret key", Some("Secret key")),
+    ("✏️ Change the code settings", Some("Code settings")),
+    ("✏️ Change the tags", Some("Tags")),
+    ("✏️ Change the aliases", Some("Aliases")),
+    ("✖️ Cancel without saving", None),
+];
+
+/// Adds a new TOTP account, one step at a time
+fn add_account(storage: &mut Storage, events: &Events, config: &Config) -> Result<(), AppError> {
+    let completion = IssuerCompletion::new(storage.get_accounts());
+    let mut answers = NewAccount::default();
+
+    let done = Wizard::new("Add New Account")
+        .step("Account name", |answers: &mut NewAccount| loop {
+            let name = answer!(wizard::text(
+                "Account name (e.g., 'me@example.com', 'my-github-username')",
+                &answers.name
+            ));
+            if name.trim().is_empty() {
+                println!("{}", "⛔ Account name cannot be empty.".red());
+                println!();
+                continue;
+            }
+            answers.name = name.trim().to_string();
+            return Ok(Step::Next(()));
+        })
+        .step("Issuer", |answers| {
+            let issuer = answer!(wizard::text_with_completion(
+                "Issuer (optional, e.g., 'Google', 'GitHub'; Tab to complete)",
+                &answers.issuer,
+                &completion
+            ));
+            answers.issuer = issuer.trim().to_string();
+            Ok(Step::Next(()))
+        })
+        .step("Secret key", |answers| {
+            answers.secret = answer!(get_validated_secret(&mut answers.encoding, &mut answers.secret_input));
+            Ok(Step::Next(()))
+        })
+        .step("Code settings", |answers| {
+            let preset = find_preset(&answers.issuer);
+            answers.parameters = Some(answer!(get_totp_parameters(preset, answers.parameters)));
+            Ok(Step::Next(()))
+        })
+        .step("Tags", |answers| {
+            let tags = answer!(wizard::text(
+                "Tags (optional, comma-separated, e.g., 'work, cloud')",
+                &answers.tags.join(", ")
+            ));
+            answers.tags = split_list(&tags);
+            Ok(Step::Next(()))
+        })
+        .step("Aliases", |answers| loop {
+            let input = answer!(wizard::text(
+                "Aliases (optional, comma-separated short names, e.g., 'gh')",
+                &answers.aliases.join(", ")
+            ));
+            let aliases = split_list(&input);
+            match storage.check_aliases(&aliases, None) {
+                Ok(()) => {
+                    answers.aliases = aliases;
+                    return Ok(Step::Next(()));
+                }
+                Err(e) => println!("{}", format!("⛔ {}", e).red()),
+            }
+        })
+        .step("Review", |answers| {
+            let account = answers.account();
+            print_new_account(&account, config);
+            println!();
+
+            let labels: Vec<&str> = REVIEW_CHOICES.iter().map(|(label, _)| *label).collect();
+            let selection = answer!(wizard::select("Save this account?", &labels, 0));
+            match REVIEW_CHOICES[selection].1 {
+                Some(step) => Ok(Step::Jump(step)),
+                None if selection == 0 => {
+                    answers.reviewed = Some(account);
+                    Ok(Step::Next(()))
+                }
+                None => Ok(Step::Cancel),
+            }
+        })
+        .run(&mut answers)?;
+
+    match answers.reviewed {
+        Some(account) if done => save_new_account(storage, events, config, account),
+        _ => Ok(()),
+    }
+}
+
+/// Shows what a new account will be saved with, the secret mostly hidden
+fn print_new_account(account: &Account, config: &Config) {
+    println!("{} {}", "Name:".blue(), ui::shown_name(account));
+    match account.issuer() {
+        Some(issuer) => println!("{} {}", "Issuer:".blue(), ui::shown_issuer(issuer)),
+        None => println!("{} None", "Issuer:".blue()),
+    }
+    println!("{} {}", "Secret key:".blue(), ui::mask_secret(account.secret()));
+    println!("{} {}", "Digits:".blue(), account.digits());
+    println!("{} {} seconds", "Period:".blue(), account.period());
+    println!("{} {}", "Algorithm:".blue(), account.algorithm_name());
+    if account.tags().is_empty() {
+        println!("{} None", "Tags:".blue());
+    } else {
+        println!("{} {}", "Tags:".blue(), account.tags().join(", "));
+    }
+    if account.aliases().is_empty() {
+        println!("{} None", "Aliases:".blue());
+    } else if !ui::is_private() {
+        println!("{} {}", "Aliases:".blue(), account.aliases().join(", "));
+    }
+    if config.is_high_value(account.tags()) {
+        println!("{} {}", "High-value:".blue(), "Yes, codes will need the master password".yellow());
+    }
+}
+
+/// Saves a newly created account and reports the outcome
+fn save_new_account(
+    storage: &mut Storage,
+    events: &Events,
+    config: &Config,
+    account: Account,
+) -> Result<(), AppError> {
+    let accounts = storage.get_accounts();
+    let duplicates = health::accounts_with_secret(accounts, &account);
+
+    if !duplicates.is_empty() {
+        println!();
+        println!(
+            "{}",
+            "⚠️  This secret is already used by:".yellow().bold()
+        );
+        for duplicate in &duplicates {
+            println!("  - {}", ui::shown_label(duplicate));
+        }
+        println!(
+            "{}",
+            "This usually means the account was imported twice or the wrong key was pasted.".bright_black()
+        );
+
+        let proceed = Confirm::with_theme(&dialoguer::theme::ColorfulTheme::default())
+            .with_prompt("Add it anyway?")
+            .default(false)
+            .interact_opt()
+            .chosen()?;
+
+        if !proceed {
+            events.note(Level::Warn, format!("Skipped adding account with duplicate secret: {}", account.name()))?;
+            println!();
+            println!("{}", "Account not added.".bright_black());
+            return wait_for_input();
+        }
+
+        events.note(Level::Warn, format!("Adding account with duplicate secret: {}", account.name()))?;
+    }
+
+    println!();
+
+    // Unverified until the provider is confirmed to accept its codes
+    let mut account = account;
+    account.set_verified(false);
+
+    match storage.add_account(account) {
+        Ok(_) => {
+            show_spinner_delay("Saving account...", config.spinner_delay_ms);
+
+            println!("{}", "👌 Account added successfully, quack!".green().bold());
+
+            let added = storage.get_accounts().last().map(|a| a.id().to_string());
+            if let Some(id) = added {
+                println!();
+                let verify = Confirm::with_theme(&dialoguer::theme::ColorfulTheme::default())
+                    .with_prompt("Verify the setup with the provider now?")
+                    .default(true)
+                    .interact_opt()
+                    .chosen()?;
+
+                if verify {
+                    verify_setup(storage, &id)?;
+                } else {
+                    println!(
+                        "{}",
+                        "The account stays marked as unverified until you verify it.".bright_black()
+                    );
+                }
+            }
+        }
+        Err(e) => {
+            println!("{}", format!("⛔ Error saving account: {}", e).red().bold());
+            println!();
+            let hint = e
+                .hint()
+                .unwrap_or_else(|| "Please try again or check your storage file permissions.".to_string());
+            println!("{}", hint.bright_black());
+        }
+    }
+
+    wait_for_input()
+}
+
+/// Shows the account's current code and asks whether the provider accepted it,
+/// recording the answer on the account
+fn verify_setup(storage: &mut Storage, id: &str) -> Result<(), AppError> {
+    let Some(account) = storage.find_by_id(id) else {
+        return Err(AppError::NotFound(format!("No account with id '{}'", id)));
+    };
+    let code = ui::format_totp(&account.generate_totp()?, "half");
+    let shown = if ui::is_private() { ui::mask_code(&code) } else { code };
+    println!();
+    println!("{}", "Enter this code on the provider's setup page:".bold());
+    println!("{} {}", "🔑 Code:".blue(), shown.bright_white().bold());
+    println!("{} {} seconds", "⌛ Expires in:".blue(), account.time_remaining());
+    println!();
+    storage.record_generation(id);
+
+    let accepted = Confirm::with_theme(&dialoguer::theme::ColorfulTheme::default())
+        .with_prompt("Did the provider accept the code?")
+        .default(true)
+        .interact_opt()
+        .chosen()?;
+
+    storage.set_verified(id, accepted)?;
+
+    if accepted {
+        println!("{}", "✅ Account verified.".green().bold());
+    } else {
+        println!("{}", "⚠️  The account stays marked as unverified.".yellow().bold());
+        println!(
+            "{}",
+            "Check the secret and settings (digits, period, algorithm) and your system clock.".bright_black()
+        );
+    }
+
+    Ok(())
+}
+
+/// Verifies an account: by showing its code for the provider, by checking a
+/// code from the provider or another device, or by setting the clock skew
+/// window accepted for that check
+fn verify_account(storage: &mut Storage, events: &Events, config: &Config) -> Result<(), AppError> {
+    display_screen("Verify Account Setup");
+
+    let accounts = storage.get_accounts();
+    if accounts.is_empty() {
+        let width = get_terminal_width();
+        println!("{}", center_text("🦉 No accounts saved yet.", width).bright_red());
+        return wait_for_input();
+    }
+
+    let account = select_account(accounts)?;
+    let id = account.id().to_string();
+    let window = account.verify_window();
+
+    let action = Select::with_theme(&dialoguer::theme::ColorfulTheme::default())
+        .with_prompt("How would you like to verify it?")
+        .default(0)
+        .items(&[
+            "🔑 Show the code to enter at the provider",
+            "⌨️  Check a code from the provider or another device",
+            "⚙️  Set this account's clock skew window",
+        ])
+        .interact_opt()
+        .chosen()?;
+
+    match action {
+        0 if !confirm_high_value(storage, events, config, account)? => {}
+        0 => verify_setup(storage, &id)?,
+        1 => check_code(storage, events, &id, window.unwrap_or(config.verify_window))?,
+        _ => {
+            let input: String = Input::with_theme(&dialoguer::theme::ColorfulTheme::default())
+                .with_prompt(format!(
+                    "Periods accepted before and after now (empty for the global setting, {})",
+                    config.verify_window
+                ))
+                .with_initial_text(window.map(|w| w.to_string()).unwrap_or_default())
+                .allow_empty(true)
+                .interact_text()
+                .answered()?;
+
+            let window = match input.trim() {
+                "" => None,
+                value => match value.parse() {
+                    Ok(window) if window <= account::MAX_VERIFY_WINDOW => Some(window),
+                    Ok(_) => {
+                        println!("{}", format!("⛔ The most is {} periods.", account::MAX_VERIFY_WINDOW).red());
+                        return wait_for_input();
+                    }
+                    Err(_) => {
+                        println!("{}", format!("⛔ '{}' is not a number.", value).red());
+                        return wait_for_input();
+                    }
+                },
+            };
+            storage.set_verify_window(&id, window)?;
+            println!("{}", "✅ Window updated.".green().bold());
+        }
+    }
+
+    print_skew_stats(storage.get_accounts());
+    wait_for_input()
+}
+
+/// Checks a code typed by the user, recording its clock skew and marking the
+/// account verified when it matches
+fn check_code(storage: &mut Storage, events: &Events, id: &str, window: u64) -> Result<(), AppError> {
+    let Some(account) = storage.find_by_id(id) else {
+        return Err(AppError::NotFound(format!("No account with id '{}'", id)));
+    };
+    let label = account_label(account);
+    let period = account.period() as i64;
+
+    let code: String = Input::with_theme(&dialoguer::theme::ColorfulTheme::default())
+        .with_prompt("Code")
+        .interact_text()
+        .answered()?;
+
+    match account.check_code(&code, window)? {
+        Some(offset) => {
+            storage.begin_batch();
+            storage.record_skew(id, offset)?;
+            storage.set_verified(id, true)?;
+            storage.flush()?;
+
+            let skew = match offset {
+                0 => "in sync with this computer".to_string(),
+                o if o > 0 => format!("{} period(s) ({}s) ahead of this computer", o, o * period),
+                o => format!("{} period(s) ({}s) behind this computer", -o, -o * period),
+            };
+            events.emit(Event::CodeChecked { name: label, skew: Some(skew.clone()) })?;
+            println!("{}", format!("✅ The code matches, {}.", skew).green().bold());
+        }
+        None => {
+            events.emit(Event::CodeChecked { name: label, skew: None })?;
+            println!(
+                "{}",
+                format!("⛔ The code doesn't match within ±{} period(s).", window).red().bold()
+            );
+            println!(
+                "{}",
+                "Check the secret and settings, or widen the window if your clock is far off.".bright_black()
+            );
+        }
+    }
+
+    Ok(())
+}
+
+/// Summarizes the clock skew measured by code checks across the vault
+fn print_skew_stats(accounts: &[Account]) {
+    let Some(stats) = health::skew_stats(accounts) else {
+        return;
+    };
+
+    println!();
+    println!(
+        "{} average {:+.1}s over {} check(s), {:.0}% outside the current period",
+        "🕰️  Clock skew:".blue(),
+        stats.mean_seconds,
+        stats.samples,
+        stats.off_share * 100.0
+    );
+    if stats.samples >= 3 && stats.mean_seconds.abs() >= 10.0 {
+        let direction = if stats.mean_seconds > 0.0 { "behind" } else { "ahead" };
+        println!(
+            "{}",
+            format!(
+                "Your clock seems to run consistently {}. Run 'quackey doctor' to compare it with network time.",
+                direction
+            )
+            .yellow()
+        );
+    }
+}
+
+/// Adds a new account using the parameters, issuer and tags of a saved template
+fn add_account_from_template(
+    storage: &mut Storage,
+    events: &Events,
+    config: &Config,
+) -> Result<(), AppError> {
+    display_screen("Add Account from Template");
+
+    if config.templates.is_empty() {
+        let width = get_terminal_width();
+        println!(
+            "{}",
+            center_text("🦉 No templates saved yet.", width).bright_red()
+        );
+        println!();
+        println!(
+            "{}",
+            "Use 'Save account as template' to create one from an existing account.".bright_black()
+        );
+        return wait_for_input();
+    }
+
+    let template_names: Vec<String> = config
+        .templates
+        .iter()
+        .map(|t| format!("{} ({})", t.name(), t.summary()))
+        .collect();
+
+    let selection = Select::with_theme(&dialoguer::theme::ColorfulTheme::default())
+        .with_prompt("Select a template")
+        .default(0)
+        .items(&template_names)
+        .interact_opt()
+        .chosen()?;
+
+    let template = &config.templates[selection];
+
+    let completion = IssuerCompletion::new(storage.get_accounts());
+    let (name, issuer) = get_edit_account_details(
+        "",
+        template.issuer().map(|s| s.as_str()),
+        &completion,
+    )?;
+
+    let secret = match get_validated_secret(&mut SecretEncoding::default(), &mut String::new()) {
+        Ok(Step::Next(secret)) => secret,
+        Ok(_) => return Ok(()),
+        Err(e) => {
+            println!("{}", format!("⛔ Error: {}", e).red().bold());
+            println!();
+            println!(
+                "{}",
+                "Please try again with a valid secret key.".bright_black()
+            );
+            wait_for_input()?;
+            return Ok(());
+        }
+    };
+
+    let account = Account::new(
+        name,
+        secret,
+        template.digits(),
+        template.period(),
+        template.algorithm(),
+        issuer,
+    )
+    .with_tags(template.tags().to_vec());
+
+    events.note(Level::Info, format!("Creating account from template: {}", template.name()))?;
+
+    save_new_account(storage, events, config, account)
+}
+
+/// Provisions a brand-new TOTP secret, stores it as an account and shows the
+/// otpauth URI and QR code to enroll it on a service or another device
+fn create_new_secret(storage: &mut Storage, events: &Events, config: &Config) -> Result<(), AppError> {
+    display_screen("Create New Secret");
+
+    println!(
+        "{}",
+        "Generate a random secret for a service you are setting up TOTP on yourself.".bright_black()
+    );
+    println!();
+
+    let completion = IssuerCompletion::new(storage.get_accounts());
+    let (name, issuer) = get_new_account_details(&completion)?;
+
+    let preset = issuer.as_deref().and_then(find_preset);
+    let Step::Next((digits, period, algorithm)) = get_totp_parameters(preset, None)? else {
+        return Ok(());
+    };
+
+    let length_options = &[
+        "160 bits (20 bytes, recommended)",
+        "128 bits (16 bytes)",
+        "256 bits (32 bytes)",
+        "512 bits (64 bytes)",
+    ];
+    let length_selection = Select::with_theme(&dialoguer::theme::ColorfulTheme::default())
+        .with_prompt("Select secret length")
+        .default(0)
+        .items(length_options)
+        .interact_opt()
+        .chosen()?;
+
+    let length = match length_selection {
+        0 => 20,
+        1 => 16,
+        2 => 32,
+        3 => 64,
+        _ => 20,
+    };
+
+    let tags = get_tags(&[])?;
+
+    let account = Account::new(
+        name,
+        generate_secret(length),
+        digits,
+        period,
+        algorithm,
+        issuer,
+    )
+    .with_tags(tags);
+
+    let uri = account.otpauth_uri();
+
+    println!();
+    println!("{}", "🎲 New secret generated".green().bold());
+    println!(
+        "{} {}",
+        "Secret:".blue(),
+        account
+            .secret()
+            .as_bytes()
+            .chunks(4)
+            .map(|chunk| String::from_utf8_lossy(chunk).to_string())
+            .collect::<Vec<_>>()
+            .join(" ")
+            .bright_white()
+            .bold()
+    );
+    println!("{} {}", "URI:".blue(), uri);
+    println!();
+
+    // Fall back to lower error correction for very long URIs
+    let qr = QrCode::encode(uri.as_bytes(), EccLevel::Medium)
+        .or_else(|_| QrCode::encode(uri.as_bytes(), EccLevel::Low));
+
+    match qr {
+        Ok(qr) => print!("{}", render_qr(&qr)),
+        Err(e) => println!("{}", format!("⛔ Could not render QR code: {}", e).red()),
+    }
+
+    println!();
+    println!(
+        "{}",
+        "⚠️  This screen shows the secret in plain text. Clear your terminal once you're done.".yellow()
+    );
+
+    events.emit(Event::SecretGenerated { name: account.name().to_string() })?;
+
+    save_new_account(storage, events, config, account)
+}
+
+/// Saves an existing account's settings as a reusable template
+fn save_account_as_template(
+    storage: &Storage,
+    events: &Events,
+    config: &mut Config,
+) -> Result<(), AppError> {
+    display_screen("Save Account as Template");
+
+    let accounts = storage.get_accounts();
+
+    if accounts.is_empty() {
+        let width = get_terminal_width();
+        println!(
+            "{}",
+            center_text("🦉 No accounts saved yet.", width).bright_red()
+        );
+        return wait_for_input();
+    }
+
+    let account = select_account(accounts)?;
+
+    let default_name = account
+        .issuer()
+        .cloned()
+        .unwrap_or_else(|| account.name().to_string());
+
+    let template_name: String = Input::with_theme(&dialoguer::theme::ColorfulTheme::default())
+        .with_prompt("Template name")
+        .default(default_name.clone())
+        .interact_text()
+        .answered()?;
+
+    let template_name = template_name.trim().to_string();
+
+    if template_name.is_empty() {
+        println!("{}", "⛔ Template name cannot be empty.".red());
+        return wait_for_input();
+    }
+
+    let template = AccountTemplate::from_account(template_name.clone(), account);
+
+    if let Some(existing) = config
+        .templates
+        .iter_mut()
+        .find(|t| t.name() == template_name)
+    {
+        let overwrite = Confirm::with_theme(&dialoguer::theme::ColorfulTheme::default())
+            .with_prompt(format!("A template named '{}' already exists. Overwrite it?", template_name))
+            .default(false)
+            .interact_opt()
+            .chosen()?;
+
+        if !overwrite {
+            println!();
+            println!("{}", "Operation cancelled.".bright_black());
+            return wait_for_input();
+        }
+
+        *existing = template;
+    } else {
+        config.templates.push(template);
+    }
+
+    config.save()?;
+    events.emit(Event::TemplateSaved { name: template_name.to_string() })?;
+
+    println!();
+    println!("{}", "✅ Template saved successfully!".green().bold());
+
+    wait_for_input()
+}
+
+/// Gets a comma-separated list of tags from user input
+fn get_tags(current: &[String]) -> Result<Vec<String>, AppError> {
+    let input: String = Input::with_theme(&dialoguer::theme::ColorfulTheme::default())
+        .with_prompt("Tags (optional, comma-separated, e.g., 'work, cloud')")
+        .default(current.join(", "))
+        .allow_empty(true)
+        .interact_text()
+        .answered()?;
+
+    Ok(split_list(&input))
+}
+
+/// Splits a comma-separated list of tags or aliases, dropping empty entries
+/// and repeats that differ only in case
+fn split_list(input: &str) -> Vec<String> {
+    let mut tags: Vec<String> = Vec::new();
+    for tag in input.split(',').map(|t| t.trim()).filter(|t| !t.is_empty()) {
+        if !tags.iter().any(|known| known.eq_ignore_ascii_case(tag)) {
+            tags.push(tag.to_string());
+        }
+    }
+    tags
+}
+
+/// Asks how the account's codes may be shown and copied, defaulting to `current`
+fn get_code_policy(current: CodePolicy) -> Result<CodePolicy, AppError> {
+    let items: Vec<&str> = CodePolicy::ALL.iter().map(CodePolicy::description).collect();
+    let default = CodePolicy::ALL.iter().position(|&policy| policy == current).unwrap_or(0);
+    let selection = Select::with_theme(&dialoguer::theme::ColorfulTheme::default())
+        .with_prompt("Codes of this account")
+        .default(default)
+        .items(&items)
+        .interact_opt()
+        .chosen()?;
+    Ok(CodePolicy::ALL[selection])
+}
+
+/// Asks for the emoji shown before the account's name, starting from `current`
+/// or else the icon of the issuer's preset; emptied for none
+fn get_icon(current: Option<&str>, preset: Option<&IssuerPreset>) -> Result<Option<String>, AppError> {
+    let initial = current.or(preset.map(|preset| preset.icon)).unwrap_or_default();
+    let input: String = Input::with_theme(&dialoguer::theme::ColorfulTheme::default())
+        .with_prompt("Icon (optional, an emoji such as 🏦; clear it for none)")
+        .with_initial_text(initial)
+        .allow_empty(true)
+        .validate_with(|input: &String| match input.trim() {
+            "" => Ok(()),
+            icon => account::check_icon(icon).map_err(|e| e.message().to_string()),
+        })
+        .interact_text()
+        .answered()?;
+
+    Ok(Some(input.trim().to_string()).filter(|icon| !icon.is_empty()))
+}
+
+/// Asks for the color of the account's name, defaulting to `current` or else
+/// the color of the issuer's preset
+fn get_color(current: Option<AccountColor>, preset: Option<&IssuerPreset>) -> Result<Option<AccountColor>, AppError> {
+    let mut items = vec!["No color".to_string()];
+    items.extend(AccountColor::ALL.iter().map(|color| color.label().color(ui::terminal_color(*color)).to_string()));
+    let suggested = current.or(preset.map(|preset| preset.color));
+    let default = suggested
+        .and_then(|suggested| AccountColor::ALL.iter().position(|&color| color == suggested))
+        .map_or(0, |index| index + 1);
+
+    let selection = Select::with_theme(&dialoguer::theme::ColorfulTheme::default())
+        .with_prompt("Color of the account in lists")
+        .default(default)
+        .items(&items)
+        .interact_opt()
+        .chosen()?;
+    Ok(selection.checked_sub(1).map(|index| AccountColor::ALL[index]))
+}
+
+/// Gets comma-separated aliases from user input, asking again while one is
+/// malformed or taken by an account other than `except` (an account id)
+fn get_aliases(storage: &Storage, current: &[String], except: Option<&str>) -> Result<Vec<String>, AppError> {
+    loop {
+        let input: String = Input::with_theme(&dialoguer::theme::ColorfulTheme::default())
+            .with_prompt("Aliases (optional, comma-separated short names, e.g., 'gh')")
+            .default(current.join(", "))
+            .allow_empty(true)
+            .interact_text()
+            .answered()?;
+
+        let aliases = split_list(&input);
+
+        match storage.check_aliases(&aliases, except) {
+            Ok(()) => return Ok(aliases),
+            Err(e) => println!("{}", format!("⛔ {}", e).red()),
+        }
+    }
+}
+
+/// Gets account name and issuer from user input for a new account
+fn get_new_account_details(completion: &IssuerCompletion) -> Result<(String, Option<String>), AppError> {
+    loop {
+        let name: String = Input::with_theme(&dialoguer::theme::ColorfulTheme::default())
+            .with_prompt("Account name (e.g., 'me@example.com', 'my-github-username')")
+            .interact_text()
+            .answered()?;
+
+        let trimmed_name = name.trim().to_string();
+
+        if trimmed_name.is_empty() {
+            println!("{}", "⛔ Account name cannot be empty.".red());
+            println!();
+            continue;
+        }
+
+        let issuer: String = Input::with_theme(&dialoguer::theme::ColorfulTheme::default())
+            .with_prompt("Issuer (optional, e.g., 'Google', 'GitHub'; Tab to complete)")
+            .completion_with(completion)
+            .allow_empty(true)
+            .interact_text()
+            .answered()?;
+
+        return Ok((
+            trimmed_name,
+            if issuer.trim().is_empty() {
+                None
+            } else {
+                Some(issuer.trim().to_string())
+            },
+        ));
+    }
+}
+
+/// Gets account name and issuer from user input for editing an existing account
+fn get_edit_account_details(
+    current_name: &str,
+    current_issuer: Option<&str>,
+    completion: &IssuerCompletion,
+) -> Result<(String, Option<String>), AppError> {
+    loop {
+        let name: String = Input::with_theme(&dialoguer::theme::ColorfulTheme::default())
+            .with_prompt("Account name (e.g., 'me@example.com', 'my-github-username')")
+            .default(current_name.to_string())
+            .interact_text()
+            .answered()?;
+
+        let trimmed_name = name.trim().to_string();
+
+        if trimmed_name.is_empty() {
+            println!("{}", "⛔ Account name cannot be empty.".red());
+            println!();
+            continue;
+        }
+
+        let issuer: String = Input::with_theme(&dialoguer::theme::ColorfulTheme::default())
+            .with_prompt("Issuer (optional, e.g., 'Google', 'GitHub'; Tab to complete)")
+            .completion_with(completion)
+            .default(current_issuer.unwrap_or("").to_string())
+            .allow_empty(true)
+            .interact_text()
+            .answered()?;
+
+        return Ok((
+            trimmed_name,
+            if issuer.trim().is_empty() {
+                None
+            } else {
+                Some(issuer.trim().to_string())
+            },
+        ));
+    }
+}
+
+/// Edits an account in storage
+fn edit_account(storage: &mut Storage, events: &Events) -> Result<(), AppError> {
+    display_screen("Edit Account");
+
+    let accounts = storage.get_accounts();
+
+    if accounts.is_empty() {
+        let width = get_terminal_width();
+        println!(
+            "{}",
+            center_text("🦉 No accounts saved yet.", width).bright_red()
+        );
+        events.note(Level::Warn, "Attempted to edit account with no accounts")?;
+        return wait_for_input();
+    }
+
+    let account = select_account(accounts)?.clone();
+    edit_selected_account(storage, account)
+}
+
+/// Asks for new details of `account`, showing the current ones first
+fn edit_selected_account(storage: &mut Storage, account: Account) -> Result<(), AppError> {
+    let completion = IssuerCompletion::new(storage.get_accounts());
+
+    println!();
+    println!("{}", "Current account details:".green().bold());
+    println!("{} {}", "Name:".blue(), account.name());
+    if let Some(issuer) = account.issuer() {
+        println!("{} {}", "Issuer:".blue(), issuer);
+    } else {
+        println!("{} None", "Issuer:".blue());
+    }
+    println!("{} {}", "Digits:".blue(), account.digits());
+    println!("{} {} seconds", "Period:".blue(), account.period());
+    println!("{} {}", "Algorithm:".blue(), account.algorithm_name());
+    if !account.tags().is_empty() {
+        println!("{} {}", "Tags:".blue(), account.tags().join(", "));
+    }
+    if !account.aliases().is_empty() {
+        println!("{} {}", "Aliases:".blue(), account.aliases().join(", "));
+    }
+    println!("{} {}", "Codes:".blue(), account.code_policy().label());
+    if let Some(icon) = account.icon() {
+        println!("{} {}", "Icon:".blue(), icon);
+    }
+    if let Some(color) = account.color() {
+        println!("{} {}", "Color:".blue(), color.label());
+    }
+    println!();
+
+    println!(
+        "{}",
+        "Enter new details (press Enter to keep current value):".bright_black()
+    );
+
+    let (name, issuer) = get_edit_account_details(
+        account.name(),
+        account.issuer().map(|s| s.as_str()),
+        &completion,
+    )?;
+    let tags = get_tags(account.tags())?;
+    let aliases = get_aliases(storage, account.aliases(), Some(account.id()))?;
+    let policy = get_code_policy(account.code_policy())?;
+    let preset = issuer.as_deref().and_then(find_preset);
+    let icon = get_icon(account.icon(), preset)?;
+    let color = get_color(account.color(), preset)?;
+
+    storage.update_account(account.name(), name.clone(), issuer, tags)?;
+    storage.set_aliases(account.id(), aliases)?;
+    storage.set_code_policy(account.id(), policy)?;
+    storage.set_appearance(account.id(), icon, color)?;
+
+    println!();
+    println!("{}", "✅ Account updated successfully!".green().bold());
+
+    wait_for_input()
+}
+
+/// Deletes an account from storage
+fn delete_account(storage: &mut Storage, events: &Events) -> Result<(), AppError> {
+    let accounts = storage.get_accounts();
+
+    if accounts.is_empty() {
+        display_screen("Delete Account");
+        let width = get_terminal_width();
+        println!(
+            "{}",
+            center_text("🦉 No accounts saved yet.", width).bright_red()
+        );
+        events.note(Level::Warn, "Attempted to delete account with no accounts")?;
+        return wait_for_input();
+    }
+
+    display_screen("Delete Account");
+
+    let indices = if accounts.len() == 1 {
+        select_account(accounts)?;
+        vec![0]
+    } else {
+        let (order, labels) = picker_items(accounts);
+        MultiSelect::with_theme(&dialoguer::theme::ColorfulTheme::default())
+            .with_prompt("Select accounts to delete (Space to select, Enter to confirm)")
+            .items(&labels)
+            .interact_opt()
+            .chosen()?
+            .into_iter()
+            .map(|selection| order[selection])
+            .collect()
+    };
+
+    let confirmed = match indices.as_slice() {
+        [] => false,
+        [index] => Confirm::with_theme(&dialoguer::theme::ColorfulTheme::default())
+            .with_prompt(format!(
+                "Are you sure you want to delete the account '{}'?",
+                ui::shown_name(&accounts[*index])
+            ))
+            .default(false)
+            .interact_opt()
+            .chosen()?,
+        _ => {
+            println!();
+            println!("{}", "These accounts will be deleted:".yellow().bold());
+            for &index in &indices {
+                println!("  - {}", ui::shown_label(&accounts[index]));
+            }
+            println!();
+            confirm_by_typing(
+                &indices.len().to_string(),
+                &format!("Type {} to delete these accounts", indices.len()),
+            )?
+        }
+    };
+
+    if !confirmed {
+        println!();
+        println!("{}", "Account deletion cancelled.".bright_black());
+        return wait_for_input();
+    }
+
+    let snapshot = storage.snapshot("delete")?;
+    storage.delete_accounts(&indices)?;
+
+    println!();
+    if indices.len() == 1 {
+        println!("{}", "✅ Account deleted successfully!".green().bold());
+    } else {
+        println!(
+            "{}",
+            format!("✅ Deleted {} accounts successfully!", indices.len()).green().bold()
+        );
+    }
+    print_snapshot_note(snapshot.as_deref());
+
+    wait_for_input()
+}
+
+/// Archives or restores accounts. Archived accounts are kept but left out of
+/// tables and pickers, for services used too rarely to list but not to delete.
+fn archive_accounts(storage: &mut Storage, events: &Events) -> Result<(), AppError> {
+    display_screen("Archive Accounts");
+
+    let accounts = storage.get_accounts();
+
+    if accounts.is_empty() {
+        let width = get_terminal_width();
+        println!(
+            "{}",
+            center_text("🦉 No accounts saved yet.", width).bright_red()
+        );
+        events.note(Level::Warn, "Attempted to archive accounts with no accounts")?;
+        return wait_for_input();
+    }
+
+    println!(
+        "{}",
+        "Archived accounts are kept, but only listed when \"Show archived accounts\" is on in the settings."
+            .bright_black()
+    );
+    println!();
+
+    // Every account is listed here, so archived ones can be restored
+    let labels: Vec<String> = accounts
+        .iter()
+        .map(|account| ui::with_appearance(account, &ui::shown_label(account)))
+        .collect();
+    let defaults: Vec<bool> = accounts.iter().map(Account::is_archived).collect();
+    let Some(ticked) = MultiSelect::with_theme(&dialoguer::theme::ColorfulTheme::default())
+        .with_prompt("Tick the accounts to archive (Space to toggle, Enter to confirm)")
+        .items(&labels)
+        .defaults(&defaults)
+        .interact_opt()
+        .answered()?
+    else {
+        println!();
+        println!("{}", "Archiving cancelled.".bright_black());
+        return wait_for_input();
+    };
+
+    let changes: Vec<(String, bool)> = accounts
+        .iter()
+        .enumerate()
+        .filter(|(index, account)| ticked.contains(index) != account.is_archived())
+        .map(|(index, account)| (account.id().to_string(), ticked.contains(&index)))
+        .collect();
+    if changes.is_empty() {
+        println!();
+        println!("{}", "Nothing changed.".bright_black());
+        return wait_for_input();
+    }
+
+    storage.begin_batch();
+    for (id, archived) in &changes {
+        storage.set_archived(id, *archived)?;
+    }
+    storage.flush()?;
+
+    let archived = changes.iter().filter(|(_, archived)| *archived).count();
+    let restored = changes.len() - archived;
+
+    println!();
+    if archived > 0 {
+        println!("{}", format!("📦 Archived {} account(s).", archived).green().bold());
+    }
+    if restored > 0 {
+        println!("{}", format!("✅ Restored {} account(s).", restored).green().bold());
+    }
+
+    wait_for_input()
+}
+
+/// Asks the user to type `expected` to confirm an action that affects several
+/// accounts, so a stray Enter can't trigger it
+fn confirm_by_typing(expected: &str, prompt: &str) -> Result<bool, AppError> {
+    let typed: String = Input::with_theme(&dialoguer::theme::ColorfulTheme::default())
+        .with_prompt(prompt)
+        .allow_empty(true)
+        .interact_text()
+        .answered()?;
+
+    Ok(typed.trim() == expected)
+}
+
+/// Exports one account as a passphrase-encrypted bundle for another Quackey user
+fn share_account(storage: &mut Storage, events: &Events) -> Result<(), AppError> {
+    display_screen("Share Account");
+
+    let accounts = storage.get_accounts();
+
+    if accounts.is_empty() {
+        let width = get_terminal_width();
+        println!(
+            "{}",
+            center_text("🦉 No accounts saved yet.", width).bright_red()
+        );
+        events.note(Level::Warn, "Attempted to share an account with no accounts")?;
+        return wait_for_input();
+    }
+
+    let account = select_account(accounts)?;
+    share_selected_account(events, account)
+}
+
+/// Encrypts `account` into a bundle with a passphrase and saves or shows it
+fn share_selected_account(events: &Events, account: &Account) -> Result<(), AppError> {
+    println!();
+    if let Some(reference) = account.secret_reference() {
+        println!(
+            "{}",
+            format!("⛔ This account fetches its secret with '{}', so it can't be shared.", reference).red()
+        );
+        return wait_for_input();
+    }
+    println!(
+        "{}",
+        "Anyone with the bundle and passphrase can generate this account's codes.".bright_black()
+    );
+    let passphrase = Password::with_theme(&dialoguer::theme::ColorfulTheme::default())
+        .with_prompt("Bundle passphrase")
+        .with_confirmation("Confirm passphrase", "⛔ Passphrases don't match.")
+        .interact()
+        .answered()?;
+
+    if passphrase.is_empty() {
+        println!("{}", "⛔ Passphrase cannot be empty.".red());
+        return wait_for_input();
+    }
+
+    let spinner = create_spinner("Encrypting account...".to_string());
+    let bundle = share::seal(account, &passphrase);
+    spinner.finish_and_clear();
+    let bundle = bundle?;
+
+    let destination = Select::with_theme(&dialoguer::theme::ColorfulTheme::default())
+        .with_prompt("How would you like to share it?")
+        .default(0)
+        .items(&["💾 Save to a file", "📋 Show as text to copy"])
+        .interact_opt()
+        .chosen()?;
+
+    if destination == 0 {
+        let default_path = format!(
+            "{}.quackey",
+            account
+                .name()
+                .chars()
+                .map(|c| if c.is_alphanumeric() || c == '-' || c == '.' { c } else { '_' })
+                .collect::<String>()
+        );
+        let path: String = Input::with_theme(&dialoguer::theme::ColorfulTheme::default())
+            .with_prompt("Save bundle as")
+            .default(default_path)
+            .interact_text()
+            .answered()?;
+
+        if let Err(e) = std::fs::write(path.trim(), &bundle) {
+            println!("{}", format!("⛔ Failed to write '{}': {}", path.trim(), e).red());
+            return wait_for_input();
+        }
+
+        println!();
+        println!("{}", format!("✅ Bundle saved to {}", path.trim()).green().bold());
+    } else {
+        println!();
+        println!("{}", bundle);
+    }
+
+    println!(
+        "{}",
+        "Send the passphrase through a different channel than the bundle.".bright_black()
+    );
+    events.note(Level::Info, format!("Shared account as encrypted bundle: {}", account.name()))?;
+
+    wait_for_input()
+}
+
+/// Reads a QR code screenshot from the clipboard and adds the account it encodes
+fn add_account_from_clipboard_qr(storage: &mut Storage, events: &Events, config: &Config) -> Result<(), AppError> {
+    display_screen("Add Account from Clipboard QR");
+
+    if !clipboard_enabled(config) {
+        println!("{}", "📋 The clipboard is disabled in your settings.".bright_black());
+        return wait_for_input();
+    }
+
+    let (width, height, pixels) = match clipboard_image() {
+        Ok(image) => image,
+        Err(e) => {
+            events.note(Level::Warn, format!("No image on the clipboard: {}", e))?;
+            println!(
+                "{}",
+                "🦉 No image found on the clipboard. Copy a screenshot of the QR code first.".bright_red()
+            );
+            return wait_for_input();
+        }
+    };
+
+    let spinner = create_spinner("Looking for a QR code...".to_string());
+    let decoded = scan::decode_rgba(width, height, &pixels);
+    spinner.finish_and_clear();
+
+    let text = match decoded {
+        Ok(data) => String::from_utf8_lossy(&data).into_owned(),
+        Err(e) => {
+            events.note(Level::Warn, format!("Failed to read QR code from clipboard: {}", e))?;
+            println!("{}", format!("⛔ {}", e).red());
+            return wait_for_input();
+        }
+    };
+
+    if text.starts_with("otpauth-migration://") {
+        println!(
+            "{}",
+            "⛔ This is an authenticator transfer QR code, which can't be imported yet.".red()
+        );
+        return wait_for_input();
+    }
+
+    let existing = storage.get_accounts();
+    let parsed = formats::find_importer("uris")?.parse(&text)?;
+    let Some(line) = import::check_duplicates(parsed, existing).into_iter().next() else {
+        println!("{}", "⛔ The QR code doesn't contain an otpauth:// URI.".red());
+        return wait_for_input();
+    };
+
+    match line.result {
+        Ok(account) => {
+            println!("{} {}", "Account:".blue(), ui::shown_label(&account));
+            println!(
+                "{} {} digits, {}s, {}",
+                "Settings:".blue(),
+                account.digits(),
+                account.period(),
+                account.algorithm_name()
+            );
+            save_new_account(storage, events, config, account)
+        }
+        Err(e) => {
+            events.note(Level::Warn, format!("Rejected QR code from clipboard: {}", e))?;
+            println!("{}", format!("⛔ {}", e).red());
+            wait_for_input()
+        }
+    }
+}
+
+/// Adds an account from a bundle created with "Share account"
+fn import_shared_account(storage: &mut Storage, events: &Events, config: &Config) -> Result<(), AppError> {
+    display_screen("Import Shared Account");
+
+    let source = Select::with_theme(&dialoguer::theme::ColorfulTheme::default())
+        .with_prompt("Where is the bundle?")
+        .default(0)
+        .items(&["📄 In a file", "📋 Paste the text"])
+        .interact_opt()
+        .chosen()?;
+
+    let armored = if source == 0 {
+        let path: String = Input::with_theme(&dialoguer::theme::ColorfulTheme::default())
+            .with_prompt("Bundle file")
+            .interact_text()
+            .answered()?;
+
+        match std::fs::read_to_string(path.trim()) {
+            Ok(contents) => contents,
+            Err(e) => {
+                println!("{}", format!("⛔ Failed to read '{}': {}", path.trim(), e).red());
+                return wait_for_input();
+            }
+        }
+    } else {
+        println!(
+            "{}",
+            format!("Paste the bundle, from {} to {}:", share::BEGIN_MARKER, share::END_MARKER).bright_black()
+        );
+
+        let mut armored = String::new();
+        loop {
+            let mut line = String::new();
+            if io::stdin().read_line(&mut line)? == 0 {
+                break;
+            }
+            armored.push_str(&line);
+            if line.contains(share::END_MARKER) {
+                break;
+            }
+        }
+        armored
+    };
+
+    let passphrase = Password::with_theme(&dialoguer::theme::ColorfulTheme::default())
+        .with_prompt("Bundle passphrase")
+        .interact()
+        .answered()?;
+
+    let spinner = create_spinner("Decrypting account...".to_string());
+    let result = share::open(&armored, &passphrase);
+    spinner.finish_and_clear();
+
+    let account = match result {
+        Ok(account) => account,
+        Err(e) => {
+            events.note(Level::Warn, format!("Failed to import shared account: {}", e))?;
+            println!("{}", format!("⛔ {}", e).red());
+            return wait_for_input();
+        }
+    };
+
+    println!();
+    println!("{} {}", "Account:".blue(), ui::shown_label(&account));
+    println!(
+        "{} {} digits, {}s, {}",
+        "Settings:".blue(),
+        account.digits(),
+        account.period(),
+        account.algorithm_name()
+    );
+
+    save_new_account(storage, events, config, account)
+}
+
+/// Shows accounts as Google Authenticator migration QR codes, cycling through
+/// several frames when they don't fit in one
+fn export_to_authenticator(storage: &mut Storage, events: &Events) -> Result<(), AppError> {
+    display_screen("Export to Authenticator App");
+
+    let accounts = storage.get_accounts();
+
+    if accounts.is_empty() {
+        let width = get_terminal_width();
+        println!(
+            "{}",
+            center_text("🦉 No accounts saved yet.", width).bright_red()
+        );
+        events.note(Level::Warn, "Attempted to export accounts with no accounts")?;
+        return wait_for_input();
+    }
+
+    let (order, labels) = picker_items(accounts);
+    let defaults = vec![true; labels.len()];
+    let indices: Vec<usize> = MultiSelect::with_theme(&dialoguer::theme::ColorfulTheme::default())
+        .with_prompt("Select accounts to export (Space to toggle, Enter to confirm)")
+        .items(&labels)
+        .defaults(&defaults)
+        .interact_opt()
+        .chosen()?
+        .into_iter()
+        .map(|selection| order[selection])
+        .collect();
+
+    if indices.is_empty() {
+        println!("{}", "Export cancelled.".bright_black());
+        return wait_for_input();
+    }
+
+    let selected: Vec<&Account> = indices.iter().map(|&i| &accounts[i]).collect();
+    let export = migration::export(&selected)?;
+
+    for (name, reason) in &export.skipped {
+        println!("{} {}: {}", "⚠️  Skipped".yellow(), name, reason.bright_black());
+    }
+
+    if export.frames.is_empty() {
+        println!("{}", "🦉 None of the selected accounts can be exported this way.".bright_red());
+        return wait_for_input();
+    }
+
+    println!();
+    println!(
+        "{}",
+        "⚠️  These QR codes contain your secrets. Make sure no one can see your screen.".yellow().bold()
+    );
+    let proceed = Confirm::with_theme(&dialoguer::theme::ColorfulTheme::default())
+        .with_prompt(format!(
+            "Show {} account(s) in {} QR code(s)?",
+            selected.len() - export.skipped.len(),
+            export.frames.len()
+        ))
+        .default(true)
+        .interact_opt()
+        .chosen()?;
+
+    if !proceed {
+        println!("{}", "Export cancelled.".bright_black());
+        return wait_for_input();
+    }
+
+    let frames = export
+        .frames
+        .iter()
+        .map(|uri| QrCode::encode(uri.as_bytes(), EccLevel::Low).map(|qr| render_qr(&qr)))
+        .collect::<Result<Vec<_>, _>>()?;
+
+    events.emit(Event::Exported {
+        what: format!(
+            "{} account(s) as {} migration QR code(s)",
+            selected.len() - export.skipped.len(),
+            frames.len()
+        ),
+    })?;
+
+    play_qr_frames(&frames, Duration::from_secs(3))
+}
+
+/// Views and maintains an account's encrypted one-time recovery codes
+fn manage_recovery_codes(storage: &mut Storage, events: &Events) -> Result<(), AppError> {
+    display_screen("Recovery Codes");
+
+    let accounts = storage.get_accounts();
+
+    if accounts.is_empty() {
+        let width = get_terminal_width();
+        println!(
+            "{}",
+            center_text("🦉 No accounts saved yet.", width).bright_red()
+        );
+        return wait_for_input();
+    }
+
+    let name = select_account(accounts)?.name().to_string();
+
+    // Remember the passphrase for the rest of this screen once it has been entered
+    let mut passphrase: Option<String> = None;
+
+    loop {
+        display_screen("Recovery Codes");
+
+        let Some(account) = storage.get_accounts().iter().find(|a| a.name() == name).cloned() else {
+            return Ok(());
+        };
+
+        println!("{} {}", "Account:".blue(), ui::shown_name(&account));
+        match account.recovery_codes() {
+            Some(codes) => println!("{} {}", "Remaining codes:".blue(), codes.remaining()),
+            None => println!("{}", "No recovery codes stored for this account.".bright_black()),
+        }
+        println!();
+
+        let options = &[
+            "👀 View codes",
+            "➕ Add codes",
+            "✔️ Mark a code as used",
+            "🗑️ Remove all codes",
+            "👈 Back",
+        ];
+        let selection = Select::with_theme(&dialoguer::theme::ColorfulTheme::default())
+            .with_prompt("Select an option")
+            .default(0)
+            .items(options)
+            .interact_opt()
+            .chosen()?;
+
+        let result = match selection {
+            0 => view_recovery_codes(&account, &mut passphrase, events),
+            1 => add_recovery_codes(storage, &account, &mut passphrase),
+            2 => mark_recovery_code_used(storage, &account, &mut passphrase),
+            3 => remove_recovery_codes(storage, &account),
+            _ => return Ok(()),
+        };
+
+        if let Err(e) = result {
+            println!();
             println!("{}", format!("⛔ Error: {}", e).red().bold());
+            passphrase = None;
+        }
+
+        wait_for_input()?;
+    }
+}
+
+/// Decrypts an account's recovery codes, asking for the passphrase if needed
+fn unlock_recovery_codes(
+    codes: &RecoveryCodes,
+    passphrase: &mut Option<String>,
+) -> Result<Vec<RecoveryCode>, AppError> {
+    let secret = match passphrase {
+        Some(secret) => secret.clone(),
+        None => Password::with_theme(&dialoguer::theme::ColorfulTheme::default())
+            .with_prompt("Recovery codes passphrase")
+            .interact()
+            .answered()?,
+    };
+
+    let spinner = create_spinner("Decrypting recovery codes...".to_string());
+    let result = codes.open(&secret);
+    spinner.finish_and_clear();
+
+    let opened = result?;
+    *passphrase = Some(secret);
+    Ok(opened)
+}
+
+/// Prints an account's recovery codes, dimming the used ones
+fn view_recovery_codes(
+    account: &Account,
+    passphrase: &mut Option<String>,
+    events: &Events,
+) -> Result<(), AppError> {
+    let Some(codes) = account.recovery_codes() else {
+        println!("{}", "🦉 No recovery codes to show.".bright_red());
+        return Ok(());
+    };
+
+    let codes = unlock_recovery_codes(codes, passphrase)?;
+
+    println!();
+    for (i, code) in codes.iter().enumerate() {
+        if code.used {
+            println!("{} {}", format!("{:>3}.", i + 1).yellow(), format!("{} (used)", code.code).bright_black().strikethrough());
+        } else {
+            println!("{} {}", format!("{:>3}.", i + 1).yellow(), code.code.bright_white().bold());
+        }
+    }
+
+    events.emit(Event::Viewed { what: format!("recovery codes for account: {}", account.name()) })
+}
+
+/// Adds recovery codes to an account, setting a passphrase on first use
+fn add_recovery_codes(
+    storage: &mut Storage,
+    account: &Account,
+    passphrase: &mut Option<String>,
+) -> Result<(), AppError> {
+    let mut codes = match account.recovery_codes() {
+        Some(existing) => unlock_recovery_codes(existing, passphrase)?,
+        None => {
+            if passphrase.is_none() {
+                println!(
+                    "{}",
+                    "Recovery codes are encrypted with a passphrase you choose.".bright_black()
+                );
+                let secret = Password::with_theme(&dialoguer::theme::ColorfulTheme::default())
+                    .with_prompt("New recovery codes passphrase")
+                    .with_confirmation("Confirm passphrase", "⛔ Passphrases don't match.")
+                    .interact()
+                    .answered()?;
+
+                if secret.is_empty() {
+                    return Err(AppError::InvalidInput("Passphrase cannot be empty".to_string()));
+                }
+                *passphrase = Some(secret);
+            }
+            Vec::new()
+        }
+    };
+
+    println!();
+    println!(
+        "{}",
+        "Enter recovery codes (several per line is fine). Press Enter on an empty line to finish.".bright_black()
+    );
+
+    let mut added = 0;
+    loop {
+        let mut line = String::new();
+        io::stdin().read_line(&mut line)?;
+        if line.trim().is_empty() {
+            break;
+        }
+        for code in recovery::parse_codes(&line) {
+            if !codes.iter().any(|c| c.code == code.code) {
+                codes.push(code);
+                added += 1;
+            }
+        }
+    }
+
+    if added == 0 {
+        println!("{}", "No new codes entered.".bright_black());
+        return Ok(());
+    }
+
+    let secret = passphrase.as_deref().unwrap_or_default();
+    let sealed = RecoveryCodes::seal(&codes, secret)?;
+    storage.set_recovery_codes(account.id(), Some(sealed))?;
+
+    println!("{}", format!("✅ Added {} recovery code(s).", added).green().bold());
+    Ok(())
+}
+
+/// Marks one of an account's recovery codes as used
+fn mark_recovery_code_used(
+    storage: &mut Storage,
+    account: &Account,
+    passphrase: &mut Option<String>,
+) -> Result<(), AppError> {
+    let Some(existing) = account.recovery_codes() else {
+        println!("{}", "🦉 No recovery codes stored.".bright_red());
+        return Ok(());
+    };
+
+    let mut codes = unlock_recovery_codes(existing, passphrase)?;
+
+    let unused: Vec<usize> = (0..codes.len()).filter(|&i| !codes[i].used).collect();
+    if unused.is_empty() {
+        println!("{}", "⚠️  All recovery codes have been used.".yellow());
+        return Ok(());
+    }
+
+    let labels: Vec<&str> = unused.iter().map(|&i| codes[i].code.as_str()).collect();
+    let selection = Select::with_theme(&dialoguer::theme::ColorfulTheme::default())
+        .with_prompt("Which code did you use?")
+        .default(0)
+        .items(&labels)
+        .interact_opt()
+        .chosen()?;
+
+    codes[unused[selection]].used = true;
+
+    let secret = passphrase.as_deref().unwrap_or_default();
+    let sealed = RecoveryCodes::seal(&codes, secret)?;
+    let remaining = sealed.remaining();
+    storage.set_recovery_codes(account.id(), Some(sealed))?;
+
+    println!();
+    println!("{}", "✅ Code marked as used.".green().bold());
+    if remaining <= 2 {
+        println!(
+            "{}",
+            format!("⚠️  Only {} unused code(s) left. Consider generating new ones with the provider.", remaining).yellow()
+        );
+    }
+    Ok(())
+}
+
+/// Removes all recovery codes from an account
+fn remove_recovery_codes(storage: &mut Storage, account: &Account) -> Result<(), AppError> {
+    if account.recovery_codes().is_none() {
+        println!("{}", "🦉 No recovery codes stored.".bright_red());
+        return Ok(());
+    }
+
+    let confirm = Confirm::with_theme(&dialoguer::theme::ColorfulTheme::default())
+        .with_prompt(format!("Remove all recovery codes for '{}'?", ui::shown_name(account)))
+        .default(false)
+        .interact_opt()
+        .chosen()?;
+
+    if !confirm {
+        println!("{}", "Operation cancelled.".bright_black());
+        return Ok(());
+    }
+
+    storage.set_recovery_codes(account.id(), None)?;
+
+    println!("{}", "✅ Recovery codes removed.".green().bold());
+    Ok(())
+}
+
+/// Reports problems in the vault, such as accounts sharing the same secret
+fn vault_health(storage: &Storage, events: &Events) -> Result<(), AppError> {
+    display_screen("Vault Health");
+
+    let accounts = storage.get_accounts();
+
+    if accounts.is_empty() {
+        let width = get_terminal_width();
+        println!(
+            "{}",
+            center_text("🦉 No accounts saved yet.", width).bright_red()
+        );
+        return wait_for_input();
+    }
+
+    let duplicates = health::find_duplicate_secrets(accounts);
+
+    println!("{} {}", "Accounts checked:".blue(), accounts.len());
+    println!();
+
+    if duplicates.is_empty() {
+        println!("{}", "✅ No accounts share a secret.".green().bold());
+    } else {
+        println!(
+            "{}",
+            format!("⚠️  {} group(s) of accounts share the same secret:", duplicates.len())
+                .yellow()
+                .bold()
+        );
+        for (i, group) in duplicates.iter().enumerate() {
             println!();
+            println!("{}", format!("Group {}:", i + 1).blue());
+            for account in group {
+                println!("  - {}", ui::shown_label(account));
+            }
+        }
+        println!();
+        println!(
+            "{}",
+            "Duplicates usually come from importing an account twice or pasting the wrong key.".bright_black()
+        );
+        println!(
+            "{}",
+            "Delete the extra copies from 'Manage Accounts' once you've confirmed which one is correct.".bright_black()
+        );
+    }
+
+    print_skew_stats(accounts);
+
+    events.note(Level::Info, format!(
+        "Ran vault health check: {} duplicate secret group(s)",
+        duplicates.len()
+    ))?;
+
+    wait_for_input()
+}
+
+/// Summarizes the vault and offers to fix what it finds
+fn vault_overview(storage: &mut Storage, events: &Events, config: &Config) -> Result<(), AppError> {
+    loop {
+        clear_screen();
+        display_screen("Vault Overview");
+
+        let accounts = storage.get_accounts();
+
+        if accounts.is_empty() {
+            let width = get_terminal_width();
             println!(
                 "{}",
-                "Please try again with a valid secret key.".bright_black()
+                center_text("🦉 No accounts saved yet.", width).bright_red()
             );
-            wait_for_input()?;
+            return wait_for_input();
+        }
+
+        let now = SystemTime::now().duration_since(UNIX_EPOCH)?.as_secs();
+        let overview = health::overview(accounts, &storage.generation_history(), now);
+
+        let archived = accounts.iter().filter(|account| account.is_archived()).count();
+        if archived > 0 {
+            println!("{} {} ({} archived)", "Accounts:".blue(), overview.total, archived);
+        } else {
+            println!("{} {}", "Accounts:".blue(), overview.total);
+        }
+        let issuers: Vec<String> = overview
+            .by_issuer
+            .iter()
+            .map(|(issuer, count)| match issuer {
+                Some(_) => format!("{} {}", issuer_label(*issuer), count),
+                None => format!("(no issuer) {}", count),
+            })
+            .collect();
+        println!("{} {}", "By issuer:".blue(), issuers.join(", "));
+        let algorithms: Vec<String> = overview
+            .by_algorithm
+            .iter()
+            .map(|(algorithm, count)| format!("{} {}", algorithm, count))
+            .collect();
+        println!("{} {}", "By algorithm:".blue(), algorithms.join(", "));
+        println!();
+
+        let mut fixes: Vec<(&str, usize)> = Vec::new();
+
+        if overview.unverified.is_empty() {
+            println!("{}", "✅ All accounts are verified.".green());
+        } else {
+            println!("{}", format!("⚠️  {} unverified account(s):", overview.unverified.len()).yellow());
+            for account in &overview.unverified {
+                println!("  - {}", ui::shown_label(account));
+            }
+            fixes.push(("✅ Verify an account", 0));
+        }
+
+        if overview.duplicates.is_empty() {
+            println!("{}", "✅ No accounts share a secret.".green());
+        } else {
+            println!(
+                "{}",
+                format!("⚠️  {} group(s) of accounts share the same secret:", overview.duplicates.len()).yellow()
+            );
+            for group in &overview.duplicates {
+                let labels: Vec<String> = group.iter().map(|account| ui::shown_label(account)).collect();
+                println!("  - {}", labels.join(" = "));
+            }
+        }
+
+        if overview.unused.is_empty() {
+            println!("{}", "✅ No accounts unused for 6 months or more.".green());
+        } else {
+            println!(
+                "{}",
+                format!("⚠️  {} account(s) without a code in 6 months or more:", overview.unused.len()).yellow()
+            );
+            for account in &overview.unused {
+                println!("  - {}", ui::shown_label(account));
+            }
+        }
+        if overview.never_used > 0 {
+            println!(
+                "{}",
+                format!("   {} account(s) have no generated code on record yet.", overview.never_used).bright_black()
+            );
+        }
+        if !overview.duplicates.is_empty() || !overview.unused.is_empty() {
+            fixes.push(("🗑️ Delete duplicate or unused accounts", 1));
+        }
+
+        match storage.last_snapshot() {
+            Some(modified) => {
+                let age_days = SystemTime::now()
+                    .duration_since(modified)
+                    .map(|age| age.as_secs() / (24 * 60 * 60))
+                    .unwrap_or(0);
+                let modified: chrono::DateTime<chrono::Local> = modified.into();
+                let message = format!(
+                    "Last backup: {} ({} day(s) ago)",
+                    modified.format("%Y-%m-%d %H:%M"),
+                    age_days
+                );
+                if age_days > 30 {
+                    println!("{}", format!("⚠️  {}", message).yellow());
+                } else {
+                    println!("{}", format!("✅ {}", message).green());
+                }
+            }
+            None => println!("{}", "⚠️  No backup of the vault yet.".yellow()),
+        }
+        fixes.push(("💾 Back up the vault now", 2));
+        fixes.push(("👈 Back", 3));
+
+        events.emit(Event::Viewed { what: "vault overview".to_string() })?;
+
+        println!();
+        let labels: Vec<&str> = fixes.iter().map(|(label, _)| *label).collect();
+        let choice = Select::with_theme(&dialoguer::theme::ColorfulTheme::default())
+            .with_prompt("Fix something?")
+            .default(labels.len() - 1)
+            .items(&labels)
+            .interact_opt()
+            .chosen()?;
+
+        clear_screen();
+        match fixes[choice].1 {
+            0 => verify_account(storage, events, config)?,
+            1 => delete_account(storage, events)?,
+            2 => {
+                display_screen("Back Up Vault");
+                match storage.snapshot("manual")? {
+                    Some(path) => println!("{}", format!("✅ Backup saved to {}", path.display()).green()),
+                    None => println!("{}", "Nothing to back up yet: the vault file doesn't exist.".yellow()),
+                }
+                wait_for_input()?;
+            }
+            _ => return Ok(()),
+        }
+    }
+}
+
+/// Shows when codes were generated for an account, newest first, for checking
+/// whether one was generated around a suspicious login
+fn code_history(storage: &Storage, events: &Events) -> Result<(), AppError> {
+    display_screen("Code History");
+
+    let accounts = storage.get_accounts();
+
+    if accounts.is_empty() {
+        let width = get_terminal_width();
+        println!(
+            "{}",
+            center_text("🦉 No accounts saved yet.", width).bright_red()
+        );
+        return wait_for_input();
+    }
+
+    let account = select_account(accounts)?;
+    let history = storage.generation_history();
+    let history = history.times(account.id());
+
+    println!();
+    println!("{} {}", "Account:".blue(), ui::shown_label(account));
+    println!();
+
+    if history.is_empty() {
+        println!("{}", "No codes have been generated for this account yet.".yellow());
+    } else {
+        println!("{}", format!("Codes generated ({} most recent, newest first):", history.len()).blue());
+        for &at in history.iter().rev() {
+            println!("  - {}", format_timestamp(at));
+        }
+    }
+
+    println!();
+    println!(
+        "{}",
+        "Only the times are recorded, never the codes.".bright_black()
+    );
+
+    events.emit(Event::Viewed { what: format!("code history for account: {}", account.name()) })?;
+    wait_for_input()
+}
+
+/// Formats Unix seconds as local time, such as `2024-05-01 14:03:22`
+fn format_timestamp(at: u64) -> String {
+    chrono::DateTime::from_timestamp(at as i64, 0)
+        .map(|time| time.with_timezone(&chrono::Local).format("%Y-%m-%d %H:%M:%S").to_string())
+        .unwrap_or_else(|| at.to_string())
+}
+
+/// Shows everything about one account on a single screen, with shortcuts to
+/// generate its code, edit it or share it
+fn account_details(storage: &mut Storage, events: &Events, config: &Config) -> Result<(), AppError> {
+    display_screen("Account Details");
+
+    let accounts = storage.get_accounts();
+    if accounts.is_empty() {
+        let width = get_terminal_width();
+        println!("{}", center_text("🦉 No accounts saved yet.", width).bright_red());
+        return wait_for_input();
+    }
+
+    let account = select_account(accounts)?;
+    let id = account.id().to_string();
+    events.emit(Event::Viewed { what: format!("details of account: {}", account.name()) })?;
+
+    loop {
+        // Read again after every action, which may have changed it
+        let Some(account) = storage.get_accounts().iter().find(|account| account.id() == id).cloned() else {
             return Ok(());
+        };
+
+        display_screen("Account Details");
+        print_account_details(&account, storage.generation_history().times(&id), config);
+
+        println!();
+        let action = Select::with_theme(&dialoguer::theme::ColorfulTheme::default())
+            .with_prompt("What would you like to do?")
+            .default(0)
+            .items(&["🔢 Generate its code", "📝 Edit it", "📤 Share it", "👈 Back"])
+            .interact_opt()
+            .chosen()?;
+
+        match action {
+            0 => {
+                display_screen("Generate TOTP");
+                show_code(storage, events, config, &account)?;
+            }
+            1 if storage.is_read_only() => {
+                display_screen("Read-Only Vault");
+                show_read_only_error(&storage::read_only_error(storage.file_path()))?;
+            }
+            1 => {
+                display_screen("Edit Account");
+                edit_selected_account(storage, account)?;
+            }
+            2 => {
+                display_screen("Share Account");
+                share_selected_account(events, &account)?;
+            }
+            _ => return Ok(()),
         }
+    }
+}
+
+/// Prints an account's settings, status and usage, with `history` the times
+/// its codes were generated, respecting privacy mode
+fn print_account_details(account: &Account, history: &[u64], config: &Config) {
+    println!();
+    println!("{} {}", "Name:".blue(), ui::with_appearance(account, &ui::shown_name(account)));
+    match account.issuer() {
+        Some(issuer) => println!("{} {}", "Issuer:".blue(), ui::shown_issuer(issuer)),
+        None => println!("{} None", "Issuer:".blue()),
+    }
+    println!("{} {}", "Digits:".blue(), account.digits());
+    println!("{} {} seconds", "Period:".blue(), account.period());
+    println!("{} {}", "Algorithm:".blue(), account.algorithm_name());
+    let secret = match account.secret_reference() {
+        None => "Stored in the vault",
+        Some(secret::SecretReference::Command(_)) => "Fetched by running a command each time",
+        Some(secret::SecretReference::Env(_)) => "Read from an environment variable each time",
     };
+    println!("{} {}", "Secret:".blue(), secret);
+    if account.tags().is_empty() {
+        println!("{} None", "Tags:".blue());
+    } else {
+        println!("{} {}", "Tags:".blue(), account.tags().join(", "));
+    }
+    if !account.aliases().is_empty() && !ui::is_private() {
+        println!("{} {}", "Aliases:".blue(), account.aliases().join(", "));
+    }
+    println!("{} {}", "Codes:".blue(), account.code_policy().label());
+    if config.is_high_value(account.tags()) {
+        println!("{} {}", "High-value:".blue(), "Yes, codes need the master password".yellow());
+    }
+
+    println!();
+    if account.is_verified() {
+        println!("{} {}", "Verified:".blue(), "✅ Yes".green());
+    } else {
+        println!("{} {}", "Verified:".blue(), "⚠ Not yet; use \"✅ Verify account setup\"".yellow());
+    }
+    if let Some(window) = account.verify_window() {
+        println!("{} {} period(s) before and after now", "Verify window:".blue(), window);
+    }
+    println!(
+        "{} {}",
+        "Archived:".blue(),
+        if account.is_archived() { "Yes" } else { "No" }
+    );
+    match account.recovery_codes() {
+        Some(codes) => println!("{} {} unused", "Recovery codes:".blue(), codes.remaining()),
+        None => println!("{} None saved", "Recovery codes:".blue()),
+    }
+
+    println!();
+    let known = |at: Option<u64>| at.map(format_timestamp).unwrap_or_else(|| "Unknown".to_string());
+    println!("{} {}", "Added:".blue(), known(account.created_at()));
+    println!("{} {}", "Last changed:".blue(), known(account.updated_at()));
+    match history.last() {
+        Some(&at) => println!("{} {}", "Last used:".blue(), format_timestamp(at)),
+        None => println!("{} Never", "Last used:".blue()),
+    }
+    println!("{} {} recorded", "Code history:".blue(), history.len());
+}
+
+/// Formats an account as "name (issuer)" for logs and command output; screens
+/// use [`ui::shown_label`], which respects privacy mode
+fn account_label(account: &Account) -> String {
+    match account.issuer() {
+        Some(issuer) => format!("{} ({})", account.name(), issuer),
+        None => account.name().to_string(),
+    }
+}
+
+/// Displays all saved accounts in a formatted table
+fn view_accounts(storage: &Storage, events: &Events) -> Result<(), AppError> {
+    display_screen("Saved Accounts");
 
-    let (digits, period, algorithm) = match get_totp_parameters() {
-        Ok(params) => params,
-        Err(e) => {
-            println!("{}", format!("⛔ Error: {}", e).red().bold());
-            println!();
-            println!(
-                "{}",
-                "Please try again with valid TOTP parameters.".bright_black()
-            );
-            wait_for_input()?;
-            return Ok(());
-        }
-    };
+    let accounts = storage.get_accounts();
 
-    let account = Account::new(
-        name.clone(),
-        secret,
-        digits,
-        period,
-        algorithm,
-        issuer.clone(),
-    );
+    if accounts.is_empty() {
+        let width = get_terminal_width();
+        println!(
+            "{}",
+            center_text("🦉 No accounts saved yet.", width).bright_red()
+        );
+        events.emit(Event::Viewed { what: "accounts (none saved)".to_string() })?;
+        return wait_for_input();
+    }
+
+    display_accounts_table(accounts);
+    let hidden = ui::hidden_archived(accounts);
+    if hidden > 0 {
+        println!(
+            "{}",
+            format!(
+                "📦 {} archived account(s) not shown; turn on \"Show archived accounts\" in the settings to list them.",
+                hidden
+            )
+            .bright_black()
+        );
+    }
+    events.emit(Event::Viewed { what: "all saved accounts".to_string() })?;
+    wait_for_input()
+}
+
+/// Generates a TOTP code for a selected account
+fn generate_totp(storage: &mut Storage, events: &Events, config: &Config) -> Result<(), AppError> {
+    let accounts = storage.get_accounts();
+
+    if accounts.is_empty() {
+        display_screen("Generate TOTP");
+        let width = get_terminal_width();
+        println!(
+            "{}",
+            center_text("🦉 No accounts saved yet.", width).bright_red()
+        );
+        events.note(Level::Warn, "Attempted to generate TOTP with no accounts")?;
+        return wait_for_input();
+    }
+
+    display_screen("Generate TOTP");
+
+    let account = pick_account_by_number(accounts)?.clone();
+    show_code(storage, events, config, &account)
+}
+
+/// Generates and shows the code of `account`, then keeps it on screen until
+/// the user moves on
+fn show_code(storage: &mut Storage, events: &Events, config: &Config, account: &Account) -> Result<(), AppError> {
+    if !confirm_high_value(storage, events, config, account)? {
+        return wait_for_input();
+    }
 
     println!();
-    let spinner = create_spinner("Saving account...".to_string());
+    show_spinner_delay("Generating TOTP code...", config.spinner_delay_ms);
 
-    match storage.add_account(account.clone()) {
-        Ok(_) => {
-            thread::sleep(Duration::from_millis(500));
-            spinner.finish_and_clear();
+    let totp_result = account.generate_totp();
+    let remaining = account.time_remaining();
 
-            logger.info(&format!("Added new account: {}", name))?;
-            println!("{}", "👌 Account added successfully, quack!".green().bold());
+    match totp_result {
+        Ok(totp) => {
+            let copy_text = clipboard_text(&config.copy_format, account, &totp);
+            let copied = display_totp_results(
+                account,
+                &totp,
+                &copy_text,
+                remaining,
+                clipboard_enabled(config),
+                config.auto_copy,
+                &config.code_grouping,
+            )?;
+            events.emit(Event::CodeGenerated { name: account.name().to_string() })?;
+            storage.record_generation(account.id());
+            let mut generated = || storage.record_generation(account.id());
+
+            if copied && config.keep_fresh_minutes > 0 {
+                events.note(Level::Info, format!(
+                    "Keeping clipboard fresh for account {} ({} minutes)",
+                    account.name(),
+                    config.keep_fresh_minutes
+                ))?;
+                return keep_clipboard_fresh(
+                    account,
+                    config.keep_fresh_minutes,
+                    &config.code_grouping,
+                    &config.copy_format,
+                    &mut generated,
+                );
+            }
+            return watch_code(
+                account,
+                &config.code_grouping,
+                config.expiry_warning_seconds,
+                config.expiry_bell,
+                copied,
+                &mut generated,
+            );
         }
         Err(e) => {
-            spinner.finish_and_clear();
-            println!("{}", format!("⛔ Error saving account: {}", e).red().bold());
-            println!();
+            println!("{}", "⛔ Error generating TOTP code, quack... *sniff*".red().bold());
             println!(
                 "{}",
-                "Please try again or check your storage file permissions.".bright_black()
+                "This account may have an invalid secret key.".bright_black()
+            );
+            println!(
+                "{}",
+                "Please delete this account and add it again with a valid key.".bright_black()
             );
+            events.emit(Event::CodeGenerationFailed { name: account.name().to_string(), error: e.to_string() })?;
         }
     }
 
     wait_for_input()
 }
 
-/// Gets account name and issuer from user input for a new account
-fn get_new_account_details() -> Result<(String, Option<String>), AppError> {
-    loop {
-        let name: String = Input::with_theme(&dialoguer::theme::ColorfulTheme::default())
-            .with_prompt("Account name (e.g., 'me@example.com', 'my-github-username')")
-            .interact_text()
-            .unwrap_or_default();
+/// Checks with the user before a code of a high-value account (see
+/// `high_value_tags`) is shown: by asking for the master password when the
+/// vault has one, or for a confirmation otherwise. Returns whether to go on.
+fn confirm_high_value(
+    storage: &Storage,
+    events: &Events,
+    config: &Config,
+    account: &Account,
+) -> Result<bool, AppError> {
+    if !config.is_high_value(account.tags()) {
+        return Ok(true);
+    }
 
-        let trimmed_name = name.trim().to_string();
+    println!();
+    println!(
+        "{}",
+        format!("🛡️  {} is a high-value account.", ui::shown_label(account)).yellow().bold()
+    );
 
-        if trimmed_name.is_empty() {
-            println!("{}", "⛔ Account name cannot be empty.".red());
+    let path = storage.file_path().to_string();
+    if encryption_mode(&path) != "password" {
+        let confirmed = Confirm::with_theme(&dialoguer::theme::ColorfulTheme::default())
+            .with_prompt("Show its code?")
+            .default(false)
+            .interact_opt()
+            .chosen()?;
+        if !confirmed {
+            println!("{}", "The code was not shown.".bright_black());
+        }
+        return Ok(confirmed);
+    }
+
+    let password = Password::with_theme(&dialoguer::theme::ColorfulTheme::default())
+        .with_prompt("Master password")
+        .interact()
+        .answered()?;
+    let spinner = create_spinner("Checking the master password...".to_string());
+    let unlocked = storage::unlock(&path, &password).is_ok();
+    spinner.finish_and_clear();
+
+    if !unlocked {
+        events.emit(Event::HighValueDenied { name: account_label(account) })?;
+        println!("{}", "⛔ Wrong master password; the code was not shown.".red().bold());
+    }
+    Ok(unlocked)
+}
+
+/// Gets and validates the secret key from user input, starting from the
+/// `encoding` and `input` given before, which are updated as the user answers
+fn get_validated_secret(encoding: &mut SecretEncoding, input: &mut String) -> Result<Step<String>, AppError> {
+    let encoding_labels: Vec<&str> = SecretEncoding::ALL.iter().map(|e| e.label()).collect();
+    let default = SecretEncoding::ALL.iter().position(|e| e == encoding).unwrap_or(0);
+    let encoding_selection = answer!(wizard::select("Secret key format", &encoding_labels, default));
+    *encoding = SecretEncoding::ALL[encoding_selection];
+
+    loop {
+        let secret_input = answer!(wizard::text("Secret key", input));
+        *input = secret_input.clone();
+
+        if secret_input.trim().is_empty() {
+            println!("{}", "⛔ Secret key cannot be empty.".red());
             println!();
             continue;
         }
 
-        let issuer: String = Input::with_theme(&dialoguer::theme::ColorfulTheme::default())
-            .with_prompt("Issuer (optional, e.g., 'Google', 'GitHub')")
-            .allow_empty(true)
-            .interact_text()
-            .unwrap_or_default();
+        if let Some(reference) = secret::SecretReference::parse(&secret_input) {
+            match reference.resolve() {
+                Ok(_) => {
+                    println!(
+                        "{}",
+                        "Only the reference is saved; the secret is fetched each time a code is generated.".bright_black()
+                    );
+                    return Ok(Step::Next(reference.to_string()));
+                }
+                Err(e) => {
+                    println!("{} {}", "⛔ Could not fetch the secret:".bright_red(), e.message());
+                    println!();
+                    continue;
+                }
+            }
+        }
 
-        return Ok((
-            trimmed_name,
-            if issuer.trim().is_empty() {
-                None
-            } else {
-                Some(issuer.trim().to_string())
-            },
+        let cleaned_secret = match secret::to_canonical_base32(&secret_input, *encoding) {
+            Ok(secret) => secret,
+            Err(AppError::InvalidInput(msg)) => {
+                println!("{} {}", "⛔ Invalid secret key:".bright_red(), msg);
+                println!();
+                continue;
+            }
+            Err(e) => return Err(e),
+        };
+
+        let secret_bytes = secret::decode_base32(&cleaned_secret)?;
+
+        let warnings = secret::sanity_warnings(&secret_input, *encoding, &secret_bytes);
+        if warnings.is_empty() {
+            return Ok(Step::Next(cleaned_secret));
+        }
+
+        println!();
+        for warning in &warnings {
+            println!("{} {}", "⚠️ ".yellow(), warning.yellow());
+        }
+        println!(
+            "{}",
+            "Codes generated from this key will probably be rejected by the service.".bright_black()
+        );
+
+        if answer!(wizard::confirm("Use this key anyway?", false)) {
+            return Ok(Step::Next(cleaned_secret));
+        }
+
+        println!();
+    }
+}
+
+/// Gets TOTP parameters (digits, period, algorithm) from user input, offering
+/// the issuer preset's known parameters first when there is one. The lists
+/// start from `current`, the parameters chosen before, if any.
+fn get_totp_parameters(
+    preset: Option<&IssuerPreset>,
+    current: Option<(usize, u64, Algorithm)>,
+) -> Result<Step<(usize, u64, Algorithm)>, AppError> {
+    if let Some(preset) = preset {
+        println!();
+        println!(
+            "{} {}",
+            format!("{} Known issuer:", preset.icon).green().bold(),
+            preset.name
+        );
+        println!(
+            "{}",
+            format!(
+                "  {} digits, {} seconds, {}",
+                preset.digits,
+                preset.period,
+                account::Algorithm::from(preset.algorithm).name()
+            )
+            .bright_black()
+        );
+
+        let preset_parameters = (preset.digits, preset.period, preset.algorithm);
+        let use_preset = answer!(wizard::confirm(
+            "Use these settings?",
+            current.is_none_or(|current| current == preset_parameters)
         ));
+
+        if use_preset {
+            return Ok(Step::Next(preset_parameters));
+        }
     }
+
+    let (current_digits, current_period, current_algorithm) = current.unwrap_or((6, 30, Algorithm::SHA1));
+
+    let digits_choices = [6, 7, 8];
+    let digits_options = &["6 digits", "7 digits", "8 digits"];
+    let default = digits_choices.iter().position(|&d| d == current_digits).unwrap_or(0);
+    let digits = digits_choices[answer!(wizard::select("Select digits", digits_options, default))];
+
+    let period_choices = [30, 60, 90];
+    let period_options = &["30 seconds", "60 seconds", "90 seconds"];
+    let default = period_choices.iter().position(|&p| p == current_period).unwrap_or(0);
+    let period = period_choices[answer!(wizard::select("Select refresh time", period_options, default))];
+
+    let algo_choices = [Algorithm::SHA1, Algorithm::SHA256, Algorithm::SHA512];
+    let algo_options = &["SHA1", "SHA256", "SHA512"];
+    let default = algo_choices.iter().position(|&a| a == current_algorithm).unwrap_or(0);
+    let algorithm = algo_choices[answer!(wizard::select("Select algorithm", algo_options, default))];
+
+    Ok(Step::Next((digits, period, algorithm)))
 }
 
-/// Gets account name and issuer from user input for editing an existing account
-fn get_edit_account_details(current_name: &str, current_issuer: Option<&str>) -> Result<(String, Option<String>), AppError> {
+/// Selects an account from the list of available accounts
+fn select_account(accounts: &[Account]) -> Result<&Account, AppError> {
+    let (order, account_names) = picker_items(accounts);
+    if order.len() == 1 {
+        println!(
+            "{} {}",
+            "Using the only available account:".blue(),
+            ui::shown_name(&accounts[order[0]])
+        );
+        return Ok(&accounts[order[0]]);
+    }
+
+    let selection = Select::with_theme(&dialoguer::theme::ColorfulTheme::default())
+        .with_prompt("Select an account")
+        .default(0)
+        .items(&account_names)
+        .interact_opt()
+        .chosen()?;
+
+    Ok(&accounts[order[selection]])
+}
+
+/// Account indices in [`display_order`] with their picker labels, prefixed
+/// with the issuer group heading when grouping by issuer
+fn picker_items(accounts: &[Account]) -> (Vec<usize>, Vec<String>) {
+    if !is_grouped_by_issuer() {
+        let order = display_order(accounts);
+        let labels = order
+            .iter()
+            .map(|&index| ui::with_appearance(&accounts[index], &ui::shown_label(&accounts[index])))
+            .collect();
+        return (order, labels);
+    }
+
+    issuer_groups(accounts)
+        .into_iter()
+        .flat_map(|(issuer, members)| {
+            let heading = group_heading(issuer, members.len());
+            members
+                .into_iter()
+                .map(move |index| {
+                    let name = ui::with_appearance(&accounts[index], &ui::shown_name(&accounts[index]));
+                    (index, format!("{} › {}", heading, name))
+                })
+        })
+        .unzip()
+}
+
+/// Shows the accounts table and asks for a row number, so an account in a
+/// long list can be picked without scrolling. "7" and "g 7" both pick row 7;
+/// leaving the input empty falls back to the list.
+fn pick_account_by_number(accounts: &[Account]) -> Result<&Account, AppError> {
+    let order = display_order(accounts);
+    if order.len() == 1 {
+        return select_account(accounts);
+    }
+
+    display_accounts_table(accounts);
+    println!();
+
     loop {
-        let name: String = Input::with_theme(&dialoguer::theme::ColorfulTheme::default())
-            .with_prompt("Account name (e.g., 'me@example.com', 'my-github-username')")
-            .default(current_name.to_string())
+        let input: String = Input::with_theme(&dialoguer::theme::ColorfulTheme::default())
+            .with_prompt(format!("Account # (1-{}, Enter to choose from a list)", order.len()))
+            .allow_empty(true)
             .interact_text()
-            .unwrap_or_else(|_| current_name.to_string());
+            .answered()?;
 
-        let trimmed_name = name.trim().to_string();
+        let input = input.trim();
+        let number = input
+            .strip_prefix(['g', 'G'])
+            .map(str::trim_start)
+            .unwrap_or(input);
+        if number.is_empty() {
+            return select_account(accounts);
+        }
 
-        if trimmed_name.is_empty() {
-            println!("{}", "⛔ Account name cannot be empty.".red());
-            println!();
-            continue;
+        match number.parse::<usize>() {
+            Ok(n) if (1..=order.len()).contains(&n) => return Ok(&accounts[order[n - 1]]),
+            _ => println!(
+                "{}",
+                format!("There is no account #{}; enter a number from the table.", number).red()
+            ),
         }
+    }
+}
 
-        let issuer: String = Input::with_theme(&dialoguer::theme::ColorfulTheme::default())
-            .with_prompt("Issuer (optional, e.g., 'Google', 'GitHub')")
-            .default(current_issuer.unwrap_or("").to_string())
-            .allow_empty(true)
-            .interact_text()
-            .unwrap_or_default();
+/// A setting that can be viewed and changed on its own from the settings menu
+#[derive(Clone, Copy)]
+enum Setting {
+    StorageFolder,
+    LogFile,
+    Syslog,
+    UseClipboard,
+    AutoCopy,
+    CopyFormat,
+    KeepFresh,
+    CodeGrouping,
+    MaskCodes,
+    ExpiryWarning,
+    ExpiryBell,
+    IdleBlank,
+    GroupByIssuer,
+    ShowArchived,
+    RecentFirst,
+    ShowTimestamps,
+    VaultEncryption,
+    HighValueTags,
+    VerifyWindow,
+    WebDav,
+}
 
-        return Ok((
-            trimmed_name,
-            if issuer.trim().is_empty() {
-                None
-            } else {
-                Some(issuer.trim().to_string())
+/// Sections of the settings menu and the settings each one lists
+const SETTINGS_SECTIONS: [(&str, &[Setting]); 6] = [
+    ("🗂️ Files and logging", &[Setting::StorageFolder, Setting::LogFile, Setting::Syslog]),
+    (
+        "📋 Clipboard",
+        &[Setting::UseClipboard, Setting::AutoCopy, Setting::CopyFormat, Setting::KeepFresh],
+    ),
+    (
+        "🔢 Codes on screen",
+        &[
+            Setting::CodeGrouping,
+            Setting::MaskCodes,
+            Setting::ExpiryWarning,
+            Setting::ExpiryBell,
+            Setting::IdleBlank,
+        ],
+    ),
+    (
+        "📚 Account lists",
+        &[Setting::GroupByIssuer, Setting::ShowArchived, Setting::RecentFirst, Setting::ShowTimestamps],
+    ),
+    ("🔐 Security", &[Setting::VaultEncryption, Setting::HighValueTags, Setting::VerifyWindow]),
+    ("☁️ WebDAV sync", &[Setting::WebDav]),
+];
+
+impl Setting {
+    fn label(self) -> &'static str {
+        match self {
+            Self::StorageFolder => "Storage folder",
+            Self::LogFile => "Log file",
+            Self::Syslog => "Also log to the system log",
+            Self::UseClipboard => "Use the clipboard",
+            Self::AutoCopy => "Copy codes automatically",
+            Self::CopyFormat => "What is copied",
+            Self::KeepFresh => "Keep the clipboard fresh",
+            Self::CodeGrouping => "Code grouping",
+            Self::MaskCodes => "Mask codes until Space is held",
+            Self::ExpiryWarning => "Warn before a code expires",
+            Self::ExpiryBell => "Ring the bell with the warning",
+            Self::IdleBlank => "Hide a code when idle",
+            Self::GroupByIssuer => "Group by issuer",
+            Self::ShowArchived => "Show archived accounts",
+            Self::RecentFirst => "Recently added first",
+            Self::ShowTimestamps => "Show added and changed times",
+            Self::VaultEncryption => "Vault encryption",
+            Self::HighValueTags => "High-value tags",
+            Self::VerifyWindow => "Clock skew accepted",
+            Self::WebDav => "WebDAV sync",
+        }
+    }
+
+    /// Whether the setting does anything with the others as they are: the
+    /// clipboard ones only when it is used, the bell only with a warning
+    fn applies(self, config: &Config) -> bool {
+        match self {
+            Self::Syslog => cfg!(unix),
+            Self::UseClipboard => cfg!(feature = "clipboard"),
+            Self::AutoCopy | Self::CopyFormat | Self::KeepFresh => clipboard_enabled(config),
+            Self::ExpiryBell => config.expiry_warning_seconds > 0,
+            _ => true,
+        }
+    }
+
+    /// The current value, as listed next to the label
+    fn value(self, storage: &Storage, config: &Config) -> String {
+        let on_off = |on: bool| if on { "On" } else { "Off" }.to_string();
+        let amount = |n: u64, unit: &str| if n == 0 { "Off".to_string() } else { format!("{} {}", n, unit) };
+
+        match self {
+            Self::StorageFolder => config.storage.path.clone(),
+            Self::LogFile => config.get_log_file_path(),
+            Self::Syslog => on_off(config.syslog),
+            Self::UseClipboard => on_off(!config.disable_clipboard),
+            Self::AutoCopy => on_off(config.auto_copy),
+            Self::CopyFormat => config.copy_format.clone(),
+            Self::KeepFresh => amount(config.keep_fresh_minutes, "min"),
+            Self::CodeGrouping => config.code_grouping.clone(),
+            Self::MaskCodes => on_off(config.mask_codes),
+            Self::ExpiryWarning => amount(config.expiry_warning_seconds, "s"),
+            Self::ExpiryBell => on_off(config.expiry_bell),
+            Self::IdleBlank => amount(config.idle_blank_seconds, "s"),
+            Self::GroupByIssuer => on_off(config.group_by_issuer),
+            Self::ShowArchived => on_off(config.show_archived),
+            Self::RecentFirst => on_off(config.recent_first),
+            Self::ShowTimestamps => on_off(config.show_timestamps),
+            Self::VaultEncryption => match encryption_mode(storage.file_path()) {
+                "password" if pin::is_set_up(storage.file_path()) => "Master password, PIN unlock".to_string(),
+                "password" => "Master password".to_string(),
+                "windows" => "Windows account".to_string(),
+                _ => "Not encrypted".to_string(),
             },
-        ));
+            Self::HighValueTags if config.high_value_tags.is_empty() => "None".to_string(),
+            Self::HighValueTags => config.high_value_tags.join(", "),
+            Self::VerifyWindow => format!("{} period(s) either way", config.verify_window),
+            Self::WebDav => config.webdav.as_ref().map_or_else(|| "Off".to_string(), |webdav| webdav.url.clone()),
+        }
     }
 }
 
-/// Edits an account in storage
-fn edit_account(storage: &mut Storage, logger: &mut Logger) -> Result<(), AppError> {
-    display_screen("Edit Account");
-
-    let accounts = storage.get_accounts()?;
+/// Settings menu: pick a section to see its settings with their values, then
+/// change any one of them on its own
+fn configure_settings(storage: &mut Storage, events: &Events, config: &mut Config) -> Result<(), AppError> {
+    loop {
+        display_screen("Configure Settings");
 
-    if accounts.is_empty() {
-        let width = get_terminal_width();
+        println!("{}", "Configure your Quackey settings".green().bold());
         println!(
             "{}",
-            center_text("🦉 No accounts saved yet.", width).bright_red()
+            "Pick a section to see its settings; each one is saved as soon as you change it.".bright_black()
         );
-        logger.warn("Attempted to edit account with no accounts")?;
-        return wait_for_input();
+        println!();
+
+        let mut sections: Vec<&str> = SETTINGS_SECTIONS.iter().map(|(title, _)| *title).collect();
+        sections.push("👈 Back to main menu");
+
+        // Esc goes back to the main menu
+        let selection = Select::with_theme(&dialoguer::theme::ColorfulTheme::default())
+            .with_prompt("Select a section")
+            .default(0)
+            .items(&sections)
+            .interact_opt()
+            .chosen()?;
+        if selection == SETTINGS_SECTIONS.len() {
+            return Ok(());
+        }
+
+        // Backing out of a section returns to this menu
+        match settings_section(selection, storage, events, config) {
+            Err(e) if matches!(e.kind(), AppError::Cancelled) => {}
+            result => result?,
+        }
+    }
+}
+
+/// Lists the settings of one section with their current values and changes
+/// the one picked, until the user goes back
+fn settings_section(
+    section: usize,
+    storage: &mut Storage,
+    events: &Events,
+    config: &mut Config,
+) -> Result<(), AppError> {
+    let (title, all) = SETTINGS_SECTIONS[section];
+
+    loop {
+        display_screen(title);
+
+        // Read again after every change, which may show or hide others
+        let settings: Vec<Setting> = all.iter().copied().filter(|setting| setting.applies(config)).collect();
+        if settings.is_empty() {
+            println!("{}", "Nothing to change here with the clipboard turned off.".bright_black());
+            return wait_for_input();
+        }
+
+        let width = settings.iter().map(|setting| setting.label().chars().count()).max().unwrap_or(0);
+        let mut items: Vec<String> = settings
+            .iter()
+            .map(|setting| {
+                format!(
+                    "{:<width$}  {}",
+                    setting.label(),
+                    setting.value(storage, config).bright_black(),
+                    width = width
+                )
+            })
+            .collect();
+        items.push("👈 Back".to_string());
+
+        let selection = Select::with_theme(&dialoguer::theme::ColorfulTheme::default())
+            .with_prompt("Select a setting to change")
+            .default(0)
+            .items(&items)
+            .interact_opt()
+            .chosen()?;
+        if selection == settings.len() {
+            return Ok(());
+        }
+
+        // Backing out of the prompt leaves the setting as it was
+        match change_setting(settings[selection], storage, events, config) {
+            Err(e) if matches!(e.kind(), AppError::Cancelled) => {}
+            result => result?,
+        }
+    }
+}
+
+/// Asks for one setting's new value, applies it and saves the configuration
+fn change_setting(
+    setting: Setting,
+    storage: &mut Storage,
+    events: &Events,
+    config: &mut Config,
+) -> Result<(), AppError> {
+    display_screen(setting.label());
+
+    match setting {
+        Setting::StorageFolder => return change_storage_folder(storage, events, config),
+        Setting::LogFile => return change_log_folder(events, config),
+        Setting::Syslog => {
+            config.syslog = Confirm::with_theme(&dialoguer::theme::ColorfulTheme::default())
+                .with_prompt("Also send log entries to the system log?")
+                .default(config.syslog)
+                .interact_opt()
+                .chosen()?;
+            events.configure(config)?;
+        }
+        Setting::UseClipboard => {
+            config.disable_clipboard = !Confirm::with_theme(&dialoguer::theme::ColorfulTheme::default())
+                .with_prompt("Use the clipboard at all?")
+                .default(!config.disable_clipboard)
+                .interact_opt()
+                .chosen()?;
+        }
+        Setting::AutoCopy => {
+            config.auto_copy = Confirm::with_theme(&dialoguer::theme::ColorfulTheme::default())
+                .with_prompt("Copy generated codes to the clipboard automatically?")
+                .default(config.auto_copy)
+                .interact_opt()
+                .chosen()?;
+        }
+        Setting::CopyFormat => config.copy_format = get_copy_format(&config.copy_format)?,
+        Setting::KeepFresh => {
+            config.keep_fresh_minutes = Input::with_theme(&dialoguer::theme::ColorfulTheme::default())
+                .with_prompt("Keep the clipboard fresh for how many minutes after copying? (0 to disable)")
+                .default(config.keep_fresh_minutes)
+                .interact_text()
+                .answered()?;
+        }
+        Setting::CodeGrouping => config.code_grouping = get_code_grouping(&config.code_grouping)?,
+        Setting::MaskCodes => {
+            config.mask_codes = Confirm::with_theme(&dialoguer::theme::ColorfulTheme::default())
+                .with_prompt("Mask codes on screen until you hold Space?")
+                .default(config.mask_codes)
+                .interact_opt()
+                .chosen()?;
+            ui::set_mask_codes(config.mask_codes);
+        }
+        Setting::ExpiryWarning => {
+            config.expiry_warning_seconds = Input::with_theme(&dialoguer::theme::ColorfulTheme::default())
+                .with_prompt("Warn how many seconds before a shown code expires? (0 to disable)")
+                .default(config.expiry_warning_seconds)
+                .interact_text()
+                .answered()?;
+        }
+        Setting::ExpiryBell => {
+            config.expiry_bell = Confirm::with_theme(&dialoguer::theme::ColorfulTheme::default())
+                .with_prompt("Ring the terminal bell with the warning?")
+                .default(config.expiry_bell)
+                .interact_opt()
+                .chosen()?;
+        }
+        Setting::IdleBlank => {
+            config.idle_blank_seconds = Input::with_theme(&dialoguer::theme::ColorfulTheme::default())
+                .with_prompt("Hide a shown code after how many seconds without a key press? (0 to disable)")
+                .default(config.idle_blank_seconds)
+                .interact_text()
+                .answered()?;
+            ui::set_idle_blank(config.idle_blank_seconds);
+        }
+        Setting::GroupByIssuer => {
+            config.group_by_issuer = Confirm::with_theme(&dialoguer::theme::ColorfulTheme::default())
+                .with_prompt("Group account lists by issuer?")
+                .default(config.group_by_issuer)
+                .interact_opt()
+                .chosen()?;
+            ui::set_group_by_issuer(config.group_by_issuer);
+        }
+        Setting::ShowArchived => {
+            config.show_archived = Confirm::with_theme(&dialoguer::theme::ColorfulTheme::default())
+                .with_prompt("Show archived accounts in account lists?")
+                .default(config.show_archived)
+                .interact_opt()
+                .chosen()?;
+            ui::set_show_archived(config.show_archived);
+        }
+        Setting::RecentFirst => {
+            config.recent_first = Confirm::with_theme(&dialoguer::theme::ColorfulTheme::default())
+                .with_prompt("List recently added accounts first?")
+                .default(config.recent_first)
+                .interact_opt()
+                .chosen()?;
+            ui::set_recent_first(config.recent_first);
+        }
+        Setting::ShowTimestamps => {
+            config.show_timestamps = Confirm::with_theme(&dialoguer::theme::ColorfulTheme::default())
+                .with_prompt("Show when accounts were added and last changed in the accounts table?")
+                .default(config.show_timestamps)
+                .interact_opt()
+                .chosen()?;
+            ui::set_show_timestamps(config.show_timestamps);
+        }
+        Setting::VaultEncryption => {
+            configure_vault_lock(storage, events)?;
+            config.encryption = encryption_mode(storage.file_path()).to_string();
+        }
+        Setting::HighValueTags => {
+            let high_value: String = Input::with_theme(&dialoguer::theme::ColorfulTheme::default())
+                .with_prompt("Tags of high-value accounts, whose codes need the master password (comma-separated)")
+                .default(config.high_value_tags.join(", "))
+                .allow_empty(true)
+                .interact_text()
+                .answered()?;
+            config.high_value_tags = split_list(&high_value);
+        }
+        Setting::VerifyWindow => {
+            config.verify_window = Input::with_theme(&dialoguer::theme::ColorfulTheme::default())
+                .with_prompt("Periods of clock skew to accept when checking a code")
+                .default(config.verify_window)
+                .validate_with(|window: &u64| {
+                    if *window <= account::MAX_VERIFY_WINDOW {
+                        Ok(())
+                    } else {
+                        Err(format!("The most is {} periods", account::MAX_VERIFY_WINDOW))
+                    }
+                })
+                .interact_text()
+                .answered()?;
+        }
+        Setting::WebDav => config.webdav = get_webdav_settings(config.webdav.clone())?,
     }
 
-    let account = select_account(&accounts)?;
+    // The WebDAV password is saved sealed with the vault key, which may have changed
+    if let Some(webdav) = config.webdav.as_mut() {
+        webdav.seal_password(storage.key());
+    }
+    config.save()?;
+    events.emit(Event::SettingChanged { setting: setting.label().to_string() })?;
 
-    println!();
-    println!("{}", "Current account details:".green().bold());
-    println!("{} {}", "Name:".blue(), account.name());
-    if let Some(issuer) = account.issuer() {
-        println!("{} {}", "Issuer:".blue(), issuer);
-    } else {
-        println!("{} {}", "Issuer:".blue(), "None");
+    // Keep what setting up the vault lock printed on screen
+    if matches!(setting, Setting::VaultEncryption) {
+        wait_for_input()?;
     }
-    println!("{} {}", "Digits:".blue(), account.digits());
-    println!("{} {} seconds", "Period:".blue(), account.period());
-    println!(
-        "{} {}",
-        "Algorithm:".blue(),
-        match account.algorithm() {
-            Algorithm::SHA1 => "SHA1",
-            Algorithm::SHA256 => "SHA256",
-            Algorithm::SHA512 => "SHA512",
-        }
-    );
-    println!();
+    Ok(())
+}
 
-    println!(
-        "{}",
-        "Enter new details (press Enter to keep current value):".bright_black()
-    );
+/// Asks for a new log folder and moves the log there, or starts a new one
+/// there, keeping the folder as it was unless that went through
+fn change_log_folder(events: &Events, config: &mut Config) -> Result<(), AppError> {
+    let previous = config.log.path.clone();
+    let switched = switch_log_folder(events, config);
+    if !matches!(switched, Ok(true)) {
+        config.log.path = previous;
+    }
 
-    let (name, issuer) = match get_edit_account_details(account.name(), account.issuer().map(|s| s.as_str())) {
-        Ok(details) => details,
-        Err(e) => return Err(e),
-    };
+    if switched? {
+        config.save()?;
+        events.emit(Event::SettingChanged { setting: Setting::LogFile.label().to_string() })?;
+    }
+    wait_for_input()
+}
+
+/// Moves the log to the folder the user enters, or starts a new log there
+/// and leaves the old one where it is. Returns `false` when the folder is
+/// the same.
+fn switch_log_folder(events: &Events, config: &mut Config) -> Result<bool, AppError> {
+    let old_path = config.get_log_file_path();
+    config.log.path = get_file_path("log file", &config.log.path)?;
+    config.validate_paths()?;
+    config.ensure_directories()?;
 
-    storage.update_account(account.name(), name.clone(), issuer.clone())?;
-    logger.info(&format!("Updated account: {}", name))?;
+    let new_path = config.get_log_file_path();
+    if storage::same_file(&new_path, &old_path) {
+        println!();
+        println!("{}", "The log folder is unchanged.".bright_black());
+        return Ok(false);
+    }
 
     println!();
-    println!("{}", "✅ Account updated successfully!".green().bold());
-
-    wait_for_input()
-}
+    println!("{}", "Changing log file path:".bright_black());
+    println!("{} {}", "From:".blue(), old_path);
+    println!("{} {}", "To:".blue(), new_path);
+    println!();
 
-/// Deletes an account from storage
-fn delete_account(storage: &mut Storage, logger: &mut Logger) -> Result<(), AppError> {
-    let accounts = storage.get_accounts()?;
+    let choice = Select::with_theme(&dialoguer::theme::ColorfulTheme::default())
+        .with_prompt("What should happen to the entries logged so far?")
+        .default(0)
+        .items(&[
+            "🚚 Move them to the new log",
+            "🆕 Leave them in the old log and start a new one",
+        ])
+        .interact_opt()
+        .chosen()?;
 
-    if accounts.is_empty() {
-        display_screen("Delete Account");
-        let width = get_terminal_width();
+    events.relocate_log(&new_path, choice == 1)?;
+    println!("{}", "✅ Log file path updated successfully!".green().bold());
+    if choice == 1 {
         println!(
             "{}",
-            center_text("🦉 No accounts saved yet.", width).bright_red()
+            format!("📜 The old log stays at {}, ending with a note saying where the new one is.", old_path).bright_black()
         );
-        logger.warn("Attempted to delete account with no accounts")?;
-        return wait_for_input();
     }
+    Ok(true)
+}
 
-    display_screen("Delete Account");
+/// Asks for a new storage folder and switches the vault to the storage file
+/// there, keeping the folder as it was unless the switch went through
+fn change_storage_folder(storage: &mut Storage, events: &Events, config: &mut Config) -> Result<(), AppError> {
+    let previous = config.storage.path.clone();
+    let switched = switch_storage_folder(storage, config);
+    if !matches!(switched, Ok(true)) {
+        config.storage.path = previous;
+    }
 
-    let account = select_account(&accounts)?;
+    if switched? {
+        config.save()?;
+        events.emit(Event::SettingChanged { setting: Setting::StorageFolder.label().to_string() })?;
+    }
+    wait_for_input()
+}
 
-    let confirm = Confirm::with_theme(&dialoguer::theme::ColorfulTheme::default())
-        .with_prompt(format!(
-            "Are you sure you want to delete the account '{}'?",
-            account.name()
-        ))
-        .default(false)
-        .interact()
-        .unwrap_or(false);
+/// Switches the vault to the storage file in the folder the user enters,
+/// moving or copying the current one there, or opening or starting the one
+/// there. Returns `false` when the folder is the same.
+fn switch_storage_folder(storage: &mut Storage, config: &mut Config) -> Result<bool, AppError> {
+    config.storage.path = get_file_path("accounts storage file", &config.storage.path)?;
+    config.validate_paths()?;
+    config.ensure_directories()?;
 
-    if !confirm {
+    let old_path = storage.file_path().to_string();
+    let new_path = config.get_storage_file_path();
+    if storage::same_file(&new_path, &old_path) {
         println!();
-        println!("{}", "Account deletion cancelled.".bright_black());
-        return wait_for_input();
+        println!("{}", "The storage folder is unchanged.".bright_black());
+        return Ok(false);
     }
 
-    storage.delete_account(account.name())?;
-    logger.info(&format!("Deleted account: {}", account.name()))?;
-
     println!();
-    println!("{}", "✅ Account deleted successfully!".green().bold());
-
-    wait_for_input()
-}
-
-/// Displays all saved accounts in a formatted table
-fn view_accounts(storage: &Storage, logger: &mut Logger) -> Result<(), AppError> {
-    display_screen("Saved Accounts");
-
-    let accounts = storage.get_accounts()?;
+    println!("{}", "Changing storage file path:".bright_black());
+    println!("{} {}", "From:".blue(), old_path);
+    println!("{} {}", "To:".blue(), new_path);
+    println!();
 
-    if accounts.is_empty() {
-        let width = get_terminal_width();
+    let taken = std::path::Path::new(&new_path).exists();
+    if taken {
+        println!("{}", "⚠️  The new folder already has a storage file.".yellow().bold());
         println!(
             "{}",
-            center_text("🦉 No accounts saved yet.", width).bright_red()
+            "Bringing your accounts replaces it; it is kept as a snapshot in the backups folder there.".bright_black()
         );
-        logger.info("Viewed accounts (none saved)")?;
-        return wait_for_input();
+        println!();
     }
 
-    display_accounts_table(&accounts);
-    logger.info("Viewed all saved accounts")?;
-    wait_for_input()
-}
+    let mut choices = Vec::new();
+    if std::path::Path::new(&old_path).exists() {
+        choices.push("🚚 Move my accounts there, with their snapshots and keys");
+        choices.push("📄 Copy my accounts there, keeping the current files too");
+    }
+    choices.push(if taken {
+        "📂 Open the accounts already there instead"
+    } else {
+        "🆕 Start with no accounts there"
+    });
 
-/// Generates a TOTP code for a selected account
-fn generate_totp(storage: &Storage, logger: &mut Logger) -> Result<(), AppError> {
-    let accounts = storage.get_accounts()?;
+    let choice = Select::with_theme(&dialoguer::theme::ColorfulTheme::default())
+        .with_prompt("What should happen to your accounts?")
+        .default(0)
+        .items(&choices)
+        .interact_opt()
+        .chosen()?;
 
-    if accounts.is_empty() {
-        display_screen("Generate TOTP");
-        let width = get_terminal_width();
+    if choice + 1 < choices.len() {
+        let keep_old = choice == 1;
+        let replaced = storage.relocate(&new_path, keep_old)?;
         println!(
             "{}",
-            center_text("🦉 No accounts saved yet.", width).bright_red()
+            if keep_old {
+                "✅ Your accounts were copied to the new folder."
+            } else {
+                "✅ Your accounts were moved to the new folder."
+            }
+            .green()
+            .bold()
         );
-        logger.warn("Attempted to generate TOTP with no accounts")?;
-        return wait_for_input();
-    }
-
-    display_screen("Generate TOTP");
-
-    let account = select_account(&accounts)?;
-
-    println!();
-    let spinner = create_spinner("Generating TOTP code...".to_string());
-
-    let totp_result = account.generate_totp();
-    let remaining = account.time_remaining();
-
-    thread::sleep(Duration::from_millis(500));
-    spinner.finish_and_clear();
-
-    match totp_result {
-        Ok(totp) => {
-            display_totp_results(&totp, remaining)?;
-            logger.info(&format!("Generated TOTP for account: {}", account.name()))?;
-        }
-        Err(e) => {
-            println!("{}", "⛔ Error generating TOTP code, quack... *sniff*".red().bold());
-            println!(
-                "{}",
-                "This account may have an invalid secret key.".bright_black()
-            );
+        if let Some(path) = replaced {
             println!(
                 "{}",
-                "Please delete this account and add it again with a valid key.".bright_black()
+                format!("🗄️  The storage file that was there is saved to {}", path.display()).bright_black()
             );
-            logger.error(&format!(
-                "Failed to generate TOTP for account {}: {}",
-                account.name(),
-                e
-            ))?;
         }
+    } else {
+        let snapshot = storage.snapshot("move")?;
+        storage.update_file_path(&new_path)?;
+        println!(
+            "{}",
+            "✅ Storage file path updated successfully!".green().bold()
+        );
+        print_snapshot_note(snapshot.as_deref());
     }
 
-    wait_for_input()
+    // The session marker follows the vault
+    integrity::end_session(&old_path)?;
+    integrity::check(&new_path)?;
+    Ok(true)
 }
 
-/// Gets and validates the secret key from user input
-fn get_validated_secret() -> Result<String, AppError> {
-    loop {
-        let secret_input: String = Input::with_theme(&dialoguer::theme::ColorfulTheme::default())
-            .with_prompt("Secret key")
-            .interact_text()
-            .unwrap_or_default();
-
-        let cleaned_secret = secret_input.trim().replace(" ", "").to_uppercase();
+/// Sets or removes the master password, and PIN quick-unlock on top of it
+fn configure_vault_lock(storage: &mut Storage, events: &Events) -> Result<(), AppError> {
+    println!();
 
-        if cleaned_secret.is_empty() {
-            println!("{}", "⛔ Secret key cannot be empty.".red());
-            println!();
-            continue;
+    if !storage.is_encrypted() {
+        let mut options = vec!["Keep the vault unencrypted", "🔐 Encrypt with a master password"];
+        if dpapi::is_available() {
+            options.push("🪟 Protect with my Windows account (no password)");
         }
+        let choice = Select::with_theme(&dialoguer::theme::ColorfulTheme::default())
+            .with_prompt("Vault encryption")
+            .default(0)
+            .items(&options)
+            .interact_opt()
+            .chosen()?;
 
-        if cleaned_secret.len() < 26 {
+        if choice == 0 {
+            return Ok(());
+        }
+        if choice == 2 {
             println!(
                 "{}",
-                "⛔ Secret key is too short. It must be at least 26 characters long.".red()
+                "Only your Windows user on this machine can open the vault. Anyone who can log in as you still can, and a copy won't open anywhere else.".bright_black()
             );
-            println!();
-            continue;
+            storage.set_key(Some(dpapi::set_up(storage.file_path())?))?;
+            events.emit(Event::LockChanged(LockChange::DpapiSet))?;
+            println!("{}", "🔐 The vault is now protected by your Windows account.".green().bold());
+            return wipe_plaintext_backups(storage.file_path(), events);
         }
 
-        let spinner = create_spinner("Validating secret key...".to_string());
-        let test_totp = TOTP::new(
-            Algorithm::SHA1,
-            6,
-            1,
-            30,
-            cleaned_secret.clone().into_bytes(),
+        println!(
+            "{}",
+            "Without the master password your accounts cannot be recovered. Keep it somewhere safe.".yellow()
         );
+        let Some(passphrase) = ask_new_master_password()? else {
+            println!("{}", "No password entered; the vault stays unencrypted.".bright_black());
+            return Ok(());
+        };
 
-        thread::sleep(Duration::from_millis(500));
-        spinner.finish_and_clear();
+        storage.set_key(Some(VaultKey::derive(&passphrase)))?;
+        events.emit(Event::LockChanged(LockChange::PasswordSet))?;
+        println!("{}", "🔐 The vault is now encrypted.".green().bold());
+        return wipe_plaintext_backups(storage.file_path(), events);
+    }
 
-        match test_totp {
-            Ok(_) => return Ok(cleaned_secret),
-            Err(e) => {
-                println!("{} {}", "⛔ Invalid secret key:".bright_red(), e);
-                println!();
-                continue;
-            }
+    let path = storage.file_path().to_string();
+    if dpapi::is_set_up(&path) {
+        let remove = Confirm::with_theme(&dialoguer::theme::ColorfulTheme::default())
+            .with_prompt("The vault is protected by your Windows account. Store it as plain text again?")
+            .default(false)
+            .interact_opt()
+            .chosen()?;
+        if remove {
+            storage.set_key(None)?;
+            dpapi::remove(&path)?;
+            events.emit(Event::LockChanged(LockChange::DpapiRemoved))?;
+            println!("{}", "🔓 The vault is stored as plain text.".yellow().bold());
         }
+        return Ok(());
     }
-}
 
-/// Gets TOTP parameters (digits, period, algorithm) from user input
-fn get_totp_parameters() -> Result<(usize, u64, Algorithm), AppError> {
-    let digits_options = &["6 digits", "7 digits", "8 digits"];
-    let digits_selection = Select::with_theme(&dialoguer::theme::ColorfulTheme::default())
-        .with_prompt("Select digits")
+    let has_pin = pin::is_set_up(&path);
+    let options = [
+        "Keep the current security settings",
+        "🔑 Change the master password",
+        if has_pin { "🔢 Turn off PIN unlock" } else { "🔢 Set up a PIN for this session" },
+        "🔓 Remove the master password",
+    ];
+    let choice = Select::with_theme(&dialoguer::theme::ColorfulTheme::default())
+        .with_prompt("Vault encryption")
         .default(0)
-        .items(digits_options)
-        .interact()
-        .unwrap_or(0);
+        .items(&options)
+        .interact_opt()
+        .chosen()?;
 
-    let digits = match digits_selection {
-        0 => 6,
-        1 => 7,
-        2 => 8,
-        _ => 6,
-    };
+    match choice {
+        1 => change_master_password(storage, events)?,
+        2 if has_pin => {
+            pin::remove(&path)?;
+            events.emit(Event::LockChanged(LockChange::PinRemoved))?;
+            println!("{}", "✅ PIN unlock turned off.".green().bold());
+        }
+        2 => {
+            println!(
+                "{}",
+                format!(
+                    "The PIN unlocks the vault until you log out or restart. After {} wrong PINs the master password is needed.",
+                    pin::MAX_ATTEMPTS
+                ).bright_black()
+            );
+            let entered = Password::with_theme(&dialoguer::theme::ColorfulTheme::default())
+                .with_prompt("New PIN (4-8 digits)")
+                .with_confirmation("Confirm PIN", "⛔ PINs don't match.")
+                .interact()
+                .answered()?;
+            let key = storage.key().expect("vault is encrypted");
+            match pin::set_up(&path, &entered, key) {
+                Ok(()) => {
+                    events.emit(Event::LockChanged(LockChange::PinSetUp))?;
+                    println!("{}", "✅ PIN unlock is set up.".green().bold());
+                }
+                Err(e) => println!("{}", format!("⛔ {}", e).red().bold()),
+            }
+        }
+        3 => {
+            let confirmed = Confirm::with_theme(&dialoguer::theme::ColorfulTheme::default())
+                .with_prompt("Store your accounts as plain text again?")
+                .default(false)
+                .interact_opt()
+                .chosen()?;
+            if confirmed {
+                storage.set_key(None)?;
+                pin::remove(&path)?;
+                events.emit(Event::LockChanged(LockChange::EncryptionRemoved))?;
+                println!("{}", "🔓 The vault is stored as plain text.".yellow().bold());
+            }
+        }
+        _ => {}
+    }
 
-    let period_options = &["30 seconds", "60 seconds", "90 seconds"];
-    let period_selection = Select::with_theme(&dialoguer::theme::ColorfulTheme::default())
-        .with_prompt("Select refresh time")
-        .default(0)
-        .items(period_options)
-        .interact()
-        .unwrap_or(0);
+    Ok(())
+}
 
-    let period = match period_selection {
-        0 => 30,
-        1 => 60,
-        2 => 90,
-        _ => 30,
-    };
+/// Replaces the master password after checking the current one, and
+/// re-encrypts the vault and its retained backups with the new one. PIN unlock
+/// wraps the old key, so it is turned off.
+fn change_master_password(storage: &mut Storage, events: &Events) -> Result<(), AppError> {
+    let path = storage.file_path().to_string();
 
-    let algo_options = &["SHA1", "SHA256", "SHA512"];
-    let algo_selection = Select::with_theme(&dialoguer::theme::ColorfulTheme::default())
-        .with_prompt("Select algorithm")
-        .default(0)
-        .items(algo_options)
+    let current = Password::with_theme(&dialoguer::theme::ColorfulTheme::default())
+        .with_prompt("Current master password")
         .interact()
-        .unwrap_or(0);
+        .answered()?;
+    if storage::unlock(&path, &current).is_err() {
+        events.note(Level::Warn, "Master password change failed: wrong current password")?;
+        println!("{}", "⛔ Wrong master password; nothing was changed.".red().bold());
+        return Ok(());
+    }
 
-    let algorithm = match algo_selection {
-        0 => Algorithm::SHA1,
-        1 => Algorithm::SHA256,
-        2 => Algorithm::SHA512,
-        _ => Algorithm::SHA1,
+    let Some(passphrase) = ask_new_master_password()? else {
+        println!("{}", "No password entered; the master password stays the same.".bright_black());
+        return Ok(());
     };
+    if passphrase == current {
+        println!("{}", "⛔ That is the current master password; nothing was changed.".red().bold());
+        return Ok(());
+    }
 
-    Ok((digits, period, algorithm))
-}
+    let old_key = storage.key().expect("vault is encrypted").clone();
+    let spinner = create_spinner("Re-encrypting the vault and its backups...".to_string());
+    let new_key = VaultKey::derive(&passphrase);
+    let result = storage
+        .set_key(Some(new_key.clone()))
+        .and_then(|()| storage::reencrypt_backups(&path, &old_key, &new_key));
+    spinner.finish_and_clear();
+    let (updated, skipped) = result?;
 
-/// Selects an account from the list of available accounts
-fn select_account(accounts: &[Account]) -> Result<&Account, AppError> {
-    if accounts.len() == 1 {
+    let had_pin = pin::is_set_up(&path);
+    pin::remove(&path)?;
+    events.emit(Event::LockChanged(LockChange::PasswordChanged { reencrypted: updated, skipped: skipped.len() }))?;
+
+    println!("{}", "🔑 The master password is changed.".green().bold());
+    println!("{}", format!("Re-encrypted the vault and {} backup(s).", updated).bright_black());
+    if !skipped.is_empty() {
         println!(
-            "{} {}",
-            "Using the only available account:".blue(),
-            accounts[0].name()
+            "{}",
+            format!(
+                "⚠️  {} backup(s) use an older master password and still open with it:",
+                skipped.len()
+            )
+            .yellow()
         );
-        return Ok(&accounts[0]);
+        for backup in &skipped {
+            println!("  {}", backup.display());
+        }
     }
-
-    let account_names: Vec<String> = accounts
-        .iter()
-        .map(|a| {
-            if let Some(issuer) = a.issuer() {
-                format!("{} ({})", a.name(), issuer)
-            } else {
-                a.name().to_string()
-            }
-        })
-        .collect();
-
-    let selection = Select::with_theme(&dialoguer::theme::ColorfulTheme::default())
-        .with_prompt("Select an account")
-        .default(0)
-        .items(&account_names)
-        .interact()
-        .unwrap_or(0);
-
-    Ok(&accounts[selection])
+    if had_pin {
+        println!("{}", "PIN unlock was turned off; set it up again to use it.".bright_black());
+    }
+    Ok(())
 }
 
-/// Configures application settings
-fn configure_settings(storage: &mut Storage, logger: &mut Logger) -> Result<(), AppError> {
-    display_screen("Configure Settings");
-
-    let config = Config::load()?;
+/// Offers to overwrite and delete the plain-text snapshots and backups left
+/// behind after the vault was encrypted
+fn wipe_plaintext_backups(path: &str, events: &Events) -> Result<(), AppError> {
+    let backups = repair::find_backups(path);
+    if backups.is_empty() {
+        return Ok(());
+    }
 
-    println!("{}", "Configure your Quackey settings".green().bold());
+    println!();
+    println!(
+        "{}",
+        format!("⚠️  {} plain-text copy(ies) of the vault are still on disk:", backups.len()).yellow().bold()
+    );
+    for backup in &backups {
+        println!("  {}", backup.path.display());
+    }
     println!(
         "{}",
-        "You can change the path for your accounts storage file.".bright_black()
+        "Overwriting is best effort: on SSDs and copy-on-write file systems the old data may stay readable until the drive reuses the space.".bright_black()
     );
+
+    let wipe = Confirm::with_theme(&dialoguer::theme::ColorfulTheme::default())
+        .with_prompt("Overwrite and delete them?")
+        .default(true)
+        .interact_opt()
+        .chosen()?;
+    if !wipe {
+        return Ok(());
+    }
+
+    for backup in &backups {
+        wipe::secure_delete(&backup.path)?;
+    }
+    events.emit(Event::LockChanged(LockChange::PlaintextWiped { count: backups.len() }))?;
+    println!("{}", format!("✅ Deleted {} plain-text copy(ies).", backups.len()).green().bold());
+    Ok(())
+}
+
+/// Asks whether to sync with a WebDAV server and for its details, keeping the
+/// current ones as defaults
+fn get_webdav_settings(current: Option<sync::WebDavSettings>) -> Result<Option<sync::WebDavSettings>, AppError> {
     println!();
+    let enabled = Confirm::with_theme(&dialoguer::theme::ColorfulTheme::default())
+        .with_prompt("Sync the encrypted vault with a WebDAV server (such as Nextcloud)?")
+        .default(current.is_some())
+        .interact_opt()
+        .chosen()?;
 
-    let storage_dir = get_file_path("accounts storage file", &config.storage_dir)?;
+    if !enabled {
+        return Ok(None);
+    }
 
-    let mut config = Config { storage_dir };
+    let url: String = Input::with_theme(&dialoguer::theme::ColorfulTheme::default())
+        .with_prompt("WebDAV file URL")
+        .with_initial_text(current.as_ref().map(|c| c.url.clone()).unwrap_or_default())
+        .interact_text()
+        .answered()?;
+    let url = url.trim().to_string();
+    if !url.starts_with("https://") && !url.starts_with("http://") {
+        return Err(AppError::InvalidInput(format!("'{}' is not an http(s) URL", url)));
+    }
 
-    config.validate_paths()?;
-    config.ensure_directories()?;
-    config.save()?;
+    let username: String = Input::with_theme(&dialoguer::theme::ColorfulTheme::default())
+        .with_prompt("Username")
+        .with_initial_text(current.as_ref().map(|c| c.username.clone()).unwrap_or_default())
+        .interact_text()
+        .answered()?;
 
-    let storage_path_changed = config.get_storage_file_path() != storage.file_path();
+    let password = Password::with_theme(&dialoguer::theme::ColorfulTheme::default())
+        .with_prompt("Password or app password (leave empty to keep the current one)")
+        .allow_empty_password(true)
+        .interact()
+        .answered()?;
 
-    if storage_path_changed {
-        let old_path = storage.file_path().to_string();
-        let new_path = config.get_storage_file_path();
+    // A different file starts over; its ETag says nothing about this one
+    let same_file = current.as_ref().is_some_and(|c| c.url == url);
+    let current_password = current.as_ref().map(|c| c.password.clone()).unwrap_or_default();
 
-        println!();
-        println!("{}", "Changing storage file path:".bright_black());
-        println!("{} {}", "From:".blue(), old_path);
-        println!("{} {}", "To:".blue(), new_path);
-        println!();
+    let mut settings = sync::WebDavSettings::new(
+        url,
+        username.trim().to_string(),
+        if password.is_empty() { current_password } else { password },
+    );
+    settings.etag = current.filter(|_| same_file).and_then(|c| c.etag);
+    Ok(Some(settings))
+}
 
-        if std::path::Path::new(&new_path).exists() {
-            println!(
-                "{}",
-                "⚠️  The new storage file already exists.".yellow().bold()
-            );
-            println!("{}", "If it contains accounts, they will be loaded instead of copying from the old file.".bright_black());
-            println!("{}", "If you want to keep your current accounts, please rename or move the existing file.".bright_black());
-            println!();
+/// Lets the user pick how generated codes are grouped on screen
+/// Lets the user choose what is copied to the clipboard: the digits, the
+/// issuer with the code, or a template of their own
+fn get_copy_format(current: &str) -> Result<String, AppError> {
+    let presets = [("{code}", "Just the code"), ("{issuer}: {code}", "Issuer and code")];
 
-            let proceed = Confirm::with_theme(&dialoguer::theme::ColorfulTheme::default())
-                .with_prompt("Do you want to proceed?")
-                .default(false)
-                .interact()
-                .unwrap_or(false);
+    let mut labels: Vec<String> = presets
+        .iter()
+        .map(|(value, label)| format!("{} [{}]", label, value))
+        .collect();
+    labels.push("Custom template...".to_string());
+    let custom = labels.len() - 1;
+    let default = presets
+        .iter()
+        .position(|(value, _)| *value == current)
+        .unwrap_or(custom);
 
-            if !proceed {
-                println!();
-                println!("{}", "Operation cancelled.".bright_black());
-                return wait_for_input();
+    let selection = Select::with_theme(&dialoguer::theme::ColorfulTheme::default())
+        .with_prompt("What should be copied to the clipboard?")
+        .default(default)
+        .items(&labels)
+        .interact_opt()
+        .chosen()?;
+    if selection < custom {
+        return Ok(presets[selection].0.to_string());
+    }
+
+    let placeholders: Vec<String> = COPY_PLACEHOLDERS.iter().map(|p| format!("{{{}}}", p)).collect();
+    println!("{}", format!("Placeholders: {}", placeholders.join(" ")).bright_black());
+    let template: String = Input::with_theme(&dialoguer::theme::ColorfulTheme::default())
+        .with_prompt("Copy template")
+        .default(current.to_string())
+        .validate_with(|input: &String| {
+            if input.contains("{code}") {
+                Ok(())
+            } else {
+                Err("The template must contain {code}")
             }
-        }
+        })
+        .interact_text()
+        .answered()?;
+    Ok(template)
+}
 
-        storage.update_file_path(&new_path)?;
-        println!(
-            "{}",
-            "✅ Storage file path updated successfully!".green().bold()
-        );
-    }
+fn get_code_grouping(current: &str) -> Result<String, AppError> {
+    let mut options = vec![
+        ("half", "Split in half (123 456)"),
+        ("3-3", "Groups of three (123 456)"),
+        ("2-2-2", "Groups of two (12 34 56)"),
+        ("none", "No spaces (123456)"),
+    ];
 
-    if config.get_log_file_path() != logger.file_path() {
-        logger.update_file_path(&config.get_log_file_path())?;
+    // Keep a custom pattern from config.json selectable
+    if !options.iter().any(|(value, _)| *value == current) {
+        options.push((current, "Custom pattern from config.json"));
     }
 
-    logger.info("Application settings updated")?;
+    let labels: Vec<String> = options
+        .iter()
+        .map(|(value, label)| format!("{} [{}]", label, value))
+        .collect();
+    let default = options
+        .iter()
+        .position(|(value, _)| *value == current)
+        .unwrap_or(0);
 
-    wait_for_input()
-}
\ No newline at end of file
+    let selection = Select::with_theme(&dialoguer::theme::ColorfulTheme::default())
+        .with_prompt("Select code grouping")
+        .default(default)
+        .items(&labels)
+        .interact_opt()
+        .chosen()?;
+
+    Ok(options[selection].0.to_string())
+}
@@ -1,12 +1,208 @@
-use crate::account::Account;
-use crate::error::AppError;
+use quackey_core::account::{Account, AccountColor, CodePolicy};
+use quackey_core::error::AppError;
+use quackey_core::import::{EntryStatus, PreviewEntry, Resolution};
+use quackey_core::presets::{find_preset, PRESETS};
+use quackey_core::qr::QrCode;
+use quackey_core::secret::SecretReference;
+use quackey_core::strength::{Estimate, Strength};
+#[cfg(feature = "clipboard")]
 use arboard::Clipboard;
+use quackey_core::config::Config;
 use colored::*;
 use dialoguer::Confirm;
+use dialoguer::Completion;
+use dialoguer::console::{Key, Term};
 use indicatif::{ProgressBar, ProgressStyle};
 use prettytable::{Cell, Table, format};
-use std::io::{self, Write};
-use totp_rs::Algorithm;
+use std::io::{self, IsTerminal, Write};
+use std::sync::atomic::{AtomicBool, AtomicU64, Ordering};
+use std::sync::mpsc::{self, RecvTimeoutError};
+use std::thread;
+use std::time::{Duration, Instant};
+
+/// Set by `--demo`: codes on screen are replaced with random digits
+static DEMO: AtomicBool = AtomicBool::new(false);
+
+/// Turns demo mode on or off for this process
+pub fn set_demo(demo: bool) {
+    DEMO.store(demo, Ordering::Relaxed);
+}
+
+/// Set from `group_by_issuer`: account tables and pickers group accounts under
+/// issuer headings
+static GROUP_BY_ISSUER: AtomicBool = AtomicBool::new(false);
+
+/// Turns grouping by issuer on or off for this process
+pub fn set_group_by_issuer(group: bool) {
+    GROUP_BY_ISSUER.store(group, Ordering::Relaxed);
+}
+
+/// Whether account tables and pickers are grouped by issuer
+pub fn is_grouped_by_issuer() -> bool {
+    GROUP_BY_ISSUER.load(Ordering::Relaxed)
+}
+
+/// Set from `show_archived`: archived accounts are listed in account tables
+/// and pickers
+static SHOW_ARCHIVED: AtomicBool = AtomicBool::new(false);
+
+/// Shows or hides archived accounts for this process
+pub fn set_show_archived(show: bool) {
+    SHOW_ARCHIVED.store(show, Ordering::Relaxed);
+}
+
+/// Whether account tables and pickers list archived accounts
+pub fn is_showing_archived() -> bool {
+    SHOW_ARCHIVED.load(Ordering::Relaxed)
+}
+
+/// Set from `recent_first`: account tables and pickers list the most recently
+/// added accounts first
+static RECENT_FIRST: AtomicBool = AtomicBool::new(false);
+
+/// Turns listing recently added accounts first on or off for this process
+pub fn set_recent_first(recent: bool) {
+    RECENT_FIRST.store(recent, Ordering::Relaxed);
+}
+
+/// Whether account tables and pickers list recently added accounts first
+pub fn is_recent_first() -> bool {
+    RECENT_FIRST.load(Ordering::Relaxed)
+}
+
+/// Set from `show_timestamps`: the accounts table has columns for when
+/// accounts were added and last changed
+static SHOW_TIMESTAMPS: AtomicBool = AtomicBool::new(false);
+
+/// Shows or hides the timestamp columns of the accounts table for this process
+pub fn set_show_timestamps(show: bool) {
+    SHOW_TIMESTAMPS.store(show, Ordering::Relaxed);
+}
+
+/// Set from `mask_codes`: codes on screen are shown as dots until revealed
+static MASK_CODES: AtomicBool = AtomicBool::new(false);
+
+/// Turns masking of codes on screen on or off for this process
+pub fn set_mask_codes(mask: bool) {
+    MASK_CODES.store(mask, Ordering::Relaxed);
+}
+
+/// Whether codes on screen are masked until revealed
+pub fn is_masking_codes() -> bool {
+    MASK_CODES.load(Ordering::Relaxed)
+}
+
+/// Set from `idle_blank_seconds`: seconds without a key press after which a
+/// shown code is cleared, 0 for never
+static IDLE_BLANK_SECONDS: AtomicU64 = AtomicU64::new(0);
+
+/// Sets how long a shown code may stay on screen without a key press for this
+/// process, 0 to keep it until the user moves on
+pub fn set_idle_blank(seconds: u64) {
+    IDLE_BLANK_SECONDS.store(seconds, Ordering::Relaxed);
+}
+
+/// How long a shown code may stay on screen without a key press, if limited
+fn idle_blank_time() -> Option<Duration> {
+    match IDLE_BLANK_SECONDS.load(Ordering::Relaxed) {
+        0 => None,
+        seconds => Some(Duration::from_secs(seconds)),
+    }
+}
+
+/// Set by `--privacy` or from the main menu: account names, issuers and codes
+/// on screen are replaced with placeholders
+static PRIVACY: AtomicBool = AtomicBool::new(false);
+
+/// Turns privacy mode on or off for this process
+pub fn set_privacy(private: bool) {
+    PRIVACY.store(private, Ordering::Relaxed);
+}
+
+/// Whether account names, issuers and codes on screen are replaced with
+/// placeholders
+pub fn is_private() -> bool {
+    PRIVACY.load(Ordering::Relaxed)
+}
+
+/// An account's name as shown on screen: in privacy mode a placeholder such as
+/// "Account 3f2a", taken from its id so it stays the same all session
+pub fn shown_name(account: &Account) -> String {
+    if is_private() {
+        format!("Account {}", account.id().chars().take(4).collect::<String>())
+    } else {
+        account.name().to_string()
+    }
+}
+
+/// An issuer as shown on screen: in privacy mode a placeholder such as
+/// "Issuer 9c1b", the same whatever the capitalization of the issuer
+pub fn shown_issuer(issuer: &str) -> String {
+    if !is_private() {
+        return issuer.to_string();
+    }
+
+    // FNV-1a, folded to 16 bits
+    let hash = issuer
+        .to_lowercase()
+        .bytes()
+        .fold(0x811c_9dc5u32, |hash, byte| (hash ^ u32::from(byte)).wrapping_mul(0x0100_0193));
+    format!("Issuer {:04x}", (hash >> 16) ^ (hash & 0xffff))
+}
+
+/// "name (issuer)" as shown on screen, see [`shown_name`] and [`shown_issuer`]
+pub fn shown_label(account: &Account) -> String {
+    match account.issuer() {
+        Some(issuer) => format!("{} ({})", shown_name(account), shown_issuer(issuer)),
+        None => shown_name(account),
+    }
+}
+
+/// `text` as a list entry for the account: drawn in its color and after its
+/// icon (see [`with_icon`])
+pub fn with_appearance(account: &Account, text: &str) -> String {
+    let text = with_icon(account, text);
+    match account.color() {
+        Some(color) => text.color(terminal_color(color)).to_string(),
+        None => text,
+    }
+}
+
+/// `text` after the account's icon, if it has one. Privacy mode leaves the
+/// icon out, as it may give the issuer away.
+fn with_icon(account: &Account, text: &str) -> String {
+    match account.icon() {
+        Some(icon) if !is_private() => format!("{} {}", icon, text),
+        _ => text.to_string(),
+    }
+}
+
+/// The terminal color an account color is drawn with
+pub fn terminal_color(color: AccountColor) -> Color {
+    match color {
+        AccountColor::Red => Color::Red,
+        AccountColor::Green => Color::Green,
+        AccountColor::Yellow => Color::Yellow,
+        AccountColor::Blue => Color::Blue,
+        AccountColor::Magenta => Color::Magenta,
+        AccountColor::Cyan => Color::Cyan,
+    }
+}
+
+/// prettytable style for text in `color`
+fn table_style(color: AccountColor) -> &'static str {
+    match color {
+        AccountColor::Red => "Fr",
+        AccountColor::Green => "Fg",
+        AccountColor::Yellow => "Fy",
+        AccountColor::Blue => "Fb",
+        AccountColor::Magenta => "Fm",
+        AccountColor::Cyan => "Fc",
+    }
+}
+
+/// How long a masked code stays visible after Space is pressed
+const REVEAL_TIME: Duration = Duration::from_secs(2);
 
 /// Application configuration constants
 const SPINNER_TEMPLATE: &str = "{spinner:.green} {msg}";
@@ -79,39 +275,478 @@ pub fn centered_duck(width: usize) -> String {
     centered
 }
 
-/// Displays the results of TOTP generation
-pub fn display_totp_results(totp: &str, remaining: u64) -> Result<(), AppError> {
-    println!("{}", "Here is your code, quack!".green().bold());
+/// Placeholders [`clipboard_text`] fills in
+pub const COPY_PLACEHOLDERS: &[&str] = &["code", "issuer", "name", "remaining"];
+
+/// The text copied for `code` of `account`: `format` with each `{placeholder}`
+/// from [`COPY_PLACEHOLDERS`] filled in. `{issuer}` falls back to the account
+/// name when there is no issuer; unknown placeholders are left as they are.
+pub fn clipboard_text(format: &str, account: &Account, code: &str) -> String {
+    format
+        .replace("{code}", code)
+        .replace("{issuer}", account.issuer().map_or(account.name(), String::as_str))
+        .replace("{name}", account.name())
+        .replace("{remaining}", &account.time_remaining().to_string())
+}
 
-    let formatted_totp = format_totp(totp);
+/// Displays the results of TOTP generation, copying `copy_text` (the code, or
+/// the code in the configured copy format) right away when `auto_copy` is set.
+/// Without `clipboard` the code is only shown. The account's [`CodePolicy`]
+/// can rule out copying, or mask the code and copy it without asking.
+/// Returns whether the code ended up on the clipboard.
+pub fn display_totp_results(
+    account: &Account,
+    totp: &str,
+    copy_text: &str,
+    remaining: u64,
+    clipboard: bool,
+    auto_copy: bool,
+    grouping: &str,
+) -> Result<bool, AppError> {
+    let policy = account.code_policy();
+    let masked = is_masking_codes() || is_private() || policy == CodePolicy::CopyOnly;
+    println!("{}", "Here is your code, quack!".green().bold());
     println!(
         "{} {}",
         "🔑 Code:".blue(),
-        formatted_totp.bright_white().bold()
+        display_code(account, totp, grouping).bright_white().bold()
     );
     println!("{} {} seconds", "⌛ Expires in:".blue(), remaining);
     println!();
 
-    if Confirm::with_theme(&dialoguer::theme::ColorfulTheme::default())
-        .with_prompt("Copy to clipboard")
-        .default(true)
-        .interact()
-        .unwrap_or(false)
+    if policy == CodePolicy::DisplayOnly {
+        if clipboard {
+            println!("{}", "📺 This account is display only, so its codes are never copied.".bright_black());
+        }
+        if is_private() {
+            println!("{}", "🕶️  Turn off privacy mode in the main menu to see the code.".bright_black());
+        }
+        return Ok(false);
+    }
+
+    if !clipboard {
+        if policy == CodePolicy::CopyOnly {
+            println!("{}", "🙈 This account is copy only, but the clipboard is turned off.".yellow());
+            reveal_code(totp, grouping)?;
+        }
+        return Ok(false);
+    }
+
+    if auto_copy
+        || policy == CodePolicy::CopyOnly
+        || Confirm::with_theme(&dialoguer::theme::ColorfulTheme::default())
+            .with_prompt("Copy to clipboard")
+            .default(true)
+            .interact_opt()
+            .chosen()?
     {
-        match copy_to_clipboard(totp) {
-            Ok(_) => println!("{}", "📋 Copied to clipboard, quack!".green()),
-            Err(_) => println!(
+        match copy_to_clipboard(copy_text) {
+            Ok(method) => {
+                println!("{}", format!("📋 Copied to clipboard{}, quack!", method.suffix()).green());
+                if copy_text != totp && !masked {
+                    println!("{} {}", "Copied text:".bright_black(), copy_text);
+                }
+                return Ok(true);
+            }
+            Err(e) if policy == CodePolicy::CopyOnly => {
+                print_copy_failure(&e, None);
+                reveal_code(totp, grouping)?;
+            }
+            Err(e) => print_copy_failure(&e, (!masked).then_some(copy_text)),
+        }
+    }
+
+    Ok(false)
+}
+
+/// Formats a code for the screen as [`format_totp`] does, masked for
+/// [`CodePolicy::CopyOnly`] accounts, while codes are masked and in privacy
+/// mode
+pub fn display_code(account: &Account, code: &str, grouping: &str) -> String {
+    let formatted = format_totp(code, grouping);
+    if is_masking_codes() || is_private() || account.code_policy() == CodePolicy::CopyOnly {
+        mask_code(&formatted)
+    } else {
+        formatted
+    }
+}
+
+/// Replaces the characters of a formatted code with dots, keeping its groups
+pub fn mask_code(formatted: &str) -> String {
+    formatted.chars().map(|c| if c == ' ' { ' ' } else { '•' }).collect()
+}
+
+/// Hides a secret but for its first and last four characters, enough to
+/// compare it with the one the provider shows. A reference to a command or
+/// variable is shown as it is, except in privacy mode, which hides it all.
+pub fn mask_secret(secret: &str) -> String {
+    let chars: Vec<char> = secret.chars().collect();
+    if is_private() || chars.len() <= 8 {
+        return "•".repeat(chars.len().max(8));
+    }
+    if SecretReference::parse(secret).is_some() {
+        return secret.to_string();
+    }
+
+    let hidden = "•".repeat(chars.len() - 8);
+    format!("{}{}{}", chars[..4].iter().collect::<String>(), hidden, chars[chars.len() - 4..].iter().collect::<String>())
+}
+
+/// Shows a masked code after all when the user asks, for when it could not be
+/// copied. Privacy mode keeps it hidden.
+fn reveal_code(code: &str, grouping: &str) -> Result<(), AppError> {
+    if is_private() {
+        println!("{}", "🕶️  Privacy mode is on, so the code stays hidden.".bright_black());
+        return Ok(());
+    }
+
+    let reveal = Confirm::with_theme(&dialoguer::theme::ColorfulTheme::default())
+        .with_prompt("Show the code on screen instead?")
+        .default(false)
+        .interact_opt()
+        .chosen()?;
+    if reveal {
+        println!("{} {}", "🔑 Code:".blue(), format_totp(code, grouping).bright_white().bold());
+    }
+    Ok(())
+}
+
+/// Counts down the shown code until the user presses Enter, printing each new
+/// code as it rotates. During the last `warning_seconds` of a code the
+/// countdown turns red and, with `bell`, the terminal bell rings once, so
+/// a code about to expire isn't pasted. While codes are masked (see
+/// [`set_mask_codes`]) the countdown line also holds the code, shown for
+/// [`REVEAL_TIME`] after each press of Space, so holding Space keeps it
+/// visible. After [`set_idle_blank`] seconds without a key press the code is
+/// cleared from the screen, and from the clipboard when `copied`. Consumes the
+/// Enter keypress itself. `generated` is called for each new code shown.
+pub fn watch_code(
+    account: &Account,
+    grouping: &str,
+    warning_seconds: u64,
+    bell: bool,
+    copied: bool,
+    generated: &mut dyn FnMut(),
+) -> Result<(), AppError> {
+    if !io::stdout().is_terminal() {
+        return wait_for_input();
+    }
+
+    // Read input on a separate thread so the countdown can keep ticking; single
+    // keys are only needed to reveal a masked code
+    let revealable = is_masking_codes() && !is_private() && account.code_policy() != CodePolicy::CopyOnly;
+    let (tx, rx) = mpsc::channel();
+    if revealable {
+        thread::spawn(move || {
+            let term = Term::stdout();
+            loop {
+                match term.read_key() {
+                    Ok(Key::Enter) | Err(_) => {
+                        let _ = tx.send(Key::Enter);
+                        return;
+                    }
+                    Ok(key) => {
+                        if tx.send(key).is_err() {
+                            return;
+                        }
+                    }
+                }
+            }
+        });
+    } else {
+        thread::spawn(move || {
+            let mut buffer = String::new();
+            let _ = io::stdin().read_line(&mut buffer);
+            let _ = tx.send(Key::Enter);
+        });
+    }
+
+    let mut last_code = account.generate_totp()?;
+    // Formatted once per code, so demo mode's random digits hold still
+    let mut formatted = format_totp(&last_code, grouping);
+    let mut warned = false;
+    let mut revealed_until: Option<Instant> = None;
+    let idle_limit = idle_blank_time();
+    let mut last_input = Instant::now();
+    loop {
+        if idle_limit.is_some_and(|limit| last_input.elapsed() >= limit) {
+            return blank_idle_code(&rx, copied);
+        }
+
+        let remaining = account.time_remaining();
+        let code = account.generate_totp()?;
+        if code != last_code {
+            println!(
+                "\r{} {}{}",
+                "🔑 New code:".blue(),
+                display_code(account, &code, grouping).bright_white().bold(),
+                " ".repeat(60)
+            );
+            generated();
+            formatted = format_totp(&code, grouping);
+            last_code = code;
+            warned = false;
+        }
+
+        let shown = if !revealable {
+            String::new()
+        } else if revealed_until.is_some_and(|until| Instant::now() < until) {
+            format!("🔑 {}  ", formatted.bright_white().bold())
+        } else {
+            format!("🔑 {}  ", mask_code(&formatted))
+        };
+        let countdown = format!("⌛ Expires in: {:>2}s", remaining);
+        if remaining <= warning_seconds {
+            if bell && !warned {
+                print!("\x07");
+            }
+            warned = true;
+            print!("\r{}{}  {}", shown, countdown.red().bold(), format!("{:<40}", "wait for the next code").red());
+        } else if revealable {
+            print!("\r{}{}  {}", shown, countdown.blue(), "Hold Space to reveal, Enter to continue".bright_black());
+        } else {
+            print!("\r{}  {}", countdown.blue(), "Press Enter to continue...".bright_black());
+        }
+        io::stdout().flush()?;
+
+        match rx.recv_timeout(Duration::from_millis(250)) {
+            Ok(Key::Enter) => {
+                // Only a line read echoes the Enter itself
+                if revealable {
+                    println!();
+                }
+                return Ok(());
+            }
+            Ok(key) => {
+                last_input = Instant::now();
+                if key == Key::Char(' ') {
+                    revealed_until = Some(last_input + REVEAL_TIME);
+                }
+            }
+            Err(RecvTimeoutError::Disconnected) => {
+                println!();
+                return Ok(());
+            }
+            Err(RecvTimeoutError::Timeout) => {}
+        }
+    }
+}
+
+/// Replaces a code left on screen with a note, clears the clipboard if the code
+/// was copied, and waits for Enter from the input thread of the code screen.
+/// Lines start with `\r` as the terminal may still be in raw mode.
+fn blank_idle_code(input: &mpsc::Receiver<Key>, copied: bool) -> Result<(), AppError> {
+    clear_screen();
+    let width = get_terminal_width();
+    println!("\r{}", center_text("🦉 The code was hidden because no key was pressed for a while.", width).bright_black());
+    if copied && clear_clipboard().is_ok() {
+        println!("\r{}", center_text("📋 The clipboard was cleared too.", width).bright_black());
+    }
+    print!("\r\n\r{}", "Press Enter to continue...".bright_black());
+    io::stdout().flush()?;
+
+    while let Ok(key) = input.recv() {
+        if key == Key::Enter {
+            break;
+        }
+    }
+    println!();
+    Ok(())
+}
+
+/// Explains a failed copy and shows the plain code, if it may be shown, to
+/// type or select instead
+fn print_copy_failure(error: &AppError, code: Option<&str>) {
+    println!("{}", "⛔ Failed to copy to clipboard, quack... *sniff*".red());
+    if let Some(code) = code {
+        println!("{} {}", "Copy it from here instead:".bright_black(), code.bright_white().bold());
+    }
+    println!("{}", error.to_string().bright_black());
+    if let Some(hint) = error.hint() {
+        println!("{}", hint.bright_black());
+    }
+}
+
+/// Re-copies the account's code, in `copy_format`, to the clipboard every time
+/// it rotates, for `minutes` minutes or until the user presses Enter. After
+/// [`set_idle_blank`] seconds the codes are cleared from the screen while the
+/// clipboard keeps being refreshed. Consumes the Enter keypress itself, so
+/// callers should not wait for input afterwards. `generated` is called for
+/// each new code copied.
+pub fn keep_clipboard_fresh(
+    account: &Account,
+    minutes: u64,
+    grouping: &str,
+    copy_format: &str,
+    generated: &mut dyn FnMut(),
+) -> Result<(), AppError> {
+    println!();
+    println!(
+        "{}",
+        format!(
+            "🔄 Keeping the clipboard fresh for {} minute{}. Press Enter to stop.",
+            minutes,
+            if minutes == 1 { "" } else { "s" }
+        )
+        .bright_black()
+    );
+
+    // Read stdin on a separate thread so the refresh loop can keep ticking
+    let (tx, rx) = mpsc::channel();
+    thread::spawn(move || {
+        let mut buffer = String::new();
+        let _ = io::stdin().read_line(&mut buffer);
+        let _ = tx.send(());
+    });
+
+    let started = Instant::now();
+    let deadline = started + Duration::from_secs(minutes * 60);
+    let blank_at = idle_blank_time().map(|limit| started + limit);
+    let mut blanked = false;
+    let mut last_code = account.generate_totp()?;
+
+    loop {
+        match rx.recv_timeout(Duration::from_secs(1)) {
+            Ok(_) | Err(RecvTimeoutError::Disconnected) => {
+                println!("{}", "Stopped refreshing the clipboard.".bright_black());
+                return Ok(());
+            }
+            Err(RecvTimeoutError::Timeout) => {}
+        }
+
+        if Instant::now() >= deadline {
+            break;
+        }
+        if !blanked && blank_at.is_some_and(|at| Instant::now() >= at) {
+            blanked = true;
+            clear_screen();
+            println!(
                 "{}",
-                "⛔ Failed to copy to clipboard, quack... *sniff*".red()
-            ),
+                "🔄 Codes hidden after inactivity; the clipboard is still kept fresh. Press Enter to stop."
+                    .bright_black()
+            );
+        }
+
+        let code = account.generate_totp()?;
+        if code != last_code {
+            generated();
+            let text = clipboard_text(copy_format, account, &code);
+            match copy_to_clipboard(&text) {
+                Ok(_) if blanked => {}
+                Ok(_) => println!(
+                    "{} {}",
+                    "📋 New code copied:".green(),
+                    display_code(account, &code, grouping).bright_white().bold()
+                ),
+                Err(e) => {
+                    let shown = (!blanked
+                        && !is_masking_codes()
+                        && !is_private()
+                        && account.code_policy() != CodePolicy::CopyOnly)
+                        .then_some(text.as_str());
+                    print_copy_failure(&e, shown)
+                }
+            }
+            last_code = code;
         }
     }
 
+    println!("{}", "⌛ Keep-fresh window ended.".bright_black());
+    println!("\n{}", "Press Enter to continue...".bright_black());
+    let _ = rx.recv();
     Ok(())
 }
 
-/// Formats a TOTP code with spaces for better readability
-pub fn format_totp(totp: &str) -> String {
+/// Shows pre-rendered QR frames one after another, looping every `interval`
+/// until the user presses Enter. A single frame is simply shown until Enter.
+pub fn play_qr_frames(frames: &[String], interval: Duration) -> Result<(), AppError> {
+    if let [frame] = frames {
+        print!("{}", frame);
+        return wait_for_input();
+    }
+
+    let (tx, rx) = mpsc::channel();
+    thread::spawn(move || {
+        let mut buffer = String::new();
+        let _ = io::stdin().read_line(&mut buffer);
+        let _ = tx.send(());
+    });
+
+    for (index, frame) in frames.iter().enumerate().cycle() {
+        clear_screen();
+        println!(
+            "{}",
+            format!("Frame {} of {}", index + 1, frames.len()).bold()
+        );
+        print!("{}", frame);
+        println!(
+            "{}",
+            "Keep scanning until your app has every frame. Press Enter to stop.".bright_black()
+        );
+
+        match rx.recv_timeout(interval) {
+            Ok(_) | Err(RecvTimeoutError::Disconnected) => break,
+            Err(RecvTimeoutError::Timeout) => {}
+        }
+    }
+
+    Ok(())
+}
+
+/// Formats a TOTP code with spaces for better readability.
+///
+/// `grouping` is either "half" (the default), "none", or dash-separated group
+/// sizes like "3-3" or "2-2-2". Digits left over after the last group are kept
+/// together in a final group; unrecognized patterns fall back to "half".
+/// In demo mode the digits are random.
+pub fn format_totp(totp: &str, grouping: &str) -> String {
+    let randomized;
+    let totp = if DEMO.load(Ordering::Relaxed) {
+        use rand::Rng;
+        let mut rng = rand::thread_rng();
+        randomized = totp
+            .chars()
+            .map(|c| if c.is_ascii_digit() { char::from(b'0' + rng.gen_range(0..10)) } else { c })
+            .collect::<String>();
+        randomized.as_str()
+    } else {
+        totp
+    };
+
+    match grouping.trim() {
+        "none" => totp.to_string(),
+        "half" | "" => format_totp_half(totp),
+        pattern => {
+            let sizes: Option<Vec<usize>> = pattern
+                .split('-')
+                .map(|size| size.trim().parse().ok().filter(|&n| n > 0))
+                .collect();
+
+            match sizes {
+                Some(sizes) => {
+                    let mut groups = Vec::new();
+                    let mut rest = totp;
+                    for size in sizes {
+                        if rest.is_empty() {
+                            break;
+                        }
+                        let (group, tail) = rest.split_at(size.min(rest.len()));
+                        groups.push(group);
+                        rest = tail;
+                    }
+                    if !rest.is_empty() {
+                        groups.push(rest);
+                    }
+                    groups.join(" ")
+                }
+                None => format_totp_half(totp),
+            }
+        }
+    }
+}
+
+/// Splits a code into two halves
+fn format_totp_half(totp: &str) -> String {
     if totp.len() <= 3 {
         return totp.to_string();
     }
@@ -120,51 +755,463 @@ pub fn format_totp(totp: &str) -> String {
     format!("{} {}", &totp[..mid], &totp[mid..])
 }
 
-/// Copies text to the system clipboard
-pub fn copy_to_clipboard(text: &str) -> Result<(), AppError> {
-    let mut clipboard = Clipboard::new().unwrap();
-    clipboard.set_text(text).unwrap();
-    Ok(())
+/// Whether the clipboard may be used: built with the `clipboard` feature and
+/// not turned off with `disable_clipboard`
+pub fn clipboard_enabled(config: &Config) -> bool {
+    cfg!(feature = "clipboard") && !config.disable_clipboard
+}
+
+/// Tries at opening a clipboard held by another program, and the pause between them
+#[cfg(feature = "clipboard")]
+const CLIPBOARD_ATTEMPTS: u32 = 3;
+#[cfg(feature = "clipboard")]
+const CLIPBOARD_RETRY_DELAY: Duration = Duration::from_millis(100);
+
+/// Clipboard programs tried, in order, when the system clipboard can't be
+/// opened directly (some Wayland compositors, X11 forwarding over SSH)
+#[cfg(feature = "clipboard")]
+pub const CLIPBOARD_COMMANDS: &[(&str, &[&str])] = &[
+    ("wl-copy", &[]),
+    ("xclip", &["-selection", "clipboard"]),
+    ("xsel", &["--clipboard", "--input"]),
+    ("pbcopy", &[]),
+];
+
+/// How text was put on the clipboard
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+#[cfg_attr(not(feature = "clipboard"), allow(dead_code))]
+pub enum ClipboardMethod {
+    /// The system clipboard, opened directly
+    Native,
+    /// A clipboard program such as wl-copy
+    Command(&'static str),
+}
+
+impl ClipboardMethod {
+    /// " with wl-copy" for a fallback program, empty otherwise
+    pub fn suffix(self) -> String {
+        match self {
+            ClipboardMethod::Native => String::new(),
+            ClipboardMethod::Command(program) => format!(" with {}", program),
+        }
+    }
 }
 
-/// Displays accounts in a formatted table
+/// Copies text to the system clipboard, falling back to the first of the
+/// [`CLIPBOARD_COMMANDS`] that works. A clipboard briefly held by another
+/// program (common on Windows) is retried a few times first.
+#[cfg(feature = "clipboard")]
+pub fn copy_to_clipboard(text: &str) -> Result<ClipboardMethod, AppError> {
+    let mut attempt = 1;
+    let native_error = loop {
+        match Clipboard::new().and_then(|mut clipboard| clipboard.set_text(text)) {
+            Ok(()) => return Ok(ClipboardMethod::Native),
+            Err(arboard::Error::ClipboardOccupied) if attempt < CLIPBOARD_ATTEMPTS => {
+                thread::sleep(CLIPBOARD_RETRY_DELAY);
+                attempt += 1;
+            }
+            Err(e) => break e,
+        }
+    };
+
+    for (program, args) in CLIPBOARD_COMMANDS {
+        if copy_with_command(program, args, text).is_ok() {
+            return Ok(ClipboardMethod::Command(program));
+        }
+    }
+
+    let programs: Vec<&str> = CLIPBOARD_COMMANDS.iter().map(|(program, _)| *program).collect();
+    Err(AppError::FileError(format!("The clipboard is unavailable: {}", native_error)).with_hint(format!(
+        "Install one of {} (e.g. wl-clipboard on Wayland or xclip on X11), or set \"disable_clipboard\": true",
+        programs.join(", ")
+    )))
+}
+
+#[cfg(not(feature = "clipboard"))]
+pub fn copy_to_clipboard(_text: &str) -> Result<ClipboardMethod, AppError> {
+    Err(AppError::InvalidInput("Quackey was built without clipboard support".to_string()))
+}
+
+/// Empties the clipboard, so a copied code doesn't outlive the screen it was
+/// shown on
+pub fn clear_clipboard() -> Result<(), AppError> {
+    copy_to_clipboard("").map(|_| ())
+}
+
+/// Pipes text into a clipboard program
+#[cfg(feature = "clipboard")]
+fn copy_with_command(program: &str, args: &[&str], text: &str) -> io::Result<()> {
+    use std::process::{Command, Stdio};
+
+    let mut child = Command::new(program)
+        .args(args)
+        .stdin(Stdio::piped())
+        .stdout(Stdio::null())
+        .stderr(Stdio::null())
+        .spawn()?;
+    child.stdin.take().expect("stdin is piped").write_all(text.as_bytes())?;
+
+    let status = child.wait()?;
+    if status.success() {
+        Ok(())
+    } else {
+        Err(io::Error::other(format!("{} exited with {}", program, status)))
+    }
+}
+
+/// Reads an image from the clipboard as width, height and RGBA pixels
+#[cfg(feature = "clipboard")]
+pub fn clipboard_image() -> Result<(usize, usize, Vec<u8>), String> {
+    let image = Clipboard::new()
+        .and_then(|mut clipboard| clipboard.get_image())
+        .map_err(|e| e.to_string())?;
+    Ok((image.width, image.height, image.bytes.into_owned()))
+}
+
+#[cfg(not(feature = "clipboard"))]
+pub fn clipboard_image() -> Result<(usize, usize, Vec<u8>), String> {
+    Err("Quackey was built without clipboard support".to_string())
+}
+
+/// Renders a QR code with half-block characters, two module rows per line,
+/// using explicit colors so it scans on both light and dark terminals
+pub fn render_qr(qr: &QrCode) -> String {
+    const QUIET_ZONE: i32 = 2;
+    let size = qr.size() as i32;
+    let mut output = String::new();
+
+    let mut y = -QUIET_ZONE;
+    while y < size + QUIET_ZONE {
+        for x in -QUIET_ZONE..size + QUIET_ZONE {
+            let top = if qr.module(x, y) { Color::Black } else { Color::BrightWhite };
+            let bottom = if qr.module(x, y + 1) { Color::Black } else { Color::BrightWhite };
+            output.push_str(&"▀".color(top).on_color(bottom).to_string());
+        }
+        output.push('\n');
+        y += 2;
+    }
+
+    output
+}
+
+/// Formats an issuer name, prefixed with its preset icon when one is known.
+/// In privacy mode it is the placeholder from [`shown_issuer`], without the
+/// icon that would give the issuer away.
+pub fn issuer_label(issuer: Option<&str>) -> String {
+    match issuer {
+        Some(issuer) if is_private() => shown_issuer(issuer),
+        Some(issuer) => match find_preset(issuer) {
+            Some(preset) => format!("{} {}", preset.icon, issuer),
+            None => issuer.to_string(),
+        },
+        None => String::new(),
+    }
+}
+
+/// Tab completion for issuer names, drawing on the issuers already in the
+/// vault (so their spelling wins) and then the built-in presets
+pub struct IssuerCompletion {
+    issuers: Vec<String>,
+}
+
+impl IssuerCompletion {
+    pub fn new(accounts: &[Account]) -> Self {
+        let mut issuers: Vec<String> = Vec::new();
+        let vault_issuers = accounts.iter().filter_map(|a| a.issuer().cloned());
+        let preset_issuers = PRESETS.iter().map(|p| p.name.to_string());
+
+        for issuer in vault_issuers.chain(preset_issuers) {
+            if !issuers.iter().any(|known| known.eq_ignore_ascii_case(&issuer)) {
+                issuers.push(issuer);
+            }
+        }
+
+        Self { issuers }
+    }
+}
+
+impl Completion for IssuerCompletion {
+    fn get(&self, input: &str) -> Option<String> {
+        let input = input.trim().to_lowercase();
+        if input.is_empty() {
+            return None;
+        }
+
+        self.issuers
+            .iter()
+            .find(|issuer| issuer.to_lowercase().starts_with(&input))
+            .cloned()
+    }
+}
+
+/// Indices of `accounts` grouped by issuer: issuers in alphabetical order
+/// (ignoring case), accounts without one last, saved order kept in each group,
+/// or newest first with `recent_first` (accounts of unknown age last).
+/// Without `group_by_issuer` there is a single group holding every account.
+/// Archived accounts are left out (see [`hidden_archived`]).
+pub fn issuer_groups(accounts: &[Account]) -> Vec<(Option<&str>, Vec<usize>)> {
+    let hide_archived = hidden_archived(accounts) > 0;
+    let mut shown: Vec<usize> =
+        (0..accounts.len()).filter(|&index| !(hide_archived && accounts[index].is_archived())).collect();
+    if is_recent_first() {
+        shown.sort_by_key(|&index| std::cmp::Reverse(accounts[index].created_at()));
+    }
+    if !is_grouped_by_issuer() {
+        return vec![(None, shown)];
+    }
+
+    let mut groups: Vec<(Option<&str>, Vec<usize>)> = Vec::new();
+    for index in shown {
+        let account = &accounts[index];
+        let issuer = account.issuer().map(String::as_str);
+        let key = issuer.map(str::to_lowercase);
+        match groups.iter_mut().find(|(other, _)| other.map(str::to_lowercase) == key) {
+            Some((_, members)) => members.push(index),
+            None => groups.push((issuer, vec![index])),
+        }
+    }
+    groups.sort_by_key(|(issuer, _)| (issuer.is_none(), issuer.map(str::to_lowercase)));
+    groups
+}
+
+/// Number of archived accounts tables and pickers leave out: none while
+/// `show_archived` is set, or when every account is archived, so a picker is
+/// never empty
+pub fn hidden_archived(accounts: &[Account]) -> usize {
+    let archived = accounts.iter().filter(|account| account.is_archived()).count();
+    if is_showing_archived() || archived == accounts.len() {
+        0
+    } else {
+        archived
+    }
+}
+
+/// Indices of `accounts` in the order tables and pickers list them
+pub fn display_order(accounts: &[Account]) -> Vec<usize> {
+    issuer_groups(accounts).into_iter().flat_map(|(_, members)| members).collect()
+}
+
+/// Heading of an issuer group, e.g. "🐙 GitHub (3)"
+pub fn group_heading(issuer: Option<&str>, count: usize) -> String {
+    match issuer {
+        Some(_) => format!("{} ({})", issuer_label(issuer), count),
+        None => format!("No issuer ({})", count),
+    }
+}
+
+/// Displays the accounts as a table, numbered in [`display_order`] and under
+/// issuer headings when grouping by issuer. The aliases column only appears
+/// when an account has one, and never in privacy mode; the added and changed
+/// dates only with `show_timestamps`.
 pub fn display_accounts_table(accounts: &[Account]) {
     let mut table = Table::new();
     table.set_format(*format::consts::FORMAT_NO_LINESEP_WITH_TITLE);
+    let show_aliases = !is_private() && accounts.iter().any(|account| !account.aliases().is_empty());
+    let show_timestamps = SHOW_TIMESTAMPS.load(Ordering::Relaxed);
 
     // Add header row
-    let headers = vec![
+    let mut headers = vec![
         Cell::new("#").style_spec("bFg"),
         Cell::new("Account Name").style_spec("bFg"),
         Cell::new("Issuer").style_spec("bFg"),
         Cell::new("Digits").style_spec("bFg"),
         Cell::new("Period").style_spec("bFg"),
         Cell::new("Algorithm").style_spec("bFg"),
+        Cell::new("Tags").style_spec("bFg"),
     ];
+    if show_aliases {
+        headers.push(Cell::new("Aliases").style_spec("bFg"));
+    }
+    if show_timestamps {
+        headers.push(Cell::new("Added").style_spec("bFg"));
+        headers.push(Cell::new("Changed").style_spec("bFg"));
+    }
+    let columns = headers.len();
     table.add_row(prettytable::Row::new(headers));
 
     // Add account rows
-    for (i, account) in accounts.iter().enumerate() {
-        let algo_name = match account.algorithm() {
-            Algorithm::SHA1 => "SHA1",
-            Algorithm::SHA256 => "SHA256",
-            Algorithm::SHA512 => "SHA512",
+    let grouped = is_grouped_by_issuer();
+    let mut number = 0;
+    for (issuer, members) in issuer_groups(accounts) {
+        if grouped {
+            table.add_row(prettytable::Row::new(vec![
+                Cell::new(&group_heading(issuer, members.len())).style_spec("bFc").with_hspan(columns),
+            ]));
+        }
+
+        for index in members {
+            let account = &accounts[index];
+            number += 1;
+            let name = with_icon(account, &shown_name(account));
+            let mut row = vec![
+                Cell::new(&format!("{}.", number)).style_spec("Fy"),
+                if !account.is_verified() {
+                    Cell::new(&format!("{} ⚠ unverified", name)).style_spec("Fy")
+                } else if account.is_archived() {
+                    Cell::new(&format!("{} 📦 archived", name)).style_spec("FD")
+                } else {
+                    Cell::new(&name).style_spec(account.color().map_or("FW", table_style))
+                },
+                Cell::new(&issuer_label(account.issuer().map(|s| s.as_str()))).style_spec("FB"),
+                Cell::new(&account.digits().to_string()).style_spec("FB"),
+                Cell::new(&format!("{}s", account.period())).style_spec("FB"),
+                Cell::new(account.algorithm_name()).style_spec("FB"),
+                Cell::new(&account.tags().join(", ")).style_spec("FB"),
+            ];
+            if show_aliases {
+                row.push(Cell::new(&account.aliases().join(", ")).style_spec("FB"));
+            }
+            if show_timestamps {
+                row.push(Cell::new(&table_date(account.created_at())).style_spec("FB"));
+                row.push(Cell::new(&table_date(account.updated_at())).style_spec("FB"));
+            }
+            table.add_row(prettytable::Row::new(row));
+        }
+    }
+
+    table.printstd();
+}
+
+/// Local date of a Unix time for the accounts table, "-" when unknown
+fn table_date(at: Option<u64>) -> String {
+    at.and_then(|at| chrono::DateTime::from_timestamp(at as i64, 0))
+        .map(|time| time.with_timezone(&chrono::Local).format("%Y-%m-%d").to_string())
+        .unwrap_or_else(|| "-".to_string())
+}
+
+/// Short label for what an import entry will do
+pub fn resolution_label(resolution: Resolution) -> &'static str {
+    match resolution {
+        Resolution::Skip => "skip",
+        Resolution::Add => "add",
+        Resolution::Replace => "replace",
+    }
+}
+
+/// Prints a five-step meter for a new password's strength, and what makes it
+/// easy to guess
+pub fn display_strength(estimate: &Estimate) {
+    let strength = estimate.strength;
+    let filled = strength.score() + 1;
+    let meter = format!("{}{}", "█".repeat(filled), "░".repeat(5 - filled));
+    let line = format!("Strength: {} {}", meter, strength.label());
+    let line = match strength {
+        Strength::VeryWeak | Strength::Weak => line.red(),
+        Strength::Fair => line.yellow(),
+        Strength::Strong | Strength::VeryStrong => line.green(),
+    };
+    println!("{}", line.bold());
+
+    for warning in &estimate.warnings {
+        println!("{}", format!("  ⚠️  {}", warning).yellow());
+    }
+    if strength < Strength::Strong {
+        println!(
+            "{}",
+            "  A few unrelated words, like a short sentence only you would write, are long and easy to remember."
+                .bright_black()
+        );
+    }
+}
+
+/// Displays an import preview: every entry with how it relates to the vault
+/// and what will happen to it
+pub fn display_import_preview(entries: &[PreviewEntry], existing: &[Account]) {
+    let mut table = Table::new();
+    table.set_format(*format::consts::FORMAT_NO_LINESEP_WITH_TITLE);
+
+    table.add_row(prettytable::Row::new(vec![
+        Cell::new("Line").style_spec("bFg"),
+        Cell::new("Account").style_spec("bFg"),
+        Cell::new("Status").style_spec("bFg"),
+        Cell::new("Action").style_spec("bFg"),
+    ]));
+
+    for entry in entries {
+        let account = match &entry.account {
+            Some(account) => match account.issuer() {
+                Some(issuer) => format!("{} ({})", shown_name(account), issuer_label(Some(issuer))),
+                None => shown_name(account),
+            },
+            None => "-".to_string(),
+        };
+
+        let (status, style) = match &entry.status {
+            EntryStatus::New => ("new".to_string(), "Fg"),
+            EntryStatus::Duplicate(reason) => (format!("duplicate: {}", reason), "Fy"),
+            EntryStatus::Conflict(index) => (format!("conflicts with '{}'", shown_name(&existing[*index])), "Fm"),
+            EntryStatus::Invalid(e) => (format!("invalid: {}", e), "Fr"),
         };
 
-        let row = vec![
-            Cell::new(&format!("{}.", i + 1)).style_spec("Fy"),
-            Cell::new(&account.name()).style_spec("FW"),
-            Cell::new(&account.issuer().unwrap_or(&"".to_string())).style_spec("FB"),
-            Cell::new(&account.digits().to_string()).style_spec("FB"),
-            Cell::new(&format!("{}s", account.period())).style_spec("FB"),
-            Cell::new(&algo_name.to_string()).style_spec("FB"),
-        ];
-        table.add_row(prettytable::Row::new(row));
+        table.add_row(prettytable::Row::new(vec![
+            Cell::new(&entry.line.to_string()).style_spec("Fy"),
+            Cell::new(&account).style_spec("FW"),
+            Cell::new(&status).style_spec(style),
+            Cell::new(resolution_label(entry.resolution)).style_spec("FB"),
+        ]));
     }
 
     table.printstd();
 }
 
+/// Set by Ctrl-C while interrupts are caught
+static INTERRUPTED: AtomicBool = AtomicBool::new(false);
+
+/// Makes Ctrl-C back out of the prompt it was pressed in, as
+/// [`AppError::Cancelled`], instead of ending the app. Elsewhere it only
+/// marks [`take_interrupt`]. Call once when the menu opens. Other systems
+/// than Unix keep the default, ending the app.
+#[cfg(unix)]
+pub fn catch_interrupts() {
+    extern "C" fn on_interrupt(_: libc::c_int) {
+        INTERRUPTED.store(true, Ordering::SeqCst);
+    }
+
+    unsafe { libc::signal(libc::SIGINT, on_interrupt as *const () as libc::sighandler_t) };
+}
+
+#[cfg(not(unix))]
+pub fn catch_interrupts() {}
+
+/// Whether Ctrl-C was pressed since the last call
+pub fn take_interrupt() -> bool {
+    INTERRUPTED.swap(false, Ordering::SeqCst)
+}
+
+/// The answer to a dialoguer prompt, telling the user backing out of it
+/// apart from failing to read the terminal
+pub trait Answered<T> {
+    /// The answer, or [`AppError::Cancelled`] when the user pressed Ctrl-C
+    fn answered(self) -> Result<T, AppError>;
+}
+
+impl<T> Answered<T> for dialoguer::Result<T> {
+    fn answered(self) -> Result<T, AppError> {
+        self.map_err(|dialoguer::Error::IO(e)| {
+            if e.kind() == io::ErrorKind::Interrupted {
+                // The prompt hid the cursor and had no chance to show it again
+                let _ = Term::stderr().show_cursor();
+                AppError::Cancelled
+            } else {
+                e.into()
+            }
+        })
+    }
+}
+
+/// The choice made in a list or yes/no prompt that can be left with Esc
+pub trait Chosen<T> {
+    /// The choice, or [`AppError::Cancelled`] when the user pressed Esc, q or
+    /// Ctrl-C
+    fn chosen(self) -> Result<T, AppError>;
+}
+
+impl<T> Chosen<T> for dialoguer::Result<Option<T>> {
+    fn chosen(self) -> Result<T, AppError> {
+        self.answered()?.ok_or(AppError::Cancelled)
+    }
+}
+
 /// Helper function to wait for user input
 pub fn wait_for_input() -> Result<(), AppError> {
     println!("\n{}", "Press Enter to continue...".bright_black());
@@ -175,12 +1222,20 @@ pub fn wait_for_input() -> Result<(), AppError> {
 
 /// Clears the terminal screen
 pub fn clear_screen() {
+    if !io::stdout().is_terminal() {
+        return;
+    }
     print!("\x1B[2J\x1B[1;1H");
     io::stdout().flush().unwrap();
 }
 
 /// Creates a new progress spinner with consistent styling
 pub fn create_spinner(message: String) -> ProgressBar {
+    // Nothing is drawn when the output goes to a pipe or file
+    if !io::stdout().is_terminal() {
+        return ProgressBar::hidden();
+    }
+
     let spinner = ProgressBar::new_spinner();
     spinner.set_style(
         ProgressStyle::default_spinner()
@@ -190,4 +1245,17 @@ pub fn create_spinner(message: String) -> ProgressBar {
     );
     spinner.set_message(message);
     spinner
-} 
\ No newline at end of file
+} 
+/// Shows a spinner for `delay_ms` milliseconds as feedback around an instant
+/// operation. Does nothing when the delay is 0 or stdout isn't a terminal, so
+/// scripted use never waits.
+pub fn show_spinner_delay(message: &str, delay_ms: u64) {
+    if delay_ms == 0 || !io::stdout().is_terminal() {
+        return;
+    }
+
+    let spinner = create_spinner(message.to_string());
+    spinner.enable_steady_tick(Duration::from_millis(80));
+    thread::sleep(Duration::from_millis(delay_ms));
+    spinner.finish_and_clear();
+}
@@ -0,0 +1,174 @@
+//! Multi-step prompts that can go back a step or be cancelled.
+//!
+//! A [`Wizard`] runs its steps in order over a state holding the answers so
+//! far. Each step asks its questions and returns a [`Step`]: on to the next
+//! one, back to the one before it, or cancel. Steps start from the answers
+//! already in the state, so going back to fix a typo keeps everything else.
+//!
+//! The prompts here turn the user's way out into a [`Step`] too: typing
+//! [`BACK`] or [`CANCEL`] at a text prompt, pressing Esc in a list to go
+//! back, or Ctrl-C anywhere to cancel.
+
+use colored::*;
+use dialoguer::{Completion, Confirm, Input, Select};
+use quackey_core::error::AppError;
+use crate::ui::{display_screen, Answered};
+
+/// Typed at a text prompt to go back a step
+pub const BACK: &str = "<";
+/// Typed at a text prompt to cancel the whole wizard
+pub const CANCEL: &str = "!";
+
+/// Where to go after a step or prompt
+pub enum Step<T = ()> {
+    /// On to the next step, with the answer
+    Next(T),
+    Back,
+    Cancel,
+    /// To the step with this name; once it is done, back to the step that
+    /// jumped, as a review step does to correct one answer
+    Jump(&'static str),
+}
+
+/// Takes the answer out of the [`Step`] a prompt returned, or returns the
+/// same way out (or the error) from the step asking it
+macro_rules! answer {
+    ($step:expr) => {
+        match $step? {
+            $crate::wizard::Step::Next(answer) => answer,
+            $crate::wizard::Step::Back => return Ok($crate::wizard::Step::Back),
+            $crate::wizard::Step::Cancel => return Ok($crate::wizard::Step::Cancel),
+            $crate::wizard::Step::Jump(step) => return Ok($crate::wizard::Step::Jump(step)),
+        }
+    };
+}
+pub(crate) use answer;
+
+type StepFn<'a, S> = Box<dyn FnMut(&mut S) -> Result<Step, AppError> + 'a>;
+
+/// Steps run in order over a state of type `S`
+pub struct Wizard<'a, S> {
+    title: &'static str,
+    steps: Vec<(&'static str, StepFn<'a, S>)>,
+}
+
+impl<'a, S> Wizard<'a, S> {
+    /// A wizard with no steps yet; `title` heads every step's screen
+    pub fn new(title: &'static str) -> Self {
+        Self { title, steps: Vec::new() }
+    }
+
+    /// Adds a step named `name` after the ones added so far
+    pub fn step(mut self, name: &'static str, run: impl FnMut(&mut S) -> Result<Step, AppError> + 'a) -> Self {
+        self.steps.push((name, Box::new(run)));
+        self
+    }
+
+    /// Runs the steps on a fresh screen each, until the last one is done.
+    /// Going back from a step that was jumped to returns to the step that
+    /// jumped. Returns `false` when the user cancelled or went back from the
+    /// first step.
+    pub fn run(mut self, state: &mut S) -> Result<bool, AppError> {
+        let total = self.steps.len();
+        let mut index = 0;
+        // The step that jumped to the current one, to return to
+        let mut jumped_from = None;
+
+        while index < total {
+            let (name, run) = &mut self.steps[index];
+
+            display_screen(self.title);
+            println!("{}", format!("Step {} of {}: {}", index + 1, total, name).blue().bold());
+            println!(
+                "{}",
+                format!("Type {} to go back a step or {} to cancel; Esc goes back from a list.", BACK, CANCEL)
+                    .bright_black()
+            );
+            println!();
+
+            match run(state)? {
+                Step::Next(()) => index = jumped_from.take().unwrap_or(index + 1),
+                Step::Back => match jumped_from.take() {
+                    Some(from) => index = from,
+                    None if index > 0 => index -= 1,
+                    None => return Ok(false),
+                },
+                Step::Cancel => return Ok(false),
+                Step::Jump(name) => {
+                    jumped_from = Some(index);
+                    index = self
+                        .steps
+                        .iter()
+                        .position(|(step, _)| *step == name)
+                        .expect("jumped to a step the wizard doesn't have");
+                }
+            }
+        }
+
+        Ok(true)
+    }
+}
+
+/// Asks for a line of text; Enter alone keeps `current`, the answer given
+/// before going back
+pub fn text(prompt: &str, current: &str) -> Result<Step<String>, AppError> {
+    finish(
+        Input::with_theme(&dialoguer::theme::ColorfulTheme::default())
+            .with_prompt(prompt)
+            .allow_empty(true),
+        current,
+    )
+}
+
+/// Like [`text`], completing with Tab from `completion`
+pub fn text_with_completion(
+    prompt: &str,
+    current: &str,
+    completion: &impl Completion,
+) -> Result<Step<String>, AppError> {
+    finish(
+        Input::with_theme(&dialoguer::theme::ColorfulTheme::default())
+            .with_prompt(prompt)
+            .completion_with(completion)
+            .allow_empty(true),
+        current,
+    )
+}
+
+fn finish(input: Input<String>, current: &str) -> Result<Step<String>, AppError> {
+    let input = if current.is_empty() { input } else { input.default(current.to_string()) };
+    Ok(match input.interact_text().answered() {
+        Ok(text) if text.trim() == BACK => Step::Back,
+        Ok(text) if text.trim() == CANCEL => Step::Cancel,
+        Ok(text) => Step::Next(text),
+        Err(AppError::Cancelled) => Step::Cancel,
+        Err(e) => return Err(e),
+    })
+}
+
+/// Asks to pick one of `items`; Esc goes back
+pub fn select<T: ToString>(prompt: &str, items: &[T], default: usize) -> Result<Step<usize>, AppError> {
+    outcome(Select::with_theme(&dialoguer::theme::ColorfulTheme::default())
+        .with_prompt(prompt)
+        .default(default)
+        .items(items)
+        .interact_opt())
+}
+
+/// Asks a yes or no question; Esc goes back
+pub fn confirm(prompt: &str, default: bool) -> Result<Step<bool>, AppError> {
+    outcome(Confirm::with_theme(&dialoguer::theme::ColorfulTheme::default())
+        .with_prompt(prompt)
+        .default(default)
+        .interact_opt())
+}
+
+/// Esc goes back, Ctrl-C cancels
+fn outcome<T>(answer: dialoguer::Result<Option<T>>) -> Result<Step<T>, AppError> {
+    match answer.answered() {
+        Ok(Some(answer)) => Ok(Step::Next(answer)),
+        Ok(None) => Ok(Step::Back),
+        Err(AppError::Cancelled) => Ok(Step::Cancel),
+        Err(e) => Err(e),
+    }
+}